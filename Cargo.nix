@@ -113,6 +113,16 @@ rec {
       # File a bug if you depend on any for non-debug work!
       debug = internal.debugCrate { inherit packageId; };
     };
+    "kennel-forge" = rec {
+      packageId = "kennel-forge";
+      build = internal.buildRustCrateWithFeatures {
+        packageId = "kennel-forge";
+      };
+
+      # Debug support which might change between releases.
+      # File a bug if you depend on any for non-debug work!
+      debug = internal.debugCrate { inherit packageId; };
+    };
     "kennel-router" = rec {
       packageId = "kennel-router";
       build = internal.buildRustCrateWithFeatures {
@@ -806,6 +816,11 @@ rec {
             name = "once_cell";
             packageId = "once_cell";
           }
+          {
+            name = "subtle";
+            packageId = "subtle";
+            usesDefaultFeatures = false;
+          }
           {
             name = "tokio";
             packageId = "tokio";
@@ -6239,6 +6254,10 @@ rec {
             name = "kennel-dns";
             packageId = "kennel-dns";
           }
+          {
+            name = "kennel-forge";
+            packageId = "kennel-forge";
+          }
           {
             name = "kennel-router";
             packageId = "kennel-router";
@@ -6300,22 +6319,53 @@ rec {
             name = "axum";
             packageId = "axum";
           }
+          {
+            name = "chrono";
+            packageId = "chrono";
+            features = [ "serde" ];
+          }
           {
             name = "entity";
             packageId = "entity";
           }
+          {
+            name = "kennel-config";
+            packageId = "kennel-config";
+          }
+          {
+            name = "kennel-deployer";
+            packageId = "kennel-deployer";
+          }
           {
             name = "kennel-store";
             packageId = "kennel-store";
           }
+          {
+            name = "kennel-webhook";
+            packageId = "kennel-webhook";
+          }
           {
             name = "sea-orm";
             packageId = "sea-orm";
           }
+          {
+            name = "serde";
+            packageId = "serde";
+            features = [ "derive" ];
+          }
           {
             name = "serde_json";
             packageId = "serde_json";
           }
+          {
+            name = "tokio";
+            packageId = "tokio";
+            features = [ "fs" "sync" ];
+          }
+          {
+            name = "tokio-stream";
+            packageId = "tokio-stream";
+          }
           {
             name = "tower-http";
             packageId = "tower-http";
@@ -6365,6 +6415,10 @@ rec {
             name = "kennel-config";
             packageId = "kennel-config";
           }
+          {
+            name = "kennel-forge";
+            packageId = "kennel-forge";
+          }
           {
             name = "kennel-store";
             packageId = "kennel-store";
@@ -6373,6 +6427,10 @@ rec {
             name = "sea-orm";
             packageId = "sea-orm";
           }
+          {
+            name = "serde_json";
+            packageId = "serde_json";
+          }
           {
             name = "thiserror";
             packageId = "thiserror 2.0.18";
@@ -6402,6 +6460,14 @@ rec {
         src = lib.cleanSourceWith { filter = sourceFilter;  src = ./crates/kennel-config; };
         libName = "kennel_config";
         dependencies = [
+          {
+            name = "chrono";
+            packageId = "chrono";
+          }
+          {
+            name = "glob";
+            packageId = "glob";
+          }
           {
             name = "serde";
             packageId = "serde";
@@ -6434,10 +6500,22 @@ rec {
             name = "anyhow";
             packageId = "anyhow";
           }
+          {
+            name = "async-trait";
+            packageId = "async-trait";
+          }
+          {
+            name = "chrono";
+            packageId = "chrono";
+          }
           {
             name = "entity";
             packageId = "entity";
           }
+          {
+            name = "hex";
+            packageId = "hex";
+          }
           {
             name = "kennel-builder";
             packageId = "kennel-builder";
@@ -6450,6 +6528,10 @@ rec {
             name = "kennel-dns";
             packageId = "kennel-dns";
           }
+          {
+            name = "kennel-forge";
+            packageId = "kennel-forge";
+          }
           {
             name = "kennel-router";
             packageId = "kennel-router";
@@ -6466,6 +6548,14 @@ rec {
             name = "sea-orm";
             packageId = "sea-orm";
           }
+          {
+            name = "serde_json";
+            packageId = "serde_json";
+          }
+          {
+            name = "sha2";
+            packageId = "sha2";
+          }
           {
             name = "thiserror";
             packageId = "thiserror 2.0.18";
@@ -6480,6 +6570,16 @@ rec {
             packageId = "tracing";
           }
         ];
+        devDependencies = [
+          {
+            name = "axum";
+            packageId = "axum";
+          }
+          {
+            name = "tempfile";
+            packageId = "tempfile";
+          }
+        ];
 
       };
       "kennel-dns" = rec {
@@ -6520,6 +6620,77 @@ rec {
           }
         ];
 
+      };
+      "kennel-forge" = rec {
+        crateName = "kennel-forge";
+        version = "0.1.0";
+        edition = "2024";
+        src = lib.cleanSourceWith { filter = sourceFilter;  src = ./crates/kennel-forge; };
+        libName = "kennel_forge";
+        dependencies = [
+          {
+            name = "async-trait";
+            packageId = "async-trait";
+          }
+          {
+            name = "entity";
+            packageId = "entity";
+          }
+          {
+            name = "kennel-config";
+            packageId = "kennel-config";
+          }
+          {
+            name = "kennel-store";
+            packageId = "kennel-store";
+          }
+          {
+            name = "reqwest";
+            packageId = "reqwest 0.13.2";
+          }
+          {
+            name = "serde";
+            packageId = "serde";
+            features = [ "derive" ];
+          }
+          {
+            name = "serde_json";
+            packageId = "serde_json";
+          }
+          {
+            name = "thiserror";
+            packageId = "thiserror 2.0.18";
+          }
+          {
+            name = "tokio";
+            packageId = "tokio";
+            features = [ "sync" ];
+          }
+          {
+            name = "tracing";
+            packageId = "tracing";
+          }
+        ];
+        devDependencies = [
+          {
+            name = "axum";
+            packageId = "axum";
+          }
+          {
+            name = "chrono";
+            packageId = "chrono";
+          }
+          {
+            name = "sea-orm";
+            packageId = "sea-orm";
+          }
+          {
+            name = "tokio";
+            packageId = "tokio";
+            features = [ "full" ];
+          }
+        ];
+
       };
       "kennel-router" = rec {
         crateName = "kennel-router";
@@ -6545,6 +6716,10 @@ rec {
             name = "axum-server";
             packageId = "axum-server";
           }
+          {
+            name = "chrono";
+            packageId = "chrono";
+          }
           {
             name = "entity";
             packageId = "entity";
@@ -6626,15 +6801,27 @@ rec {
             name = "entity";
             packageId = "entity";
           }
+          {
+            name = "kennel-config";
+            packageId = "kennel-config";
+          }
           {
             name = "sea-orm";
             packageId = "sea-orm";
             features = [ "sqlx-postgres" "runtime-tokio-rustls" ];
           }
+          {
+            name = "serde_json";
+            packageId = "serde_json";
+          }
           {
             name = "thiserror";
             packageId = "thiserror 2.0.18";
           }
+          {
+            name = "tracing";
+            packageId = "tracing";
+          }
         ];
         devDependencies = [
           {
@@ -6660,6 +6847,14 @@ rec {
             name = "bytes";
             packageId = "bytes";
           }
+          {
+            name = "chrono";
+            packageId = "chrono";
+          }
+          {
+            name = "entity";
+            packageId = "entity";
+          }
           {
             name = "hex";
             packageId = "hex";
@@ -6668,10 +6863,22 @@ rec {
             name = "hmac";
             packageId = "hmac";
           }
+          {
+            name = "kennel-config";
+            packageId = "kennel-config";
+          }
+          {
+            name = "kennel-forge";
+            packageId = "kennel-forge";
+          }
           {
             name = "kennel-store";
             packageId = "kennel-store";
           }
+          {
+            name = "sea-orm";
+            packageId = "sea-orm";
+          }
           {
             name = "serde";
             packageId = "serde";
@@ -6684,6 +6891,11 @@ rec {
             name = "sha2";
             packageId = "sha2";
           }
+          {
+            name = "subtle";
+            packageId = "subtle";
+            usesDefaultFeatures = false;
+          }
           {
             name = "thiserror";
             packageId = "thiserror 2.0.18";
@@ -6691,7 +6903,7 @@ rec {
           {
             name = "tokio";
             packageId = "tokio";
-            features = [ "sync" ];
+            features = [ "rt" "sync" ];
           }
           {
             name = "tracing";
@@ -24,12 +24,29 @@ pub struct Model {
     pub finished_at: Option<DateTime>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub author: Option<String>,
+    pub approval_expires_at: Option<DateTime>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<DateTime>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub hold_reason: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub pr_head_ref: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub pr_base_ref: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub pr_title: Option<String>,
+    pub pr_from_fork: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::build_results::Entity")]
     BuildResults,
+    #[sea_orm(has_many = "super::deployments::Entity")]
+    Deployments,
     #[sea_orm(
         belongs_to = "super::projects::Entity",
         from = "Column::ProjectName",
@@ -38,6 +55,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Projects,
+    #[sea_orm(has_many = "super::webhook_deliveries::Entity")]
+    WebhookDeliveries,
 }
 
 impl Related<super::build_results::Entity> for Entity {
@@ -46,10 +65,22 @@ impl Related<super::build_results::Entity> for Entity {
     }
 }
 
+impl Related<super::deployments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Deployments.def()
+    }
+}
+
 impl Related<super::projects::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Projects.def()
     }
 }
 
+impl Related<super::webhook_deliveries::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WebhookDeliveries.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
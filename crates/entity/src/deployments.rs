@@ -1,6 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
 use super::sea_orm_active_enums::DeploymentStatus;
+use super::sea_orm_active_enums::ServiceType;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -34,30 +35,74 @@ pub struct Model {
     pub last_activity: DateTime,
     #[sea_orm(column_type = "Text")]
     pub dns_status: String,
+    pub build_id: Option<i32>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub status_message: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub unit_name: Option<String>,
+    pub service_type: ServiceType,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub release_log: Option<String>,
+    pub idle_since: Option<DateTime>,
+    pub request_count: i64,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub promoted_from_branch: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub promoted_from_environment: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::builds::Entity",
+        from = "Column::BuildId",
+        to = "super::builds::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Builds,
+    #[sea_orm(has_many = "super::deployment_events::Entity")]
+    DeploymentEvents,
     #[sea_orm(has_many = "super::dns_records::Entity")]
     DnsRecords,
+    #[sea_orm(has_many = "super::job_runs::Entity")]
+    JobRuns,
     #[sea_orm(has_many = "super::port_allocations::Entity")]
     PortAllocations,
     #[sea_orm(
         belongs_to = "super::services::Entity",
-        from = "(Column::ProjectName, Column::ProjectName, Column::ServiceName, Column::ServiceName)",
-        to = "(super::services::Column::Name, super::services::Column::ProjectName, super::services::Column::Name, super::services::Column::ProjectName)",
+        from = "(Column::ProjectName, Column::ServiceName)",
+        to = "(super::services::Column::ProjectName, super::services::Column::Name)",
         on_update = "NoAction",
         on_delete = "Cascade"
     )]
     Services,
 }
 
+impl Related<super::builds::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Builds.def()
+    }
+}
+
+impl Related<super::deployment_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DeploymentEvents.def()
+    }
+}
+
 impl Related<super::dns_records::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::DnsRecords.def()
     }
 }
 
+impl Related<super::job_runs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::JobRuns.def()
+    }
+}
+
 impl Related<super::port_allocations::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PortAllocations.def()
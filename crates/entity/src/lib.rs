@@ -2,12 +2,18 @@
 
 pub mod prelude;
 
+pub mod branch_pins;
 pub mod build_results;
 pub mod builds;
+pub mod deployment_events;
 pub mod deployments;
 pub mod dns_records;
+pub mod freeze_windows;
+pub mod job_runs;
 pub mod port_allocations;
+pub mod preview_comments;
 pub mod preview_databases;
 pub mod projects;
 pub mod sea_orm_active_enums;
 pub mod services;
+pub mod webhook_deliveries;
@@ -1,10 +1,16 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+pub use super::branch_pins::Entity as BranchPins;
 pub use super::build_results::Entity as BuildResults;
 pub use super::builds::Entity as Builds;
+pub use super::deployment_events::Entity as DeploymentEvents;
 pub use super::deployments::Entity as Deployments;
 pub use super::dns_records::Entity as DnsRecords;
+pub use super::freeze_windows::Entity as FreezeWindows;
+pub use super::job_runs::Entity as JobRuns;
 pub use super::port_allocations::Entity as PortAllocations;
+pub use super::preview_comments::Entity as PreviewComments;
 pub use super::preview_databases::Entity as PreviewDatabases;
 pub use super::projects::Entity as Projects;
 pub use super::services::Entity as Services;
+pub use super::webhook_deliveries::Entity as WebhookDeliveries;
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+use super::sea_orm_active_enums::ForkPolicy;
 use super::sea_orm_active_enums::RepoType;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -20,16 +21,41 @@ pub struct Model {
     pub default_branch: String,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub expiry_policy: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub environment_rules: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub approval_policy: Option<Json>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub forge_token: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub tag_pattern: Option<String>,
+    pub fork_policy: ForkPolicy,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::branch_pins::Entity")]
+    BranchPins,
     #[sea_orm(has_many = "super::builds::Entity")]
     Builds,
+    #[sea_orm(has_many = "super::freeze_windows::Entity")]
+    FreezeWindows,
+    #[sea_orm(has_many = "super::preview_comments::Entity")]
+    PreviewComments,
     #[sea_orm(has_many = "super::preview_databases::Entity")]
     PreviewDatabases,
     #[sea_orm(has_many = "super::services::Entity")]
     Services,
+    #[sea_orm(has_many = "super::webhook_deliveries::Entity")]
+    WebhookDeliveries,
+}
+
+impl Related<super::branch_pins::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BranchPins.def()
+    }
 }
 
 impl Related<super::builds::Entity> for Entity {
@@ -38,6 +64,18 @@ impl Related<super::builds::Entity> for Entity {
     }
 }
 
+impl Related<super::freeze_windows::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FreezeWindows.def()
+    }
+}
+
+impl Related<super::preview_comments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PreviewComments.def()
+    }
+}
+
 impl Related<super::preview_databases::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PreviewDatabases.def()
@@ -50,4 +88,10 @@ impl Related<super::services::Entity> for Entity {
     }
 }
 
+impl Related<super::webhook_deliveries::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WebhookDeliveries.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -54,6 +54,54 @@ pub enum BuildStatus {
     Failed,
     #[sea_orm(string_value = "cancelled")]
     Cancelled,
+    #[sea_orm(string_value = "awaiting_approval")]
+    AwaitingApproval,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+    #[sea_orm(string_value = "expired")]
+    Expired,
+    #[sea_orm(string_value = "frozen")]
+    Frozen,
+}
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+    utoipa :: ToSchema,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "deployment_event_kind"
+)]
+pub enum DeploymentEventKind {
+    #[sea_orm(string_value = "deployed")]
+    Deployed,
+    #[sea_orm(string_value = "expired")]
+    Expired,
+    #[sea_orm(string_value = "expiry_warning")]
+    ExpiryWarning,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "freeze_overridden")]
+    FreezeOverridden,
+    #[sea_orm(string_value = "promoted")]
+    Promoted,
+    #[sea_orm(string_value = "replaced")]
+    Replaced,
+    #[sea_orm(string_value = "restarted")]
+    Restarted,
+    #[sea_orm(string_value = "restored")]
+    Restored,
+    #[sea_orm(string_value = "rolled_back")]
+    RolledBack,
+    #[sea_orm(string_value = "torn_down")]
+    TornDown,
 }
 #[derive(
     Debug,
@@ -92,12 +140,54 @@ pub enum DeploymentStatus {
     Deserialize,
     utoipa :: ToSchema,
 )]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "fork_policy")]
+pub enum ForkPolicy {
+    #[sea_orm(string_value = "approve")]
+    Approve,
+    #[sea_orm(string_value = "build")]
+    Build,
+    #[sea_orm(string_value = "refuse")]
+    Refuse,
+}
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+    utoipa :: ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "load_balancing")]
+pub enum LoadBalancing {
+    #[sea_orm(string_value = "least_connections")]
+    LeastConnections,
+    #[sea_orm(string_value = "round_robin")]
+    RoundRobin,
+}
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+    utoipa :: ToSchema,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "repo_type")]
 pub enum RepoType {
     #[sea_orm(string_value = "forgejo")]
     Forgejo,
+    #[sea_orm(string_value = "gitea")]
+    Gitea,
     #[sea_orm(string_value = "github")]
     Github,
+    #[sea_orm(string_value = "gitlab")]
+    Gitlab,
 }
 #[derive(
     Debug,
@@ -118,4 +208,8 @@ pub enum ServiceType {
     Static,
     #[sea_orm(string_value = "image")]
     Image,
+    #[sea_orm(string_value = "job")]
+    Job,
+    #[sea_orm(string_value = "worker")]
+    Worker,
 }
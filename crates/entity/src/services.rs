@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+use super::sea_orm_active_enums::LoadBalancing;
 use super::sea_orm_active_enums::ServiceType;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -25,6 +26,8 @@ pub struct Model {
     pub spa: bool,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    pub load_balancing: LoadBalancing,
+    pub idle_timeout_secs: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
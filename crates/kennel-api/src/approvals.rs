@@ -0,0 +1,211 @@
+use crate::auth::AuthUser;
+use crate::{ApiConfig, ApiError, api_error};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use entity::builds;
+use entity::sea_orm_active_enums::BuildStatus;
+use kennel_deployer::{DeploymentRequest, DeploymentTrigger, approval_policy};
+use kennel_store::project_environment;
+use tracing::info;
+
+#[utoipa::path(
+    get,
+    path = "/projects/{project}/approvals",
+    params(("project" = String, Path,)),
+    responses(
+        (status = OK, description = "Builds waiting for approval, newest first", body = Vec<builds::Model>),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+    ),
+    tag = "approvals"
+)]
+pub async fn list_pending(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path(project): Path<String>,
+) -> Result<Json<Vec<builds::Model>>, ApiError> {
+    user.require_project(&project)?;
+
+    let builds = config
+        .store
+        .builds()
+        .list_awaiting_approval(&project)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(builds))
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects/{project}/builds/{build_id}/approve",
+    params(("project" = String, Path,), ("build_id" = i32, Path,)),
+    responses(
+        (status = ACCEPTED, description = "Build approved and its deployment queued", body = builds::Model),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project or is not an approver"),
+        (status = NOT_FOUND, description = "Build not found"),
+        (status = CONFLICT, description = "Build is not waiting for approval or its approval expired"),
+    ),
+    tag = "approvals"
+)]
+pub async fn approve(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, build_id)): Path<(String, i32)>,
+) -> Result<(StatusCode, Json<builds::Model>), ApiError> {
+    let build = review(&config, &user, &project, build_id, true).await?;
+
+    config
+        .deploy_tx
+        .send(DeploymentRequest {
+            build_id: build.id,
+            project_name: build.project_name.clone(),
+            git_ref: build.git_ref.clone(),
+            services: Vec::new(),
+            trigger: DeploymentTrigger::Build,
+        })
+        .await
+        .map_err(|e| api_error(StatusCode::SERVICE_UNAVAILABLE, e))?;
+
+    Ok((StatusCode::ACCEPTED, Json(build)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects/{project}/builds/{build_id}/reject",
+    params(("project" = String, Path,), ("build_id" = i32, Path,)),
+    responses(
+        (status = OK, description = "Build rejected; it will not be deployed", body = builds::Model),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project or is not an approver"),
+        (status = NOT_FOUND, description = "Build not found"),
+        (status = CONFLICT, description = "Build is not waiting for approval or its approval expired"),
+    ),
+    tag = "approvals"
+)]
+pub async fn reject(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, build_id)): Path<(String, i32)>,
+) -> Result<Json<builds::Model>, ApiError> {
+    let build = review(&config, &user, &project, build_id, false).await?;
+
+    Ok(Json(build))
+}
+
+/// Refuse a manual deployment to `branch` by a token that could not approve
+/// it, when the project requires approval for the branch's environment.
+/// Rollbacks, restores and promotions deploy builds that never wait for
+/// review, so the request itself stands in for the approval.
+pub(crate) async fn ensure_may_approve(
+    config: &ApiConfig,
+    user: &AuthUser,
+    project: &str,
+    branch: &str,
+) -> Result<(), ApiError> {
+    let Some(project) = config
+        .store
+        .projects()
+        .find_by_name(project)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+    else {
+        return Ok(());
+    };
+
+    let environment = project_environment(&project, branch);
+    let policy = approval_policy(&project);
+    if policy.requires(&environment) && !policy.can_review(&user.name) {
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            format!(
+                "Deployments to {} of {} require approval, which token '{}' cannot give",
+                environment, project.name, user.name
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn review(
+    config: &ApiConfig,
+    user: &AuthUser,
+    project: &str,
+    build_id: i32,
+    approved: bool,
+) -> Result<builds::Model, ApiError> {
+    user.require_project(project)?;
+
+    let project_model = config
+        .store
+        .projects()
+        .find_by_name(project)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!("Project {} not found", project),
+            )
+        })?;
+
+    if !approval_policy(&project_model).can_review(&user.name) {
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            format!(
+                "Token '{}' cannot approve deployments of {}",
+                user.name, project
+            ),
+        ));
+    }
+
+    let build = config
+        .store
+        .builds()
+        .find_by_id(build_id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .filter(|b| b.project_name == project)
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!("Build {} not found for {}", build_id, project),
+            )
+        })?;
+
+    let reviewed = config
+        .store
+        .builds()
+        .review(build.id, approved, &user.name)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::CONFLICT,
+                if build.status == BuildStatus::AwaitingApproval {
+                    format!("Approval of build {} has expired", build.id)
+                } else {
+                    format!(
+                        "Build {} is not waiting for approval ({:?})",
+                        build.id, build.status
+                    )
+                },
+            )
+        })?;
+
+    info!(
+        "{} {} build {} of {}/{}",
+        user.name,
+        if approved { "approved" } else { "rejected" },
+        reviewed.id,
+        reviewed.project_name,
+        reviewed.git_ref
+    );
+
+    Ok(reviewed)
+}
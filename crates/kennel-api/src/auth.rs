@@ -0,0 +1,198 @@
+use crate::{ApiConfig, ApiError, api_error};
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{StatusCode, header, request::Parts},
+};
+use serde::Deserialize;
+use subtle::ConstantTimeEq;
+use tracing::{info, warn};
+
+#[derive(Debug, Deserialize)]
+struct TokenConfig {
+    name: String,
+    token_file: String,
+    /// Projects this token may act on; all projects when absent.
+    #[serde(default)]
+    projects: Option<Vec<String>>,
+}
+
+struct ApiToken {
+    name: String,
+    token: String,
+    projects: Option<Vec<String>>,
+}
+
+/// Bearer tokens accepted by the API, loaded from the NixOS-generated config.
+#[derive(Default)]
+pub struct ApiAuth {
+    tokens: Vec<ApiToken>,
+}
+
+impl ApiAuth {
+    pub async fn load(path: &str) -> anyhow::Result<Self> {
+        let tokens_json = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(_) => {
+                info!("No API tokens configured, authenticated endpoints are disabled");
+                return Ok(Self::default());
+            }
+        };
+
+        let configs: Vec<TokenConfig> = serde_json::from_str(&tokens_json)?;
+        let mut tokens = Vec::with_capacity(configs.len());
+
+        for config in configs {
+            match tokio::fs::read_to_string(&config.token_file).await {
+                Ok(token) => tokens.push(ApiToken {
+                    name: config.name,
+                    token: token.trim().to_string(),
+                    projects: config.projects,
+                }),
+                Err(e) => {
+                    warn!("Failed to read API token '{}': {}", config.name, e);
+                }
+            }
+        }
+
+        info!("Loaded {} API token(s)", tokens.len());
+
+        Ok(Self { tokens })
+    }
+
+    fn authenticate(&self, presented: &str) -> Option<AuthUser> {
+        self.tokens
+            .iter()
+            .find(|t| {
+                !t.token.is_empty() && bool::from(t.token.as_bytes().ct_eq(presented.as_bytes()))
+            })
+            .map(|t| AuthUser {
+                name: t.name.clone(),
+                projects: t.projects.clone(),
+            })
+    }
+}
+
+/// Caller identified by an API token.
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub name: String,
+    projects: Option<Vec<String>>,
+}
+
+impl AuthUser {
+    pub fn can_access(&self, project: &str) -> bool {
+        self.projects
+            .as_ref()
+            .is_none_or(|projects| projects.iter().any(|p| p == project))
+    }
+
+    pub fn require_project(&self, project: &str) -> Result<(), ApiError> {
+        if self.can_access(project) {
+            Ok(())
+        } else {
+            Err(api_error(
+                StatusCode::FORBIDDEN,
+                format!("Token '{}' cannot access project {}", self.name, project),
+            ))
+        }
+    }
+
+    /// Tokens not limited to a list of projects administer the whole host.
+    pub fn is_admin(&self) -> bool {
+        self.projects.is_none()
+    }
+
+    pub fn require_admin(&self) -> Result<(), ApiError> {
+        if self.is_admin() {
+            Ok(())
+        } else {
+            Err(api_error(
+                StatusCode::FORBIDDEN,
+                format!("Token '{}' is not an admin token", self.name),
+            ))
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for AuthUser
+where
+    ApiConfig: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = ApiConfig::from_ref(state);
+
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .ok_or_else(|| api_error(StatusCode::UNAUTHORIZED, "Missing bearer token"))?;
+
+        config
+            .auth
+            .authenticate(token.trim())
+            .ok_or_else(|| api_error(StatusCode::UNAUTHORIZED, "Invalid token"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth() -> ApiAuth {
+        ApiAuth {
+            tokens: vec![
+                ApiToken {
+                    name: "admin".to_string(),
+                    token: "secret-admin".to_string(),
+                    projects: None,
+                },
+                ApiToken {
+                    name: "ci".to_string(),
+                    token: "secret-ci".to_string(),
+                    projects: Some(vec!["kennel".to_string()]),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let auth = auth();
+
+        assert_eq!(auth.authenticate("secret-admin").unwrap().name, "admin");
+        assert_eq!(auth.authenticate("secret-ci").unwrap().name, "ci");
+        assert!(auth.authenticate("secret").is_none());
+        assert!(auth.authenticate("").is_none());
+    }
+
+    #[test]
+    fn test_project_access() {
+        let auth = auth();
+
+        let admin = auth.authenticate("secret-admin").unwrap();
+        assert!(admin.can_access("kennel"));
+        assert!(admin.can_access("other"));
+
+        let ci = auth.authenticate("secret-ci").unwrap();
+        assert!(ci.can_access("kennel"));
+        assert!(!ci.can_access("other"));
+        assert!(ci.require_project("other").is_err());
+    }
+
+    #[test]
+    fn test_admin() {
+        let auth = auth();
+
+        assert!(auth.authenticate("secret-admin").unwrap().is_admin());
+        assert!(
+            auth.authenticate("secret-ci")
+                .unwrap()
+                .require_admin()
+                .is_err()
+        );
+    }
+}
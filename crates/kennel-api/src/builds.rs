@@ -1,12 +1,63 @@
+use crate::auth::AuthUser;
+use crate::{ApiConfig, ApiError, api_error};
 use axum::{
     Json,
     extract::{Path, State},
     http::StatusCode,
 };
 use entity::sea_orm_active_enums::BuildStatus;
+use entity::{build_results, builds};
 use kennel_store::Store;
 use sea_orm::{ActiveValue::Set, IntoActiveModel};
+use serde::Serialize;
 use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BuildDetail {
+    pub build: builds::Model,
+    /// One result per service, static site and job the build produced.
+    pub results: Vec<build_results::Model>,
+}
+
+/// Commit statuses posted to the forge link here.
+#[utoipa::path(
+    get,
+    path = "/projects/{project}/builds/{build_id}",
+    params(("project" = String, Path,), ("build_id" = i32, Path,)),
+    responses(
+        (status = OK, description = "The build and its per-service results", body = BuildDetail),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+        (status = NOT_FOUND, description = "Build not found"),
+    ),
+    tag = "builds"
+)]
+pub async fn get_build(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, build_id)): Path<(String, i32)>,
+) -> Result<Json<BuildDetail>, ApiError> {
+    user.require_project(&project)?;
+
+    let build = config
+        .store
+        .builds()
+        .find_by_id(build_id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .filter(|build| build.project_name == project)
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Build not found"))?;
+
+    let results = config
+        .store
+        .build_results()
+        .find_by_build_id(build_id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(BuildDetail { build, results }))
+}
 
 #[utoipa::path(
     post,
@@ -0,0 +1,99 @@
+use crate::auth::AuthUser;
+use crate::{ApiConfig, ApiError, api_error};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use entity::webhook_deliveries;
+use kennel_webhook::replay_delivery;
+use tracing::info;
+
+/// Most deliveries returned by one listing.
+const DELIVERY_LIST_LIMIT: u64 = 100;
+
+#[utoipa::path(
+    get,
+    path = "/projects/{project}/webhook-deliveries",
+    params(("project" = String, Path,)),
+    responses(
+        (status = OK, description = "The project's most recent webhook deliveries, newest first", body = Vec<webhook_deliveries::Model>),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+    ),
+    tag = "webhooks"
+)]
+pub async fn list_deliveries(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path(project): Path<String>,
+) -> Result<Json<Vec<webhook_deliveries::Model>>, ApiError> {
+    user.require_project(&project)?;
+
+    let deliveries = config
+        .store
+        .webhook_deliveries()
+        .list_by_project(&project, DELIVERY_LIST_LIMIT)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(deliveries))
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects/{project}/webhook-deliveries/{id}/replay",
+    params(("project" = String, Path,), ("id" = i32, Path,)),
+    responses(
+        (status = CREATED, description = "Delivery handled again; the replay is recorded as a new delivery", body = webhook_deliveries::Model),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+        (status = NOT_FOUND, description = "Delivery not found"),
+        (status = CONFLICT, description = "Delivery failed signature verification"),
+    ),
+    tag = "webhooks"
+)]
+pub async fn replay(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, id)): Path<(String, i32)>,
+) -> Result<(StatusCode, Json<webhook_deliveries::Model>), ApiError> {
+    user.require_project(&project)?;
+
+    let original = config
+        .store
+        .webhook_deliveries()
+        .find_by_id(id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .filter(|d| d.project_name == project)
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!("Webhook delivery {} not found for {}", id, project),
+            )
+        })?;
+
+    // The signature is not stored, so only deliveries known to come from the
+    // forge may be handled again
+    if !original.verified {
+        return Err(api_error(
+            StatusCode::CONFLICT,
+            format!(
+                "Webhook delivery {} failed signature verification and cannot be replayed",
+                id
+            ),
+        ));
+    }
+
+    let delivery = replay_delivery(&config.webhooks, &original, &user.name)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    info!(
+        "{} replayed webhook delivery {} of {} as delivery {}",
+        user.name, original.id, project, delivery.id
+    );
+
+    Ok((StatusCode::CREATED, Json(delivery)))
+}
@@ -0,0 +1,585 @@
+use crate::auth::AuthUser;
+use crate::{ApiConfig, ApiError, api_error};
+use crate::{approvals, freezes};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use chrono::{DateTime, Utc};
+use entity::sea_orm_active_enums::DeploymentEventKind;
+use entity::sea_orm_active_enums::ServiceType;
+use entity::{branch_pins, deployment_events, deployments};
+use kennel_deployer::{DeployerError, DeploymentRequest, DeploymentTrigger, LogEntry, LogQuery};
+use serde::{Deserialize, Serialize};
+use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+use tracing::info;
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_TIMELINE_LIMIT: u64 = 100;
+const MAX_TIMELINE_LIMIT: u64 = 1000;
+const DEFAULT_LOG_LINES: usize = 200;
+const MAX_LOG_LINES: usize = 10000;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RollbackRequest {
+    /// Earlier build of this branch to redeploy.
+    pub build_id: i32,
+    /// Only roll back this service; all services from the build when omitted.
+    pub service: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RollbackResponse {
+    pub build_id: i32,
+    pub services: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects/{project}/branches/{branch}/rollback",
+    params(("project" = String, Path,), ("branch" = String, Path,)),
+    request_body = RollbackRequest,
+    responses(
+        (status = ACCEPTED, description = "Rollback queued", body = RollbackResponse),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project or approve deployments to its environment"),
+        (status = NOT_FOUND, description = "Build not found for this branch"),
+        (status = LOCKED, description = "The branch's environment is frozen"),
+        (status = BAD_REQUEST, description = "Build has nothing to deploy"),
+    ),
+    tag = "deployments"
+)]
+pub async fn rollback(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, branch)): Path<(String, String)>,
+    Json(body): Json<RollbackRequest>,
+) -> Result<(StatusCode, Json<RollbackResponse>), ApiError> {
+    user.require_project(&project)?;
+    freezes::ensure_not_frozen(&config, &project, &branch).await?;
+    approvals::ensure_may_approve(&config, &user, &project, &branch).await?;
+
+    let build = config
+        .store
+        .builds()
+        .find_by_id(body.build_id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .filter(|b| b.project_name == project && b.git_ref == branch)
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!(
+                    "Build {} not found for {}/{}",
+                    body.build_id, project, branch
+                ),
+            )
+        })?;
+
+    let services: Vec<String> = config
+        .store
+        .build_results()
+        .find_successful_by_build_id(build.id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .into_iter()
+        .filter(|r| r.store_path.is_some())
+        .map(|r| r.service_name)
+        .filter(|name| body.service.as_ref().is_none_or(|s| s == name))
+        .collect();
+
+    if services.is_empty() {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            match &body.service {
+                Some(service) => format!(
+                    "Build {} has no successful output for service {}",
+                    build.id, service
+                ),
+                None => format!("Build {} has no successful outputs", build.id),
+            },
+        ));
+    }
+
+    info!(
+        "{} requested rollback of {}/{} to build {} ({})",
+        user.name,
+        project,
+        branch,
+        build.id,
+        services.join(", ")
+    );
+
+    config
+        .deploy_tx
+        .send(DeploymentRequest {
+            build_id: build.id,
+            project_name: project,
+            git_ref: branch,
+            services: services.clone(),
+            trigger: DeploymentTrigger::Rollback { actor: user.name },
+        })
+        .await
+        .map_err(|e| api_error(StatusCode::SERVICE_UNAVAILABLE, e))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(RollbackResponse {
+            build_id: build.id,
+            services,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects/{project}/branches/{branch}/restore",
+    params(("project" = String, Path,), ("branch" = String, Path,)),
+    responses(
+        (status = ACCEPTED, description = "Redeploy of the last expired build queued", body = RollbackResponse),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project or approve deployments to its environment"),
+        (status = NOT_FOUND, description = "Branch has not expired"),
+        (status = LOCKED, description = "The branch's environment is frozen"),
+        (status = CONFLICT, description = "Every service of the build is already deployed"),
+    ),
+    tag = "deployments"
+)]
+pub async fn restore(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, branch)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<RollbackResponse>), ApiError> {
+    user.require_project(&project)?;
+    freezes::ensure_not_frozen(&config, &project, &branch).await?;
+    approvals::ensure_may_approve(&config, &user, &project, &branch).await?;
+
+    let build_id = config
+        .store
+        .deployment_events()
+        .latest_by_branch(&project, &branch, DeploymentEventKind::Expired)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .and_then(|event| event.build_id)
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!("{}/{} has no expired build to restore", project, branch),
+            )
+        })?;
+
+    let live: Vec<String> = config
+        .store
+        .deployments()
+        .list_active_by_project(&project)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .into_iter()
+        .filter(|d| d.branch == branch)
+        .map(|d| d.service_name)
+        .collect();
+
+    let services: Vec<String> = config
+        .store
+        .build_results()
+        .find_successful_by_build_id(build_id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .into_iter()
+        .filter(|r| r.store_path.is_some() && !live.contains(&r.service_name))
+        .map(|r| r.service_name)
+        .collect();
+
+    if services.is_empty() {
+        return Err(api_error(
+            StatusCode::CONFLICT,
+            format!(
+                "Every service of build {} is already deployed on {}/{}",
+                build_id, project, branch
+            ),
+        ));
+    }
+
+    info!(
+        "{} requested restore of {}/{} from build {} ({})",
+        user.name,
+        project,
+        branch,
+        build_id,
+        services.join(", ")
+    );
+
+    config
+        .deploy_tx
+        .send(DeploymentRequest {
+            build_id,
+            project_name: project,
+            git_ref: branch,
+            services: services.clone(),
+            trigger: DeploymentTrigger::Restore { actor: user.name },
+        })
+        .await
+        .map_err(|e| api_error(StatusCode::SERVICE_UNAVAILABLE, e))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(RollbackResponse { build_id, services }),
+    ))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PromoteRequest {
+    /// Branch to deploy the builds to, e.g. `main` when promoting staging.
+    pub to: String,
+    /// Only promote this service; every live service when omitted.
+    pub service: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PromoteResponse {
+    pub to: String,
+    pub builds: Vec<RollbackResponse>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects/{project}/branches/{branch}/promote",
+    params(("project" = String, Path,), ("branch" = String, Path,)),
+    request_body = PromoteRequest,
+    responses(
+        (status = ACCEPTED, description = "Deploy of the branch's live builds to the target branch queued", body = PromoteResponse),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project or approve deployments to its environment"),
+        (status = NOT_FOUND, description = "Nothing deployed on the branch"),
+        (status = LOCKED, description = "The branch's environment is frozen"),
+        (status = BAD_REQUEST, description = "Target is the source branch"),
+    ),
+    tag = "deployments"
+)]
+pub async fn promote(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, branch)): Path<(String, String)>,
+    Json(body): Json<PromoteRequest>,
+) -> Result<(StatusCode, Json<PromoteResponse>), ApiError> {
+    user.require_project(&project)?;
+    freezes::ensure_not_frozen(&config, &project, &body.to).await?;
+    approvals::ensure_may_approve(&config, &user, &project, &body.to).await?;
+
+    let requests = kennel_deployer::plan_promotion(
+        &config.store,
+        &project,
+        &branch,
+        &body.to,
+        body.service.as_deref(),
+        &user.name,
+    )
+    .await
+    .map_err(|e| match e {
+        DeployerError::NotFound(_) => api_error(StatusCode::NOT_FOUND, e),
+        DeployerError::Invalid(_) => api_error(StatusCode::BAD_REQUEST, e),
+        _ => api_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    })?;
+
+    let mut builds = Vec::new();
+    for request in requests {
+        info!(
+            "{} requested promotion of {}/{} to {} from build {} ({})",
+            user.name,
+            project,
+            branch,
+            body.to,
+            request.build_id,
+            request.services.join(", ")
+        );
+
+        builds.push(RollbackResponse {
+            build_id: request.build_id,
+            services: request.services.clone(),
+        });
+
+        config
+            .deploy_tx
+            .send(request)
+            .await
+            .map_err(|e| api_error(StatusCode::SERVICE_UNAVAILABLE, e))?;
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(PromoteResponse {
+            to: body.to,
+            builds,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/projects/{project}/branches/{branch}/pin",
+    params(("project" = String, Path,), ("branch" = String, Path,)),
+    responses(
+        (status = OK, description = "Branch pinned; its deployments no longer expire", body = branch_pins::Model),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+        (status = NOT_FOUND, description = "Project not found"),
+    ),
+    tag = "deployments"
+)]
+pub async fn pin_branch(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, branch)): Path<(String, String)>,
+) -> Result<Json<branch_pins::Model>, ApiError> {
+    user.require_project(&project)?;
+
+    config
+        .store
+        .projects()
+        .find_by_name(&project)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!("Project {} not found", project),
+            )
+        })?;
+
+    let pin = config
+        .store
+        .branch_pins()
+        .pin(&project, &branch, &user.name)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    info!("{} pinned {}/{}", user.name, project, branch);
+
+    Ok(Json(pin))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/projects/{project}/branches/{branch}/pin",
+    params(("project" = String, Path,), ("branch" = String, Path,)),
+    responses(
+        (status = NO_CONTENT, description = "Branch unpinned; its deployments expire again"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+        (status = NOT_FOUND, description = "Branch is not pinned"),
+    ),
+    tag = "deployments"
+)]
+pub async fn unpin_branch(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, branch)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    user.require_project(&project)?;
+
+    let unpinned = config
+        .store
+        .branch_pins()
+        .unpin(&project, &branch)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if !unpinned {
+        return Err(api_error(
+            StatusCode::NOT_FOUND,
+            format!("{}/{} is not pinned", project, branch),
+        ));
+    }
+
+    info!("{} unpinned {}/{}", user.name, project, branch);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/projects/{project}/deployments",
+    params(("project" = String, Path,)),
+    responses(
+        (status = OK, description = "Active deployments of the project with their request counts", body = Vec<deployments::Model>),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+    ),
+    tag = "deployments"
+)]
+pub async fn list_deployments(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path(project): Path<String>,
+) -> Result<Json<Vec<deployments::Model>>, ApiError> {
+    user.require_project(&project)?;
+
+    let deployments = config
+        .store
+        .deployments()
+        .list_active_by_project(&project)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(deployments))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TimelineQuery {
+    /// Maximum number of events to return, newest first.
+    pub limit: Option<u64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/projects/{project}/branches/{branch}/events",
+    params(("project" = String, Path,), ("branch" = String, Path,), TimelineQuery),
+    responses(
+        (status = OK, description = "Deployment history for the branch", body = Vec<deployment_events::Model>),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+    ),
+    tag = "deployments"
+)]
+pub async fn branch_timeline(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, branch)): Path<(String, String)>,
+    Query(query): Query<TimelineQuery>,
+) -> Result<Json<Vec<deployment_events::Model>>, ApiError> {
+    user.require_project(&project)?;
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_TIMELINE_LIMIT)
+        .min(MAX_TIMELINE_LIMIT);
+
+    let events = config
+        .store
+        .deployment_events()
+        .list_by_branch(&project, &branch, limit)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(events))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LogsQuery {
+    /// Only entries written at or after this time (RFC 3339).
+    pub since: Option<DateTime<Utc>>,
+    /// Only entries written before this time (RFC 3339).
+    pub until: Option<DateTime<Utc>>,
+    /// Only entries whose message matches this regular expression.
+    pub grep: Option<String>,
+    /// Maximum number of entries to return, newest last.
+    pub lines: Option<usize>,
+    /// Keep the connection open and stream new entries as server-sent events.
+    #[serde(default)]
+    pub follow: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LogLine {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub message: String,
+}
+
+impl From<LogEntry> for LogLine {
+    fn from(entry: LogEntry) -> Self {
+        Self {
+            timestamp: entry.timestamp,
+            message: entry.message,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/deployments/{id}/logs",
+    params(("id" = i32, Path,), LogsQuery),
+    responses(
+        (status = OK, description = "Service output; a text/event-stream of LogLine events when following", body = Vec<LogLine>),
+        (status = BAD_REQUEST, description = "Deployment has no service unit, or follow was combined with until"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+        (status = NOT_FOUND, description = "Deployment not found"),
+    ),
+    tag = "deployments"
+)]
+pub async fn deployment_logs(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Query(query): Query<LogsQuery>,
+) -> Result<Response, ApiError> {
+    let deployment = config
+        .store
+        .deployments()
+        .find_by_id(id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!("Deployment {} not found", id),
+            )
+        })?;
+
+    user.require_project(&deployment.project_name)?;
+
+    // Static sites are served by the router and have no unit to read
+    if deployment.service_type == ServiceType::Static {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            format!("Deployment {} has no service logs", id),
+        ));
+    }
+
+    if query.follow && query.until.is_some() {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "until cannot be used with follow",
+        ));
+    }
+
+    let unit_name = kennel_deployer::service_unit_name(&deployment);
+    let log_query = LogQuery {
+        since: query.since,
+        until: query.until,
+        grep: query.grep,
+        lines: query.lines.unwrap_or(DEFAULT_LOG_LINES).min(MAX_LOG_LINES),
+    };
+
+    if !query.follow {
+        let entries = config
+            .runtime
+            .logs(&unit_name, &log_query)
+            .await
+            .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        return Ok(
+            Json(entries.into_iter().map(LogLine::from).collect::<Vec<_>>()).into_response(),
+        );
+    }
+
+    info!("{} is following logs of deployment {}", user.name, id);
+
+    let entries = config
+        .runtime
+        .follow_logs(&unit_name, &log_query)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let events =
+        ReceiverStream::new(entries).map(|entry| Event::default().json_data(LogLine::from(entry)));
+
+    Ok(Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
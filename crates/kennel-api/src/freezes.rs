@@ -0,0 +1,360 @@
+use crate::auth::AuthUser;
+use crate::{ApiConfig, ApiError, api_error};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use entity::{builds, freeze_windows, projects};
+use kennel_config::{EnvironmentKind, Recurrence, environment_kind};
+use kennel_deployer::{DeploymentRequest, DeploymentTrigger, active_freeze, freeze_reason};
+use kennel_store::{project_environment, project_environment_kind};
+use sea_orm::ActiveValue::Set;
+use serde::Deserialize;
+use tracing::info;
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateFreezeRequest {
+    /// Shown on held builds and refused deployments, e.g. "Finals week".
+    pub reason: String,
+    /// Environment to freeze; every environment but previews when omitted.
+    pub environment: Option<String>,
+    /// Start of a one-off freeze, or of the period a recurring freeze applies in.
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    /// Weekly freeze in UTC such as `fri 15:00-08:00`; the days are those the
+    /// window starts on.
+    pub recurrence: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/freezes",
+    responses(
+        (status = OK, description = "Freeze windows applying to every project", body = Vec<freeze_windows::Model>),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+    ),
+    tag = "freezes"
+)]
+pub async fn list_global(
+    State(config): State<ApiConfig>,
+    _user: AuthUser,
+) -> Result<Json<Vec<freeze_windows::Model>>, ApiError> {
+    let windows = config
+        .store
+        .freeze_windows()
+        .list_global()
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(windows))
+}
+
+#[utoipa::path(
+    post,
+    path = "/freezes",
+    request_body = CreateFreezeRequest,
+    responses(
+        (status = CREATED, description = "Freeze window created for every project", body = freeze_windows::Model),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Not an admin token"),
+        (status = BAD_REQUEST, description = "Invalid window"),
+    ),
+    tag = "freezes"
+)]
+pub async fn create_global(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Json(body): Json<CreateFreezeRequest>,
+) -> Result<(StatusCode, Json<freeze_windows::Model>), ApiError> {
+    user.require_admin()?;
+
+    create(&config, &user, None, body).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/projects/{project}/freezes",
+    params(("project" = String, Path,)),
+    responses(
+        (status = OK, description = "The project's freeze windows followed by global ones", body = Vec<freeze_windows::Model>),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+    ),
+    tag = "freezes"
+)]
+pub async fn list_project(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path(project): Path<String>,
+) -> Result<Json<Vec<freeze_windows::Model>>, ApiError> {
+    user.require_project(&project)?;
+
+    let windows = config
+        .store
+        .freeze_windows()
+        .list_applicable(&project)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(windows))
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects/{project}/freezes",
+    params(("project" = String, Path,)),
+    request_body = CreateFreezeRequest,
+    responses(
+        (status = CREATED, description = "Freeze window created", body = freeze_windows::Model),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot access this project"),
+        (status = NOT_FOUND, description = "Project not found"),
+        (status = BAD_REQUEST, description = "Invalid window"),
+    ),
+    tag = "freezes"
+)]
+pub async fn create_project(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path(project): Path<String>,
+    Json(body): Json<CreateFreezeRequest>,
+) -> Result<(StatusCode, Json<freeze_windows::Model>), ApiError> {
+    user.require_project(&project)?;
+
+    let project = config
+        .store
+        .projects()
+        .find_by_name(&project)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!("Project {} not found", project),
+            )
+        })?;
+
+    create(&config, &user, Some(project), body).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/freezes/{id}",
+    params(("id" = i32, Path,)),
+    responses(
+        (status = NO_CONTENT, description = "Freeze window removed"),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Token cannot manage this window"),
+        (status = NOT_FOUND, description = "Freeze window not found"),
+    ),
+    tag = "freezes"
+)]
+pub async fn delete_freeze(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    let window = config
+        .store
+        .freeze_windows()
+        .find_by_id(id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!("Freeze window {} not found", id),
+            )
+        })?;
+
+    match &window.project_name {
+        Some(project) => user.require_project(project)?,
+        None => user.require_admin()?,
+    }
+
+    config
+        .store
+        .freeze_windows()
+        .delete(id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    info!("{} removed freeze window {}", user.name, id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects/{project}/builds/{build_id}/override-freeze",
+    params(("project" = String, Path,), ("build_id" = i32, Path,)),
+    responses(
+        (status = ACCEPTED, description = "Held build released and its deployment queued", body = builds::Model),
+        (status = UNAUTHORIZED, description = "Missing or invalid token"),
+        (status = FORBIDDEN, description = "Not an admin token"),
+        (status = NOT_FOUND, description = "Build not found"),
+        (status = CONFLICT, description = "Build is not held by a freeze"),
+    ),
+    tag = "freezes"
+)]
+pub async fn override_freeze(
+    State(config): State<ApiConfig>,
+    user: AuthUser,
+    Path((project, build_id)): Path<(String, i32)>,
+) -> Result<(StatusCode, Json<builds::Model>), ApiError> {
+    user.require_admin()?;
+
+    config
+        .store
+        .builds()
+        .find_by_id(build_id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .filter(|b| b.project_name == project)
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::NOT_FOUND,
+                format!("Build {} not found for {}", build_id, project),
+            )
+        })?;
+
+    let build = config
+        .store
+        .builds()
+        .release_hold(build_id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            api_error(
+                StatusCode::CONFLICT,
+                format!("Build {} is not held by a deploy freeze", build_id),
+            )
+        })?;
+
+    info!(
+        "{} overrode the deploy freeze for build {} of {}/{}",
+        user.name, build.id, build.project_name, build.git_ref
+    );
+
+    config
+        .deploy_tx
+        .send(DeploymentRequest {
+            build_id: build.id,
+            project_name: build.project_name.clone(),
+            git_ref: build.git_ref.clone(),
+            services: Vec::new(),
+            trigger: DeploymentTrigger::OverrideFreeze { actor: user.name },
+        })
+        .await
+        .map_err(|e| api_error(StatusCode::SERVICE_UNAVAILABLE, e))?;
+
+    Ok((StatusCode::ACCEPTED, Json(build)))
+}
+
+/// Refuse a manual deployment to `branch` while its environment is frozen.
+pub(crate) async fn ensure_not_frozen(
+    config: &ApiConfig,
+    project: &str,
+    branch: &str,
+) -> Result<(), ApiError> {
+    let Some(project) = config
+        .store
+        .projects()
+        .find_by_name(project)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+    else {
+        return Ok(());
+    };
+
+    let environment = project_environment(&project, branch);
+
+    match active_freeze(&config.store, &project, &environment)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?
+    {
+        Some(window) => Err(api_error(
+            StatusCode::LOCKED,
+            freeze_reason(&window, &environment),
+        )),
+        None => Ok(()),
+    }
+}
+
+async fn create(
+    config: &ApiConfig,
+    user: &AuthUser,
+    project: Option<projects::Model>,
+    body: CreateFreezeRequest,
+) -> Result<(StatusCode, Json<freeze_windows::Model>), ApiError> {
+    if body.reason.trim().is_empty() {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "A freeze needs a reason",
+        ));
+    }
+
+    if let Some(environment) = &body.environment {
+        let kind = match &project {
+            Some(project) => project_environment_kind(project, environment),
+            None => environment_kind(&[], environment),
+        };
+        if kind == EnvironmentKind::Preview {
+            return Err(api_error(
+                StatusCode::BAD_REQUEST,
+                "Previews cannot be frozen",
+            ));
+        }
+    }
+
+    if body.starts_at.is_none() && body.ends_at.is_none() && body.recurrence.is_none() {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "A freeze needs a start, an end or a recurrence",
+        ));
+    }
+
+    if let (Some(starts_at), Some(ends_at)) = (body.starts_at, body.ends_at)
+        && ends_at <= starts_at
+    {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "A freeze must end after it starts",
+        ));
+    }
+
+    if let Some(recurrence) = &body.recurrence {
+        recurrence
+            .parse::<Recurrence>()
+            .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    }
+
+    let window = config
+        .store
+        .freeze_windows()
+        .create(freeze_windows::ActiveModel {
+            project_name: Set(project.map(|p| p.name)),
+            environment: Set(body.environment),
+            reason: Set(body.reason),
+            starts_at: Set(body.starts_at.map(|t| t.naive_utc())),
+            ends_at: Set(body.ends_at.map(|t| t.naive_utc())),
+            recurrence: Set(body.recurrence),
+            created_by: Set(user.name.clone()),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    info!(
+        "{} created freeze window {} for {}: {}",
+        user.name,
+        window.id,
+        window.project_name.as_deref().unwrap_or("all projects"),
+        window.reason
+    );
+
+    Ok((StatusCode::CREATED, Json(window)))
+}
@@ -1,8 +1,18 @@
+mod approvals;
+mod auth;
 mod builds;
+mod deliveries;
+mod deployments;
+mod freezes;
 
-use axum::Router;
+pub use auth::{ApiAuth, AuthUser};
+
+use axum::{Json, Router, extract::FromRef, http::StatusCode};
+use kennel_deployer::{DeploymentRequest, ServiceRuntime};
 use kennel_store::Store;
+use kennel_webhook::WebhookConfig;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 use tower_http::trace::TraceLayer;
 use utoipa::OpenApi;
 use utoipa_axum::router::OpenApiRouter;
@@ -12,11 +22,35 @@ use utoipa_swagger_ui::SwaggerUi;
 #[openapi(
     paths(
         health,
+        builds::get_build,
         builds::cancel_build,
+        approvals::list_pending,
+        approvals::approve,
+        approvals::reject,
+        deployments::list_deployments,
+        deployments::rollback,
+        deployments::restore,
+        deployments::promote,
+        deployments::pin_branch,
+        deployments::unpin_branch,
+        deployments::branch_timeline,
+        deployments::deployment_logs,
+        freezes::list_global,
+        freezes::create_global,
+        freezes::list_project,
+        freezes::create_project,
+        freezes::delete_freeze,
+        freezes::override_freeze,
+        deliveries::list_deliveries,
+        deliveries::replay,
     ),
     tags(
+        (name = "approvals", description = "Deployment approval endpoints"),
         (name = "builds", description = "Build management endpoints"),
+        (name = "deployments", description = "Deployment management endpoints"),
+        (name = "freezes", description = "Deploy freeze endpoints"),
         (name = "health", description = "Health check endpoints"),
+        (name = "webhooks", description = "Webhook delivery log endpoints"),
     ),
     info(
         title = "Kennel API",
@@ -32,14 +66,65 @@ async fn health() -> &'static str {
     "ok"
 }
 
-pub fn router(store: Arc<Store>) -> Router {
+#[derive(Clone)]
+pub struct ApiConfig {
+    pub store: Arc<Store>,
+    pub deploy_tx: mpsc::Sender<DeploymentRequest>,
+    pub auth: Arc<ApiAuth>,
+    pub runtime: Arc<dyn ServiceRuntime>,
+    /// Used to replay recorded webhook deliveries.
+    pub webhooks: WebhookConfig,
+}
+
+impl FromRef<ApiConfig> for Arc<Store> {
+    fn from_ref(config: &ApiConfig) -> Self {
+        config.store.clone()
+    }
+}
+
+pub(crate) type ApiError = (StatusCode, Json<serde_json::Value>);
+
+pub(crate) fn api_error(status: StatusCode, message: impl std::fmt::Display) -> ApiError {
+    (
+        status,
+        Json(serde_json::json!({ "error": message.to_string() })),
+    )
+}
+
+pub fn router(config: ApiConfig) -> Router {
     let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
         .routes(utoipa_axum::routes!(health))
+        .routes(utoipa_axum::routes!(builds::get_build))
         .routes(utoipa_axum::routes!(builds::cancel_build))
+        .routes(utoipa_axum::routes!(approvals::list_pending))
+        .routes(utoipa_axum::routes!(approvals::approve))
+        .routes(utoipa_axum::routes!(approvals::reject))
+        .routes(utoipa_axum::routes!(deployments::list_deployments))
+        .routes(utoipa_axum::routes!(deployments::rollback))
+        .routes(utoipa_axum::routes!(deployments::restore))
+        .routes(utoipa_axum::routes!(deployments::promote))
+        .routes(utoipa_axum::routes!(
+            deployments::pin_branch,
+            deployments::unpin_branch
+        ))
+        .routes(utoipa_axum::routes!(deployments::branch_timeline))
+        .routes(utoipa_axum::routes!(deployments::deployment_logs))
+        .routes(utoipa_axum::routes!(
+            freezes::list_global,
+            freezes::create_global
+        ))
+        .routes(utoipa_axum::routes!(
+            freezes::list_project,
+            freezes::create_project
+        ))
+        .routes(utoipa_axum::routes!(freezes::delete_freeze))
+        .routes(utoipa_axum::routes!(freezes::override_freeze))
+        .routes(utoipa_axum::routes!(deliveries::list_deliveries))
+        .routes(utoipa_axum::routes!(deliveries::replay))
         .split_for_parts();
 
     router
         .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", api))
         .layer(TraceLayer::new_for_http())
-        .with_state(store)
+        .with_state(config)
 }
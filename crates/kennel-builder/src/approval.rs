@@ -0,0 +1,63 @@
+use crate::error::Result;
+use chrono::{TimeDelta, Utc};
+use entity::sea_orm_active_enums::ForkPolicy;
+use entity::{builds, projects};
+use kennel_config::ApprovalPolicy;
+use kennel_store::{Store, project_environment};
+use tracing::{info, warn};
+
+/// The project's `require_approval` policy from projects.json.
+pub fn approval_policy(project: &projects::Model) -> ApprovalPolicy {
+    match project.approval_policy.clone().map(serde_json::from_value) {
+        Some(Ok(policy)) => policy,
+        Some(Err(e)) => {
+            warn!(
+                "Invalid approval policy for project {}: {}",
+                project.name, e
+            );
+            ApprovalPolicy::default()
+        }
+        None => ApprovalPolicy::default(),
+    }
+}
+
+/// Hold a successful build for approval if its ref deploys to an environment
+/// the project gates, or it comes from a fork the project wants reviewed.
+/// Returns whether the build is now waiting.
+pub(crate) async fn hold_if_gated(store: &Store, build: &builds::Model) -> Result<bool> {
+    let Some(project) = store.projects().find_by_name(&build.project_name).await? else {
+        return Ok(false);
+    };
+
+    let environment = project_environment(&project, &build.git_ref);
+    let policy = approval_policy(&project);
+    let fork_review = build.pr_from_fork && project.fork_policy == ForkPolicy::Approve;
+    if !policy.requires(&environment) && !fork_review {
+        return Ok(false);
+    }
+
+    let expires_at = Utc::now().naive_utc() + TimeDelta::hours(i64::from(policy.expiry_hours()));
+    store
+        .builds()
+        .hold_for_approval(build.clone(), expires_at)
+        .await?;
+
+    info!(
+        "Build {} of {}/{} is waiting for approval to deploy to {}",
+        build.id, build.project_name, build.git_ref, environment
+    );
+
+    Ok(true)
+}
+
+/// Whether a successful build may deploy without review. Builds of pull
+/// requests from forks only deploy once approved, so their untrusted code
+/// never runs outside the build sandbox unless someone looked at it.
+pub(crate) async fn may_deploy(store: &Store, build: &builds::Model) -> Result<bool> {
+    if !build.pr_from_fork {
+        return Ok(true);
+    }
+
+    let project = store.projects().find_by_name(&build.project_name).await?;
+    Ok(project.is_some_and(|p| p.fork_policy == ForkPolicy::Approve))
+}
@@ -1,11 +1,14 @@
+mod approval;
 mod cachix;
 mod error;
 mod git;
 mod nix;
 mod worker;
 
+pub use approval::approval_policy;
 pub use error::{BuilderError, Result};
 
+use kennel_forge::StatusReporter;
 use kennel_store::Store;
 use std::sync::Arc;
 use tokio::sync::{Semaphore, mpsc};
@@ -17,6 +20,8 @@ pub struct BuilderConfig {
     pub deploy_tx: mpsc::Sender<DeploymentRequest>,
     pub max_concurrent_builds: usize,
     pub work_dir: String,
+    /// Posts build progress to the project's forge, when configured.
+    pub forge: Option<Arc<StatusReporter>>,
 }
 
 #[derive(Debug, Clone)]
@@ -24,6 +29,28 @@ pub struct DeploymentRequest {
     pub build_id: i32,
     pub project_name: String,
     pub git_ref: String,
+    /// Limit the deployment to these services; empty deploys everything the build produced.
+    pub services: Vec<String>,
+    pub trigger: DeploymentTrigger,
+}
+
+/// Why a deployment was requested, recorded in deployment history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeploymentTrigger {
+    /// The build for this ref just finished.
+    Build,
+    /// An earlier build is being redeployed.
+    Rollback { actor: String },
+    /// The last build of an expired branch is being redeployed.
+    Restore { actor: String },
+    /// The build live on another branch is being deployed here unchanged.
+    Promote {
+        actor: String,
+        from_branch: String,
+        from_environment: String,
+    },
+    /// A build held by a deploy freeze is being deployed anyway.
+    OverrideFreeze { actor: String },
 }
 
 pub async fn run_worker_pool(mut build_rx: mpsc::Receiver<i32>, config: BuilderConfig) {
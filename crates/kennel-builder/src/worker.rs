@@ -1,8 +1,9 @@
 use crate::error::Result;
-use crate::{BuilderConfig, cachix, git, nix};
-use entity::build_results;
+use crate::{BuilderConfig, approval, cachix, git, nix};
 use entity::sea_orm_active_enums::{BuildResultStatus, BuildStatus};
+use entity::{build_results, builds};
 use kennel_config::parse_kennel_toml;
+use kennel_forge::{StatusState, build_context, service_build_context};
 use kennel_store::Store;
 use sea_orm::{ActiveValue::Set, IntoActiveModel};
 use std::path::{Path, PathBuf};
@@ -16,9 +17,26 @@ pub async fn process_build(build_id: i32, config: Arc<BuilderConfig>) -> Result<
 
     if check_cancelled(&config.store, build_id).await? {
         info!("Build {} cancelled before starting", build_id);
+        report(
+            &config,
+            &build,
+            build_context(),
+            StatusState::Error,
+            "Build cancelled",
+        )
+        .await;
         return Ok(());
     }
 
+    report(
+        &config,
+        &build,
+        build_context(),
+        StatusState::Pending,
+        "Building",
+    )
+    .await;
+
     let (project_name, git_ref, work_dir) =
         setup_build_environment(&config, &build, build_id).await?;
 
@@ -26,6 +44,14 @@ pub async fn process_build(build_id: i32, config: Arc<BuilderConfig>) -> Result<
 
     if check_cancelled(&config.store, build_id).await? {
         info!("Build {} cancelled after clone", build_id);
+        report(
+            &config,
+            &build,
+            build_context(),
+            StatusState::Error,
+            "Build cancelled",
+        )
+        .await;
         return Ok(());
     }
 
@@ -58,6 +84,28 @@ pub async fn process_build(build_id: i32, config: Arc<BuilderConfig>) -> Result<
     .await
 }
 
+/// Post a commit status for the build.
+async fn report(
+    config: &BuilderConfig,
+    build: &builds::Model,
+    context: String,
+    state: StatusState,
+    description: &str,
+) {
+    if let Some(forge) = &config.forge {
+        forge.report(build, context, state, description, None).await;
+    }
+}
+
+/// Refresh the preview comment of a pull request build.
+async fn update_preview_comment(config: &BuilderConfig, build: &builds::Model) {
+    if let Some(forge) = &config.forge {
+        forge
+            .update_preview_comment(&build.project_name, &build.git_ref)
+            .await;
+    }
+}
+
 async fn record_failed_build_result(
     store: &Store,
     build_id: i32,
@@ -92,22 +140,26 @@ async fn check_cancelled(store: &Store, build_id: i32) -> Result<bool> {
         .unwrap_or(false))
 }
 
-async fn mark_build_failed(store: &Store, build_id: i32, error: &str) -> Result<()> {
+async fn mark_build_failed(config: &BuilderConfig, build_id: i32, error: &str) -> Result<()> {
+    let store = &config.store;
     let build = store
         .builds()
         .find_by_id(build_id)
         .await?
         .ok_or_else(|| anyhow::anyhow!("Build {} not found", build_id))?;
 
+    report(config, &build, build_context(), StatusState::Failure, error).await;
+
     let mut build_active = build.into_active_model();
     build_active.status = Set(BuildStatus::Failed);
     build_active.finished_at = Set(Some(chrono::Utc::now().naive_utc()));
 
-    store
+    let build = store
         .builds()
         .update(build_active)
         .await
         .map_err(|e| anyhow::anyhow!(e))?;
+    update_preview_comment(config, &build).await;
 
     warn!("Build {} marked as failed: {}", build_id, error);
     Ok(())
@@ -170,7 +222,7 @@ async fn clone_and_parse_config(
     info!("Cloning repository for build {}", build_id);
     if let Err(e) = git::clone(&project.repo_url, &build.commit_sha, work_dir).await {
         error!("Git clone failed for build {}: {}", build_id, e);
-        mark_build_failed(&config.store, build_id, &e.to_string()).await?;
+        mark_build_failed(config, build_id, &e.to_string()).await?;
         return Err(e);
     }
 
@@ -178,22 +230,48 @@ async fn clone_and_parse_config(
         crate::BuilderError::Other(anyhow::anyhow!("Failed to parse kennel.toml: {}", e))
     })?;
 
-    if kennel_config.services.is_empty() && kennel_config.static_sites.is_empty() {
+    if kennel_config.services.is_empty()
+        && kennel_config.static_sites.is_empty()
+        && kennel_config.jobs.is_empty()
+    {
         warn!(
-            "No services or static sites defined in kennel.toml for build {}",
+            "No services, static sites or jobs defined in kennel.toml for build {}",
             build_id
         );
         mark_build_failed(
-            &config.store,
+            config,
             build_id,
-            "No services or static sites defined",
+            "No services, static sites or jobs defined",
         )
         .await?;
         return Err(crate::BuilderError::Other(anyhow::anyhow!(
-            "No services or static sites defined"
+            "No services, static sites or jobs defined"
         )));
     }
 
+    // Build results and deployments are keyed by name alone
+    if let Some(name) = kennel_config.jobs.keys().find(|name| {
+        kennel_config.services.contains_key(*name) || kennel_config.static_sites.contains_key(*name)
+    }) {
+        let message = format!(
+            "Job '{}' has the same name as a service or static site",
+            name
+        );
+        mark_build_failed(config, build_id, &message).await?;
+        return Err(crate::BuilderError::Other(anyhow::anyhow!(message)));
+    }
+
+    if let Err(message) = kennel_config.service_order() {
+        mark_build_failed(config, build_id, &message).await?;
+        return Err(crate::BuilderError::Other(anyhow::anyhow!(message)));
+    }
+
+    if let Err(e) = kennel_config.expiry.validate() {
+        let message = format!("Invalid expiry policy: {}", e);
+        mark_build_failed(config, build_id, &message).await?;
+        return Err(crate::BuilderError::Other(anyhow::anyhow!(message)));
+    }
+
     Ok(kennel_config)
 }
 
@@ -255,6 +333,30 @@ async fn build_all_packages(
         }
     }
 
+    for job_name in kennel_config.jobs.keys() {
+        if check_cancelled(&config.store, build_id)
+            .await
+            .unwrap_or(false)
+        {
+            info!("Build {} cancelled during job builds", build_id);
+            return false;
+        }
+
+        if !build_package(
+            config,
+            build,
+            work_dir,
+            job_name,
+            build_id,
+            store_paths,
+            true,
+        )
+        .await
+        {
+            all_succeeded = false;
+        }
+    }
+
     all_succeeded
 }
 
@@ -269,15 +371,27 @@ async fn build_package(
 ) -> bool {
     let package_type = if is_service { "service" } else { "static site" };
 
+    let context = service_build_context(package_name);
+
     if let Err(e) = nix::validate_service_name(package_name) {
         error!(
             "Invalid {} name '{}' for build {}: {}",
             package_type, package_name, build_id, e
         );
         record_failed_build_result(&config.store, build_id, package_name, &e.to_string()).await;
+        report(config, build, context, StatusState::Failure, &e.to_string()).await;
         return false;
     }
 
+    report(
+        config,
+        build,
+        context.clone(),
+        StatusState::Pending,
+        &format!("Building {}", package_type),
+    )
+    .await;
+
     info!(
         "Building {} '{}' for build {}",
         package_type, package_name, build_id
@@ -332,6 +446,13 @@ async fn build_package(
                 error!("Failed to record build result: {}", e);
             }
 
+            let description = if is_unchanged {
+                "Built, unchanged since the last build"
+            } else {
+                "Built"
+            };
+            report(config, build, context, StatusState::Success, description).await;
+
             true
         }
         Err(e) => {
@@ -340,6 +461,7 @@ async fn build_package(
                 package_type, package_name, build_id, e
             );
             record_failed_build_result(&config.store, build_id, package_name, &e.to_string()).await;
+            report(config, build, context, StatusState::Failure, &e.to_string()).await;
             false
         }
     }
@@ -353,17 +475,66 @@ async fn finalize_build(
     project_name: String,
     git_ref: String,
 ) -> Result<()> {
+    if all_succeeded && approval::hold_if_gated(&config.store, &build).await? {
+        report(
+            config,
+            &build,
+            build_context(),
+            StatusState::Success,
+            "Built, waiting for approval to deploy",
+        )
+        .await;
+        update_preview_comment(config, &build).await;
+        return Ok(());
+    }
+
+    let deploy = all_succeeded && approval::may_deploy(&config.store, &build).await?;
+
+    if all_succeeded {
+        let description = if deploy {
+            "Built"
+        } else {
+            "Built; pull requests from forks are not deployed"
+        };
+        report(
+            config,
+            &build,
+            build_context(),
+            StatusState::Success,
+            description,
+        )
+        .await;
+    } else {
+        report(
+            config,
+            &build,
+            build_context(),
+            StatusState::Failure,
+            "One or more packages failed to build",
+        )
+        .await;
+    }
+
     let mut build_active = build.into_active_model();
 
     if all_succeeded {
         build_active.status = Set(BuildStatus::Success);
         build_active.finished_at = Set(Some(chrono::Utc::now().naive_utc()));
-        config
+        let build = config
             .store
             .builds()
             .update(build_active)
             .await
             .map_err(|e| anyhow::anyhow!(e))?;
+        update_preview_comment(config, &build).await;
+
+        if !deploy {
+            info!(
+                "Build {} of {}/{} is from a fork and will not be deployed",
+                build_id, project_name, git_ref
+            );
+            return Ok(());
+        }
 
         if let Err(e) = config
             .deploy_tx
@@ -371,6 +542,8 @@ async fn finalize_build(
                 build_id,
                 project_name,
                 git_ref,
+                services: Vec::new(),
+                trigger: crate::DeploymentTrigger::Build,
             })
             .await
         {
@@ -381,12 +554,13 @@ async fn finalize_build(
     } else {
         build_active.status = Set(BuildStatus::Failed);
         build_active.finished_at = Set(Some(chrono::Utc::now().naive_utc()));
-        config
+        let build = config
             .store
             .builds()
             .update(build_active)
             .await
             .map_err(|e| anyhow::anyhow!(e))?;
+        update_preview_comment(config, &build).await;
 
         Err(crate::BuilderError::Other(anyhow::anyhow!(
             "One or more builds failed"
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+/// Environments whose deployments wait for someone to approve the build. Set
+/// per project under `require_approval` in projects.json.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalPolicy {
+    /// Environments such as `prod` that builds wait for approval to reach.
+    #[serde(default)]
+    pub environments: Vec<String>,
+    /// API token names allowed to approve or reject; any token with access
+    /// to the project when empty.
+    #[serde(default)]
+    pub approvers: Vec<String>,
+    /// Hours a build waits for review before it expires.
+    pub expiry_hours: Option<u32>,
+}
+
+impl ApprovalPolicy {
+    pub fn requires(&self, environment: &str) -> bool {
+        self.environments.iter().any(|e| e == environment)
+    }
+
+    pub fn can_review(&self, name: &str) -> bool {
+        self.approvers.is_empty() || self.approvers.iter().any(|a| a == name)
+    }
+
+    pub fn expiry_hours(&self) -> u32 {
+        self.expiry_hours
+            .unwrap_or(crate::constants::DEFAULT_APPROVAL_EXPIRY_HOURS)
+    }
+
+    /// Reject zero-hour expiry, which would expire builds before anyone could
+    /// review them.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.expiry_hours == Some(0) {
+            return Err("approval expiry must be at least one hour".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &ApprovalPolicy::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requires_listed_environments() {
+        let policy = ApprovalPolicy {
+            environments: vec!["prod".to_string()],
+            ..Default::default()
+        };
+
+        assert!(policy.requires("prod"));
+        assert!(!policy.requires("staging"));
+        assert!(!ApprovalPolicy::default().requires("prod"));
+    }
+
+    #[test]
+    fn test_approvers() {
+        assert!(ApprovalPolicy::default().can_review("anyone"));
+
+        let policy = ApprovalPolicy {
+            approvers: vec!["release-manager".to_string()],
+            ..Default::default()
+        };
+        assert!(policy.can_review("release-manager"));
+        assert!(!policy.can_review("ci"));
+    }
+
+    #[test]
+    fn test_expiry() {
+        assert_eq!(
+            ApprovalPolicy::default().expiry_hours(),
+            crate::constants::DEFAULT_APPROVAL_EXPIRY_HOURS
+        );
+
+        let policy = ApprovalPolicy {
+            expiry_hours: Some(0),
+            ..Default::default()
+        };
+        assert!(policy.validate().is_err());
+    }
+}
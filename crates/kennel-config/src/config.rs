@@ -1,5 +1,6 @@
+use crate::{ExpiryPolicy, ResourceLimits};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -10,12 +11,96 @@ pub struct KennelConfig {
     #[serde(default)]
     pub static_sites: HashMap<String, StaticSiteConfig>,
 
+    #[serde(default)]
+    pub jobs: HashMap<String, JobConfig>,
+
     #[serde(default)]
     pub cachix: Option<CachixConfig>,
+
+    /// Overrides the project's expiry policy for deployments of this
+    /// repository.
+    #[serde(default)]
+    pub expiry: ExpiryPolicy,
+}
+
+impl KennelConfig {
+    /// Reject a name declared more than once across services, static sites
+    /// and jobs; each name maps to a single service of the project.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen = HashMap::new();
+        let names = self
+            .services
+            .keys()
+            .map(|name| (name, "services"))
+            .chain(self.static_sites.keys().map(|name| (name, "static_sites")))
+            .chain(self.jobs.keys().map(|name| (name, "jobs")));
+
+        for (name, section) in names {
+            if let Some(first) = seen.insert(name, section) {
+                return Err(format!(
+                    "'{}' is declared in both [{}] and [{}]",
+                    name, first, section
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Service names ordered so that every service comes after the services
+    /// it depends on. Services without dependencies between them are ordered
+    /// by name. Fails on unknown dependencies and cycles.
+    pub fn service_order(&self) -> Result<Vec<String>, String> {
+        let mut remaining: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+        for (name, service) in &self.services {
+            for dependency in &service.depends_on {
+                if !self.services.contains_key(dependency) {
+                    return Err(format!(
+                        "Service '{}' depends on unknown service '{}'",
+                        name, dependency
+                    ));
+                }
+            }
+
+            remaining.insert(
+                name,
+                service.depends_on.iter().map(String::as_str).collect(),
+            );
+        }
+
+        let mut order = Vec::with_capacity(remaining.len());
+
+        while !remaining.is_empty() {
+            let ready: Vec<&str> = remaining
+                .iter()
+                .filter(|(_, dependencies)| dependencies.iter().all(|d| !remaining.contains_key(d)))
+                .map(|(name, _)| *name)
+                .collect();
+
+            if ready.is_empty() {
+                let cycle: Vec<&str> = remaining.keys().copied().collect();
+                return Err(format!(
+                    "Dependency cycle between services: {}",
+                    cycle.join(", ")
+                ));
+            }
+
+            for name in ready {
+                remaining.remove(name);
+                order.push(name.to_string());
+            }
+        }
+
+        Ok(order)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServiceConfig {
+    #[serde(default)]
+    pub kind: ServiceKind,
+
     pub flake_output: Option<String>,
 
     #[serde(default = "default_health_check_path")]
@@ -35,6 +120,93 @@ pub struct ServiceConfig {
 
     #[serde(default)]
     pub secrets: Vec<String>,
+
+    #[serde(default)]
+    pub remediation: RemediationConfig,
+
+    /// How long a replaced deployment may keep serving in-flight requests
+    /// before it is stopped.
+    #[serde(default = "default_drain_timeout")]
+    pub drain_timeout_secs: u64,
+
+    #[serde(default)]
+    pub resources: ResourceLimits,
+
+    /// Workers only: how long the unit must stay active after starting to
+    /// count as healthy.
+    #[serde(default = "default_grace_period")]
+    pub grace_period_secs: u64,
+
+    /// Shell command run once before each deployment starts, such as
+    /// database migrations. Failing it aborts the deployment.
+    #[serde(default)]
+    pub release_command: Option<String>,
+
+    #[serde(default = "default_release_timeout")]
+    pub release_timeout_secs: u64,
+
+    /// Services on the same branch that must be deployed before this one.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    /// Instances to run, each HTTP replica on its own port.
+    #[serde(default = "default_replicas")]
+    pub replicas: u32,
+
+    /// How the router spreads requests across replicas.
+    #[serde(default)]
+    pub load_balancing: LoadBalancing,
+
+    /// Stop non-production deployments after this long without a request.
+    /// The router starts them again on the next request.
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceKind {
+    /// Serves HTTP on `$PORT` behind the router.
+    #[default]
+    Http,
+    /// Long-running process without a port, such as a queue consumer.
+    Worker,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalancing {
+    /// Take turns between replicas.
+    #[default]
+    RoundRobin,
+    /// Send each request to the replica with the fewest requests in flight.
+    LeastConnections,
+}
+
+/// What the deployer does when a live deployment keeps failing health checks.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RemediationConfig {
+    /// Consecutive failed health checks before the deployer acts.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// Unit restarts to attempt before giving up on the deployment.
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+
+    /// Roll back to the previous successful build once restarts are exhausted.
+    #[serde(default = "default_true")]
+    pub rollback: bool,
+}
+
+impl Default for RemediationConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_failure_threshold(),
+            max_restarts: default_max_restarts(),
+            rollback: true,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -48,6 +220,28 @@ pub struct StaticSiteConfig {
     pub custom_domain: Option<String>,
 }
 
+/// A command run on a schedule by a systemd timer.
+#[derive(Debug, Deserialize, Clone)]
+pub struct JobConfig {
+    pub flake_output: Option<String>,
+
+    /// systemd `OnCalendar` expression, e.g. `"daily"` or `"*-*-* 03:00:00"`.
+    pub schedule: String,
+
+    /// Only run the job on the project's default branch.
+    #[serde(default)]
+    pub default_branch_only: bool,
+
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    #[serde(default)]
+    pub secrets: Vec<String>,
+
+    #[serde(default)]
+    pub resources: ResourceLimits,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct CachixConfig {
     pub cache_name: String,
@@ -62,6 +256,34 @@ fn default_health_check_timeout() -> u64 {
     30
 }
 
+fn default_drain_timeout() -> u64 {
+    crate::constants::BLUE_GREEN_DRAIN_TIMEOUT.as_secs()
+}
+
+fn default_grace_period() -> u64 {
+    crate::constants::WORKER_GRACE_PERIOD.as_secs()
+}
+
+fn default_release_timeout() -> u64 {
+    crate::constants::RELEASE_COMMAND_TIMEOUT.as_secs()
+}
+
+fn default_replicas() -> u32 {
+    1
+}
+
+fn default_failure_threshold() -> u32 {
+    crate::constants::MAX_CONSECUTIVE_HEALTH_FAILURES
+}
+
+fn default_max_restarts() -> u32 {
+    1
+}
+
+fn default_true() -> bool {
+    true
+}
+
 pub async fn parse_kennel_toml(repo_path: &Path) -> std::io::Result<KennelConfig> {
     let config_path = repo_path.join("kennel.toml");
 
@@ -69,13 +291,18 @@ pub async fn parse_kennel_toml(repo_path: &Path) -> std::io::Result<KennelConfig
         return Ok(KennelConfig {
             services: HashMap::new(),
             static_sites: HashMap::new(),
+            jobs: HashMap::new(),
             cachix: None,
+            expiry: ExpiryPolicy::default(),
         });
     }
 
     let content = tokio::fs::read_to_string(&config_path).await?;
     let config: KennelConfig = toml::from_str(&content)
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    config
+        .validate()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
 
     Ok(config)
 }
@@ -115,6 +342,183 @@ DATABASE_URL = "postgres://localhost/db"
         assert_eq!(api.health_check_timeout_secs, 60);
         assert_eq!(api.env.get("PORT"), Some(&"8080".to_string()));
         assert_eq!(api.secrets.len(), 2);
+        assert_eq!(api.remediation.failure_threshold, 3);
+        assert_eq!(api.remediation.max_restarts, 1);
+        assert!(api.remediation.rollback);
+        assert_eq!(api.drain_timeout_secs, 30);
+        assert!(api.resources.is_empty());
+        assert_eq!(api.kind, ServiceKind::Http);
+        assert!(api.release_command.is_none());
+        assert_eq!(api.release_timeout_secs, 300);
+        assert!(api.depends_on.is_empty());
+        assert_eq!(api.replicas, 1);
+        assert_eq!(api.load_balancing, LoadBalancing::RoundRobin);
+        assert!(api.idle_timeout_secs.is_none());
+    }
+
+    #[test]
+    fn test_parse_replicas_config() {
+        let toml_str = r#"
+[services.api]
+replicas = 3
+load_balancing = "least_connections"
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+
+        let api = config.services.get("api").unwrap();
+        assert_eq!(api.replicas, 3);
+        assert_eq!(api.load_balancing, LoadBalancing::LeastConnections);
+
+        assert!(
+            toml::from_str::<KennelConfig>("[services.api]\nload_balancing = \"random\"\n")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_idle_timeout() {
+        let toml_str = r#"
+[services.api]
+idle_timeout_secs = 900
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.services["api"].idle_timeout_secs, Some(900));
+    }
+
+    #[test]
+    fn test_parse_expiry() {
+        let toml_str = r#"
+[expiry]
+preview_ttl_days = 3
+never_expire = ["release/*"]
+
+[services.api]
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.expiry.preview_ttl_days, Some(3));
+        assert_eq!(config.expiry.branch_ttl_days, None);
+        assert_eq!(config.expiry.never_expire, vec!["release/*"]);
+    }
+
+    #[test]
+    fn test_service_order() {
+        let toml_str = r#"
+[services.web]
+depends_on = ["api", "auth"]
+
+[services.api]
+depends_on = ["auth"]
+
+[services.auth]
+
+[services.admin]
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(
+            config.service_order().unwrap(),
+            vec!["admin", "auth", "api", "web"]
+        );
+    }
+
+    #[test]
+    fn test_service_order_rejects_cycles_and_unknown_services() {
+        let cycle: KennelConfig = toml::from_str(
+            r#"
+[services.a]
+depends_on = ["b"]
+
+[services.b]
+depends_on = ["a"]
+
+[services.c]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cycle.service_order().unwrap_err(),
+            "Dependency cycle between services: a, b"
+        );
+
+        let unknown: KennelConfig =
+            toml::from_str("[services.web]\ndepends_on = [\"db\"]\n").unwrap();
+        assert_eq!(
+            unknown.service_order().unwrap_err(),
+            "Service 'web' depends on unknown service 'db'"
+        );
+    }
+
+    #[test]
+    fn test_parse_release_command() {
+        let toml_str = r#"
+[services.api]
+release_command = "api migrate --yes"
+release_timeout_secs = 60
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+
+        let api = config.services.get("api").unwrap();
+        assert_eq!(api.release_command.as_deref(), Some("api migrate --yes"));
+        assert_eq!(api.release_timeout_secs, 60);
+    }
+
+    #[test]
+    fn test_parse_worker_config() {
+        let toml_str = r#"
+[services.queue]
+kind = "worker"
+grace_period_secs = 5
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+
+        let queue = config.services.get("queue").unwrap();
+        assert_eq!(queue.kind, ServiceKind::Worker);
+        assert_eq!(queue.grace_period_secs, 5);
+
+        assert!(toml::from_str::<KennelConfig>("[services.queue]\nkind = \"cron\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_resources_config() {
+        let toml_str = r#"
+[services.api.resources]
+memory_max = "512M"
+cpu_quota = "50%"
+tasks_max = 128
+io_weight = 200
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+
+        let resources = &config.services.get("api").unwrap().resources;
+        assert_eq!(resources.memory_max.as_deref(), Some("512M"));
+        assert_eq!(resources.cpu_quota.as_deref(), Some("50%"));
+        assert_eq!(resources.tasks_max, Some(128));
+        assert_eq!(resources.io_weight, Some(200));
+    }
+
+    #[test]
+    fn test_parse_remediation_config() {
+        let toml_str = r#"
+[services.api]
+flake_output = "api"
+drain_timeout_secs = 120
+
+[services.api.remediation]
+failure_threshold = 5
+max_restarts = 2
+rollback = false
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+
+        let api = config.services.get("api").unwrap();
+        assert_eq!(api.drain_timeout_secs, 120);
+
+        let remediation = &api.remediation;
+        assert_eq!(remediation.failure_threshold, 5);
+        assert_eq!(remediation.max_restarts, 2);
+        assert!(!remediation.rollback);
     }
 
     #[test]
@@ -138,6 +542,59 @@ spa = true
         assert!(web.spa);
     }
 
+    #[test]
+    fn test_parse_job_config() {
+        let toml_str = r#"
+[jobs.cleanup]
+schedule = "*-*-* 03:00:00"
+default_branch_only = true
+secrets = ["S3_TOKEN"]
+
+[jobs.cleanup.env]
+RETENTION_DAYS = "30"
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+
+        let cleanup = config.jobs.get("cleanup").unwrap();
+        assert_eq!(cleanup.schedule, "*-*-* 03:00:00");
+        assert!(cleanup.default_branch_only);
+        assert_eq!(cleanup.env.get("RETENTION_DAYS"), Some(&"30".to_string()));
+        assert_eq!(cleanup.secrets, vec!["S3_TOKEN".to_string()]);
+
+        assert!(toml::from_str::<KennelConfig>("[jobs.cleanup]\n").is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_duplicate_names() {
+        let toml_str = r#"
+[services.api]
+
+[static_sites.docs]
+
+[jobs.cleanup]
+schedule = "daily"
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.validate().is_ok());
+
+        let toml_str = r#"
+[services.api]
+
+[jobs.api]
+schedule = "daily"
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.validate().is_err());
+
+        let toml_str = r#"
+[services.docs]
+
+[static_sites.docs]
+"#;
+        let config: KennelConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_parse_cachix_config() {
         let toml_str = r#"
@@ -6,6 +6,7 @@ pub const DEFAULT_API_PORT: &str = "3000";
 pub const DEFAULT_BASE_DOMAIN: &str = "scottylabs.org";
 
 pub const DEFAULT_MAX_CONCURRENT_BUILDS: usize = 2;
+pub const DEFAULT_MAX_CONCURRENT_DEPLOYS: usize = 4;
 pub const DEFAULT_WORK_DIR: &str = "/var/lib/kennel/builds";
 
 pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(300);
@@ -15,25 +16,60 @@ pub const PORT_RANGE_END: u16 = 19999;
 
 pub const SITES_BASE_DIR: &str = "/var/lib/kennel/sites";
 pub const SECRETS_DIR: &str = "/run/kennel/secrets";
+/// Secret values, one file per name under `<project>/<environment>/`.
+pub const SECRET_SOURCES_DIR: &str = "/var/lib/kennel/secrets";
 pub const LOGS_DIR: &str = "/var/lib/kennel/logs";
-pub const SERVICES_BASE_DIR: &str = "/var/lib/kennel/services";
+/// Service state directories, relative to the systemd state root (`/var/lib`).
+pub const SERVICES_STATE_DIR: &str = "kennel/services";
 pub const SYSTEMD_UNIT_DIR: &str = "/etc/systemd/system";
 pub const ACME_CACHE_DIR: &str = "/var/lib/kennel/acme";
 pub const PROJECTS_CONFIG_PATH: &str = "/etc/kennel/projects.json";
+pub const API_TOKENS_CONFIG_PATH: &str = "/etc/kennel/api-tokens.json";
+pub const RESOURCES_CONFIG_PATH: &str = "/etc/kennel/resources.json";
 
 pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 pub const MAX_CONSECUTIVE_HEALTH_FAILURES: u32 = 3;
+/// How long a restarted deployment must stay healthy before its restart
+/// count is forgotten.
+pub const REMEDIATION_COOLDOWN: Duration = Duration::from_secs(600);
+/// Commit status calls are best effort, so a slow forge never stalls a build.
+pub const FORGE_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub const ROUTER_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+/// How often the router writes request counts and times to the store.
+pub const TRAFFIC_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
 
 pub const CLEANUP_JOB_INTERVAL: Duration = Duration::from_secs(600);
+pub const DEFAULT_EXPIRY_DAYS: u32 = 7;
+pub const DEFAULT_EXPIRY_WARNING_HOURS: u32 = 24;
+/// How long a build waits for approval before it expires.
+pub const DEFAULT_APPROVAL_EXPIRY_HOURS: u32 = 72;
 pub const LOG_CLEANUP_INTERVAL: Duration = Duration::from_secs(86400);
 pub const LOG_RETENTION_DAYS: i64 = 30;
+pub const DEPLOYMENT_RETENTION_DAYS: i64 = 30;
+pub const DEPLOYMENT_EVENT_RETENTION_DAYS: i64 = 90;
+pub const WEBHOOK_DELIVERY_RETENTION_DAYS: i64 = 30;
 
 pub const BUILD_CHANNEL_CAPACITY: usize = 1000;
 pub const DEPLOY_CHANNEL_CAPACITY: usize = 100;
 pub const TEARDOWN_CHANNEL_CAPACITY: usize = 100;
 pub const ROUTER_UPDATE_CHANNEL_CAPACITY: usize = 100;
+pub const HEALTH_REPORT_CHANNEL_CAPACITY: usize = 100;
 
 pub const BLUE_GREEN_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+pub const WORKER_GRACE_PERIOD: Duration = Duration::from_secs(10);
+pub const MAX_REPLICAS: u32 = 16;
+pub const RELEASE_COMMAND_TIMEOUT: Duration = Duration::from_secs(300);
+pub const RELEASE_LOG_LINES: usize = 500;
+
+pub const JOB_MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often builds held by a deploy freeze are checked for release.
+pub const FREEZE_MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+
+pub const IDLE_MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+/// How long the router holds a request while its idle deployment starts.
+pub const WAKE_TIMEOUT: Duration = Duration::from_secs(60);
+pub const WAKE_CHANNEL_CAPACITY: usize = 100;
+pub const JOB_RUN_LOG_LINES: usize = 500;
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+
+/// Sends deployments of branches matching `branch` to `environment`. Set per
+/// project in projects.json; the first matching rule wins.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct EnvironmentRule {
+    /// Glob pattern such as `release/*`. Pull requests are matched as
+    /// `pr-<number>`.
+    pub branch: String,
+    pub environment: String,
+    /// What the environment is for, when its name does not say so.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<EnvironmentKind>,
+}
+
+/// What an environment is for. Production deployments never idle, and
+/// previews are never frozen.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvironmentKind {
+    Production,
+    Staging,
+    Development,
+    Preview,
+}
+
+/// The environment deployments of `git_ref` run in: the first of `rules`
+/// that matches, otherwise `prod` for the default branch, `staging` for
+/// `staging`, `preview` for pull requests and `dev` for anything else.
+pub fn resolve_environment(
+    rules: &[EnvironmentRule],
+    default_branch: &str,
+    git_ref: &str,
+) -> String {
+    if let Some(rule) = rules
+        .iter()
+        .find(|rule| glob::Pattern::new(&rule.branch).is_ok_and(|pattern| pattern.matches(git_ref)))
+    {
+        return rule.environment.clone();
+    }
+
+    match git_ref {
+        _ if git_ref == default_branch => "prod".to_string(),
+        "staging" => "staging".to_string(),
+        s if s.starts_with("pr-") => "preview".to_string(),
+        _ => "dev".to_string(),
+    }
+}
+
+/// The kind of `environment`: the first of `rules` sending branches to it
+/// that names one, otherwise what the default mapping uses the name for, with
+/// `prod` for production, `staging`, `preview` and development for the rest.
+pub fn environment_kind(rules: &[EnvironmentRule], environment: &str) -> EnvironmentKind {
+    if let Some(kind) = rules
+        .iter()
+        .filter(|rule| rule.environment == environment)
+        .find_map(|rule| rule.kind)
+    {
+        return kind;
+    }
+
+    match environment {
+        "prod" => EnvironmentKind::Production,
+        "staging" => EnvironmentKind::Staging,
+        "preview" => EnvironmentKind::Preview,
+        _ => EnvironmentKind::Development,
+    }
+}
+
+/// Reject patterns that cannot match and environment names that are unsafe
+/// in paths and unit names.
+pub fn validate_environment_rules(rules: &[EnvironmentRule]) -> Result<(), String> {
+    for rule in rules {
+        glob::Pattern::new(&rule.branch)
+            .map_err(|e| format!("invalid branch pattern '{}': {}", rule.branch, e))?;
+
+        if rule.environment.is_empty()
+            || !rule
+                .environment
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(format!(
+                "invalid environment '{}': use lowercase letters, digits and '-'",
+                rule.environment
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(branch: &str, environment: &str) -> EnvironmentRule {
+        EnvironmentRule {
+            branch: branch.to_string(),
+            environment: environment.to_string(),
+            kind: None,
+        }
+    }
+
+    #[test]
+    fn test_default_mapping_follows_default_branch() {
+        assert_eq!(resolve_environment(&[], "master", "master"), "prod");
+        assert_eq!(resolve_environment(&[], "master", "main"), "dev");
+        assert_eq!(resolve_environment(&[], "main", "main"), "prod");
+        assert_eq!(resolve_environment(&[], "main", "staging"), "staging");
+        assert_eq!(resolve_environment(&[], "main", "pr-7"), "preview");
+        assert_eq!(resolve_environment(&[], "main", "feature/x"), "dev");
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = vec![
+            rule("release/*", "staging"),
+            rule("pr-*", "review"),
+            rule("*", "sandbox"),
+        ];
+
+        assert_eq!(
+            resolve_environment(&rules, "main", "release/2.0"),
+            "staging"
+        );
+        assert_eq!(resolve_environment(&rules, "main", "pr-12"), "review");
+        assert_eq!(resolve_environment(&rules, "main", "main"), "sandbox");
+    }
+
+    #[test]
+    fn test_environment_kind() {
+        let rules = vec![
+            EnvironmentRule {
+                kind: Some(EnvironmentKind::Preview),
+                ..rule("pr-*", "review")
+            },
+            rule("release/*", "review"),
+            EnvironmentRule {
+                kind: Some(EnvironmentKind::Production),
+                ..rule("main", "live")
+            },
+        ];
+
+        assert_eq!(environment_kind(&rules, "review"), EnvironmentKind::Preview);
+        assert_eq!(
+            environment_kind(&rules, "live"),
+            EnvironmentKind::Production
+        );
+        assert_eq!(
+            environment_kind(&rules, "prod"),
+            EnvironmentKind::Production
+        );
+        assert_eq!(
+            environment_kind(&rules, "staging"),
+            EnvironmentKind::Staging
+        );
+        assert_eq!(environment_kind(&[], "preview"), EnvironmentKind::Preview);
+        assert_eq!(environment_kind(&[], "qa"), EnvironmentKind::Development);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate_environment_rules(&[rule("release/*", "staging")]).is_ok());
+        assert!(validate_environment_rules(&[rule("release/[", "staging")]).is_err());
+        assert!(validate_environment_rules(&[rule("main", "../prod")]).is_err());
+        assert!(validate_environment_rules(&[rule("main", "")]).is_err());
+    }
+}
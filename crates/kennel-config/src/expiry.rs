@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+/// When deployments of a branch are torn down for lack of requests. Set per
+/// project in projects.json and per repository in the `[expiry]` table of
+/// kennel.toml; unset values fall back to the project, then the host default.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ExpiryPolicy {
+    /// Days without requests before a deployment in the `preview`
+    /// environment expires.
+    pub preview_ttl_days: Option<u32>,
+    /// Days without requests before a deployment in any other environment
+    /// expires.
+    pub branch_ttl_days: Option<u32>,
+    /// Branches that never expire, as glob patterns such as `release/*`.
+    /// Pull requests are matched as `pr-<number>`.
+    #[serde(default)]
+    pub never_expire: Vec<String>,
+    /// Hours before expiry to record a warning event for the branch.
+    pub warning_hours: Option<u32>,
+}
+
+impl ExpiryPolicy {
+    /// The built-in policy: a week without requests, with a day's warning.
+    pub fn host_default() -> Self {
+        Self {
+            preview_ttl_days: Some(crate::constants::DEFAULT_EXPIRY_DAYS),
+            branch_ttl_days: Some(crate::constants::DEFAULT_EXPIRY_DAYS),
+            never_expire: Vec::new(),
+            warning_hours: Some(crate::constants::DEFAULT_EXPIRY_WARNING_HOURS),
+        }
+    }
+
+    /// Fill unset values from `defaults`. Never-expire patterns from both
+    /// apply.
+    pub fn or(&self, defaults: &ExpiryPolicy) -> ExpiryPolicy {
+        let mut never_expire = self.never_expire.clone();
+        never_expire.extend(
+            defaults
+                .never_expire
+                .iter()
+                .filter(|p| !self.never_expire.contains(p))
+                .cloned(),
+        );
+
+        ExpiryPolicy {
+            preview_ttl_days: self.preview_ttl_days.or(defaults.preview_ttl_days),
+            branch_ttl_days: self.branch_ttl_days.or(defaults.branch_ttl_days),
+            never_expire,
+            warning_hours: self.warning_hours.or(defaults.warning_hours),
+        }
+    }
+
+    /// Days without requests before deployments of `git_ref` in
+    /// `environment` expire, or `None` if they never do.
+    pub fn ttl_days(&self, git_ref: &str, environment: &str) -> Option<u32> {
+        if self.never_expires(git_ref) {
+            return None;
+        }
+
+        if environment == "preview" {
+            self.preview_ttl_days
+        } else {
+            self.branch_ttl_days
+        }
+    }
+
+    pub fn never_expires(&self, git_ref: &str) -> bool {
+        self.never_expire.iter().any(|pattern| {
+            glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(git_ref))
+        })
+    }
+
+    /// Reject patterns that cannot match and zero-day lifetimes.
+    pub fn validate(&self) -> Result<(), String> {
+        for pattern in &self.never_expire {
+            glob::Pattern::new(pattern)
+                .map_err(|e| format!("invalid never_expire pattern '{}': {}", pattern, e))?;
+        }
+
+        if self.preview_ttl_days == Some(0) || self.branch_ttl_days == Some(0) {
+            return Err("expiry lifetimes must be at least one day".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &ExpiryPolicy::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ttl_by_branch() {
+        let policy = ExpiryPolicy {
+            preview_ttl_days: Some(3),
+            branch_ttl_days: Some(14),
+            never_expire: vec!["main".to_string(), "release/*".to_string()],
+            warning_hours: None,
+        };
+
+        assert_eq!(policy.ttl_days("pr-42", "preview"), Some(3));
+        assert_eq!(policy.ttl_days("feature/login", "dev"), Some(14));
+        assert_eq!(policy.ttl_days("feature/login", "preview"), Some(3));
+        assert_eq!(policy.ttl_days("main", "prod"), None);
+        assert_eq!(policy.ttl_days("release/1.2", "staging"), None);
+        assert_eq!(policy.ttl_days("mainline", "dev"), Some(14));
+    }
+
+    #[test]
+    fn test_repository_policy_overrides_project() {
+        let project = ExpiryPolicy {
+            preview_ttl_days: Some(3),
+            branch_ttl_days: Some(14),
+            never_expire: vec!["main".to_string()],
+            warning_hours: Some(12),
+        };
+        let repository = ExpiryPolicy {
+            preview_ttl_days: Some(1),
+            never_expire: vec!["staging".to_string(), "main".to_string()],
+            ..Default::default()
+        };
+
+        let merged = repository.or(&project).or(&ExpiryPolicy::host_default());
+        assert_eq!(
+            merged,
+            ExpiryPolicy {
+                preview_ttl_days: Some(1),
+                branch_ttl_days: Some(14),
+                never_expire: vec!["staging".to_string(), "main".to_string()],
+                warning_hours: Some(12),
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ExpiryPolicy::host_default().validate().is_ok());
+
+        let bad_pattern = ExpiryPolicy {
+            never_expire: vec!["release/[".to_string()],
+            ..Default::default()
+        };
+        assert!(bad_pattern.validate().is_err());
+
+        let zero_days = ExpiryPolicy {
+            branch_ttl_days: Some(0),
+            ..Default::default()
+        };
+        assert!(zero_days.validate().is_err());
+    }
+}
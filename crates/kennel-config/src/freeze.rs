@@ -0,0 +1,155 @@
+use chrono::{Datelike, NaiveDateTime, NaiveTime, TimeDelta, Weekday};
+use std::str::FromStr;
+
+/// A weekly recurring freeze such as `fri 15:00-00:00` or
+/// `sat,sun 00:00-00:00`, in UTC. The days are those the window starts on; a
+/// window whose end is not after its start runs past midnight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl Recurrence {
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        // A window that started yesterday may still be running
+        [now.date(), now.date() - TimeDelta::days(1)]
+            .into_iter()
+            .filter(|day| self.days.contains(&day.weekday()))
+            .any(|day| {
+                let start = day.and_time(self.start);
+                let end = if self.end > self.start {
+                    day.and_time(self.end)
+                } else {
+                    (day + TimeDelta::days(1)).and_time(self.end)
+                };
+
+                start <= now && now < end
+            })
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (days, times) = s
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| format!("invalid recurrence '{}': expected '<days> HH:MM-HH:MM'", s))?;
+
+        let days = days
+            .split(',')
+            .map(|day| {
+                day.parse::<Weekday>()
+                    .map_err(|_| format!("invalid day '{}' in recurrence '{}'", day, s))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (start, end) = times
+            .trim()
+            .split_once('-')
+            .ok_or_else(|| format!("invalid times in recurrence '{}': expected HH:MM-HH:MM", s))?;
+        let parse_time = |time: &str| {
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|_| format!("invalid time '{}' in recurrence '{}'", time, s))
+        };
+
+        Ok(Self {
+            days,
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        })
+    }
+}
+
+/// Whether a freeze window is in effect at `now`: inside its optional
+/// bounds and, if it recurs, inside one of its weekly windows.
+pub fn freeze_active(
+    starts_at: Option<NaiveDateTime>,
+    ends_at: Option<NaiveDateTime>,
+    recurrence: Option<&Recurrence>,
+    now: NaiveDateTime,
+) -> bool {
+    starts_at.is_none_or(|starts_at| starts_at <= now)
+        && ends_at.is_none_or(|ends_at| now < ends_at)
+        && recurrence.is_none_or(|recurrence| recurrence.contains(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        // 2026-03-09 is a Monday
+        NaiveDate::from_ymd_opt(2026, 3, day)
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    #[test]
+    fn test_parse_recurrence() {
+        let recurrence: Recurrence = "fri,sat 15:00-08:00".parse().unwrap();
+        assert_eq!(recurrence.days, vec![Weekday::Fri, Weekday::Sat]);
+        assert_eq!(recurrence.start, NaiveTime::from_hms_opt(15, 0, 0).unwrap());
+        assert_eq!(recurrence.end, NaiveTime::from_hms_opt(8, 0, 0).unwrap());
+
+        assert!("fri".parse::<Recurrence>().is_err());
+        assert!("someday 15:00-16:00".parse::<Recurrence>().is_err());
+        assert!("fri 15:00".parse::<Recurrence>().is_err());
+        assert!("fri 25:00-26:00".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn test_recurrence_within_a_day() {
+        let recurrence: Recurrence = "mon,wed 09:00-17:00".parse().unwrap();
+
+        assert!(recurrence.contains(at(9, "09:00")));
+        assert!(recurrence.contains(at(11, "16:59")));
+        assert!(!recurrence.contains(at(9, "17:00")));
+        assert!(!recurrence.contains(at(10, "12:00")));
+    }
+
+    #[test]
+    fn test_recurrence_past_midnight() {
+        let recurrence: Recurrence = "fri 15:00-08:00".parse().unwrap();
+
+        assert!(!recurrence.contains(at(13, "14:59")));
+        assert!(recurrence.contains(at(13, "23:00")));
+        assert!(recurrence.contains(at(14, "07:59")));
+        assert!(!recurrence.contains(at(14, "08:00")));
+
+        let whole_day: Recurrence = "sun 00:00-00:00".parse().unwrap();
+        assert!(whole_day.contains(at(15, "00:00")));
+        assert!(whole_day.contains(at(15, "23:59")));
+        assert!(!whole_day.contains(at(16, "00:00")));
+    }
+
+    #[test]
+    fn test_freeze_active() {
+        assert!(freeze_active(
+            Some(at(9, "00:00")),
+            Some(at(14, "00:00")),
+            None,
+            at(11, "12:00")
+        ));
+        assert!(!freeze_active(
+            Some(at(9, "00:00")),
+            Some(at(14, "00:00")),
+            None,
+            at(14, "00:00")
+        ));
+
+        let weekends: Recurrence = "sat,sun 00:00-00:00".parse().unwrap();
+        assert!(freeze_active(None, None, Some(&weekends), at(14, "12:00")));
+        assert!(!freeze_active(None, None, Some(&weekends), at(13, "12:00")));
+        assert!(!freeze_active(
+            None,
+            Some(at(10, "00:00")),
+            Some(&weekends),
+            at(14, "12:00")
+        ));
+    }
+}
@@ -1,4 +1,22 @@
+mod approval;
 mod config;
 pub mod constants;
+mod environments;
+mod expiry;
+mod freeze;
+mod resources;
+mod tags;
 
-pub use config::{CachixConfig, KennelConfig, ServiceConfig, StaticSiteConfig, parse_kennel_toml};
+pub use approval::ApprovalPolicy;
+pub use config::{
+    CachixConfig, JobConfig, KennelConfig, LoadBalancing, RemediationConfig, ServiceConfig,
+    ServiceKind, StaticSiteConfig, parse_kennel_toml,
+};
+pub use environments::{
+    EnvironmentKind, EnvironmentRule, environment_kind, resolve_environment,
+    validate_environment_rules,
+};
+pub use expiry::ExpiryPolicy;
+pub use freeze::{Recurrence, freeze_active};
+pub use resources::{ResourceLimits, ResourcePolicy, parse_cpu_quota, parse_memory};
+pub use tags::{tag_matches, tag_ref, validate_tag_pattern};
@@ -0,0 +1,206 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// systemd resource controls for a service unit. Values use systemd syntax:
+/// `memory_max = "512M"`, `cpu_quota = "50%"`.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceLimits {
+    pub memory_max: Option<String>,
+    pub cpu_quota: Option<String>,
+    pub tasks_max: Option<u32>,
+    pub io_weight: Option<u16>,
+}
+
+impl ResourceLimits {
+    /// Fill unset limits from `defaults`.
+    pub fn or(&self, defaults: &ResourceLimits) -> ResourceLimits {
+        ResourceLimits {
+            memory_max: self
+                .memory_max
+                .clone()
+                .or_else(|| defaults.memory_max.clone()),
+            cpu_quota: self
+                .cpu_quota
+                .clone()
+                .or_else(|| defaults.cpu_quota.clone()),
+            tasks_max: self.tasks_max.or(defaults.tasks_max),
+            io_weight: self.io_weight.or(defaults.io_weight),
+        }
+    }
+
+    /// Clamp every limit to `caps`; a capped limit that is unset or
+    /// unparseable takes the cap.
+    pub fn capped(&self, caps: &ResourceLimits) -> ResourceLimits {
+        ResourceLimits {
+            memory_max: cap_by(&self.memory_max, &caps.memory_max, parse_memory),
+            cpu_quota: cap_by(&self.cpu_quota, &caps.cpu_quota, parse_cpu_quota),
+            tasks_max: cap_ord(self.tasks_max, caps.tasks_max),
+            io_weight: cap_ord(self.io_weight, caps.io_weight),
+        }
+    }
+
+    /// Reject values systemd would silently ignore.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(memory) = &self.memory_max
+            && parse_memory(memory).is_none()
+        {
+            return Err(format!("invalid memory_max '{}'", memory));
+        }
+
+        if let Some(cpu) = &self.cpu_quota
+            && parse_cpu_quota(cpu).is_none()
+        {
+            return Err(format!("invalid cpu_quota '{}'", cpu));
+        }
+
+        if let Some(weight) = self.io_weight
+            && !(1..=10000).contains(&weight)
+        {
+            return Err(format!("io_weight {} is outside 1-10000", weight));
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &ResourceLimits::default()
+    }
+}
+
+/// Host-wide limits from the NixOS module: defaults for services that do not
+/// set their own, and hard caps per environment (`prod`, `preview`, ...).
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ResourcePolicy {
+    #[serde(default)]
+    pub defaults: ResourceLimits,
+
+    #[serde(default)]
+    pub caps: HashMap<String, ResourceLimits>,
+}
+
+impl ResourcePolicy {
+    /// Limits to apply to a service deployed to `environment`.
+    pub fn effective(&self, service: &ResourceLimits, environment: &str) -> ResourceLimits {
+        let limits = service.or(&self.defaults);
+
+        match self.caps.get(environment) {
+            Some(caps) => limits.capped(caps),
+            None => limits,
+        }
+    }
+}
+
+fn cap_by(
+    value: &Option<String>,
+    cap: &Option<String>,
+    parse: fn(&str) -> Option<u64>,
+) -> Option<String> {
+    let Some(cap) = cap else {
+        return value.clone();
+    };
+
+    match value.as_deref().and_then(parse) {
+        Some(v) if parse(cap).is_none_or(|c| v <= c) => value.clone(),
+        _ => Some(cap.clone()),
+    }
+}
+
+fn cap_ord<T: Ord + Copy>(value: Option<T>, cap: Option<T>) -> Option<T> {
+    match (value, cap) {
+        (Some(v), Some(c)) => Some(v.min(c)),
+        (None, Some(c)) => Some(c),
+        (v, None) => v,
+    }
+}
+
+/// Bytes for a systemd memory size such as `512M` or `2G`; `infinity` is
+/// treated as unbounded.
+pub fn parse_memory(value: &str) -> Option<u64> {
+    let value = value.trim();
+
+    if value == "infinity" {
+        return Some(u64::MAX);
+    }
+
+    let (digits, multiplier) = match value.chars().last()? {
+        'K' => (&value[..value.len() - 1], 1u64 << 10),
+        'M' => (&value[..value.len() - 1], 1 << 20),
+        'G' => (&value[..value.len() - 1], 1 << 30),
+        'T' => (&value[..value.len() - 1], 1 << 40),
+        _ => (value, 1),
+    };
+
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Percentage of one CPU for a systemd quota such as `150%`.
+pub fn parse_cpu_quota(value: &str) -> Option<u64> {
+    value.trim().strip_suffix('%')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(memory: &str, cpu: &str, tasks: u32, io: u16) -> ResourceLimits {
+        ResourceLimits {
+            memory_max: Some(memory.to_string()),
+            cpu_quota: Some(cpu.to_string()),
+            tasks_max: Some(tasks),
+            io_weight: Some(io),
+        }
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        assert_eq!(parse_memory("512M"), Some(512 << 20));
+        assert_eq!(parse_memory("2G"), Some(2 << 30));
+        assert_eq!(parse_memory("1024"), Some(1024));
+        assert_eq!(parse_memory("infinity"), Some(u64::MAX));
+        assert_eq!(parse_memory("lots"), None);
+
+        assert_eq!(parse_cpu_quota("150%"), Some(150));
+        assert_eq!(parse_cpu_quota("1.5"), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(limits("512M", "50%", 64, 100).validate().is_ok());
+        assert!(limits("lots", "50%", 64, 100).validate().is_err());
+        assert!(limits("512M", "half", 64, 100).validate().is_err());
+        assert!(limits("512M", "50%", 64, 0).validate().is_err());
+    }
+
+    #[test]
+    fn test_defaults_fill_unset_limits() {
+        let service = ResourceLimits {
+            memory_max: Some("256M".to_string()),
+            ..Default::default()
+        };
+
+        let merged = service.or(&limits("1G", "100%", 512, 100));
+        assert_eq!(merged, limits("256M", "100%", 512, 100));
+    }
+
+    #[test]
+    fn test_caps_clamp_limits() {
+        let policy = ResourcePolicy {
+            defaults: ResourceLimits::default(),
+            caps: HashMap::from([("preview".to_string(), limits("512M", "50%", 256, 50))]),
+        };
+
+        let service = ResourceLimits {
+            memory_max: Some("2G".to_string()),
+            cpu_quota: Some("25%".to_string()),
+            tasks_max: None,
+            io_weight: Some(500),
+        };
+
+        assert_eq!(
+            policy.effective(&service, "preview"),
+            limits("512M", "25%", 256, 50)
+        );
+        assert_eq!(policy.effective(&service, "prod"), service);
+    }
+}
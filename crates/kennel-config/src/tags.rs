@@ -0,0 +1,42 @@
+/// Whether pushing `tag` starts a release build under a project's tag
+/// pattern, a glob such as `v*`.
+pub fn tag_matches(pattern: &str, tag: &str) -> bool {
+    glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(tag))
+}
+
+/// The ref builds and deployments of `tag` are recorded under, kept apart
+/// from branches of the same name.
+pub fn tag_ref(tag: &str) -> String {
+    format!("refs/tags/{}", tag)
+}
+
+/// Reject tag patterns that cannot match.
+pub fn validate_tag_pattern(pattern: &str) -> Result<(), String> {
+    glob::Pattern::new(pattern)
+        .map(|_| ())
+        .map_err(|e| format!("invalid tag pattern '{}': {}", pattern, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_matches() {
+        assert!(tag_matches("v*", "v1.0.0"));
+        assert!(tag_matches("release-*", "release-2026.03"));
+        assert!(!tag_matches("v*", "nightly"));
+        assert!(!tag_matches("v[", "v["));
+    }
+
+    #[test]
+    fn test_tag_ref() {
+        assert_eq!(tag_ref("v1.0.0"), "refs/tags/v1.0.0");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate_tag_pattern("v*").is_ok());
+        assert!(validate_tag_pattern("v[").is_err());
+    }
+}
@@ -8,9 +8,18 @@ pub enum DeployerError {
     #[error("health check failed: {0}")]
     HealthCheck(String),
 
+    #[error("release command failed: {0}")]
+    Release(String),
+
     #[error("deployment not found: {0}")]
     NotFound(String),
 
+    #[error("invalid request: {0}")]
+    Invalid(String),
+
+    #[error("deployments are frozen: {0}")]
+    Frozen(String),
+
     #[error("port allocation failed: {0}")]
     PortAllocation(String),
 
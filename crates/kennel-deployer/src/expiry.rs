@@ -0,0 +1,224 @@
+use crate::DeployerConfig;
+use crate::error::Result;
+use crate::remediation::load_kennel_config;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use entity::deployments;
+use entity::sea_orm_active_enums::DeploymentEventKind;
+use kennel_config::ExpiryPolicy;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+const ACTOR: &str = "kennel";
+
+pub async fn run_cleanup_job(config: DeployerConfig, teardown_tx: mpsc::Sender<i32>) {
+    info!("Starting auto-expiry cleanup job");
+
+    let mut interval = tokio::time::interval(kennel_config::constants::CLEANUP_JOB_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        info!("Running auto-expiry cleanup");
+
+        match expire_deployments(&config, &teardown_tx).await {
+            Ok(0) => {}
+            Ok(expired) => info!("Marked {} deployment(s) for auto-expiry teardown", expired),
+            Err(e) => error!("Cleanup job failed to expire deployments: {}", e),
+        }
+
+        match config.store.builds().expire_pending_approvals().await {
+            Ok(0) => {}
+            Ok(expired) => info!("Expired {} build(s) waiting for approval", expired),
+            Err(e) => error!("Cleanup job failed to expire pending approvals: {}", e),
+        }
+    }
+}
+
+/// Tear down deployments of branches that have gone without requests for
+/// longer than their expiry policy allows, and warn about those that soon
+/// will. Only the router sees requests, so a branch counts as in use while
+/// any of its deployments is, keeping its workers, jobs and internal services
+/// alongside its frontend. Pinned branches and branches matching a
+/// never-expire pattern are left alone. Returns the number of deployments
+/// expired.
+pub async fn expire_deployments(
+    config: &DeployerConfig,
+    teardown_tx: &mpsc::Sender<i32>,
+) -> Result<usize> {
+    let now = Utc::now().naive_utc();
+    let mut project_policies: HashMap<String, ExpiryPolicy> = HashMap::new();
+    let mut repository_policies: HashMap<Option<i32>, ExpiryPolicy> = HashMap::new();
+    let mut pinned: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut expired = 0;
+
+    let deployments = config.store.find_expirable_deployments().await?;
+
+    let mut branch_activity: HashMap<(String, String), NaiveDateTime> = HashMap::new();
+    for deployment in &deployments {
+        branch_activity
+            .entry((deployment.project_name.clone(), deployment.git_ref.clone()))
+            .and_modify(|at| *at = (*at).max(deployment.last_activity))
+            .or_insert(deployment.last_activity);
+    }
+
+    for deployment in deployments {
+        if !project_policies.contains_key(&deployment.project_name) {
+            let policy = project_policy(config, &deployment.project_name).await?;
+            let branches = config
+                .store
+                .branch_pins()
+                .list_by_project(&deployment.project_name)
+                .await?
+                .into_iter()
+                .map(|pin| pin.branch)
+                .collect();
+
+            project_policies.insert(deployment.project_name.clone(), policy);
+            pinned.insert(deployment.project_name.clone(), branches);
+        }
+
+        if pinned[&deployment.project_name].contains(&deployment.branch) {
+            continue;
+        }
+
+        if let Entry::Vacant(entry) = repository_policies.entry(deployment.build_id) {
+            let policy = load_kennel_config(config, deployment.build_id)
+                .await
+                .map(|c| c.expiry)
+                .unwrap_or_default();
+            entry.insert(policy);
+        }
+
+        let policy = repository_policies[&deployment.build_id]
+            .or(&project_policies[&deployment.project_name])
+            .or(&ExpiryPolicy::host_default());
+
+        let Some(ttl_days) = policy.ttl_days(&deployment.branch, &deployment.environment) else {
+            continue;
+        };
+        let last_activity =
+            branch_activity[&(deployment.project_name.clone(), deployment.git_ref.clone())];
+        let expires_at = last_activity + TimeDelta::days(ttl_days.into());
+
+        if now >= expires_at {
+            expire(config, teardown_tx, &deployment, last_activity, ttl_days).await?;
+            expired += 1;
+        } else if let Some(hours) = policy.warning_hours.filter(|&hours| hours > 0)
+            && now >= expires_at - TimeDelta::hours(hours.into())
+        {
+            warn_of_expiry(config, &deployment, last_activity, expires_at).await?;
+        }
+    }
+
+    Ok(expired)
+}
+
+/// The expiry policy from projects.json, stored with the project.
+async fn project_policy(config: &DeployerConfig, project_name: &str) -> Result<ExpiryPolicy> {
+    let stored = config
+        .store
+        .projects()
+        .find_by_name(project_name)
+        .await?
+        .and_then(|project| project.expiry_policy);
+
+    Ok(match stored.map(serde_json::from_value) {
+        Some(Ok(policy)) => policy,
+        Some(Err(e)) => {
+            warn!("Invalid expiry policy for project {}: {}", project_name, e);
+            ExpiryPolicy::default()
+        }
+        None => ExpiryPolicy::default(),
+    })
+}
+
+async fn expire(
+    config: &DeployerConfig,
+    teardown_tx: &mpsc::Sender<i32>,
+    deployment: &deployments::Model,
+    last_activity: NaiveDateTime,
+    ttl_days: u32,
+) -> Result<()> {
+    info!(
+        "Auto-expiry: deployment {} (project: {}, ref: {}, last_activity: {:?})",
+        deployment.id, deployment.project_name, deployment.git_ref, last_activity
+    );
+
+    config
+        .store
+        .deployments()
+        .mark_ids_tearing_down(
+            &[deployment.id],
+            &format!("Expired after {} days without requests", ttl_days),
+        )
+        .await?;
+
+    if let Err(e) = config
+        .store
+        .deployment_events()
+        .record(
+            deployment,
+            DeploymentEventKind::Expired,
+            deployment.build_id,
+            Some(format!("Last request to the branch at {}", last_activity)),
+            ACTOR,
+        )
+        .await
+    {
+        warn!(
+            "Failed to record expiry of deployment {}: {}",
+            deployment.id, e
+        );
+    }
+
+    if let Err(e) = teardown_tx.send(deployment.id).await {
+        error!(
+            "Failed to send teardown request for deployment {}: {}",
+            deployment.id, e
+        );
+    }
+
+    Ok(())
+}
+
+/// Record a warning event, once per stretch without requests to the branch.
+async fn warn_of_expiry(
+    config: &DeployerConfig,
+    deployment: &deployments::Model,
+    last_activity: NaiveDateTime,
+    expires_at: NaiveDateTime,
+) -> Result<()> {
+    let already_warned = config
+        .store
+        .deployment_events()
+        .latest_by_deployment(deployment.id, DeploymentEventKind::ExpiryWarning)
+        .await?
+        .is_some_and(|event| event.created_at >= last_activity);
+    if already_warned {
+        return Ok(());
+    }
+
+    info!(
+        "Deployment {} (project: {}, ref: {}) expires at {}",
+        deployment.id, deployment.project_name, deployment.git_ref, expires_at
+    );
+
+    config
+        .store
+        .deployment_events()
+        .record(
+            deployment,
+            DeploymentEventKind::ExpiryWarning,
+            deployment.build_id,
+            Some(format!(
+                "Expires at {} UTC unless it receives a request or the branch is pinned",
+                expires_at.format("%Y-%m-%d %H:%M")
+            )),
+            ACTOR,
+        )
+        .await?;
+
+    Ok(())
+}
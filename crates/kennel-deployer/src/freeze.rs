@@ -0,0 +1,137 @@
+use crate::error::Result;
+use crate::{DeployerConfig, DeploymentRequest, DeploymentTrigger};
+use chrono::Utc;
+use entity::sea_orm_active_enums::BuildStatus;
+use entity::{freeze_windows, projects};
+use kennel_config::{EnvironmentKind, Recurrence, constants, freeze_active};
+use kennel_store::{Store, project_environment, project_environment_kind};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// Periodically deploy builds held by freeze windows that have since ended.
+pub async fn run_freeze_monitor(
+    config: DeployerConfig,
+    deploy_tx: mpsc::Sender<DeploymentRequest>,
+) {
+    info!("Starting freeze monitor");
+
+    let mut interval = tokio::time::interval(constants::FREEZE_MONITOR_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match release_frozen_builds(&config, &deploy_tx).await {
+            Ok(0) => {}
+            Ok(released) => info!("Released {} build(s) held by a deploy freeze", released),
+            Err(e) => error!("Freeze monitor failed: {}", e),
+        }
+    }
+}
+
+/// The first freeze window keeping deployments of `project` out of
+/// `environment` right now. Environments the project uses for previews are
+/// never frozen.
+pub async fn active_freeze(
+    store: &Store,
+    project: &projects::Model,
+    environment: &str,
+) -> Result<Option<freeze_windows::Model>> {
+    if project_environment_kind(project, environment) == EnvironmentKind::Preview {
+        return Ok(None);
+    }
+
+    let now = Utc::now().naive_utc();
+
+    Ok(store
+        .freeze_windows()
+        .list_applicable(&project.name)
+        .await?
+        .into_iter()
+        .filter(|w| w.environment.as_deref().is_none_or(|e| e == environment))
+        .find(|w| {
+            let recurrence = match w.recurrence.as_deref().map(str::parse::<Recurrence>) {
+                Some(Ok(recurrence)) => Some(recurrence),
+                Some(Err(e)) => {
+                    warn!("Ignoring freeze window {}: {}", w.id, e);
+                    return false;
+                }
+                None => None,
+            };
+
+            freeze_active(w.starts_at, w.ends_at, recurrence.as_ref(), now)
+        }))
+}
+
+/// Why a deployment to `environment` is frozen, shown on held builds and in
+/// refusals.
+pub fn freeze_reason(window: &freeze_windows::Model, environment: &str) -> String {
+    format!(
+        "{} is frozen by freeze window {}: {}",
+        environment, window.id, window.reason
+    )
+}
+
+/// Queue held builds whose environment is no longer frozen. Returns the
+/// number released.
+pub async fn release_frozen_builds(
+    config: &DeployerConfig,
+    deploy_tx: &mpsc::Sender<DeploymentRequest>,
+) -> Result<usize> {
+    let held = config
+        .store
+        .builds()
+        .list_by_status(BuildStatus::Frozen)
+        .await
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
+
+    let mut released = 0;
+
+    for build in held {
+        let Some(project) = config
+            .store
+            .projects()
+            .find_by_name(&build.project_name)
+            .await?
+        else {
+            continue;
+        };
+
+        let environment = project_environment(&project, &build.git_ref);
+        if active_freeze(&config.store, &project, &environment)
+            .await?
+            .is_some()
+        {
+            continue;
+        }
+
+        if config
+            .store
+            .builds()
+            .release_hold(build.id)
+            .await?
+            .is_none()
+        {
+            continue;
+        }
+
+        info!(
+            "Freeze on {}/{} has ended, deploying held build {}",
+            build.project_name, environment, build.id
+        );
+
+        deploy_tx
+            .send(DeploymentRequest {
+                build_id: build.id,
+                project_name: build.project_name,
+                git_ref: build.git_ref,
+                services: Vec::new(),
+                trigger: DeploymentTrigger::Build,
+            })
+            .await
+            .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
+
+        released += 1;
+    }
+
+    Ok(released)
+}
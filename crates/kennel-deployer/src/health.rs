@@ -1,4 +1,5 @@
 use crate::error::Result;
+use crate::runtime::{ServiceRuntime, UnitStatus};
 use std::time::Duration;
 use tracing::{info, warn};
 
@@ -44,6 +45,35 @@ pub async fn check_health(port: u16, path: &str, timeout_secs: u64) -> Result<()
     }
 }
 
+/// A worker has nothing to probe, so it is healthy once its unit has stayed
+/// running for the whole grace period.
+pub async fn check_stays_active(
+    runtime: &dyn ServiceRuntime,
+    unit_name: &str,
+    grace_period: Duration,
+) -> Result<()> {
+    let start = std::time::Instant::now();
+
+    loop {
+        let status = runtime.status(unit_name).await?;
+        if status != UnitStatus::Running {
+            return Err(crate::DeployerError::HealthCheck(format!(
+                "Unit {} stopped during its {}s grace period ({:?})",
+                unit_name,
+                grace_period.as_secs(),
+                status
+            )));
+        }
+
+        if start.elapsed() >= grace_period {
+            info!("Unit {} stayed active for its grace period", unit_name);
+            return Ok(());
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
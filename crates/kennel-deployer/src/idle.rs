@@ -0,0 +1,309 @@
+use crate::DeployerConfig;
+use crate::error::Result;
+use crate::remediation::load_kennel_config;
+use crate::{health, utils};
+use chrono::Utc;
+use entity::sea_orm_active_enums::DeploymentStatus;
+use entity::{deployments, projects, services};
+use kennel_config::{EnvironmentKind, KennelConfig, constants};
+use kennel_router::{RouterUpdate, WakeRequest};
+use kennel_store::project_environment_kind;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+/// Periodically stops deployments that have gone without requests for
+/// longer than their service's idle timeout.
+pub async fn run_idle_monitor(config: DeployerConfig) {
+    info!("Starting idle monitor");
+
+    let mut interval = tokio::time::interval(constants::IDLE_MONITOR_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = stop_idle_deployments(&config).await {
+            warn!("Failed to stop idle deployments: {}", e);
+        }
+    }
+}
+
+/// Stop every non-production service deployment that opted into idling and
+/// has had no request for its idle timeout, keeping its route so the router
+/// can wake it. Returns the number of deployments stopped.
+pub async fn stop_idle_deployments(config: &DeployerConfig) -> Result<usize> {
+    let mut stopped = 0;
+
+    let active = config
+        .store
+        .deployments()
+        .list_active_with_services()
+        .await
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
+    let mut projects: HashMap<String, Option<projects::Model>> = HashMap::new();
+    let mut kennel_configs: HashMap<i32, Option<KennelConfig>> = HashMap::new();
+
+    for (deployment, service) in &active {
+        let Some(idle_timeout) = service.as_ref().and_then(|s| s.idle_timeout_secs) else {
+            continue;
+        };
+        if deployment.port.is_none() || deployment.idle_since.is_some() {
+            continue;
+        }
+
+        if idle_for(config, deployment) < Duration::from_secs(idle_timeout as u64) {
+            continue;
+        }
+
+        if !projects.contains_key(&deployment.project_name) {
+            let project = config
+                .store
+                .projects()
+                .find_by_name(&deployment.project_name)
+                .await?;
+            projects.insert(deployment.project_name.clone(), project);
+        }
+        let is_production = projects[&deployment.project_name].as_ref().is_none_or(|p| {
+            project_environment_kind(p, &deployment.environment) == EnvironmentKind::Production
+        });
+        if is_production {
+            continue;
+        }
+
+        // Requests from dependents go straight to its port, so the router
+        // never sees them
+        if has_running_dependent(config, &mut kennel_configs, deployment, &active).await {
+            continue;
+        }
+
+        let _lock = config
+            .branch_locks
+            .lock(&deployment.project_name, &deployment.git_ref)
+            .await;
+
+        // It may have been replaced or had a request while waiting for the lock
+        let Some(deployment) = config
+            .store
+            .deployments()
+            .find_by_id(deployment.id)
+            .await
+            .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?
+            .filter(|d| d.status == DeploymentStatus::Active && d.idle_since.is_none())
+        else {
+            continue;
+        };
+        if config.in_flight.count(deployment.id) > 0
+            || idle_for(config, &deployment) < Duration::from_secs(idle_timeout as u64)
+        {
+            continue;
+        }
+
+        let unit_name = utils::service_unit_name(&deployment);
+        info!(
+            "Stopping {} after {}s without requests",
+            unit_name, idle_timeout
+        );
+
+        config
+            .store
+            .deployments()
+            .set_idle(deployment.id, true)
+            .await?;
+
+        if let Some(router_tx) = &config.router_tx
+            && let Err(e) = router_tx.send(RouterUpdate::DeploymentIdle {
+                deployment_id: deployment.id,
+            })
+        {
+            warn!("Failed to send router update: {}", e);
+        }
+
+        config.runtime.stop(&unit_name).await?;
+        stopped += 1;
+    }
+
+    Ok(stopped)
+}
+
+/// Whether a running deployment on the branch of `deployment` declares
+/// `depends_on` its service.
+async fn has_running_dependent(
+    config: &DeployerConfig,
+    kennel_configs: &mut HashMap<i32, Option<KennelConfig>>,
+    deployment: &deployments::Model,
+    active: &[(deployments::Model, Option<services::Model>)],
+) -> bool {
+    for (other, _) in active {
+        if other.id == deployment.id
+            || other.idle_since.is_some()
+            || other.project_name != deployment.project_name
+            || other.git_ref != deployment.git_ref
+        {
+            continue;
+        }
+        let Some(build_id) = other.build_id else {
+            continue;
+        };
+
+        if let Entry::Vacant(entry) = kennel_configs.entry(build_id) {
+            entry.insert(load_kennel_config(config, Some(build_id)).await);
+        }
+        if kennel_configs[&build_id]
+            .as_ref()
+            .and_then(|c| c.services.get(&other.service_name))
+            .is_some_and(|s| s.depends_on.contains(&deployment.service_name))
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Time since the last request to `deployment`, or since the deployment
+/// last changed (went live or woke up) if that was more recent.
+fn idle_for(config: &DeployerConfig, deployment: &deployments::Model) -> Duration {
+    let since_update = (Utc::now().naive_utc() - deployment.updated_at)
+        .to_std()
+        .unwrap_or_default();
+
+    config
+        .in_flight
+        .last_request(deployment.id)
+        .map_or(since_update, |at| at.elapsed().min(since_update))
+}
+
+/// Start idle deployments the router asks for, replying once each is
+/// healthy again.
+pub async fn run_wake_handler(mut wake_rx: mpsc::Receiver<WakeRequest>, config: DeployerConfig) {
+    info!("Starting wake handler");
+
+    while let Some(request) = wake_rx.recv().await {
+        let config = config.clone();
+
+        tokio::spawn(async move {
+            let woken = match wake_deployment(request.deployment_id, &config).await {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Failed to wake deployment {}: {}", request.deployment_id, e);
+                    false
+                }
+            };
+
+            let _ = request.reply.send(woken);
+        });
+    }
+
+    info!("Wake handler shutting down");
+}
+
+/// Start the idle deployment `deployment_id` and wait for every replica to
+/// pass its health check. Waking a deployment that is already running does
+/// nothing, so concurrent requests can all ask.
+pub async fn wake_deployment(deployment_id: i32, config: &DeployerConfig) -> Result<()> {
+    let not_found = || crate::DeployerError::NotFound(format!("Deployment {}", deployment_id));
+
+    let deployment = config
+        .store
+        .deployments()
+        .find_by_id(deployment_id)
+        .await
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?
+        .ok_or_else(not_found)?;
+
+    // Its dependencies may have been stopped once nothing else used them
+    wake_dependencies(&deployment, config).await?;
+
+    let _lock = config
+        .branch_locks
+        .lock(&deployment.project_name, &deployment.git_ref)
+        .await;
+
+    // Another request may have woken it while waiting for the lock
+    let deployment = config
+        .store
+        .deployments()
+        .find_by_id(deployment_id)
+        .await
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?
+        .filter(|d| d.status == DeploymentStatus::Active)
+        .ok_or_else(not_found)?;
+    if deployment.idle_since.is_none() {
+        return Ok(());
+    }
+
+    let unit_name = utils::service_unit_name(&deployment);
+    info!("Waking {}", unit_name);
+
+    let health_check_path = config
+        .store
+        .services()
+        .find_by_project_and_name(&deployment.project_name, &deployment.service_name)
+        .await
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?
+        .and_then(|s| s.health_check)
+        .unwrap_or_else(|| "/health".to_string());
+
+    config.runtime.start(&unit_name).await?;
+
+    for allocation in config
+        .store
+        .port_allocations()
+        .list_by_deployment(deployment.id)
+        .await?
+    {
+        if let Err(e) = health::check_health(
+            allocation.port as u16,
+            &health_check_path,
+            constants::WAKE_TIMEOUT.as_secs(),
+        )
+        .await
+        {
+            // Leave it idle so the next request tries again
+            if let Err(stop_err) = config.runtime.stop(&unit_name).await {
+                warn!("Failed to stop {}: {}", unit_name, stop_err);
+            }
+            return Err(e);
+        }
+    }
+
+    config
+        .store
+        .deployments()
+        .set_idle(deployment.id, false)
+        .await?;
+
+    info!("Woke {}", unit_name);
+    Ok(())
+}
+
+/// Wake the idle deployments of the services `deployment` depends on.
+async fn wake_dependencies(deployment: &deployments::Model, config: &DeployerConfig) -> Result<()> {
+    let Some(kennel_config) = load_kennel_config(config, deployment.build_id).await else {
+        return Ok(());
+    };
+    let Some(service_config) = kennel_config.services.get(&deployment.service_name) else {
+        return Ok(());
+    };
+
+    for dependency in &service_config.depends_on {
+        let Some(dependency) = config
+            .store
+            .deployments()
+            .find_active_by_ref(&deployment.project_name, &deployment.git_ref, dependency)
+            .await
+            .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?
+        else {
+            continue;
+        };
+
+        // kennel.toml rejects dependency cycles, so this ends
+        if dependency.idle_since.is_some() {
+            Box::pin(wake_deployment(dependency.id, config)).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::DeployerConfig;
+use crate::error::Result;
+use crate::runtime::LogQuery;
+use crate::utils;
+use chrono::{SubsecRound, TimeDelta};
+use kennel_config::constants;
+use tracing::{info, warn};
+
+/// Periodically records finished runs of scheduled jobs.
+pub async fn run_job_monitor(config: DeployerConfig) {
+    info!("Starting job monitor");
+
+    let mut interval = tokio::time::interval(constants::JOB_MONITOR_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = collect_job_runs(&config).await {
+            warn!("Failed to collect job runs: {}", e);
+        }
+    }
+}
+
+/// Record the last run of every active job deployment that has finished a
+/// run since the last one recorded, with the output it wrote. Returns the
+/// number of runs recorded.
+pub async fn collect_job_runs(config: &DeployerConfig) -> Result<usize> {
+    let mut recorded = 0;
+
+    for deployment in config.store.find_active_job_deployments().await? {
+        let unit_name = utils::service_unit_name(&deployment);
+
+        let run = match config.runtime.last_run(&unit_name).await {
+            Ok(Some(run)) => run,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Failed to read last run of {}: {}", unit_name, e);
+                continue;
+            }
+        };
+
+        // Postgres keeps microseconds; compare at the precision stored
+        let started_at = run.started_at.naive_utc().trunc_subsecs(6);
+        let latest = config
+            .store
+            .job_runs()
+            .latest_by_deployment(deployment.id)
+            .await?;
+        if latest.is_some_and(|latest| latest.started_at >= started_at) {
+            continue;
+        }
+
+        // journald filters by whole seconds, so pad the end of the window
+        let log = match config
+            .runtime
+            .logs(
+                &unit_name,
+                &LogQuery {
+                    since: Some(run.started_at),
+                    until: Some(run.finished_at + TimeDelta::seconds(1)),
+                    grep: None,
+                    lines: constants::JOB_RUN_LOG_LINES,
+                },
+            )
+            .await
+        {
+            Ok(entries) => Some(
+                entries
+                    .into_iter()
+                    .map(|entry| entry.message)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            Err(e) => {
+                warn!("Failed to read output of {}: {}", unit_name, e);
+                None
+            }
+        };
+
+        config
+            .store
+            .job_runs()
+            .record(
+                &deployment,
+                started_at,
+                run.finished_at.naive_utc().trunc_subsecs(6),
+                run.succeeded,
+                run.exit_code,
+                log,
+            )
+            .await?;
+        recorded += 1;
+
+        if run.succeeded {
+            info!("Job {} finished successfully", unit_name);
+        } else {
+            warn!(
+                "Job {} failed with exit code {}",
+                unit_name,
+                run.exit_code
+                    .map_or_else(|| "unknown".to_string(), |code| code.to_string())
+            );
+        }
+    }
+
+    Ok(recorded)
+}
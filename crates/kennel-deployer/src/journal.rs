@@ -0,0 +1,112 @@
+use crate::runtime::{LogEntry, LogQuery};
+use chrono::DateTime;
+
+/// Arguments for `journalctl` reading `unit_name` as JSON, one entry per line.
+pub(crate) fn journalctl_args(unit_name: &str, query: &LogQuery, follow: bool) -> Vec<String> {
+    let mut args = vec![
+        "--no-pager".to_string(),
+        "--output=json".to_string(),
+        format!("--unit={}.service", unit_name),
+        format!("--lines={}", query.lines),
+    ];
+
+    if let Some(since) = query.since {
+        args.push(format!("--since=@{}", since.timestamp()));
+    }
+
+    if let Some(until) = query.until {
+        args.push(format!("--until=@{}", until.timestamp()));
+    }
+
+    if let Some(grep) = &query.grep {
+        args.push(format!("--grep={}", grep));
+    }
+
+    if follow {
+        args.push("--follow".to_string());
+    }
+
+    args
+}
+
+/// Parse one line of `journalctl --output=json`. Returns `None` for lines
+/// without a message.
+pub(crate) fn parse_journal_entry(line: &str) -> Option<LogEntry> {
+    let entry: serde_json::Value = serde_json::from_str(line).ok()?;
+
+    // Binary or non-UTF-8 messages are serialized as an array of bytes
+    let message = match entry.get("MESSAGE")? {
+        serde_json::Value::String(message) => message.clone(),
+        serde_json::Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+        _ => return None,
+    };
+
+    let timestamp = entry
+        .get("__REALTIME_TIMESTAMP")
+        .and_then(|t| t.as_str())
+        .and_then(|t| t.parse::<i64>().ok())
+        .and_then(DateTime::from_timestamp_micros);
+
+    Some(LogEntry { timestamp, message })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_journalctl_args() {
+        let query = LogQuery {
+            since: Some(Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap()),
+            until: None,
+            grep: Some("error|panic".to_string()),
+            lines: 100,
+        };
+
+        let args = journalctl_args("kennel-app-main-api-7", &query, true);
+
+        assert_eq!(
+            args,
+            vec![
+                "--no-pager",
+                "--output=json",
+                "--unit=kennel-app-main-api-7.service",
+                "--lines=100",
+                "--since=@1767323045",
+                "--grep=error|panic",
+                "--follow",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_journal_entry() {
+        let entry = parse_journal_entry(
+            r#"{"__REALTIME_TIMESTAMP":"1767323045123456","MESSAGE":"listening on 8080","PRIORITY":"6"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(entry.message, "listening on 8080");
+        assert_eq!(
+            entry.timestamp.unwrap().timestamp_micros(),
+            1_767_323_045_123_456
+        );
+    }
+
+    #[test]
+    fn test_parse_journal_entry_binary_message() {
+        let entry =
+            parse_journal_entry(r#"{"__REALTIME_TIMESTAMP":"1","MESSAGE":[104,105,255]}"#).unwrap();
+
+        assert_eq!(entry.message, "hi\u{fffd}");
+        assert!(parse_journal_entry(r#"{"MESSAGE":null}"#).is_none());
+        assert!(parse_journal_entry("not json").is_none());
+    }
+}
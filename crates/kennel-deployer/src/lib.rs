@@ -1,24 +1,51 @@
 mod error;
+mod expiry;
+mod freeze;
 mod health;
+mod idle;
+mod jobs;
+mod journal;
+mod locks;
 mod log_cleanup;
+mod process;
+mod promote;
+mod remediation;
+mod runtime;
 mod secrets;
 mod service;
 mod static_site;
 mod systemd;
 mod teardown;
-mod user;
 mod utils;
 
 pub use error::{DeployerError, Result};
-pub use kennel_builder::DeploymentRequest;
+pub use expiry::{expire_deployments, run_cleanup_job};
+pub use freeze::{active_freeze, freeze_reason, release_frozen_builds, run_freeze_monitor};
+pub use idle::{run_idle_monitor, run_wake_handler, stop_idle_deployments, wake_deployment};
+pub use jobs::{collect_job_runs, run_job_monitor};
+pub use kennel_builder::{DeploymentRequest, DeploymentTrigger, approval_policy};
+pub use locks::BranchLocks;
 pub use log_cleanup::run_log_cleanup_job;
-pub use teardown::run_teardown_worker;
-
+pub use process::ProcessRuntime;
+pub use promote::plan_promotion;
+pub use remediation::run_remediation_worker;
+pub use runtime::{
+    JobRun, LogEntry, LogQuery, ReleaseRun, ServiceRuntime, ServiceSpec, UnitStatus,
+};
+pub use service::deploy_build;
+pub use systemd::SystemdRuntime;
+pub use teardown::{process_teardown, run_teardown_worker};
+pub use utils::service_unit_name;
+
+use kennel_config::ResourcePolicy;
 use kennel_dns::DnsManager;
-use kennel_router::RouterUpdate;
+use kennel_forge::StatusReporter;
+use kennel_router::{InFlightTracker, RouterUpdate};
 use kennel_store::Store;
+use locks::BranchQueues;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{Semaphore, mpsc};
 use tracing::{error, info};
 
 #[derive(Clone)]
@@ -27,13 +54,55 @@ pub struct DeployerConfig {
     pub router_tx: Option<tokio::sync::broadcast::Sender<RouterUpdate>>,
     pub dns_manager: Option<Arc<DnsManager>>,
     pub base_domain: String,
+    pub max_concurrent_deploys: usize,
+    /// Requests the router is currently proxying, used to drain replaced backends.
+    pub in_flight: InFlightTracker,
+    pub branch_locks: BranchLocks,
+    pub runtime: Arc<dyn ServiceRuntime>,
+    pub paths: DeployerPaths,
+    /// Host-wide resource defaults and per-environment caps.
+    pub resources: ResourcePolicy,
+    /// Posts deployment progress to the project's forge, when configured.
+    pub forge: Option<Arc<StatusReporter>>,
+}
+
+/// Directories the deployer reads builds from and writes deployments to.
+#[derive(Debug, Clone)]
+pub struct DeployerPaths {
+    /// Build checkouts, one directory per build id containing kennel.toml.
+    pub builds_dir: PathBuf,
+    pub sites_dir: PathBuf,
+    /// Generated env files, one per deployment.
+    pub secrets_dir: PathBuf,
+    /// Secret values declared in kennel.toml, read from
+    /// `<project>/<environment>/<NAME>`.
+    pub secret_sources_dir: PathBuf,
+}
+
+impl Default for DeployerPaths {
+    fn default() -> Self {
+        use kennel_config::constants;
+
+        Self {
+            builds_dir: PathBuf::from(constants::DEFAULT_WORK_DIR),
+            sites_dir: PathBuf::from(constants::SITES_BASE_DIR),
+            secrets_dir: PathBuf::from(constants::SECRETS_DIR),
+            secret_sources_dir: PathBuf::from(constants::SECRET_SOURCES_DIR),
+        }
+    }
 }
 
 pub async fn run_deployer(
     mut deploy_rx: mpsc::Receiver<DeploymentRequest>,
     config: DeployerConfig,
 ) {
-    info!("Starting deployer");
+    info!(
+        "Starting deployer with max_concurrent_deploys={}",
+        config.max_concurrent_deploys
+    );
+
+    let semaphore = Arc::new(Semaphore::new(config.max_concurrent_deploys));
+    let queues = BranchQueues::default();
 
     while let Some(request) = deploy_rx.recv().await {
         info!(
@@ -41,61 +110,46 @@ pub async fn run_deployer(
             request.build_id, request.project_name, request.git_ref
         );
 
-        if let Err(e) = service::deploy_build(&request, &config).await {
-            error!("Deployment failed for build {}: {}", request.build_id, e);
+        let project_name = request.project_name.clone();
+        let git_ref = request.git_ref.clone();
+
+        // A branch that is already deploying picks the request up when its
+        // earlier ones are done
+        if queues.push(request) {
+            tokio::spawn(drain_branch(
+                project_name,
+                git_ref,
+                queues.clone(),
+                semaphore.clone(),
+                config.clone(),
+            ));
         }
     }
 
     info!("Deployer shutting down");
 }
 
-pub async fn run_cleanup_job(config: DeployerConfig, teardown_tx: mpsc::Sender<i32>) {
-    info!("Starting auto-expiry cleanup job");
-
-    let mut interval = tokio::time::interval(kennel_config::constants::CLEANUP_JOB_INTERVAL);
-
-    loop {
-        interval.tick().await;
-
-        info!("Running auto-expiry cleanup");
-
-        match config.store.find_expired_deployments(7).await {
-            Ok(expired) if !expired.is_empty() => {
-                let ids: Vec<i32> = expired.iter().map(|d| d.id).collect();
-
-                for deployment in &expired {
-                    info!(
-                        "Auto-expiry: deployment {} (project: {}, ref: {}, last_activity: {:?})",
-                        deployment.id,
-                        deployment.project_name,
-                        deployment.git_ref,
-                        deployment.last_activity
-                    );
-                }
-
-                if let Err(e) = config.store.deployments().mark_ids_tearing_down(&ids).await {
-                    error!("Failed to mark deployments for teardown: {}", e);
-                    continue;
-                }
-
-                for id in &ids {
-                    if let Err(e) = teardown_tx.send(*id).await {
-                        error!(
-                            "Failed to send teardown request for deployment {}: {}",
-                            id, e
-                        );
+async fn drain_branch(
+    project_name: String,
+    git_ref: String,
+    queues: BranchQueues,
+    semaphore: Arc<Semaphore>,
+    config: DeployerConfig,
+) {
+    queues
+        .drain(
+            &project_name,
+            &git_ref,
+            &config.branch_locks,
+            &semaphore,
+            |request| {
+                let config = &config;
+                async move {
+                    if let Err(e) = service::deploy_build(&request, config).await {
+                        error!("Deployment failed for build {}: {}", request.build_id, e);
                     }
                 }
-
-                info!(
-                    "Marked {} deployment(s) for auto-expiry teardown",
-                    ids.len()
-                );
-            }
-            Err(e) => {
-                error!("Cleanup job failed to find expired deployments: {}", e);
-            }
-            _ => {}
-        }
-    }
+            },
+        )
+        .await;
 }
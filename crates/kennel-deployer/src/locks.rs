@@ -0,0 +1,183 @@
+use crate::DeploymentRequest;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedMutexGuard, Semaphore};
+
+type LockMap = HashMap<(String, String), Arc<tokio::sync::Mutex<()>>>;
+type QueueMap = HashMap<(String, String), VecDeque<DeploymentRequest>>;
+
+/// One lock per project/branch so deployments of the same branch never
+/// interleave while different branches deploy concurrently.
+#[derive(Debug, Clone, Default)]
+pub struct BranchLocks {
+    locks: Arc<Mutex<LockMap>>,
+}
+
+impl BranchLocks {
+    pub async fn lock(&self, project: &str, git_ref: &str) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap();
+
+            // Forget locks nobody is holding or waiting on
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+
+            locks
+                .entry((project.to_string(), git_ref.to_string()))
+                .or_default()
+                .clone()
+        };
+
+        lock.lock_owned().await
+    }
+}
+
+/// Pending deployment requests per project/branch, kept in arrival order so
+/// an older build can never be deployed after a newer one.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BranchQueues {
+    queues: Arc<Mutex<QueueMap>>,
+}
+
+impl BranchQueues {
+    /// Queue a request behind the earlier ones of its branch. Returns true if
+    /// nothing was queued for the branch, in which case the caller must start
+    /// draining it.
+    pub fn push(&self, request: DeploymentRequest) -> bool {
+        let mut queues = self.queues.lock().unwrap();
+
+        match queues.entry((request.project_name.clone(), request.git_ref.clone())) {
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().push_back(request);
+                false
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(VecDeque::from([request]));
+                true
+            }
+        }
+    }
+
+    /// Take the oldest pending request of a branch. Once the branch is empty
+    /// it is forgotten, so the next push starts a new drain.
+    pub fn pop(&self, project: &str, git_ref: &str) -> Option<DeploymentRequest> {
+        let mut queues = self.queues.lock().unwrap();
+        let key = (project.to_string(), git_ref.to_string());
+
+        let request = queues.get_mut(&key)?.pop_front();
+        if request.is_none() {
+            queues.remove(&key);
+        }
+
+        request
+    }
+
+    /// Deploy the queued requests of one branch in order until none are
+    /// left. The permit is only taken once the branch lock is held, so a busy
+    /// branch never ties up permits other branches could use.
+    pub async fn drain<F, Fut>(
+        &self,
+        project: &str,
+        git_ref: &str,
+        locks: &BranchLocks,
+        semaphore: &Semaphore,
+        mut deploy: F,
+    ) where
+        F: FnMut(DeploymentRequest) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        while let Some(request) = self.pop(project, git_ref) {
+            let _lock = locks.lock(project, git_ref).await;
+            let _permit = semaphore.acquire().await.unwrap();
+
+            deploy(request).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_same_branch_is_serialized() {
+        let locks = BranchLocks::default();
+
+        let guard = locks.lock("kennel", "main").await;
+
+        let contended =
+            tokio::time::timeout(Duration::from_millis(50), locks.lock("kennel", "main")).await;
+        assert!(contended.is_err());
+
+        let other_branch =
+            tokio::time::timeout(Duration::from_millis(50), locks.lock("kennel", "dev")).await;
+        assert!(other_branch.is_ok());
+
+        drop(guard);
+        let _guard = locks.lock("kennel", "main").await;
+    }
+
+    fn request(build_id: i32, git_ref: &str) -> DeploymentRequest {
+        DeploymentRequest {
+            build_id,
+            project_name: "kennel".to_string(),
+            git_ref: git_ref.to_string(),
+            services: Vec::new(),
+            trigger: crate::DeploymentTrigger::Build,
+        }
+    }
+
+    #[test]
+    fn test_branch_queue_keeps_arrival_order() {
+        let queues = BranchQueues::default();
+
+        assert!(queues.push(request(1, "main")));
+        assert!(!queues.push(request(2, "main")));
+        assert!(queues.push(request(3, "dev")));
+
+        assert_eq!(queues.pop("kennel", "main").map(|r| r.build_id), Some(1));
+        assert_eq!(queues.pop("kennel", "main").map(|r| r.build_id), Some(2));
+        assert!(queues.pop("kennel", "main").is_none());
+
+        // An emptied branch starts a new drain on its next request
+        assert!(queues.push(request(4, "main")));
+        assert_eq!(queues.pop("kennel", "dev").map(|r| r.build_id), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_back_to_back_requests_deploy_in_order() {
+        let queues = BranchQueues::default();
+        let locks = BranchLocks::default();
+        let semaphore = Semaphore::new(1);
+        let deployed = Mutex::new(Vec::new());
+
+        // Another deployment of the branch, such as a rollback, is running
+        let guard = locks.lock("kennel", "main").await;
+
+        assert!(queues.push(request(1, "main")));
+        assert!(!queues.push(request(2, "main")));
+
+        let drain = queues.drain("kennel", "main", &locks, &semaphore, |request| {
+            deployed.lock().unwrap().push(request.build_id);
+            async {}
+        });
+        tokio::pin!(drain);
+
+        // Waiting on the branch holds no permit
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut drain)
+                .await
+                .is_err()
+        );
+        assert_eq!(semaphore.available_permits(), 1);
+        assert!(deployed.lock().unwrap().is_empty());
+
+        drop(guard);
+        drain.await;
+
+        // The newer build is deployed last, so it is the one left running
+        assert_eq!(*deployed.lock().unwrap(), vec![1, 2]);
+        assert!(queues.pop("kennel", "main").is_none());
+    }
+}
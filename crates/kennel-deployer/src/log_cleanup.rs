@@ -17,7 +17,8 @@ async fn clean_build(config: &DeployerConfig, build_id: i32) {
     }
 }
 
-/// Periodically deletes build logs and records older than the retention period.
+/// Periodically deletes build logs and records, deployment history and webhook
+/// deliveries older than their retention periods.
 pub async fn run_log_cleanup_job(config: DeployerConfig) {
     info!("Starting build log cleanup job");
 
@@ -47,5 +48,40 @@ pub async fn run_log_cleanup_job(config: DeployerConfig) {
             }
             _ => {}
         }
+
+        match config
+            .store
+            .prune_deployment_history(
+                constants::DEPLOYMENT_RETENTION_DAYS,
+                constants::DEPLOYMENT_EVENT_RETENTION_DAYS,
+            )
+            .await
+        {
+            Ok((deployments, events)) if deployments > 0 || events > 0 => {
+                info!(
+                    "Pruned {} old deployment(s) and {} deployment event(s)",
+                    deployments, events
+                );
+            }
+            Err(e) => {
+                error!("Failed to prune deployment history: {}", e);
+            }
+            _ => {}
+        }
+
+        match config
+            .store
+            .webhook_deliveries()
+            .delete_older_than(constants::WEBHOOK_DELIVERY_RETENTION_DAYS)
+            .await
+        {
+            Ok(deliveries) if deliveries > 0 => {
+                info!("Pruned {} old webhook deliveries", deliveries);
+            }
+            Err(e) => {
+                error!("Failed to prune webhook deliveries: {}", e);
+            }
+            _ => {}
+        }
     }
 }
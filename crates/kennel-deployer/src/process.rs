@@ -0,0 +1,458 @@
+use crate::error::Result;
+use crate::runtime::{
+    JobRun, LogEntry, LogQuery, ReleaseRun, ServiceRuntime, ServiceSpec, UnitStatus,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::{Child, Command};
+use tokio::sync::{Mutex, mpsc};
+use tracing::{info, warn};
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+struct ProcessUnit {
+    spec: ServiceSpec,
+    /// One process per replica.
+    children: Vec<Child>,
+    /// Jobs only: whether the unit has been started.
+    armed: bool,
+    last_run: Option<JobRun>,
+}
+
+/// Runs each deployment as a plain child process of kennel under `root`:
+/// state directories live in `{root}/state` and output is written to
+/// `{root}/logs/{unit}.log`. Meant for tests and local development, so job
+/// schedules never fire; use [`ProcessRuntime::run_job`] instead.
+pub struct ProcessRuntime {
+    root: PathBuf,
+    units: Mutex<HashMap<String, ProcessUnit>>,
+}
+
+impl ProcessRuntime {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            units: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn state_dir(&self, state_directory: &Path) -> PathBuf {
+        self.root.join("state").join(state_directory)
+    }
+
+    fn log_path(&self, unit_name: &str) -> PathBuf {
+        self.root.join("logs").join(format!("{}.log", unit_name))
+    }
+
+    /// Spawn every replica of `spec`: one per port for HTTP services, or
+    /// `replicas` for workers. Replicas share the unit's log.
+    async fn spawn(&self, spec: &ServiceSpec) -> Result<Vec<Child>> {
+        let ports: Vec<Option<u16>> = if spec.ports.is_empty() {
+            vec![None; spec.replicas.max(1) as usize]
+        } else {
+            spec.ports.iter().copied().map(Some).collect()
+        };
+
+        let mut children = Vec::with_capacity(ports.len());
+        for port in ports {
+            children.push(
+                self.spawn_command(spec, &spec.unit_name, port, Command::new(spec.exec_path()))
+                    .await?,
+            );
+        }
+
+        Ok(children)
+    }
+
+    /// Spawn `command` with the environment and working directory of `spec`
+    /// and `port` as `$PORT`, writing its output to the log of `log_unit`.
+    async fn spawn_command(
+        &self,
+        spec: &ServiceSpec,
+        log_unit: &str,
+        port: Option<u16>,
+        mut command: Command,
+    ) -> Result<Child> {
+        let working_dir = self.state_dir(&spec.state_directory);
+        tokio::fs::create_dir_all(&working_dir).await?;
+        tokio::fs::create_dir_all(self.root.join("logs")).await?;
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path(log_unit))?;
+
+        command
+            .current_dir(&working_dir)
+            .envs(port.map(|port| ("PORT", port.to_string())))
+            .envs(spec.env.iter().cloned())
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .kill_on_drop(true);
+
+        if let Some(env_file) = &spec.env_file {
+            command.envs(read_env_file(env_file).await?);
+        }
+
+        Ok(command.spawn()?)
+    }
+
+    /// Run an armed job to completion now, as if its timer had fired.
+    pub async fn run_job(&self, unit_name: &str) -> Result<JobRun> {
+        let spec = {
+            let units = self.units.lock().await;
+            let unit = units
+                .get(unit_name)
+                .filter(|unit| unit.armed)
+                .ok_or_else(|| crate::DeployerError::NotFound(format!("Job {}", unit_name)))?;
+            unit.spec.clone()
+        };
+
+        let started_at = Utc::now();
+        let status = self
+            .spawn_command(&spec, unit_name, None, Command::new(spec.exec_path()))
+            .await?
+            .wait()
+            .await?;
+        let run = JobRun {
+            started_at,
+            finished_at: Utc::now(),
+            succeeded: status.success(),
+            exit_code: status.code(),
+        };
+
+        if let Some(unit) = self.units.lock().await.get_mut(unit_name) {
+            unit.last_run = Some(run.clone());
+        }
+
+        Ok(run)
+    }
+}
+
+#[async_trait]
+impl ServiceRuntime for ProcessRuntime {
+    async fn install(&self, spec: &ServiceSpec) -> Result<()> {
+        let mut units = self.units.lock().await;
+
+        if let Some(existing) = units.remove(&spec.unit_name) {
+            for mut child in existing.children {
+                let _ = child.kill().await;
+            }
+        }
+
+        units.insert(
+            spec.unit_name.clone(),
+            ProcessUnit {
+                spec: spec.clone(),
+                children: Vec::new(),
+                armed: false,
+                last_run: None,
+            },
+        );
+
+        info!("Installed process unit: {}", spec.unit_name);
+        Ok(())
+    }
+
+    async fn start(&self, unit_name: &str) -> Result<()> {
+        let mut units = self.units.lock().await;
+        let unit = units
+            .get_mut(unit_name)
+            .ok_or_else(|| crate::DeployerError::NotFound(format!("Unit {}", unit_name)))?;
+
+        if unit.spec.schedule.is_some() {
+            unit.armed = true;
+            info!("Armed process job: {}", unit_name);
+            return Ok(());
+        }
+
+        let mut running = !unit.children.is_empty();
+        for child in &mut unit.children {
+            running &= child.try_wait()?.is_none();
+        }
+        if running {
+            return Ok(());
+        }
+
+        for mut child in unit.children.drain(..) {
+            let _ = child.kill().await;
+        }
+        unit.children = self.spawn(&unit.spec).await?;
+
+        info!("Started process unit: {}", unit_name);
+        Ok(())
+    }
+
+    async fn stop(&self, unit_name: &str) -> Result<()> {
+        let mut units = self.units.lock().await;
+
+        if let Some(unit) = units.get_mut(unit_name) {
+            unit.armed = false;
+
+            if !unit.children.is_empty() {
+                for mut child in unit.children.drain(..) {
+                    child.kill().await?;
+                }
+                info!("Stopped process unit: {}", unit_name);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn restart_replica(&self, unit_name: &str, port: u16) -> Result<()> {
+        let mut units = self.units.lock().await;
+        let unit = units
+            .get_mut(unit_name)
+            .ok_or_else(|| crate::DeployerError::NotFound(format!("Unit {}", unit_name)))?;
+
+        let replica = unit
+            .spec
+            .ports
+            .iter()
+            .position(|p| *p == port)
+            .filter(|&i| unit.spec.is_replicated() && i < unit.children.len());
+        let Some(replica) = replica else {
+            drop(units);
+            return self.restart(unit_name).await;
+        };
+
+        let _ = unit.children[replica].kill().await;
+        let spec = unit.spec.clone();
+        unit.children[replica] = self
+            .spawn_command(&spec, unit_name, Some(port), Command::new(spec.exec_path()))
+            .await?;
+
+        info!(
+            "Restarted replica on port {} of process unit: {}",
+            port, unit_name
+        );
+        Ok(())
+    }
+
+    async fn run_release(
+        &self,
+        spec: &ServiceSpec,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<ReleaseRun> {
+        let release_unit = format!("{}-release", spec.unit_name);
+
+        let path = std::iter::once(spec.bin_dir())
+            .chain(std::env::split_paths(
+                &std::env::var_os("PATH").unwrap_or_default(),
+            ))
+            .collect::<Vec<_>>();
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command).env(
+            "PATH",
+            std::env::join_paths(path).map_err(anyhow::Error::from)?,
+        );
+
+        let mut child = self
+            .spawn_command(spec, &release_unit, spec.ports.first().copied(), shell)
+            .await?;
+
+        let mut run = match tokio::time::timeout(timeout, child.wait()).await {
+            Ok(status) => {
+                let status = status?;
+                ReleaseRun {
+                    succeeded: status.success(),
+                    exit_code: status.code(),
+                    timed_out: false,
+                    log: String::new(),
+                }
+            }
+            Err(_) => {
+                child.kill().await?;
+                ReleaseRun {
+                    succeeded: false,
+                    exit_code: None,
+                    timed_out: true,
+                    log: String::new(),
+                }
+            }
+        };
+
+        run.log = self
+            .logs(
+                &release_unit,
+                &LogQuery {
+                    lines: kennel_config::constants::RELEASE_LOG_LINES,
+                    ..Default::default()
+                },
+            )
+            .await?
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(run)
+    }
+
+    async fn uninstall(&self, unit_name: &str) {
+        if let Err(e) = self.stop(unit_name).await {
+            warn!("Failed to stop unit {}: {}", unit_name, e);
+        }
+
+        self.units.lock().await.remove(unit_name);
+    }
+
+    async fn clean_state(&self, unit_name: &str) -> Result<()> {
+        let units = self.units.lock().await;
+        let unit = units
+            .get(unit_name)
+            .ok_or_else(|| crate::DeployerError::NotFound(format!("Unit {}", unit_name)))?;
+
+        match tokio::fs::remove_dir_all(self.state_dir(&unit.spec.state_directory)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn status(&self, unit_name: &str) -> Result<UnitStatus> {
+        let mut units = self.units.lock().await;
+
+        let Some(unit) = units.get_mut(unit_name) else {
+            return Ok(UnitStatus::NotFound);
+        };
+
+        if unit.spec.schedule.is_some() {
+            return Ok(if unit.armed {
+                UnitStatus::Running
+            } else {
+                UnitStatus::Stopped
+            });
+        }
+
+        if unit.children.is_empty() {
+            return Ok(UnitStatus::Stopped);
+        }
+
+        // Failed if any replica failed, running only if all of them are
+        let mut statuses = Vec::with_capacity(unit.children.len());
+        for child in &mut unit.children {
+            statuses.push(match child.try_wait()? {
+                None => UnitStatus::Running,
+                Some(status) if status.success() => UnitStatus::Stopped,
+                Some(_) => UnitStatus::Failed,
+            });
+        }
+
+        Ok(if statuses.contains(&UnitStatus::Failed) {
+            UnitStatus::Failed
+        } else if statuses.iter().all(|status| *status == UnitStatus::Running) {
+            UnitStatus::Running
+        } else {
+            UnitStatus::Stopped
+        })
+    }
+
+    async fn logs(&self, unit_name: &str, query: &LogQuery) -> Result<Vec<LogEntry>> {
+        let content = match tokio::fs::read_to_string(self.log_path(unit_name)).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let matching: Vec<&str> = content
+            .lines()
+            .filter(|line| matches_grep(line, query))
+            .collect();
+
+        Ok(matching[matching.len().saturating_sub(query.lines)..]
+            .iter()
+            .map(|line| log_entry(line))
+            .collect())
+    }
+
+    async fn follow_logs(
+        &self,
+        unit_name: &str,
+        query: &LogQuery,
+    ) -> Result<mpsc::Receiver<LogEntry>> {
+        let backlog = self.logs(unit_name, query).await?;
+        let mut offset = match tokio::fs::metadata(self.log_path(unit_name)).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+
+        let (tx, rx) = mpsc::channel(backlog.len().max(1));
+        for entry in backlog {
+            let _ = tx.send(entry).await;
+        }
+
+        let path = self.log_path(unit_name);
+        let query = query.clone();
+
+        tokio::spawn(async move {
+            let mut pending = String::new();
+
+            while !tx.is_closed() {
+                tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+
+                let Ok(content) = tokio::fs::read(&path).await else {
+                    continue;
+                };
+                let Some(new) = content.get(offset as usize..) else {
+                    continue;
+                };
+                offset = content.len() as u64;
+                pending.push_str(&String::from_utf8_lossy(new));
+
+                // Hold back a trailing partial line until it is complete
+                let complete = pending.rfind('\n').map_or(0, |i| i + 1);
+                let lines: String = pending.drain(..complete).collect();
+
+                for line in lines.lines().filter(|line| matches_grep(line, &query)) {
+                    if tx.send(log_entry(line)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    async fn last_run(&self, unit_name: &str) -> Result<Option<JobRun>> {
+        Ok(self
+            .units
+            .lock()
+            .await
+            .get(unit_name)
+            .and_then(|unit| unit.last_run.clone()))
+    }
+
+    async fn list_units(&self) -> Result<Vec<String>> {
+        Ok(self.units.lock().await.keys().cloned().collect())
+    }
+}
+
+/// Plain substring match; the process runtime keeps no timestamps, so
+/// `since` and `until` are ignored.
+fn matches_grep(line: &str, query: &LogQuery) -> bool {
+    query.grep.as_ref().is_none_or(|grep| line.contains(grep))
+}
+
+fn log_entry(line: &str) -> LogEntry {
+    LogEntry {
+        timestamp: None,
+        message: line.to_string(),
+    }
+}
+
+async fn read_env_file(path: &Path) -> Result<Vec<(String, String)>> {
+    let content = tokio::fs::read_to_string(path).await?;
+
+    Ok(content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
@@ -0,0 +1,84 @@
+use crate::error::Result;
+use crate::{DeployerError, DeploymentRequest, DeploymentTrigger};
+use kennel_store::Store;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// Deployment requests that put the builds live on `from_branch` onto
+/// `to_branch` unchanged: the same store paths, deployed with the target
+/// branch's environment, env and secrets through the usual blue-green flow.
+/// Services live from different builds get one request per build. Only
+/// `service` is promoted when given.
+pub async fn plan_promotion(
+    store: &Store,
+    project_name: &str,
+    from_branch: &str,
+    to_branch: &str,
+    service: Option<&str>,
+    actor: &str,
+) -> Result<Vec<DeploymentRequest>> {
+    if from_branch == to_branch {
+        return Err(DeployerError::Invalid(format!(
+            "Cannot promote {} to itself",
+            from_branch
+        )));
+    }
+
+    let live: Vec<_> = store
+        .deployments()
+        .list_active_by_project(project_name)
+        .await
+        .map_err(|e| DeployerError::Other(anyhow::anyhow!(e)))?
+        .into_iter()
+        .filter(|d| d.branch == from_branch)
+        .filter(|d| service.is_none_or(|s| s == d.service_name))
+        .collect();
+
+    if live.is_empty() {
+        return Err(DeployerError::NotFound(match service {
+            Some(service) => format!("{} on {}/{}", service, project_name, from_branch),
+            None => format!("Active deployments on {}/{}", project_name, from_branch),
+        }));
+    }
+
+    let mut builds: BTreeMap<i32, (String, Vec<String>)> = BTreeMap::new();
+    for deployment in live {
+        let Some(build_id) = deployment.build_id else {
+            warn!(
+                "Not promoting deployment {}: its build is gone",
+                deployment.id
+            );
+            continue;
+        };
+
+        builds
+            .entry(build_id)
+            .or_insert_with(|| (deployment.environment.clone(), Vec::new()))
+            .1
+            .push(deployment.service_name);
+    }
+
+    if builds.is_empty() {
+        return Err(DeployerError::NotFound(format!(
+            "Builds of {}/{}",
+            project_name, from_branch
+        )));
+    }
+
+    Ok(builds
+        .into_iter()
+        .map(
+            |(build_id, (from_environment, services))| DeploymentRequest {
+                build_id,
+                project_name: project_name.to_string(),
+                git_ref: to_branch.to_string(),
+                services,
+                trigger: DeploymentTrigger::Promote {
+                    actor: actor.to_string(),
+                    from_branch: from_branch.to_string(),
+                    from_environment,
+                },
+            },
+        )
+        .collect())
+}
@@ -0,0 +1,469 @@
+use crate::error::Result;
+use crate::{DeployerConfig, DeploymentRequest, DeploymentTrigger, service, utils};
+use entity::sea_orm_active_enums::{DeploymentEventKind, DeploymentStatus};
+use entity::{build_results, deployments};
+use kennel_config::{KennelConfig, RemediationConfig, constants, parse_kennel_toml};
+use kennel_router::HealthReport;
+use std::collections::HashMap;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// How many recent successful builds to consider when looking for a rollback target.
+const ROLLBACK_CANDIDATES: u64 = 10;
+
+const ACTOR: &str = "kennel";
+
+#[derive(Debug, Default)]
+struct RemediationState {
+    consecutive_failures: u32,
+    restarts: u32,
+    /// When the replica last went from failing to passing checks.
+    healthy_since: Option<Instant>,
+}
+
+/// How to respond to a deployment that keeps failing health checks.
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    /// Not enough failures in a row to act on yet.
+    Wait,
+    Restart,
+    GiveUp,
+}
+
+impl RemediationState {
+    /// Decide what to do under `policy`, counting the restart if it is one.
+    fn next_action(&mut self, policy: &RemediationConfig) -> Action {
+        if self.consecutive_failures < policy.failure_threshold {
+            return Action::Wait;
+        }
+
+        if self.restarts < policy.max_restarts {
+            self.restarts += 1;
+            self.consecutive_failures = 0;
+            return Action::Restart;
+        }
+
+        Action::GiveUp
+    }
+}
+
+pub async fn run_remediation_worker(
+    mut report_rx: mpsc::Receiver<HealthReport>,
+    config: DeployerConfig,
+) {
+    info!("Starting remediation worker");
+
+    // Keyed by deployment and replica port
+    let mut states: HashMap<(i32, u16), RemediationState> = HashMap::new();
+
+    while let Some(report) = report_rx.recv().await {
+        let key = (report.deployment_id, report.port);
+
+        if report.healthy {
+            // A flapping service passes the odd check between crashes, so
+            // restarts are only forgotten once it has stayed up for a while
+            if let Some(state) = states.get_mut(&key) {
+                state.consecutive_failures = 0;
+                let healthy_since = *state.healthy_since.get_or_insert_with(Instant::now);
+                if healthy_since.elapsed() >= constants::REMEDIATION_COOLDOWN {
+                    states.remove(&key);
+                }
+            }
+            continue;
+        }
+
+        let state = states.entry(key).or_default();
+        state.consecutive_failures += 1;
+        state.healthy_since = None;
+
+        match remediate(report.deployment_id, report.port, state, &config).await {
+            Ok(true) => {
+                states.retain(|(deployment_id, _), _| *deployment_id != report.deployment_id);
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!(
+                    "Remediation failed for deployment {}: {}",
+                    report.deployment_id, e
+                );
+            }
+        }
+    }
+
+    info!("Remediation worker shutting down");
+}
+
+/// Act on an unhealthy replica of a deployment. Returns true once the
+/// deployment has been given up on and no longer needs to be tracked.
+async fn remediate(
+    deployment_id: i32,
+    port: u16,
+    state: &mut RemediationState,
+    config: &DeployerConfig,
+) -> Result<bool> {
+    let Some(deployment) = config
+        .store
+        .deployments()
+        .find_by_id(deployment_id)
+        .await
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?
+    else {
+        return Ok(true);
+    };
+
+    if deployment.status != DeploymentStatus::Active || deployment.port.is_none() {
+        return Ok(true);
+    }
+
+    let kennel_config = load_kennel_config(config, deployment.build_id).await;
+    let policy = kennel_config
+        .as_ref()
+        .and_then(|c| c.services.get(&deployment.service_name))
+        .map(|s| s.remediation.clone())
+        .unwrap_or_default();
+
+    let action = state.next_action(&policy);
+    if action == Action::Wait {
+        return Ok(false);
+    }
+
+    if action == Action::Restart {
+        let unit_name = utils::service_unit_name(&deployment);
+        warn!(
+            "Replica on port {} of deployment {} is unhealthy, restarting it in {} (attempt {} of {})",
+            port, deployment.id, unit_name, state.restarts, policy.max_restarts
+        );
+
+        config.runtime.restart_replica(&unit_name, port).await?;
+
+        config
+            .store
+            .deployment_events()
+            .record(
+                &deployment,
+                DeploymentEventKind::Restarted,
+                deployment.build_id,
+                Some(format!(
+                    "Restarted the replica on port {} after {} failed health checks (attempt {} of {})",
+                    port, policy.failure_threshold, state.restarts, policy.max_restarts
+                )),
+                ACTOR,
+            )
+            .await?;
+
+        return Ok(false);
+    }
+
+    give_up(&deployment, &policy, config).await?;
+
+    Ok(true)
+}
+
+/// Mark a deployment that did not recover as failed, then roll back to the
+/// previous successful build if the policy allows it and stop the unit in
+/// the background, so a slow rollback does not hold up other reports.
+async fn give_up(
+    deployment: &deployments::Model,
+    policy: &RemediationConfig,
+    config: &DeployerConfig,
+) -> Result<()> {
+    let reason = format!(
+        "Failed health checks after {} restart(s)",
+        policy.max_restarts
+    );
+
+    error!("Deployment {}: {}", deployment.id, reason);
+
+    config
+        .store
+        .deployments()
+        .mark_failed(deployment.id, &reason)
+        .await?;
+
+    config
+        .store
+        .deployment_events()
+        .record(
+            deployment,
+            DeploymentEventKind::Failed,
+            deployment.build_id,
+            Some(reason),
+            ACTOR,
+        )
+        .await?;
+
+    tokio::spawn(replace_failed(
+        deployment.clone(),
+        policy.rollback,
+        config.clone(),
+    ));
+
+    Ok(())
+}
+
+async fn replace_failed(
+    deployment: deployments::Model,
+    rollback_enabled: bool,
+    config: DeployerConfig,
+) {
+    let deployment = &deployment;
+    let config = &config;
+
+    let rolled_back = if rollback_enabled {
+        match rollback(deployment, config).await {
+            Ok(rolled_back) => rolled_back,
+            Err(e) => {
+                error!("Rollback of deployment {} failed: {}", deployment.id, e);
+                false
+            }
+        }
+    } else {
+        false
+    };
+
+    if !rolled_back && let Some(ref router_tx) = config.router_tx {
+        let update = kennel_router::RouterUpdate::DeploymentRemoved {
+            domain: deployment.domain.clone(),
+        };
+
+        if let Err(e) = router_tx.send(update) {
+            warn!("Failed to send router update: {}", e);
+        }
+    }
+
+    config
+        .runtime
+        .uninstall(&utils::service_unit_name(deployment))
+        .await;
+
+    if let Err(e) = config
+        .store
+        .port_allocations()
+        .release_deployment_ports(deployment.id)
+        .await
+    {
+        warn!(
+            "Failed to release ports of deployment {}: {}",
+            deployment.id, e
+        );
+    }
+}
+
+/// Redeploy the most recent successful build that differs from the failing
+/// one. Returns false if there is nothing to roll back to.
+async fn rollback(deployment: &deployments::Model, config: &DeployerConfig) -> Result<bool> {
+    let candidates = config
+        .store
+        .build_results()
+        .find_recent_successful(
+            &deployment.project_name,
+            &deployment.git_ref,
+            &deployment.service_name,
+            ROLLBACK_CANDIDATES,
+        )
+        .await?;
+
+    let Some(previous) = rollback_target(
+        candidates,
+        deployment.build_id,
+        deployment.store_path.as_deref(),
+    ) else {
+        warn!(
+            "No previous build to roll back to for deployment {}",
+            deployment.id
+        );
+        return Ok(false);
+    };
+
+    info!(
+        "Rolling back {}/{}/{} to build {}",
+        deployment.project_name, deployment.git_ref, deployment.service_name, previous.build_id
+    );
+
+    let kennel_config = match load_kennel_config(config, Some(previous.build_id)).await {
+        Some(kennel_config) => kennel_config,
+        None => load_kennel_config(config, deployment.build_id)
+            .await
+            .ok_or_else(|| {
+                crate::DeployerError::Other(anyhow::anyhow!(
+                    "No kennel.toml available for build {}",
+                    previous.build_id
+                ))
+            })?,
+    };
+
+    let request = DeploymentRequest {
+        build_id: previous.build_id,
+        project_name: deployment.project_name.clone(),
+        git_ref: deployment.git_ref.clone(),
+        services: vec![deployment.service_name.clone()],
+        trigger: DeploymentTrigger::Rollback {
+            actor: ACTOR.to_string(),
+        },
+    };
+
+    let replacement = {
+        let _lock = config
+            .branch_locks
+            .lock(&request.project_name, &request.git_ref)
+            .await;
+
+        service::deploy_service(
+            &request,
+            &previous,
+            &deployment.environment,
+            config,
+            &kennel_config,
+        )
+        .await?
+    };
+
+    if let Err(e) = config
+        .store
+        .dns_records()
+        .reassign_deployment(deployment.id, replacement.id)
+        .await
+    {
+        warn!(
+            "Failed to move DNS records from deployment {}: {}",
+            deployment.id, e
+        );
+    }
+
+    config
+        .store
+        .deployment_events()
+        .record(
+            deployment,
+            DeploymentEventKind::RolledBack,
+            Some(previous.build_id),
+            Some(format!(
+                "Rolled back to build {} as deployment {}",
+                previous.build_id, replacement.id
+            )),
+            ACTOR,
+        )
+        .await?;
+
+    Ok(true)
+}
+
+/// The most recent of `candidates` that runs something other than the failing
+/// build and store path.
+fn rollback_target(
+    candidates: Vec<build_results::Model>,
+    failing_build: Option<i32>,
+    failing_store_path: Option<&str>,
+) -> Option<build_results::Model> {
+    candidates.into_iter().find(|r| {
+        Some(r.build_id) != failing_build
+            && r.store_path.is_some()
+            && r.store_path.as_deref() != failing_store_path
+    })
+}
+
+pub(crate) async fn load_kennel_config(
+    config: &DeployerConfig,
+    build_id: Option<i32>,
+) -> Option<KennelConfig> {
+    let build_id = build_id?;
+    let work_dir = config.paths.builds_dir.join(build_id.to_string());
+
+    match parse_kennel_toml(&work_dir).await {
+        Ok(kennel_config) => Some(kennel_config),
+        Err(e) => {
+            warn!("Failed to parse kennel.toml for build {}: {}", build_id, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entity::sea_orm_active_enums::BuildResultStatus;
+
+    fn policy(failure_threshold: u32, max_restarts: u32) -> RemediationConfig {
+        RemediationConfig {
+            failure_threshold,
+            max_restarts,
+            rollback: true,
+        }
+    }
+
+    fn result(build_id: i32, store_path: Option<&str>) -> build_results::Model {
+        build_results::Model {
+            id: build_id,
+            build_id,
+            service_name: "api".to_string(),
+            store_path: store_path.map(str::to_string),
+            status: BuildResultStatus::Success,
+            changed: true,
+            log_path: None,
+            started_at: None,
+            finished_at: None,
+            created_at: Default::default(),
+            error_message: None,
+        }
+    }
+
+    #[test]
+    fn test_restarts_until_exhausted_then_gives_up() {
+        let policy = policy(2, 2);
+        let mut state = RemediationState::default();
+
+        let mut actions = Vec::new();
+        for _ in 0..6 {
+            state.consecutive_failures += 1;
+            actions.push(state.next_action(&policy));
+        }
+
+        assert_eq!(
+            actions,
+            vec![
+                Action::Wait,
+                Action::Restart,
+                Action::Wait,
+                Action::Restart,
+                Action::Wait,
+                Action::GiveUp,
+            ]
+        );
+        assert_eq!(state.restarts, 2);
+    }
+
+    #[test]
+    fn test_gives_up_right_away_without_restarts() {
+        let mut state = RemediationState {
+            consecutive_failures: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(state.next_action(&policy(1, 0)), Action::GiveUp);
+        assert_eq!(state.restarts, 0);
+    }
+
+    #[test]
+    fn test_rollback_target_skips_the_failing_build() {
+        let candidates = vec![
+            result(3, Some("/nix/store/c-api")),
+            result(2, Some("/nix/store/b-api")),
+            result(1, Some("/nix/store/a-api")),
+        ];
+
+        let target = rollback_target(candidates.clone(), Some(3), Some("/nix/store/c-api"));
+        assert_eq!(target.map(|r| r.build_id), Some(2));
+
+        // A rebuild of the same output would fail the same way
+        let target = rollback_target(candidates, Some(4), Some("/nix/store/c-api"));
+        assert_eq!(target.map(|r| r.build_id), Some(2));
+
+        let target = rollback_target(
+            vec![result(3, Some("/nix/store/c-api")), result(2, None)],
+            Some(3),
+            Some("/nix/store/c-api"),
+        );
+        assert!(target.is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::error::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use kennel_config::ResourceLimits;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Everything a runtime needs to run one service deployment.
+#[derive(Debug, Clone)]
+pub struct ServiceSpec {
+    pub unit_name: String,
+    pub service_name: String,
+    /// Nix store path; the binary is `{store_path}/bin/{service_name}`.
+    pub store_path: String,
+    /// `$PORT` of each replica for HTTP services; workers listen on nothing.
+    pub ports: Vec<u16>,
+    /// Instances of the unit to run. More than one runs the unit as a
+    /// template with one instance per replica.
+    pub replicas: u32,
+    /// Name of the dynamic user. Shared by every deployment and replica of
+    /// the service on a branch, so they all own its state directory.
+    pub user: String,
+    /// Working directory relative to the runtime's state root (`/var/lib`
+    /// for systemd). Shared by every deployment of the service on a branch,
+    /// so it outlives blue-green swaps.
+    pub state_directory: PathBuf,
+    pub env: Vec<(String, String)>,
+    pub env_file: Option<PathBuf>,
+    /// Effective limits after host defaults and environment caps.
+    pub resources: ResourceLimits,
+    /// `OnCalendar` schedule for jobs. A job runs to completion each time
+    /// its timer fires; starting it arms the timer.
+    pub schedule: Option<String>,
+}
+
+impl ServiceSpec {
+    pub fn bin_dir(&self) -> PathBuf {
+        PathBuf::from(&self.store_path).join("bin")
+    }
+
+    pub fn exec_path(&self) -> PathBuf {
+        self.bin_dir().join(&self.service_name)
+    }
+
+    pub fn is_replicated(&self) -> bool {
+        self.replicas > 1
+    }
+
+    /// Instance names of a replicated unit: the port of each HTTP replica,
+    /// or the index of each worker replica.
+    pub fn instances(&self) -> Vec<String> {
+        if self.ports.is_empty() {
+            (0..self.replicas).map(|i| i.to_string()).collect()
+        } else {
+            self.ports.iter().map(u16::to_string).collect()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitStatus {
+    Running,
+    Stopped,
+    Failed,
+    NotFound,
+}
+
+/// Which output of a unit to read.
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Only entries whose message matches this pattern.
+    pub grep: Option<String>,
+    /// Keep the last `lines` matching entries.
+    pub lines: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// When the line was written, if the runtime records it.
+    pub timestamp: Option<DateTime<Utc>>,
+    pub message: String,
+}
+
+/// The most recent completed run of a scheduled job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobRun {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub succeeded: bool,
+    pub exit_code: Option<i32>,
+}
+
+/// How a release command finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseRun {
+    pub succeeded: bool,
+    pub exit_code: Option<i32>,
+    /// The command was killed for running past its timeout.
+    pub timed_out: bool,
+    /// The last lines the command wrote to stdout and stderr.
+    pub log: String,
+}
+
+/// Where and how service deployments are run. The deployer only talks to
+/// this trait, so deploys and teardowns can run against a fake in tests.
+#[async_trait]
+pub trait ServiceRuntime: Send + Sync {
+    /// Install and enable the unit described by `spec` without starting it.
+    async fn install(&self, spec: &ServiceSpec) -> Result<()>;
+
+    async fn start(&self, unit_name: &str) -> Result<()>;
+
+    async fn stop(&self, unit_name: &str) -> Result<()>;
+
+    async fn restart(&self, unit_name: &str) -> Result<()> {
+        self.stop(unit_name).await?;
+        self.start(unit_name).await
+    }
+
+    /// Restart only the replica listening on `port`, or the whole unit if it
+    /// is not replicated.
+    async fn restart_replica(&self, unit_name: &str, port: u16) -> Result<()>;
+
+    /// Run `command` once through the shell with the environment, user and
+    /// state directory of `spec`, with the package's `bin` on `PATH`. The
+    /// command is killed once `timeout` elapses.
+    async fn run_release(
+        &self,
+        spec: &ServiceSpec,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<ReleaseRun>;
+
+    /// Stop and remove a unit, logging rather than failing on each step.
+    async fn uninstall(&self, unit_name: &str);
+
+    /// Delete the state directory of an installed, stopped unit.
+    async fn clean_state(&self, unit_name: &str) -> Result<()>;
+
+    async fn status(&self, unit_name: &str) -> Result<UnitStatus>;
+
+    /// Output from the unit matching `query`, oldest first.
+    async fn logs(&self, unit_name: &str, query: &LogQuery) -> Result<Vec<LogEntry>>;
+
+    /// The entries `logs` would return, then new entries as they are
+    /// written. Following stops when the receiver is dropped.
+    async fn follow_logs(
+        &self,
+        unit_name: &str,
+        query: &LogQuery,
+    ) -> Result<mpsc::Receiver<LogEntry>>;
+
+    /// The last completed run of a job unit, if it has finished one.
+    async fn last_run(&self, unit_name: &str) -> Result<Option<JobRun>>;
+
+    /// Names of all kennel-managed units known to the runtime.
+    async fn list_units(&self) -> Result<Vec<String>>;
+}
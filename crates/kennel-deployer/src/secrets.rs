@@ -1,15 +1,49 @@
+use crate::DeployerConfig;
 use crate::error::Result;
+use entity::deployments;
 use std::path::{Path, PathBuf};
 use tracing::info;
 
+/// The value of secret `name` for the environment `deployment` runs in, so
+/// previews never see production credentials.
+pub async fn read_secret(
+    config: &DeployerConfig,
+    deployment: &deployments::Model,
+    name: &str,
+) -> Result<String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(crate::DeployerError::Other(anyhow::anyhow!(
+            "Invalid secret name '{}'",
+            name
+        )));
+    }
+
+    let path = config
+        .paths
+        .secret_sources_dir
+        .join(&deployment.project_name)
+        .join(&deployment.environment)
+        .join(name);
+
+    match tokio::fs::read_to_string(&path).await {
+        Ok(value) => Ok(value.trim().to_string()),
+        Err(e) => Err(crate::DeployerError::Other(anyhow::anyhow!(
+            "Failed to read secret {} from {}: {}",
+            name,
+            path.display(),
+            e
+        ))),
+    }
+}
+
 pub async fn generate_env_file(
+    secrets_dir: &Path,
     project: &str,
     branch: &str,
     service: &str,
     env_vars: &[(String, String)],
 ) -> Result<PathBuf> {
-    let secrets_dir = Path::new(kennel_config::constants::SECRETS_DIR);
-    tokio::fs::create_dir_all(&secrets_dir).await?;
+    tokio::fs::create_dir_all(secrets_dir).await?;
 
     let filename = format!("{}-{}-{}.env", project, branch, service);
     let secrets_path = secrets_dir.join(&filename);
@@ -1,30 +1,31 @@
 use crate::error::Result;
+use crate::runtime::ServiceSpec;
 use crate::{
-    DeployerConfig, DeploymentRequest, health, secrets, static_site, systemd, user, utils,
+    DeployerConfig, DeploymentRequest, DeploymentTrigger, freeze, health, secrets, static_site,
+    utils,
 };
-use entity::sea_orm_active_enums::DeploymentStatus;
-use entity::{build_results, deployments};
-use kennel_config::parse_kennel_toml;
+use entity::sea_orm_active_enums::{
+    DeploymentEventKind, DeploymentStatus, LoadBalancing, ServiceType,
+};
+use entity::{build_results, builds, deployments, services};
+use kennel_config::{ServiceKind, parse_kennel_toml};
+use kennel_forge::{StatusState, deploy_context};
+use kennel_store::project_environment;
 use sea_orm::IntoActiveModel;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::{error, info, warn};
 
-pub(crate) fn determine_environment(git_ref: &str) -> String {
-    match git_ref {
-        "main" => "prod".to_string(),
-        "staging" => "staging".to_string(),
-        "dev" => "dev".to_string(),
-        s if s.starts_with("pr-") => "preview".to_string(),
-        _ => "dev".to_string(),
-    }
-}
-
 pub async fn deploy_build(request: &DeploymentRequest, config: &DeployerConfig) -> Result<()> {
-    let build_results = config
+    let mut build_results: Vec<_> = config
         .store
         .build_results()
         .find_successful_by_build_id(request.build_id)
-        .await?;
+        .await?
+        .into_iter()
+        .filter(|r| request.services.is_empty() || request.services.contains(&r.service_name))
+        .collect();
 
     if build_results.is_empty() {
         warn!(
@@ -34,64 +35,515 @@ pub async fn deploy_build(request: &DeploymentRequest, config: &DeployerConfig)
         return Ok(());
     }
 
-    let _build = config
+    let build = config
         .store
         .builds()
         .find_by_id(request.build_id)
         .await?
         .ok_or_else(|| crate::DeployerError::NotFound(format!("Build {}", request.build_id)))?;
 
-    let work_dir = PathBuf::from(kennel_config::constants::DEFAULT_WORK_DIR)
-        .join(request.build_id.to_string());
+    let work_dir = config.paths.builds_dir.join(request.build_id.to_string());
+
+    // Without kennel.toml every output would be deployed as a service
+    if !work_dir.join("kennel.toml").exists() {
+        return Err(crate::DeployerError::NotFound(format!(
+            "kennel.toml for build {}",
+            request.build_id
+        )));
+    }
+
     let config_file = parse_kennel_toml(&work_dir).await.map_err(|e| {
         crate::DeployerError::Other(anyhow::anyhow!("Failed to parse kennel.toml: {}", e))
     })?;
 
+    let service_order = config_file
+        .service_order()
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
+
+    // Services come after what they depend on; sites and jobs go last
+    build_results.sort_by_key(|r| {
+        service_order
+            .iter()
+            .position(|name| *name == r.service_name)
+            .unwrap_or(usize::MAX)
+    });
+
     info!(
         "Deploying {} items for build {}",
         build_results.len(),
         request.build_id
     );
 
+    let project = config
+        .store
+        .projects()
+        .find_by_name(&request.project_name)
+        .await
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?
+        .ok_or_else(|| {
+            crate::DeployerError::NotFound(format!("Project {}", request.project_name))
+        })?;
+
+    let environment = project_environment(&project, &request.git_ref);
+
+    if !matches!(request.trigger, DeploymentTrigger::OverrideFreeze { .. })
+        && let Some(window) = freeze::active_freeze(&config.store, &project, &environment).await?
+    {
+        let reason = freeze::freeze_reason(&window, &environment);
+
+        // Builds wait for the freeze to end; manual deployments are refused
+        if request.trigger == DeploymentTrigger::Build {
+            info!("Holding build {}: {}", request.build_id, reason);
+            config
+                .store
+                .builds()
+                .hold_for_freeze(build.clone(), &reason)
+                .await?;
+            for build_result in &build_results {
+                report_deploy(
+                    config,
+                    &build,
+                    &build_result.service_name,
+                    StatusState::Pending,
+                    &reason,
+                    None,
+                )
+                .await;
+            }
+            return Ok(());
+        }
+
+        return Err(crate::DeployerError::Frozen(reason));
+    }
+
+    let mut deployed = HashSet::new();
+    let mut moved = HashSet::new();
+    let mut failed = HashSet::new();
+
     for build_result in build_results {
+        if let Some(dependency) = config_file
+            .services
+            .get(&build_result.service_name)
+            .and_then(|s| s.depends_on.iter().find(|d| failed.contains(*d)))
+        {
+            error!(
+                "Skipping service '{}' from build {}: its dependency '{}' failed to deploy",
+                build_result.service_name, request.build_id, dependency
+            );
+            failed.insert(build_result.service_name.clone());
+            report_deploy(
+                config,
+                &build,
+                &build_result.service_name,
+                StatusState::Failure,
+                &format!("Dependency '{}' failed to deploy", dependency),
+                None,
+            )
+            .await;
+            continue;
+        }
+
+        if let Some(job) = config_file.jobs.get(&build_result.service_name)
+            && job.default_branch_only
+            && request.git_ref != project.default_branch
+        {
+            info!(
+                "Skipping job '{}' on {}: it only runs on {}",
+                build_result.service_name, request.git_ref, project.default_branch
+            );
+            continue;
+        }
+
         let is_static_site = config_file
             .static_sites
             .contains_key(&build_result.service_name);
 
-        if is_static_site {
-            if let Err(e) = static_site::deploy_site(
+        if let Err(e) = register_service(
+            &request.project_name,
+            &build_result.service_name,
+            &config_file,
+            config,
+        )
+        .await
+        {
+            error!(
+                "Failed to register service '{}' for {}: {}",
+                build_result.service_name, request.project_name, e
+            );
+            failed.insert(build_result.service_name.clone());
+            report_deploy(
+                config,
+                &build,
+                &build_result.service_name,
+                StatusState::Failure,
+                &e.to_string(),
+                None,
+            )
+            .await;
+            continue;
+        }
+
+        report_deploy(
+            config,
+            &build,
+            &build_result.service_name,
+            StatusState::Pending,
+            &format!("Deploying to {}", environment),
+            None,
+        )
+        .await;
+
+        let result = if is_static_site {
+            static_site::deploy_site(
                 request,
                 &build_result,
+                &environment,
                 &config.store,
                 config,
                 &config_file,
             )
             .await
-            {
+        } else {
+            deploy_service(request, &build_result, &environment, config, &config_file).await
+        };
+
+        match result {
+            Ok(deployment) => {
+                record_deployment(request, &build, &deployment, config).await;
+                // Only services with a port and static sites answer on their domain
+                let url = (is_static_site || deployment.port.is_some())
+                    .then(|| format!("https://{}", deployment.domain));
+                report_deploy(
+                    config,
+                    &build,
+                    &build_result.service_name,
+                    StatusState::Success,
+                    &format!("Deployed to {}", environment),
+                    url,
+                )
+                .await;
+                deployed.insert(build_result.service_name.clone());
+                if deployment.port.is_some() {
+                    moved.insert(build_result.service_name.clone());
+                }
+            }
+            Err(e) => {
+                failed.insert(build_result.service_name.clone());
+                report_deploy(
+                    config,
+                    &build,
+                    &build_result.service_name,
+                    StatusState::Failure,
+                    &e.to_string(),
+                    None,
+                )
+                .await;
                 error!(
-                    "Failed to deploy static site '{}' from build {}: {}",
-                    build_result.service_name, request.build_id, e
+                    "Failed to deploy {} '{}' from build {}: {}",
+                    if is_static_site {
+                        "static site"
+                    } else {
+                        "service"
+                    },
+                    build_result.service_name,
+                    request.build_id,
+                    e
                 );
             }
-        } else {
-            if let Err(e) = deploy_service(request, &build_result, config, &config_file).await {
-                error!(
-                    "Failed to deploy service '{}' from build {}: {}",
-                    build_result.service_name, request.build_id, e
+        }
+    }
+
+    refresh_dependents(request, &config_file, &deployed, &moved, config).await;
+
+    if let Some(forge) = &config.forge {
+        forge
+            .update_preview_comment(&project.name, &request.git_ref)
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Post a service's deployment status on the build's commit, linking to
+/// `target_url` when the service has one.
+async fn report_deploy(
+    config: &DeployerConfig,
+    build: &builds::Model,
+    service_name: &str,
+    state: StatusState,
+    description: &str,
+    target_url: Option<String>,
+) {
+    if let Some(forge) = &config.forge {
+        forge
+            .report(
+                build,
+                deploy_context(service_name),
+                state,
+                description,
+                target_url,
+            )
+            .await;
+    }
+}
+
+/// The branch and environment a promoted deployment's build came from.
+pub(crate) fn provenance(request: &DeploymentRequest) -> (Option<String>, Option<String>) {
+    match &request.trigger {
+        DeploymentTrigger::Promote {
+            from_branch,
+            from_environment,
+            ..
+        } => (Some(from_branch.clone()), Some(from_environment.clone())),
+        _ => (None, None),
+    }
+}
+
+/// Restart live services on the branch that were not deployed just now but
+/// depend on a service that `moved` to a new port, so their discovery
+/// variables point at it.
+async fn refresh_dependents(
+    request: &DeploymentRequest,
+    config_file: &kennel_config::KennelConfig,
+    deployed: &HashSet<String>,
+    moved: &HashSet<String>,
+    config: &DeployerConfig,
+) {
+    for (service_name, service_config) in &config_file.services {
+        if deployed.contains(service_name)
+            || !service_config.depends_on.iter().any(|d| moved.contains(d))
+        {
+            continue;
+        }
+
+        let deployment = match config
+            .store
+            .deployments()
+            .find_active_by_ref(&request.project_name, &request.git_ref, service_name)
+            .await
+        {
+            Ok(Some(deployment)) => deployment,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!(
+                    "Failed to look up deployment of '{}' to refresh: {}",
+                    service_name, e
                 );
+                continue;
             }
+        };
+
+        let unit_name = utils::service_unit_name(&deployment);
+        info!(
+            "Restarting {} to pick up its dependencies' new addresses",
+            unit_name
+        );
+
+        let port = deployment.port.map(|port| port as u16);
+        if let Err(e) = write_env_file(config, config_file, &deployment, port).await {
+            warn!("Failed to rewrite env file of {}: {}", unit_name, e);
+            continue;
+        }
+
+        // An idle deployment reads the new file when it is woken
+        if deployment.idle_since.is_none()
+            && let Err(e) = config.runtime.restart(&unit_name).await
+        {
+            warn!("Failed to restart {}: {}", unit_name, e);
         }
     }
+}
+
+/// Keep the project's service row in line with kennel.toml; deployments
+/// reference it.
+async fn register_service(
+    project_name: &str,
+    service_name: &str,
+    config_file: &kennel_config::KennelConfig,
+    config: &DeployerConfig,
+) -> Result<()> {
+    use sea_orm::ActiveValue::Set;
+
+    let service = if let Some(job) = config_file.jobs.get(service_name) {
+        services::ActiveModel {
+            r#type: Set(ServiceType::Job),
+            package: Set(job
+                .flake_output
+                .clone()
+                .unwrap_or_else(|| service_name.to_string())),
+            health_check: Set(None),
+            custom_domain: Set(None),
+            spa: Set(false),
+            ..Default::default()
+        }
+    } else {
+        match config_file.static_sites.get(service_name) {
+            Some(site) => services::ActiveModel {
+                r#type: Set(ServiceType::Static),
+                package: Set(site
+                    .flake_output
+                    .clone()
+                    .unwrap_or_else(|| service_name.to_string())),
+                health_check: Set(None),
+                custom_domain: Set(site.custom_domain.clone()),
+                spa: Set(site.spa),
+                ..Default::default()
+            },
+            None => {
+                let service_config = config_file.services.get(service_name);
+                let is_worker = service_config.is_some_and(|s| s.kind == ServiceKind::Worker);
+                services::ActiveModel {
+                    r#type: Set(if is_worker {
+                        ServiceType::Worker
+                    } else {
+                        ServiceType::Service
+                    }),
+                    package: Set(service_config
+                        .and_then(|s| s.flake_output.clone())
+                        .unwrap_or_else(|| service_name.to_string())),
+                    health_check: Set(service_config
+                        .filter(|_| !is_worker)
+                        .map(|s| s.health_check_path.clone())),
+                    custom_domain: Set(service_config.and_then(|s| s.custom_domain.clone())),
+                    spa: Set(false),
+                    load_balancing: Set(load_balancing(service_config)),
+                    idle_timeout_secs: Set(service_config
+                        .filter(|_| !is_worker)
+                        .and_then(|s| s.idle_timeout_secs)
+                        .map(|secs| secs.min(i32::MAX as u64) as i32)),
+                    ..Default::default()
+                }
+            }
+        }
+    };
+
+    config
+        .store
+        .services()
+        .upsert(services::ActiveModel {
+            project_name: Set(project_name.to_string()),
+            name: Set(service_name.to_string()),
+            ..service
+        })
+        .await
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
 
     Ok(())
 }
 
-async fn deploy_service(
+fn load_balancing(service_config: Option<&kennel_config::ServiceConfig>) -> LoadBalancing {
+    match service_config.map(|s| &s.load_balancing) {
+        Some(kennel_config::LoadBalancing::LeastConnections) => LoadBalancing::LeastConnections,
+        Some(kennel_config::LoadBalancing::RoundRobin) | None => LoadBalancing::RoundRobin,
+    }
+}
+
+async fn record_deployment(
+    request: &DeploymentRequest,
+    build: &builds::Model,
+    deployment: &deployments::Model,
+    config: &DeployerConfig,
+) {
+    let (kind, message, actor) = match &request.trigger {
+        DeploymentTrigger::Build => (
+            DeploymentEventKind::Deployed,
+            match &build.reviewed_by {
+                Some(reviewer) => format!(
+                    "Deployed commit {}, approved by {}",
+                    build.commit_sha, reviewer
+                ),
+                None => format!("Deployed commit {}", build.commit_sha),
+            },
+            build.author.clone().unwrap_or_else(|| "kennel".to_string()),
+        ),
+        DeploymentTrigger::Rollback { actor } => (
+            DeploymentEventKind::RolledBack,
+            format!("Rolled back to build {}", request.build_id),
+            actor.clone(),
+        ),
+        DeploymentTrigger::Restore { actor } => (
+            DeploymentEventKind::Restored,
+            format!("Restored build {} after expiry", request.build_id),
+            actor.clone(),
+        ),
+        DeploymentTrigger::Promote {
+            actor,
+            from_branch,
+            from_environment,
+        } => (
+            DeploymentEventKind::Promoted,
+            format!(
+                "Promoted build {} from {} ({})",
+                request.build_id, from_environment, from_branch
+            ),
+            actor.clone(),
+        ),
+        DeploymentTrigger::OverrideFreeze { actor } => (
+            DeploymentEventKind::FreezeOverridden,
+            format!(
+                "Deployed build {} during a deploy freeze, overriding it",
+                request.build_id
+            ),
+            actor.clone(),
+        ),
+    };
+
+    if let Err(e) = config
+        .store
+        .deployment_events()
+        .record(
+            deployment,
+            kind,
+            Some(request.build_id),
+            Some(message),
+            &actor,
+        )
+        .await
+    {
+        warn!(
+            "Failed to record deployment history for {}: {}",
+            deployment.id, e
+        );
+    }
+}
+
+/// Record that `old` was superseded by `new` in a blue-green swap.
+pub(crate) async fn record_replaced(
+    old: &deployments::Model,
+    new: &deployments::Model,
+    config: &DeployerConfig,
+) {
+    if let Err(e) = config
+        .store
+        .deployment_events()
+        .record(
+            old,
+            DeploymentEventKind::Replaced,
+            old.build_id,
+            Some(format!(
+                "Replaced by deployment {} (build {})",
+                new.id,
+                new.build_id
+                    .map_or_else(|| "unknown".to_string(), |id| id.to_string())
+            )),
+            "kennel",
+        )
+        .await
+    {
+        warn!(
+            "Failed to record replacement of deployment {}: {}",
+            old.id, e
+        );
+    }
+}
+
+pub(crate) async fn deploy_service(
     request: &DeploymentRequest,
     build_result: &build_results::Model,
+    environment: &str,
     config: &DeployerConfig,
     config_file: &kennel_config::KennelConfig,
-) -> Result<()> {
+) -> Result<deployments::Model> {
     let store_path = build_result
         .store_path
         .as_ref()
@@ -102,7 +554,7 @@ async fn deploy_service(
         build_result.service_name, store_path
     );
 
-    let branch_sanitized = utils::sanitize_identifier(&request.git_ref);
+    let branch_sanitized = utils::ref_slug(&request.git_ref);
 
     // Check for existing active deployment (blue-green)
     let existing_deployment = config
@@ -116,126 +568,47 @@ async fn deploy_service(
         .await
         .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
 
-    let unit_name = format!(
-        "kennel-{}-{}-{}",
-        request.project_name, branch_sanitized, build_result.service_name
-    );
+    // Blue and green units share the user so either can own the state directory
     let username = utils::sanitize_username(
         &request.project_name,
         &branch_sanitized,
         &build_result.service_name,
     );
 
-    user::ensure_user_exists(&username).await?;
-
-    let work_dir = PathBuf::from(kennel_config::constants::SERVICES_BASE_DIR)
+    let state_directory = PathBuf::from(kennel_config::constants::SERVICES_STATE_DIR)
         .join(&request.project_name)
         .join(&branch_sanitized)
         .join(&build_result.service_name);
-    tokio::fs::create_dir_all(&work_dir).await?;
-
-    let port = config
-        .store
-        .port_allocations()
-        .find_available_port()
-        .await
-        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))? as u16;
-
-    // Check if service needs preview database
-    let service_config = config_file.services.get(&build_result.service_name);
 
-    let preview_db_num = if service_config.map(|s| s.preview_database).unwrap_or(false) {
-        // Allocate preview database
-        match config
-            .store
-            .preview_databases()
-            .allocate(&request.project_name, &request.git_ref)
-            .await
-        {
-            Ok(db_num) => {
-                info!(
-                    "Allocated preview database {} for {}/{}",
-                    db_num, request.project_name, request.git_ref
-                );
-                Some(db_num)
-            }
-            Err(e) => {
-                error!("Failed to allocate preview database: {}", e);
-                return Err(crate::DeployerError::Other(anyhow::anyhow!(
-                    "Failed to allocate preview database: {}",
-                    e
-                )));
-            }
-        }
+    let service_type = if config_file.jobs.contains_key(&build_result.service_name) {
+        ServiceType::Job
+    } else if config_file
+        .services
+        .get(&build_result.service_name)
+        .is_some_and(|s| s.kind == ServiceKind::Worker)
+    {
+        ServiceType::Worker
     } else {
-        None
+        ServiceType::Service
     };
 
-    let env_vars = vec![("PORT".to_string(), port.to_string())];
-
-    let mut env_vars_with_db = env_vars.clone();
-    if let Some(db_num) = preview_db_num {
-        env_vars_with_db.push((
-            "VALKEY_URL".to_string(),
-            format!("redis://127.0.0.1:6379/{}", db_num),
-        ));
-        env_vars_with_db.push((
-            "DATABASE_URL".to_string(),
-            format!(
-                "postgresql://127.0.0.1:5432/{}_{}",
-                request.project_name.replace('-', "_"),
-                branch_sanitized.replace('-', "_")
-            ),
-        ));
-    }
-
-    let secrets_path = secrets::generate_env_file(
-        &request.project_name,
-        &branch_sanitized,
-        &build_result.service_name,
-        &env_vars_with_db,
-    )
-    .await?;
-
-    let unit_content = systemd::generate_service_unit(
-        &build_result.service_name,
-        store_path,
-        port,
-        &username,
-        &work_dir,
-        &[],
-        Some(&secrets_path),
-    );
-
-    systemd::install_unit(&unit_name, &unit_content).await?;
-    systemd::daemon_reload().await?;
-    systemd::enable_unit(&unit_name).await?;
-    systemd::start_unit(&unit_name).await?;
-
-    let service_config = config_file.services.get(&build_result.service_name);
-    let health_check_path = service_config
-        .map(|s| s.health_check_path.as_str())
-        .unwrap_or("/health");
-    let health_check_timeout = service_config
-        .map(|s| s.health_check_timeout_secs)
-        .unwrap_or(30);
-
-    if let Err(e) = health::check_health(port, health_check_path, health_check_timeout).await {
-        error!("Health check failed for {}: {}", unit_name, e);
-        systemd::stop_unit(&unit_name).await?;
-        return Err(e);
-    }
+    let (promoted_from_branch, promoted_from_environment) = provenance(request);
 
+    // Reserve the deployment row first: its id names the unit and owns the port
     let deployment = deployments::ActiveModel {
         project_name: sea_orm::ActiveValue::Set(request.project_name.clone()),
         git_ref: sea_orm::ActiveValue::Set(request.git_ref.clone()),
         service_name: sea_orm::ActiveValue::Set(build_result.service_name.clone()),
         branch: sea_orm::ActiveValue::Set(request.git_ref.clone()),
         branch_slug: sea_orm::ActiveValue::Set(branch_sanitized.clone()),
-        environment: sea_orm::ActiveValue::Set(determine_environment(&request.git_ref)),
+        environment: sea_orm::ActiveValue::Set(environment.to_string()),
         store_path: sea_orm::ActiveValue::Set(Some(store_path.clone())),
-        port: sea_orm::ActiveValue::Set(Some(port as i32)),
-        status: sea_orm::ActiveValue::Set(DeploymentStatus::Active),
+        port: sea_orm::ActiveValue::Set(None),
+        service_type: sea_orm::ActiveValue::Set(service_type),
+        status: sea_orm::ActiveValue::Set(DeploymentStatus::Pending),
+        promoted_from_branch: sea_orm::ActiveValue::Set(promoted_from_branch),
+        promoted_from_environment: sea_orm::ActiveValue::Set(promoted_from_environment),
+        build_id: sea_orm::ActiveValue::Set(Some(request.build_id)),
         domain: sea_orm::ActiveValue::Set(utils::generate_deployment_domain(
             &build_result.service_name,
             &branch_sanitized,
@@ -252,27 +625,102 @@ async fn deploy_service(
         .await
         .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
 
-    config
+    let unit_name = utils::service_unit_name(&new_deployment);
+
+    let ports = match start_service(
+        request,
+        build_result,
+        config,
+        config_file,
+        &new_deployment,
+        &username,
+        &state_directory,
+    )
+    .await
+    {
+        Ok(ports) => ports,
+        Err(e) => {
+            config.runtime.uninstall(&unit_name).await;
+
+            let _ = config
+                .store
+                .port_allocations()
+                .release_deployment_ports(new_deployment.id)
+                .await;
+
+            if let Err(mark_err) = config
+                .store
+                .deployments()
+                .mark_failed(new_deployment.id, &e.to_string())
+                .await
+            {
+                warn!(
+                    "Failed to mark deployment {} as failed: {}",
+                    new_deployment.id, mark_err
+                );
+            }
+
+            if let Err(record_err) = config
+                .store
+                .deployment_events()
+                .record(
+                    &new_deployment,
+                    DeploymentEventKind::Failed,
+                    Some(request.build_id),
+                    Some(e.to_string()),
+                    "kennel",
+                )
+                .await
+            {
+                warn!(
+                    "Failed to record failure of deployment {}: {}",
+                    new_deployment.id, record_err
+                );
+            }
+
+            return Err(e);
+        }
+    };
+
+    // The first replica's port stands for the deployment
+    let port = ports.first().copied();
+    let mut active = new_deployment.into_active_model();
+    active.port = sea_orm::ActiveValue::Set(port.map(i32::from));
+    active.status = sea_orm::ActiveValue::Set(DeploymentStatus::Active);
+    let new_deployment = config
         .store
-        .port_allocations()
-        .allocate_for_deployment(
-            port as i32,
-            new_deployment.id,
-            &request.project_name,
-            &build_result.service_name,
-            &branch_sanitized,
-        )
+        .deployments()
+        .update(active)
         .await
         .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
 
-    info!(
-        "Successfully deployed service '{}' on port {}",
-        build_result.service_name, port
-    );
+    match port {
+        Some(_) => info!(
+            "Successfully deployed service '{}' on port(s) {:?}",
+            build_result.service_name, ports
+        ),
+        None => info!(
+            "Successfully deployed worker '{}'",
+            build_result.service_name
+        ),
+    }
 
-    // Create DNS records for custom domain if configured
+    // Carry DNS records over from the old deployment, or create them if configured
     let service_config = config_file.services.get(&build_result.service_name);
-    if let Some(dns_manager) = &config.dns_manager
+    if let Some(old_deployment) = &existing_deployment {
+        if let Err(e) = config
+            .store
+            .dns_records()
+            .reassign_deployment(old_deployment.id, new_deployment.id)
+            .await
+        {
+            warn!(
+                "Failed to move DNS records from deployment {}: {}",
+                old_deployment.id, e
+            );
+        }
+    } else if let Some(dns_manager) = &config.dns_manager
+        && port.is_some()
         && let Some(custom_domain) = service_config.and_then(|s| s.custom_domain.as_ref())
     {
         info!("Creating DNS records for custom domain: {}", custom_domain);
@@ -295,12 +743,15 @@ async fn deploy_service(
         }
     }
 
-    // Notify router of new deployment
-    if let Some(ref router_tx) = config.router_tx {
+    // Notify router of new deployment; workers are not routed
+    if let Some(ref router_tx) = config.router_tx
+        && !ports.is_empty()
+    {
         let update = kennel_router::RouterUpdate::DeploymentActive {
             deployment_id: new_deployment.id,
             domain: new_deployment.domain.clone(),
-            port: Some(port),
+            ports: ports.clone(),
+            load_balancing: load_balancing(service_config),
             store_path: Some(store_path.clone()),
             spa: false,
         };
@@ -310,54 +761,429 @@ async fn deploy_service(
         }
     }
 
-    // Drain and tear down old deployment (blue-green)
+    // Stop routing to the old deployment right away and drain it in the background
     if let Some(old_deployment) = existing_deployment {
-        let old_deployment_id = old_deployment.id;
-        let old_port = old_deployment.port;
-
-        info!(
-            "Blue-green deployment: waiting 30s to drain connections for old deployment {}",
-            old_deployment_id
+        let drain_timeout = Duration::from_secs(
+            service_config
+                .map(|s| s.drain_timeout_secs)
+                .unwrap_or(kennel_config::constants::BLUE_GREEN_DRAIN_TIMEOUT.as_secs()),
         );
 
-        tokio::time::sleep(kennel_config::constants::BLUE_GREEN_DRAIN_TIMEOUT).await;
-
-        info!("Tearing down old deployment {}", old_deployment_id);
-
-        // Mark for teardown
-        let mut old_active = old_deployment.into_active_model();
-        old_active.status = sea_orm::ActiveValue::Set(DeploymentStatus::TearingDown);
         if let Err(e) = config
             .store
             .deployments()
-            .update(old_active)
+            .mark_ids_tearing_down(
+                &[old_deployment.id],
+                &format!(
+                    "Draining after replacement by deployment {}",
+                    new_deployment.id
+                ),
+            )
             .await
-            .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))
         {
-            error!("Failed to mark old deployment for teardown: {}", e);
+            warn!(
+                "Failed to mark old deployment {} as draining: {}",
+                old_deployment.id, e
+            );
         }
 
-        // Stop old service
-        let old_unit = format!(
-            "kennel-{}-{}-{}",
-            request.project_name, branch_sanitized, build_result.service_name
+        tokio::spawn(drain_replaced(
+            old_deployment,
+            new_deployment.clone(),
+            drain_timeout,
+            config.clone(),
+        ));
+    }
+
+    Ok(new_deployment)
+}
+
+/// Wait for the router to finish in-flight requests to a replaced deployment,
+/// then stop it and release its ports.
+async fn drain_replaced(
+    old: deployments::Model,
+    new: deployments::Model,
+    timeout: Duration,
+    config: DeployerConfig,
+) {
+    info!(
+        "Draining old deployment {} for up to {}s",
+        old.id,
+        timeout.as_secs()
+    );
+
+    if !config.in_flight.wait_idle(old.id, timeout).await {
+        warn!(
+            "Drain timeout reached for deployment {} with {} request(s) in flight",
+            old.id,
+            config.in_flight.count(old.id)
         );
+    }
+
+    info!("Tearing down old deployment {}", old.id);
+
+    config
+        .runtime
+        .uninstall(&utils::service_unit_name(&old))
+        .await;
+
+    if let Err(e) = config
+        .store
+        .port_allocations()
+        .release_deployment_ports(old.id)
+        .await
+    {
+        warn!("Failed to release ports of deployment {}: {}", old.id, e);
+    }
+
+    record_replaced(&old, &new, &config).await;
+
+    let old_id = old.id;
+    let mut old_active = old.into_active_model();
+    old_active.status = sea_orm::ActiveValue::Set(DeploymentStatus::TornDown);
+    if let Err(e) = config.store.deployments().update(old_active).await {
+        error!(
+            "Failed to mark old deployment {} as torn down: {}",
+            old_id, e
+        );
+    }
+}
+
+/// Allocate a port per replica, write the unit for `deployment` and start
+/// it, returning the ports once every replica passes its health check.
+/// Workers get no ports and only have to stay running through their grace
+/// period; jobs only have to have their timer armed.
+async fn start_service(
+    request: &DeploymentRequest,
+    build_result: &build_results::Model,
+    config: &DeployerConfig,
+    config_file: &kennel_config::KennelConfig,
+    deployment: &deployments::Model,
+    username: &str,
+    state_directory: &Path,
+) -> Result<Vec<u16>> {
+    let store_path = deployment
+        .store_path
+        .as_ref()
+        .ok_or_else(|| crate::DeployerError::Other(anyhow::anyhow!("No store path")))?;
+    let unit_name = utils::service_unit_name(deployment);
 
-        if let Err(e) = systemd::stop_unit(&old_unit).await {
-            warn!("Failed to stop old unit {}: {}", old_unit, e);
+    let service_config = config_file.services.get(&build_result.service_name);
+    let job = config_file.jobs.get(&build_result.service_name);
+    let is_worker = deployment.service_type == ServiceType::Worker;
+
+    // Jobs run once per activation, so they never have replicas
+    let replicas = match (job, service_config) {
+        (None, Some(service_config)) => service_config.replicas,
+        _ => 1,
+    };
+    if !(1..=kennel_config::constants::MAX_REPLICAS).contains(&replicas) {
+        return Err(crate::DeployerError::Other(anyhow::anyhow!(
+            "Service '{}' has {} replicas; it must have between 1 and {}",
+            build_result.service_name,
+            replicas,
+            kennel_config::constants::MAX_REPLICAS
+        )));
+    }
+
+    let mut ports = Vec::new();
+    if !is_worker && job.is_none() {
+        for _ in 0..replicas {
+            ports.push(
+                config
+                    .store
+                    .port_allocations()
+                    .allocate_port(
+                        deployment.id,
+                        &request.project_name,
+                        &build_result.service_name,
+                        &deployment.branch_slug,
+                    )
+                    .await
+                    .map_err(|e| crate::DeployerError::PortAllocation(e.to_string()))?
+                    as u16,
+            );
         }
+    }
+
+    let secrets_path =
+        write_env_file(config, config_file, deployment, ports.first().copied()).await?;
 
-        // Release old port if different from new one
-        if let Some(old_port_val) = old_port
-            && old_port_val as u16 != port
+    let resources = config.resources.effective(
+        &job.map(|j| j.resources.clone())
+            .or_else(|| service_config.map(|s| s.resources.clone()))
+            .unwrap_or_default(),
+        &deployment.environment,
+    );
+    resources.validate().map_err(|e| {
+        crate::DeployerError::Other(anyhow::anyhow!(
+            "Invalid resources for service '{}': {}",
+            build_result.service_name,
+            e
+        ))
+    })?;
+
+    let spec = ServiceSpec {
+        unit_name: unit_name.clone(),
+        service_name: build_result.service_name.clone(),
+        store_path: store_path.clone(),
+        ports: ports.clone(),
+        replicas,
+        user: username.to_string(),
+        state_directory: state_directory.to_path_buf(),
+        env: Vec::new(),
+        env_file: Some(secrets_path),
+        resources,
+        schedule: job.map(|j| j.schedule.clone()),
+    };
+
+    if let Some(service_config) = service_config
+        && let Some(command) = &service_config.release_command
+    {
+        run_release_command(
+            config,
+            deployment,
+            &spec,
+            command,
+            Duration::from_secs(service_config.release_timeout_secs),
+        )
+        .await?;
+    }
+
+    config.runtime.install(&spec).await?;
+    config.runtime.start(&unit_name).await?;
+
+    let health = match ports.as_slice() {
+        // A job only has to have its timer armed
+        [] if job.is_some() => {
+            health::check_stays_active(config.runtime.as_ref(), &unit_name, Duration::ZERO).await
+        }
+        [] => {
+            let grace_period = Duration::from_secs(
+                service_config
+                    .map(|s| s.grace_period_secs)
+                    .unwrap_or(kennel_config::constants::WORKER_GRACE_PERIOD.as_secs()),
+            );
+
+            health::check_stays_active(config.runtime.as_ref(), &unit_name, grace_period).await
+        }
+        ports => {
+            let health_check_path = service_config
+                .map(|s| s.health_check_path.as_str())
+                .unwrap_or("/health");
+            let health_check_timeout = service_config
+                .map(|s| s.health_check_timeout_secs)
+                .unwrap_or(30);
+
+            // Every replica has to come up before any of them gets traffic
+            let mut health = Ok(());
+            for &port in ports {
+                health = health::check_health(port, health_check_path, health_check_timeout).await;
+                if health.is_err() {
+                    break;
+                }
+            }
+            health
+        }
+    };
+
+    if let Err(e) = health {
+        error!("Health check failed for {}: {}", unit_name, e);
+        return Err(e);
+    }
+
+    Ok(ports)
+}
+
+/// Write the env file of `deployment`: its preview database, job env and
+/// the addresses of the other services on its branch, with the deployment
+/// itself reachable on `port`. `$PORT` is left to the runtime, which gives
+/// each replica its own.
+async fn write_env_file(
+    config: &DeployerConfig,
+    config_file: &kennel_config::KennelConfig,
+    deployment: &deployments::Model,
+    port: Option<u16>,
+) -> Result<PathBuf> {
+    let service_config = config_file.services.get(&deployment.service_name);
+    let job = config_file.jobs.get(&deployment.service_name);
+    let branch_sanitized = &deployment.branch_slug;
+
+    // Check if service needs preview database
+    let preview_db_num = if service_config.map(|s| s.preview_database).unwrap_or(false) {
+        // Allocate preview database
+        match config
+            .store
+            .preview_databases()
+            .allocate(&deployment.project_name, &deployment.branch)
+            .await
         {
-            let _ = config
-                .store
-                .port_allocations()
-                .release_port(old_port_val)
-                .await;
+            Ok(db_num) => {
+                info!(
+                    "Allocated preview database {} for {}/{}",
+                    db_num, deployment.project_name, deployment.branch
+                );
+                Some(db_num)
+            }
+            Err(e) => {
+                error!("Failed to allocate preview database: {}", e);
+                return Err(crate::DeployerError::Other(anyhow::anyhow!(
+                    "Failed to allocate preview database: {}",
+                    e
+                )));
+            }
         }
+    } else {
+        None
+    };
+
+    let mut env_vars = vec![(
+        "KENNEL_ENVIRONMENT".to_string(),
+        deployment.environment.clone(),
+    )];
+
+    if let Some(db_num) = preview_db_num {
+        env_vars.push((
+            "VALKEY_URL".to_string(),
+            format!("redis://127.0.0.1:6379/{}", db_num),
+        ));
+        env_vars.push((
+            "DATABASE_URL".to_string(),
+            format!(
+                "postgresql://127.0.0.1:5432/{}_{}",
+                deployment.project_name.replace('-', "_"),
+                branch_sanitized.replace('-', "_")
+            ),
+        ));
+    }
+    if let Some(job) = job {
+        env_vars.extend(job.env.iter().map(|(k, v)| (k.clone(), v.clone())));
     }
+    let secret_names = job
+        .map(|j| &j.secrets)
+        .or(service_config.map(|s| &s.secrets));
+    for name in secret_names.into_iter().flatten() {
+        env_vars.push((
+            name.clone(),
+            secrets::read_secret(config, deployment, name).await?,
+        ));
+    }
+    env_vars.extend(discovery_env(config, deployment, port).await?);
 
-    Ok(())
+    secrets::generate_env_file(
+        &config.paths.secrets_dir,
+        &deployment.project_name,
+        branch_sanitized,
+        &deployment.service_name,
+        &env_vars,
+    )
+    .await
+}
+
+/// `KENNEL_SERVICE_<NAME>_URL` and `KENNEL_SERVICE_<NAME>_PUBLIC_URL` for
+/// every live service on the branch of `deployment`, counting `deployment`
+/// itself as live on `port`. Static sites only get a public URL; workers and
+/// jobs get neither.
+async fn discovery_env(
+    config: &DeployerConfig,
+    deployment: &deployments::Model,
+    port: Option<u16>,
+) -> Result<Vec<(String, String)>> {
+    let mut live: Vec<(String, ServiceType, Option<u16>, String)> = config
+        .store
+        .deployments()
+        .find_live_by_branch(&deployment.project_name, &deployment.branch, None)
+        .await?
+        .into_iter()
+        .filter(|d| d.status == DeploymentStatus::Active)
+        .filter(|d| d.service_name != deployment.service_name)
+        .map(|d| {
+            (
+                d.service_name,
+                d.service_type,
+                d.port.map(|port| port as u16),
+                d.domain,
+            )
+        })
+        .collect();
+    live.push((
+        deployment.service_name.clone(),
+        deployment.service_type.clone(),
+        port,
+        deployment.domain.clone(),
+    ));
+    live.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut env_vars = Vec::new();
+    for (service_name, service_type, port, domain) in live {
+        let prefix = utils::discovery_env_prefix(&service_name);
+
+        match service_type {
+            ServiceType::Static => {}
+            ServiceType::Worker | ServiceType::Job => continue,
+            _ => {
+                let Some(port) = port else {
+                    continue;
+                };
+                env_vars.push((
+                    format!("{}_URL", prefix),
+                    format!("http://127.0.0.1:{}", port),
+                ));
+            }
+        }
+
+        env_vars.push((
+            format!("{}_PUBLIC_URL", prefix),
+            format!("https://{}", domain),
+        ));
+    }
+
+    Ok(env_vars)
+}
+
+/// Run a service's release command before its new unit starts, keeping the
+/// output on the deployment. Any failure aborts the deployment, so the
+/// deployment it would replace keeps serving.
+async fn run_release_command(
+    config: &DeployerConfig,
+    deployment: &deployments::Model,
+    spec: &ServiceSpec,
+    command: &str,
+    timeout: Duration,
+) -> Result<()> {
+    info!(
+        "Running release command for {}: {}",
+        spec.unit_name, command
+    );
+
+    let run = config.runtime.run_release(spec, command, timeout).await?;
+
+    if let Err(e) = config
+        .store
+        .deployments()
+        .set_release_log(deployment.id, &run.log)
+        .await
+    {
+        warn!(
+            "Failed to store release output of deployment {}: {}",
+            deployment.id, e
+        );
+    }
+
+    if run.succeeded {
+        info!("Release command for {} succeeded", spec.unit_name);
+        return Ok(());
+    }
+
+    let reason = if run.timed_out {
+        format!("timed out after {}s", timeout.as_secs())
+    } else {
+        match run.exit_code {
+            Some(code) => format!("exited with code {}", code),
+            None => "was killed".to_string(),
+        }
+    };
+    error!("Release command for {} {}", spec.unit_name, reason);
+
+    Err(crate::DeployerError::Release(reason))
 }
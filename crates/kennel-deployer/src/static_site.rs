@@ -1,20 +1,21 @@
 use crate::error::Result;
 use crate::{DeployerConfig, DeploymentRequest, utils};
-use entity::sea_orm_active_enums::DeploymentStatus;
+use entity::sea_orm_active_enums::{DeploymentStatus, LoadBalancing, ServiceType};
 use entity::{build_results, deployments};
 use kennel_config::KennelConfig;
 use kennel_store::Store;
-use std::path::PathBuf;
+use sea_orm::IntoActiveModel;
 use std::sync::Arc;
 use tracing::{info, warn};
 
 pub async fn deploy_site(
     request: &DeploymentRequest,
     build_result: &build_results::Model,
+    environment: &str,
     store: &Arc<Store>,
     config: &DeployerConfig,
     kennel_config: &KennelConfig,
-) -> Result<()> {
+) -> Result<deployments::Model> {
     let store_path = build_result
         .store_path
         .as_ref()
@@ -25,8 +26,21 @@ pub async fn deploy_site(
         build_result.service_name, store_path
     );
 
-    let branch_sanitized = utils::sanitize_identifier(&request.git_ref);
-    let site_base_dir = PathBuf::from(kennel_config::constants::SITES_BASE_DIR)
+    let branch_sanitized = utils::ref_slug(&request.git_ref);
+
+    let existing_deployment = store
+        .deployments()
+        .find_active_by_ref(
+            &request.project_name,
+            &request.git_ref,
+            &build_result.service_name,
+        )
+        .await
+        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
+
+    let site_base_dir = config
+        .paths
+        .sites_dir
         .join(&request.project_name)
         .join(&branch_sanitized);
 
@@ -55,18 +69,22 @@ pub async fn deploy_site(
 
     tokio::fs::rename(&temp_link, &site_link).await?;
 
+    let (promoted_from_branch, promoted_from_environment) = crate::service::provenance(request);
+
     let deployment = deployments::ActiveModel {
         project_name: sea_orm::ActiveValue::Set(request.project_name.clone()),
         git_ref: sea_orm::ActiveValue::Set(request.git_ref.clone()),
         service_name: sea_orm::ActiveValue::Set(build_result.service_name.clone()),
         branch: sea_orm::ActiveValue::Set(request.git_ref.clone()),
         branch_slug: sea_orm::ActiveValue::Set(branch_sanitized.clone()),
-        environment: sea_orm::ActiveValue::Set(crate::service::determine_environment(
-            &request.git_ref,
-        )),
+        environment: sea_orm::ActiveValue::Set(environment.to_string()),
         store_path: sea_orm::ActiveValue::Set(Some(store_path.clone())),
         port: sea_orm::ActiveValue::Set(None),
+        service_type: sea_orm::ActiveValue::Set(ServiceType::Static),
         status: sea_orm::ActiveValue::Set(DeploymentStatus::Active),
+        promoted_from_branch: sea_orm::ActiveValue::Set(promoted_from_branch),
+        promoted_from_environment: sea_orm::ActiveValue::Set(promoted_from_environment),
+        build_id: sea_orm::ActiveValue::Set(Some(request.build_id)),
         domain: sea_orm::ActiveValue::Set(utils::generate_deployment_domain(
             &build_result.service_name,
             &branch_sanitized,
@@ -88,9 +106,29 @@ pub async fn deploy_site(
         site_link.display()
     );
 
-    // Create DNS records for custom domain if configured
+    // The symlink has already been swapped, so the previous deployment is
+    // simply retired; its DNS records move to the new one
     let site_config = kennel_config.static_sites.get(&build_result.service_name);
-    if let Some(dns_manager) = &config.dns_manager
+    if let Some(old_deployment) = existing_deployment {
+        if let Err(e) = store
+            .dns_records()
+            .reassign_deployment(old_deployment.id, new_deployment.id)
+            .await
+        {
+            warn!(
+                "Failed to move DNS records from deployment {}: {}",
+                old_deployment.id, e
+            );
+        }
+
+        crate::service::record_replaced(&old_deployment, &new_deployment, config).await;
+
+        let mut old_active = old_deployment.into_active_model();
+        old_active.status = sea_orm::ActiveValue::Set(DeploymentStatus::TornDown);
+        if let Err(e) = store.deployments().update(old_active).await {
+            warn!("Failed to retire previous static site deployment: {}", e);
+        }
+    } else if let Some(dns_manager) = &config.dns_manager
         && let Some(custom_domain) = site_config.and_then(|s| s.custom_domain.as_ref())
     {
         info!("Creating DNS records for custom domain: {}", custom_domain);
@@ -118,7 +156,8 @@ pub async fn deploy_site(
         let update = kennel_router::RouterUpdate::DeploymentActive {
             deployment_id: new_deployment.id,
             domain: new_deployment.domain.clone(),
-            port: None,
+            ports: Vec::new(),
+            load_balancing: LoadBalancing::RoundRobin,
             store_path: Some(store_path.clone()),
             spa: site_config.map(|s| s.spa).unwrap_or(false),
         };
@@ -128,5 +167,5 @@ pub async fn deploy_site(
         }
     }
 
-    Ok(())
+    Ok(new_deployment)
 }
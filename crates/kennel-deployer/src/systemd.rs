@@ -1,61 +1,630 @@
 use crate::error::Result;
-use std::path::Path;
+use crate::journal;
+use crate::runtime::{
+    JobRun, LogEntry, LogQuery, ReleaseRun, ServiceRuntime, ServiceSpec, UnitStatus,
+};
+use async_trait::async_trait;
+use chrono::DateTime;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
+use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
-pub fn generate_service_unit(
-    service_name: &str,
-    store_path: &str,
-    port: u16,
-    user: &str,
-    working_dir: &Path,
-    env_vars: &[(String, String)],
-    secrets_path: Option<&Path>,
+/// Entries buffered between journalctl and a slow log reader.
+const LOG_CHANNEL_CAPACITY: usize = 256;
+
+pub fn generate_service_unit(spec: &ServiceSpec) -> String {
+    // Jobs run once per timer activation instead of being kept alive
+    let lifecycle = if spec.schedule.is_some() {
+        "Type=oneshot"
+    } else {
+        "Type=simple\nRestart=on-failure\nRestartSec=5s"
+    };
+
+    // Replicas are instances of a template, started through their target
+    let description = if spec.is_replicated() {
+        format!("Kennel service: {} (replica %i)", spec.service_name)
+    } else {
+        format!("Kennel service: {}", spec.service_name)
+    };
+
+    let mut unit = generate_unit(
+        spec,
+        &description,
+        lifecycle,
+        &spec.exec_path().display().to_string(),
+        spec.is_replicated(),
+    );
+
+    if spec.schedule.is_none() && !spec.is_replicated() {
+        unit.push_str("\n[Install]\nWantedBy=multi-user.target\n");
+    }
+
+    unit
+}
+
+/// The target grouping the instances of a replicated service. Starting,
+/// stopping or restarting it does the same to every replica.
+pub fn generate_target_unit(spec: &ServiceSpec) -> String {
+    let instances: Vec<String> = spec
+        .instances()
+        .iter()
+        .map(|instance| format!("{}@{}.service", spec.unit_name, instance))
+        .collect();
+
+    format!(
+        r#"[Unit]
+Description=Kennel service replicas: {service_name}
+Wants={instances}
+
+[Install]
+WantedBy=multi-user.target
+"#,
+        service_name = spec.service_name,
+        instances = instances.join(" "),
+    )
+}
+
+/// A oneshot unit running `command` with everything the service unit for
+/// `spec` runs with. Starting it blocks until the command exits.
+pub fn generate_release_unit(spec: &ServiceSpec, command: &str, timeout: Duration) -> String {
+    let lifecycle = format!(
+        "Type=oneshot\nTimeoutStartSec={}\nEnvironment=\"PATH={}:/run/current-system/sw/bin\"",
+        timeout.as_secs(),
+        spec.bin_dir().display(),
+    );
+
+    generate_unit(
+        spec,
+        &format!("Kennel release command: {}", spec.service_name),
+        &lifecycle,
+        &format!("/bin/sh -c \"{}\"", escape_exec_arg(command)),
+        false,
+    )
+}
+
+/// Escape `arg` for use inside a double-quoted `ExecStart=` argument, so
+/// systemd passes it through without expanding specifiers or variables.
+fn escape_exec_arg(arg: &str) -> String {
+    arg.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$")
+}
+
+/// A service unit for `spec`. A `template` is instantiated once per
+/// replica, with the instance name as `$PORT` for HTTP services.
+fn generate_unit(
+    spec: &ServiceSpec,
+    description: &str,
+    lifecycle: &str,
+    exec_start: &str,
+    template: bool,
 ) -> String {
+    let part_of = if template {
+        format!("PartOf={}.target\n", spec.unit_name)
+    } else {
+        String::new()
+    };
+
     let mut unit = format!(
         r#"[Unit]
-Description=Kennel service: {service_name}
+Description={description}
 After=network.target
-
+{part_of}
 [Service]
-Type=simple
+{lifecycle}
+DynamicUser=yes
 User={user}
-WorkingDirectory={working_dir}
-ExecStart={store_path}/bin/{service_name}
-Restart=on-failure
-RestartSec=5s
+StateDirectory={state_directory}
+WorkingDirectory=%S/{state_directory}
+ExecStart={exec_start}
+
+ProtectSystem=strict
+ProtectHome=true
+PrivateTmp=true
+PrivateDevices=true
+NoNewPrivileges=true
+ProtectKernelTunables=true
+ProtectKernelModules=true
+ProtectKernelLogs=true
+ProtectControlGroups=true
+RestrictNamespaces=true
+RestrictRealtime=true
+RestrictSUIDSGID=true
+LockPersonality=true
+RestrictAddressFamilies=AF_INET AF_INET6 AF_UNIX
+ReadWritePaths=%S/{state_directory}
 
-Environment="PORT={port}"
 "#,
-        working_dir = working_dir.display()
+        user = spec.user,
+        state_directory = spec.state_directory.display(),
     );
 
-    for (key, value) in env_vars {
+    if template && !spec.ports.is_empty() {
+        unit.push_str("Environment=\"PORT=%i\"\n");
+    } else if let Some(port) = spec.ports.first() {
+        unit.push_str(&format!("Environment=\"PORT={}\"\n", port));
+    }
+
+    for (key, value) in &spec.env {
         unit.push_str(&format!("Environment=\"{}={}\"\n", key, value));
     }
 
-    if let Some(secrets) = secrets_path {
+    if let Some(secrets) = &spec.env_file {
         unit.push_str(&format!("EnvironmentFile={}\n", secrets.display()));
     }
 
-    unit.push_str("\n[Install]\nWantedBy=multi-user.target\n");
+    let resources = &spec.resources;
+    if let Some(memory_max) = &resources.memory_max {
+        unit.push_str(&format!("MemoryMax={}\n", memory_max));
+    }
+    if let Some(cpu_quota) = &resources.cpu_quota {
+        unit.push_str(&format!("CPUQuota={}\n", cpu_quota));
+    }
+    if let Some(tasks_max) = resources.tasks_max {
+        unit.push_str(&format!("TasksMax={}\n", tasks_max));
+    }
+    if let Some(io_weight) = resources.io_weight {
+        unit.push_str(&format!("IOWeight={}\n", io_weight));
+    }
+
     unit
 }
 
-pub async fn install_unit(unit_name: &str, unit_content: &str) -> Result<()> {
-    let unit_path = format!(
-        "{}/{}.service",
-        kennel_config::constants::SYSTEMD_UNIT_DIR,
-        unit_name
-    );
+pub fn generate_timer_unit(spec: &ServiceSpec, schedule: &str) -> String {
+    format!(
+        r#"[Unit]
+Description=Kennel job schedule: {service_name}
 
-    tokio::fs::write(&unit_path, unit_content).await?;
-    info!("Installed systemd unit: {}", unit_name);
+[Timer]
+OnCalendar={schedule}
+Persistent=true
+Unit={unit_name}.service
 
-    Ok(())
+[Install]
+WantedBy=timers.target
+"#,
+        service_name = spec.service_name,
+        unit_name = spec.unit_name,
+    )
 }
 
-pub async fn daemon_reload() -> Result<()> {
+/// Runs deployments as systemd units with a dynamic user per service. Jobs get
+/// a paired `.timer` unit; replicated services run as instances of a
+/// template unit grouped by a `.target`.
+pub struct SystemdRuntime {
+    unit_dir: PathBuf,
+}
+
+impl SystemdRuntime {
+    pub fn new(unit_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            unit_dir: unit_dir.into(),
+        }
+    }
+
+    fn unit_path(&self, unit_name: &str) -> PathBuf {
+        self.unit_dir.join(format!("{}.service", unit_name))
+    }
+
+    fn timer_path(&self, unit_name: &str) -> PathBuf {
+        self.unit_dir.join(format!("{}.timer", unit_name))
+    }
+
+    fn template_path(&self, unit_name: &str) -> PathBuf {
+        self.unit_dir.join(format!("{}@.service", unit_name))
+    }
+
+    fn target_path(&self, unit_name: &str) -> PathBuf {
+        self.unit_dir.join(format!("{}.target", unit_name))
+    }
+
+    /// The service units doing the work: every replica of a replicated
+    /// service, or the one service unit.
+    async fn service_units(&self, unit_name: &str) -> Result<Vec<String>> {
+        if !self.target_path(unit_name).exists() {
+            return Ok(vec![format!("{}.service", unit_name)]);
+        }
+
+        let output = Command::new("systemctl")
+            .args(["show", "--property=Wants", "--value"])
+            .arg(format!("{}.target", unit_name))
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::DeployerError::Systemd(format!(
+                "show failed: {}",
+                stderr
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// The name journald knows the unit's output by; a glob over the
+    /// instances of a replicated service.
+    fn journal_unit(&self, unit_name: &str) -> String {
+        if self.target_path(unit_name).exists() {
+            format!("{}@*", unit_name)
+        } else {
+            unit_name.to_string()
+        }
+    }
+
+    /// The unit to start, stop and query: the timer for jobs, the target
+    /// for replicated services, the service otherwise.
+    fn entry_unit(&self, unit_name: &str) -> String {
+        if self.timer_path(unit_name).exists() {
+            format!("{}.timer", unit_name)
+        } else if self.target_path(unit_name).exists() {
+            format!("{}.target", unit_name)
+        } else {
+            format!("{}.service", unit_name)
+        }
+    }
+
+    async fn remove_unit(&self, unit_name: &str) -> Result<()> {
+        for path in [
+            self.unit_path(unit_name),
+            self.timer_path(unit_name),
+            self.template_path(unit_name),
+            self.target_path(unit_name),
+        ] {
+            if let Err(e) = tokio::fs::remove_file(path).await
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                return Err(e.into());
+            }
+        }
+
+        info!("Removed systemd unit file: {}", unit_name);
+        Ok(())
+    }
+}
+
+impl Default for SystemdRuntime {
+    fn default() -> Self {
+        Self::new(kennel_config::constants::SYSTEMD_UNIT_DIR)
+    }
+}
+
+#[async_trait]
+impl ServiceRuntime for SystemdRuntime {
+    async fn install(&self, spec: &ServiceSpec) -> Result<()> {
+        let unit_content = generate_service_unit(spec);
+
+        if spec.is_replicated() {
+            tokio::fs::write(self.template_path(&spec.unit_name), unit_content).await?;
+            tokio::fs::write(
+                self.target_path(&spec.unit_name),
+                generate_target_unit(spec),
+            )
+            .await?;
+        } else {
+            tokio::fs::write(self.unit_path(&spec.unit_name), unit_content).await?;
+        }
+
+        if let Some(schedule) = &spec.schedule {
+            tokio::fs::write(
+                self.timer_path(&spec.unit_name),
+                generate_timer_unit(spec, schedule),
+            )
+            .await?;
+        }
+        info!("Installed systemd unit: {}", spec.unit_name);
+
+        daemon_reload().await?;
+        systemctl("enable", &self.entry_unit(&spec.unit_name)).await?;
+        info!("Enabled systemd unit: {}", spec.unit_name);
+
+        Ok(())
+    }
+
+    async fn start(&self, unit_name: &str) -> Result<()> {
+        systemctl("start", &self.entry_unit(unit_name)).await?;
+        info!("Started systemd unit: {}", unit_name);
+        Ok(())
+    }
+
+    async fn stop(&self, unit_name: &str) -> Result<()> {
+        let entry_unit = self.entry_unit(unit_name);
+        systemctl("stop", &entry_unit).await?;
+
+        // Stopping a timer leaves a running job alone
+        if entry_unit.ends_with(".timer") {
+            systemctl("stop", &format!("{}.service", unit_name)).await?;
+        }
+
+        info!("Stopped systemd unit: {}", unit_name);
+        Ok(())
+    }
+
+    async fn restart(&self, unit_name: &str) -> Result<()> {
+        systemctl("restart", &self.entry_unit(unit_name)).await?;
+        info!("Restarted systemd unit: {}", unit_name);
+        Ok(())
+    }
+
+    async fn restart_replica(&self, unit_name: &str, port: u16) -> Result<()> {
+        if !self.target_path(unit_name).exists() {
+            return self.restart(unit_name).await;
+        }
+
+        let instance = format!("{}@{}.service", unit_name, port);
+        systemctl("restart", &instance).await?;
+        info!("Restarted systemd unit: {}", instance);
+        Ok(())
+    }
+
+    async fn run_release(
+        &self,
+        spec: &ServiceSpec,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<ReleaseRun> {
+        let release_unit = format!("{}-release", spec.unit_name);
+        let unit = format!("{}.service", release_unit);
+
+        tokio::fs::write(
+            self.unit_path(&release_unit),
+            generate_release_unit(spec, command, timeout),
+        )
+        .await?;
+        daemon_reload().await?;
+
+        // Starting a oneshot unit returns once the command has exited
+        if let Err(e) = systemctl("start", &unit).await {
+            warn!("Release command {} failed: {}", release_unit, e);
+        }
+
+        let output = Command::new("systemctl")
+            .args(["show", "--property=Result,ExecMainStatus"])
+            .arg(&unit)
+            .output()
+            .await?;
+        let mut run = parse_release_run(&String::from_utf8_lossy(&output.stdout));
+
+        match self
+            .logs(
+                &release_unit,
+                &LogQuery {
+                    lines: kennel_config::constants::RELEASE_LOG_LINES,
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            Ok(entries) => {
+                run.log = entries
+                    .into_iter()
+                    .map(|entry| entry.message)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+            }
+            Err(e) => warn!("Failed to read output of {}: {}", release_unit, e),
+        }
+
+        // A failed unit stays loaded until reset, even without its file
+        if !run.succeeded
+            && let Err(e) = systemctl("reset-failed", &unit).await
+        {
+            warn!("Failed to reset {}: {}", release_unit, e);
+        }
+        self.remove_unit(&release_unit).await?;
+        daemon_reload().await?;
+
+        Ok(run)
+    }
+
+    async fn uninstall(&self, unit_name: &str) {
+        if let Err(e) = self.stop(unit_name).await {
+            warn!("Failed to stop unit {}: {}", unit_name, e);
+        }
+
+        if let Err(e) = systemctl("disable", &self.entry_unit(unit_name)).await {
+            warn!("Failed to disable unit {}: {}", unit_name, e);
+        }
+
+        if let Err(e) = self.remove_unit(unit_name).await {
+            warn!("Failed to remove unit {}: {}", unit_name, e);
+        }
+
+        if let Err(e) = daemon_reload().await {
+            warn!("Failed to reload systemd daemon: {}", e);
+        }
+    }
+
+    async fn clean_state(&self, unit_name: &str) -> Result<()> {
+        // Replicas share one state directory, so cleaning any of them will do
+        let service_unit = self
+            .service_units(unit_name)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| crate::DeployerError::NotFound(format!("unit {}", unit_name)))?;
+
+        let output = Command::new("systemctl")
+            .args(["clean", "--what=state"])
+            .arg(service_unit)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::DeployerError::Systemd(format!(
+                "clean failed: {}",
+                stderr
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn status(&self, unit_name: &str) -> Result<UnitStatus> {
+        // A target stays active when its replicas fail, so ask them instead
+        let units = if self.target_path(unit_name).exists() {
+            self.service_units(unit_name).await?
+        } else {
+            vec![self.entry_unit(unit_name)]
+        };
+
+        let output = Command::new("systemctl")
+            .args(["show", "--property=LoadState,ActiveState"])
+            .args(&units)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::DeployerError::Systemd(format!(
+                "show failed: {}",
+                stderr
+            )));
+        }
+
+        Ok(parse_replica_status(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    async fn logs(&self, unit_name: &str, query: &LogQuery) -> Result<Vec<LogEntry>> {
+        let output = Command::new("journalctl")
+            .args(journal::journalctl_args(
+                &self.journal_unit(unit_name),
+                query,
+                false,
+            ))
+            .output()
+            .await?;
+
+        // journalctl exits 1 without an error message when --grep matches nothing
+        if !output.status.success() && !output.stderr.is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::DeployerError::Systemd(format!(
+                "journalctl failed: {}",
+                stderr
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(journal::parse_journal_entry)
+            .collect())
+    }
+
+    async fn follow_logs(
+        &self,
+        unit_name: &str,
+        query: &LogQuery,
+    ) -> Result<mpsc::Receiver<LogEntry>> {
+        let mut child = Command::new("journalctl")
+            .args(journal::journalctl_args(
+                &self.journal_unit(unit_name),
+                query,
+                true,
+            ))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| crate::DeployerError::Systemd("journalctl has no stdout".into()))?;
+
+        let (tx, rx) = mpsc::channel(LOG_CHANNEL_CAPACITY);
+
+        tokio::spawn(async move {
+            // Dropping the child when the reader goes away kills journalctl
+            let _child = child;
+            let mut lines = BufReader::new(stdout).lines();
+
+            loop {
+                tokio::select! {
+                    line = lines.next_line() => match line {
+                        Ok(Some(line)) => {
+                            if let Some(entry) = journal::parse_journal_entry(&line)
+                                && tx.send(entry).await.is_err()
+                            {
+                                break;
+                            }
+                        }
+                        _ => break,
+                    },
+                    _ = tx.closed() => break,
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    async fn last_run(&self, unit_name: &str) -> Result<Option<JobRun>> {
+        let output = Command::new("systemctl")
+            .args([
+                "show",
+                "--timestamp=unix",
+                "--property=ActiveState,ExecMainStartTimestamp,ExecMainExitTimestamp,ExecMainStatus,Result",
+            ])
+            .arg(format!("{}.service", unit_name))
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::DeployerError::Systemd(format!(
+                "show failed: {}",
+                stderr
+            )));
+        }
+
+        Ok(parse_last_run(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    async fn list_units(&self) -> Result<Vec<String>> {
+        let output = Command::new("systemctl")
+            .args(["list-units", "--all", "--plain", "--no-legend", "kennel-*"])
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::DeployerError::Systemd(format!(
+                "list-units failed: {}",
+                stderr
+            )));
+        }
+
+        let units: BTreeSet<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .filter_map(|unit| {
+                unit.strip_suffix(".service")
+                    .or_else(|| unit.strip_suffix(".timer"))
+                    .or_else(|| unit.strip_suffix(".target"))
+            })
+            // Replicas belong to the unit they are instances of
+            .map(|unit| unit.split_once('@').map_or(unit, |(name, _)| name))
+            .filter(|unit| unit.starts_with("kennel-"))
+            .map(str::to_string)
+            .collect();
+
+        Ok(units.into_iter().collect())
+    }
+}
+
+async fn daemon_reload() -> Result<()> {
     let output = Command::new("systemctl")
         .arg("daemon-reload")
         .output()
@@ -73,121 +642,349 @@ pub async fn daemon_reload() -> Result<()> {
     Ok(())
 }
 
-pub async fn enable_unit(unit_name: &str) -> Result<()> {
+/// Run `systemctl <action>` on a full unit name such as `foo.service`.
+async fn systemctl(action: &str, unit: &str) -> Result<()> {
     let output = Command::new("systemctl")
-        .arg("enable")
-        .arg(format!("{}.service", unit_name))
+        .arg(action)
+        .arg(unit)
         .output()
         .await?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        error!("systemctl enable failed for {}: {}", unit_name, stderr);
+        error!("systemctl {} failed for {}: {}", action, unit, stderr);
         return Err(crate::DeployerError::Systemd(format!(
-            "enable failed: {}",
-            stderr
+            "{} failed: {}",
+            action, stderr
         )));
     }
 
-    info!("Enabled systemd unit: {}", unit_name);
     Ok(())
 }
 
-pub async fn start_unit(unit_name: &str) -> Result<()> {
-    let output = Command::new("systemctl")
-        .arg("start")
-        .arg(format!("{}.service", unit_name))
-        .output()
-        .await?;
+fn show_property<'a>(show_output: &'a str, name: &str) -> &'a str {
+    show_output
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+        .unwrap_or_default()
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        error!("systemctl start failed for {}: {}", unit_name, stderr);
-        return Err(crate::DeployerError::Systemd(format!(
-            "start failed: {}",
-            stderr
-        )));
+fn parse_unit_status(show_output: &str) -> UnitStatus {
+    let property = |name: &str| show_property(show_output, name);
+
+    if property("LoadState") == "not-found" {
+        return UnitStatus::NotFound;
     }
 
-    info!("Started systemd unit: {}", unit_name);
-    Ok(())
+    match property("ActiveState") {
+        "active" | "activating" | "reloading" => UnitStatus::Running,
+        "failed" => UnitStatus::Failed,
+        _ => UnitStatus::Stopped,
+    }
 }
 
-pub async fn stop_unit(unit_name: &str) -> Result<()> {
-    let output = Command::new("systemctl")
-        .arg("stop")
-        .arg(format!("{}.service", unit_name))
-        .output()
-        .await?;
+/// Combine the `systemctl show` blocks of every replica of a unit: failed if
+/// any replica failed, running only if all of them are.
+fn parse_replica_status(show_output: &str) -> UnitStatus {
+    let statuses: Vec<UnitStatus> = show_output
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(parse_unit_status)
+        .collect();
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        warn!("systemctl stop failed for {}: {}", unit_name, stderr);
+    if statuses.is_empty() || statuses.contains(&UnitStatus::NotFound) {
+        UnitStatus::NotFound
+    } else if statuses.contains(&UnitStatus::Failed) {
+        UnitStatus::Failed
+    } else if statuses.iter().all(|status| *status == UnitStatus::Running) {
+        UnitStatus::Running
     } else {
-        info!("Stopped systemd unit: {}", unit_name);
+        UnitStatus::Stopped
     }
-
-    Ok(())
 }
 
-pub async fn disable_unit(unit_name: &str) -> Result<()> {
-    let output = Command::new("systemctl")
-        .arg("disable")
-        .arg(format!("{}.service", unit_name))
-        .output()
-        .await?;
+/// Parse `systemctl show --timestamp=unix` output for a job's service unit.
+/// A run still in progress is not a completed run.
+fn parse_last_run(show_output: &str) -> Option<JobRun> {
+    let property = |name: &str| show_property(show_output, name);
+    let timestamp = |name: &str| {
+        property(name)
+            .strip_prefix('@')
+            .and_then(|secs| secs.parse::<i64>().ok())
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+    };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        warn!("systemctl disable failed for {}: {}", unit_name, stderr);
-    } else {
-        info!("Disabled systemd unit: {}", unit_name);
+    if property("ActiveState") == "activating" {
+        return None;
     }
 
-    Ok(())
+    let started_at = timestamp("ExecMainStartTimestamp")?;
+    let finished_at = timestamp("ExecMainExitTimestamp").filter(|t| *t >= started_at)?;
+
+    Some(JobRun {
+        started_at,
+        finished_at,
+        succeeded: property("Result") == "success",
+        exit_code: property("ExecMainStatus").parse().ok(),
+    })
 }
 
-pub async fn remove_unit(unit_name: &str) -> Result<()> {
-    let unit_path = format!(
-        "{}/{}.service",
-        kennel_config::constants::SYSTEMD_UNIT_DIR,
-        unit_name
-    );
+/// Parse `systemctl show` output for a finished release unit. The log is
+/// read separately.
+fn parse_release_run(show_output: &str) -> ReleaseRun {
+    let property = |name: &str| show_property(show_output, name);
+    let timed_out = property("Result") == "timeout";
 
-    if let Err(e) = tokio::fs::remove_file(&unit_path).await
-        && e.kind() != std::io::ErrorKind::NotFound
-    {
-        return Err(e.into());
+    ReleaseRun {
+        succeeded: property("Result") == "success",
+        // A command killed on timeout has a signal, not an exit code
+        exit_code: if timed_out {
+            None
+        } else {
+            property("ExecMainStatus").parse().ok()
+        },
+        timed_out,
+        log: String::new(),
     }
-
-    info!("Removed systemd unit file: {}", unit_name);
-    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use kennel_config::ResourceLimits;
 
-    #[test]
-    fn test_generate_service_unit() {
-        let unit = generate_service_unit(
-            "test-api",
-            "/nix/store/abc123-test-api",
-            8080,
-            "kennel-test-api",
-            Path::new("/var/lib/kennel/services/test-project/main/test-api"),
-            &[(
+    fn spec(resources: ResourceLimits) -> ServiceSpec {
+        ServiceSpec {
+            unit_name: "kennel-test-project-main-test-api-1".to_string(),
+            service_name: "test-api".to_string(),
+            store_path: "/nix/store/abc123-test-api".to_string(),
+            ports: vec![8080],
+            replicas: 1,
+            user: "kennel-test-project-main-test-api".to_string(),
+            state_directory: PathBuf::from("kennel/services/test-project/main/test-api"),
+            env: vec![(
                 "DATABASE_URL".to_string(),
                 "postgres://localhost/test".to_string(),
             )],
-            Some(Path::new("/run/kennel/secrets/test-api.env")),
-        );
+            env_file: Some(PathBuf::from("/run/kennel/secrets/test-api.env")),
+            resources,
+            schedule: None,
+        }
+    }
+
+    #[test]
+    fn test_generate_service_unit() {
+        let unit = generate_service_unit(&spec(ResourceLimits::default()));
 
         assert!(unit.contains("Description=Kennel service: test-api"));
-        assert!(unit.contains("User=kennel-test-api"));
+        assert!(unit.contains("DynamicUser=yes"));
+        assert!(unit.contains("StateDirectory=kennel/services/test-project/main/test-api\n"));
+        assert!(unit.contains("WorkingDirectory=%S/kennel/services/test-project/main/test-api\n"));
+        assert!(unit.contains("User=kennel-test-project-main-test-api\n"));
         assert!(unit.contains("ExecStart=/nix/store/abc123-test-api/bin/test-api"));
         assert!(unit.contains("Environment=\"PORT=8080\""));
         assert!(unit.contains("Environment=\"DATABASE_URL=postgres://localhost/test\""));
         assert!(unit.contains("EnvironmentFile=/run/kennel/secrets/test-api.env"));
+        assert!(!unit.contains("MemoryMax="));
+    }
+
+    #[test]
+    fn test_generate_worker_unit() {
+        let unit = generate_service_unit(&ServiceSpec {
+            ports: Vec::new(),
+            ..spec(ResourceLimits::default())
+        });
+
+        assert!(!unit.contains("PORT="));
+        assert!(unit.contains("Environment=\"DATABASE_URL=postgres://localhost/test\""));
+    }
+
+    #[test]
+    fn test_generate_service_unit_hardening() {
+        let unit = generate_service_unit(&spec(ResourceLimits::default()));
+
+        assert!(unit.contains("ProtectSystem=strict"));
+        assert!(unit.contains("PrivateTmp=true"));
+        assert!(unit.contains("NoNewPrivileges=true"));
+        assert!(unit.contains("RestrictAddressFamilies=AF_INET AF_INET6 AF_UNIX"));
+        assert!(unit.contains("ReadWritePaths=%S/kennel/services/test-project/main/test-api\n"));
+    }
+
+    #[test]
+    fn test_generate_service_unit_resources() {
+        let unit = generate_service_unit(&spec(ResourceLimits {
+            memory_max: Some("512M".to_string()),
+            cpu_quota: Some("50%".to_string()),
+            tasks_max: Some(64),
+            io_weight: Some(100),
+        }));
+
+        assert!(unit.contains("MemoryMax=512M"));
+        assert!(unit.contains("CPUQuota=50%"));
+        assert!(unit.contains("TasksMax=64"));
+        assert!(unit.contains("IOWeight=100"));
+    }
+
+    #[test]
+    fn test_generate_replicated_units() {
+        let spec = ServiceSpec {
+            ports: vec![18000, 18001],
+            replicas: 2,
+            ..spec(ResourceLimits::default())
+        };
+
+        let template = generate_service_unit(&spec);
+        assert!(template.contains("Description=Kennel service: test-api (replica %i)"));
+        assert!(template.contains("PartOf=kennel-test-project-main-test-api-1.target\n"));
+        assert!(template.contains("Environment=\"PORT=%i\""));
+        // Every instance runs as the one user that owns the state directory
+        assert!(template.contains("User=kennel-test-project-main-test-api\n"));
+        assert!(template.contains("StateDirectory=kennel/services/test-project/main/test-api\n"));
+        assert!(!template.contains("[Install]"));
+
+        let target = generate_target_unit(&spec);
+        assert!(target.contains(
+            "Wants=kennel-test-project-main-test-api-1@18000.service kennel-test-project-main-test-api-1@18001.service\n"
+        ));
+        assert!(target.contains("WantedBy=multi-user.target"));
+
+        // Worker replicas are numbered instead
+        let workers = ServiceSpec {
+            ports: Vec::new(),
+            ..spec
+        };
+        assert!(!generate_service_unit(&workers).contains("PORT="));
+        assert!(generate_target_unit(&workers).contains(
+            "Wants=kennel-test-project-main-test-api-1@0.service kennel-test-project-main-test-api-1@1.service\n"
+        ));
+    }
+
+    #[test]
+    fn test_generate_job_units() {
+        let spec = ServiceSpec {
+            ports: Vec::new(),
+            schedule: Some("*-*-* 03:00:00".to_string()),
+            ..spec(ResourceLimits::default())
+        };
+
+        let service = generate_service_unit(&spec);
+        assert!(service.contains("Type=oneshot"));
+        assert!(!service.contains("Restart="));
+        assert!(!service.contains("[Install]"));
+        assert!(service.contains("DynamicUser=yes"));
+
+        let timer = generate_timer_unit(&spec, "*-*-* 03:00:00");
+        assert!(timer.contains("OnCalendar=*-*-* 03:00:00"));
+        assert!(timer.contains("Unit=kennel-test-project-main-test-api-1.service"));
+        assert!(timer.contains("WantedBy=timers.target"));
+    }
+
+    #[test]
+    fn test_generate_release_unit() {
+        let unit = generate_release_unit(
+            &spec(ResourceLimits::default()),
+            r#"test-api migrate --dsn "$DATABASE_URL" --pct 100%"#,
+            Duration::from_secs(120),
+        );
+
+        assert!(unit.contains("Description=Kennel release command: test-api"));
+        assert!(unit.contains("Type=oneshot\nTimeoutStartSec=120\n"));
+        assert!(unit.contains("Environment=\"PATH=/nix/store/abc123-test-api/bin:"));
+        assert!(unit.contains(
+            r#"ExecStart=/bin/sh -c "test-api migrate --dsn \"$$DATABASE_URL\" --pct 100%%""#
+        ));
+        assert!(unit.contains("DynamicUser=yes"));
+        assert!(unit.contains("User=kennel-test-project-main-test-api\n"));
+        assert!(unit.contains("StateDirectory=kennel/services/test-project/main/test-api\n"));
+        assert!(unit.contains("EnvironmentFile=/run/kennel/secrets/test-api.env"));
+        assert!(!unit.contains("Restart="));
+        assert!(!unit.contains("[Install]"));
+    }
+
+    #[test]
+    fn test_parse_release_run() {
+        assert_eq!(
+            parse_release_run("Result=success\nExecMainStatus=0\n"),
+            ReleaseRun {
+                succeeded: true,
+                exit_code: Some(0),
+                timed_out: false,
+                log: String::new(),
+            }
+        );
+
+        let failed = parse_release_run("Result=exit-code\nExecMainStatus=2\n");
+        assert!(!failed.succeeded);
+        assert_eq!(failed.exit_code, Some(2));
+
+        let timed_out = parse_release_run("Result=timeout\nExecMainStatus=15\n");
+        assert!(timed_out.timed_out);
+        assert_eq!(timed_out.exit_code, None);
+    }
+
+    #[test]
+    fn test_parse_last_run() {
+        let run = parse_last_run(
+            "ActiveState=inactive\nExecMainStartTimestamp=@1767323045\nExecMainExitTimestamp=@1767323050\nExecMainStatus=3\nResult=exit-code\n",
+        )
+        .unwrap();
+        assert_eq!(run.started_at.timestamp(), 1767323045);
+        assert_eq!(run.finished_at.timestamp(), 1767323050);
+        assert!(!run.succeeded);
+        assert_eq!(run.exit_code, Some(3));
+
+        assert!(
+            parse_last_run(
+                "ActiveState=inactive\nExecMainStartTimestamp=\nExecMainExitTimestamp=\n"
+            )
+            .is_none()
+        );
+        assert!(
+            parse_last_run(
+                "ActiveState=activating\nExecMainStartTimestamp=@1767323045\nExecMainExitTimestamp=@1767300000\n"
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_parse_replica_status() {
+        let running = "LoadState=loaded\nActiveState=active\n";
+        let failed = "LoadState=loaded\nActiveState=failed\n";
+        let stopped = "LoadState=loaded\nActiveState=inactive\n";
+
+        assert_eq!(
+            parse_replica_status(&format!("{}\n{}", running, running)),
+            UnitStatus::Running
+        );
+        assert_eq!(
+            parse_replica_status(&format!("{}\n{}", running, failed)),
+            UnitStatus::Failed
+        );
+        assert_eq!(
+            parse_replica_status(&format!("{}\n{}", running, stopped)),
+            UnitStatus::Stopped
+        );
+        assert_eq!(parse_replica_status(""), UnitStatus::NotFound);
+    }
+
+    #[test]
+    fn test_parse_unit_status() {
+        assert_eq!(
+            parse_unit_status("LoadState=loaded\nActiveState=active\n"),
+            UnitStatus::Running
+        );
+        assert_eq!(
+            parse_unit_status("LoadState=loaded\nActiveState=failed\n"),
+            UnitStatus::Failed
+        );
+        assert_eq!(
+            parse_unit_status("LoadState=loaded\nActiveState=inactive\n"),
+            UnitStatus::Stopped
+        );
+        assert_eq!(
+            parse_unit_status("LoadState=not-found\nActiveState=inactive\n"),
+            UnitStatus::NotFound
+        );
     }
 }
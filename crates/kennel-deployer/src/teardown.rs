@@ -1,8 +1,7 @@
 use crate::error::Result;
-use crate::{DeployerConfig, secrets, systemd, user, utils};
-use entity::sea_orm_active_enums::DeploymentStatus;
+use crate::{DeployerConfig, secrets, utils};
+use entity::sea_orm_active_enums::{DeploymentEventKind, DeploymentStatus, ServiceType};
 use sea_orm::{ActiveValue::Set, IntoActiveModel};
-use std::path::{Path, PathBuf};
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
@@ -23,7 +22,9 @@ pub async fn run_teardown_worker(mut teardown_rx: mpsc::Receiver<i32>, config: D
     info!("Teardown worker shutting down");
 }
 
-async fn process_teardown(deployment_id: i32, config: &DeployerConfig) -> Result<()> {
+/// Tear down a deployment already marked `TearingDown`: stop its unit, remove
+/// its files and release whatever the branch no longer uses.
+pub async fn process_teardown(deployment_id: i32, config: &DeployerConfig) -> Result<()> {
     let deployment = config
         .store
         .deployments()
@@ -44,51 +45,72 @@ async fn process_teardown(deployment_id: i32, config: &DeployerConfig) -> Result
 
     let branch_sanitized = deployment.branch_slug.clone();
 
-    // Stop systemd service if it's a service deployment
-    if deployment.port.is_some() {
-        let unit_name = format!(
-            "kennel-{}-{}-{}",
-            deployment.project_name, branch_sanitized, deployment.service_name
-        );
+    // Other deployments on this branch that may share resources with this one
+    let remaining_deployments: Vec<_> = config
+        .store
+        .deployments()
+        .find_live_by_branch(&deployment.project_name, &deployment.branch, None)
+        .await?
+        .into_iter()
+        .filter(|d| d.id != deployment.id)
+        .collect();
 
-        info!("Stopping systemd unit: {}", unit_name);
+    let last_of_service = !remaining_deployments
+        .iter()
+        .any(|d| d.service_name == deployment.service_name);
 
-        if let Err(e) = systemd::stop_unit(&unit_name).await {
-            warn!("Failed to stop unit {}: {}", unit_name, e);
-        }
+    let is_static_site = deployment.service_type == ServiceType::Static;
 
-        if let Err(e) = systemd::disable_unit(&unit_name).await {
-            warn!("Failed to disable unit {}: {}", unit_name, e);
-        }
+    // Stop the unit if it's a service or worker deployment
+    if !is_static_site {
+        let unit_name = utils::service_unit_name(&deployment);
 
-        if let Err(e) = systemd::remove_unit(&unit_name).await {
-            warn!("Failed to remove unit {}: {}", unit_name, e);
-        }
+        info!("Stopping unit: {}", unit_name);
 
-        if let Err(e) = systemd::daemon_reload().await {
-            warn!("Failed to reload systemd daemon: {}", e);
-        }
+        // The state directory is shared by every deployment of this
+        // service on the branch, so only the last one removes it
+        if last_of_service {
+            if let Err(e) = config.runtime.stop(&unit_name).await {
+                warn!("Failed to stop unit {}: {}", unit_name, e);
+            }
 
-        // Release port
-        if let Some(port) = deployment.port {
-            if let Err(e) = config.store.port_allocations().release_port(port).await {
-                warn!("Failed to release port {}: {}", port, e);
+            if let Err(e) = config.runtime.clean_state(&unit_name).await {
+                warn!("Failed to remove state of {}: {}", unit_name, e);
             } else {
-                info!("Released port {}", port);
+                info!("Removed state directory of {}", unit_name);
             }
         }
+
+        config.runtime.uninstall(&unit_name).await;
+
+        // Release the port of every replica
+        match config
+            .store
+            .port_allocations()
+            .release_deployment_ports(deployment.id)
+            .await
+        {
+            Ok(0) => {}
+            Ok(released) => info!(
+                "Released {} port(s) of deployment {}",
+                released, deployment.id
+            ),
+            Err(e) => warn!(
+                "Failed to release ports of deployment {}: {}",
+                deployment.id, e
+            ),
+        }
     }
 
-    // Remove static symlink if it's a static deployment (port is None for static sites)
-    if deployment.port.is_none() {
-        let static_link_path = format!(
-            "{}/{}/{}/{}",
-            kennel_config::constants::SITES_BASE_DIR,
-            deployment.project_name,
-            deployment.branch_slug,
-            deployment.service_name
-        );
-        let static_link = Path::new(&static_link_path);
+    // Remove static symlink if it's a static deployment
+    if is_static_site {
+        let static_link_path = config
+            .paths
+            .sites_dir
+            .join(&deployment.project_name)
+            .join(&deployment.branch_slug)
+            .join(&deployment.service_name);
+        let static_link = static_link_path.as_path();
         if let Err(e) = tokio::fs::remove_file(static_link).await {
             if e.kind() != std::io::ErrorKind::NotFound {
                 warn!("Failed to remove static symlink {:?}: {}", static_link, e);
@@ -98,32 +120,21 @@ async fn process_teardown(deployment_id: i32, config: &DeployerConfig) -> Result
         }
     }
 
-    // Remove secrets file
-    let secrets_path = PathBuf::from(format!(
-        "{}/{}-{}-{}.env",
-        kennel_config::constants::SECRETS_DIR,
-        deployment.project_name,
-        branch_sanitized,
-        deployment.service_name
-    ));
-
-    if let Err(e) = secrets::remove_secrets_file(&secrets_path).await {
-        warn!("Failed to remove secrets file: {}", e);
-    }
+    // The secrets file is shared the same way, and a replacement or restored
+    // deployment still reads it whenever its unit restarts
+    if last_of_service {
+        let secrets_path = config.paths.secrets_dir.join(format!(
+            "{}-{}-{}.env",
+            deployment.project_name, branch_sanitized, deployment.service_name
+        ));
 
-    // Release preview database if this was the last deployment for this branch
-    let remaining_deployments = config
-        .store
-        .deployments()
-        .find_by_project_service_branch(
-            &deployment.project_name,
-            &deployment.service_name,
-            &deployment.branch,
-        )
-        .await
-        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
+        if let Err(e) = secrets::remove_secrets_file(&secrets_path).await {
+            warn!("Failed to remove secrets file: {}", e);
+        }
+    }
 
-    if remaining_deployments.is_none() {
+    // Release shared resources once nothing else on this branch is using them
+    if remaining_deployments.is_empty() {
         // No more deployments for this branch, release preview database
         if let Err(e) = config
             .store
@@ -138,19 +149,6 @@ async fn process_teardown(deployment_id: i32, config: &DeployerConfig) -> Result
                 deployment.project_name, deployment.branch
             );
         }
-
-        // No more deployments for this project+branch+service, remove system user
-        let username = utils::sanitize_username(
-            &deployment.project_name,
-            &deployment.branch,
-            &deployment.service_name,
-        );
-
-        if let Err(e) = user::remove_user(&username).await {
-            warn!("Failed to remove system user {}: {}", username, e);
-        } else {
-            info!("Removed system user: {}", username);
-        }
     }
 
     // Delete DNS records for custom domains
@@ -166,23 +164,34 @@ async fn process_teardown(deployment_id: i32, config: &DeployerConfig) -> Result
         }
     }
 
-    // Mark as torn down
+    // Mark as torn down, keeping the row for history
+    let reason = deployment.status_message.clone();
     let mut deployment_active = deployment.into_active_model();
     deployment_active.status = Set(DeploymentStatus::TornDown);
-    config
+    let deployment = config
         .store
         .deployments()
         .update(deployment_active)
         .await
         .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
 
-    // Delete deployment record
-    config
+    if let Err(e) = config
         .store
-        .deployments()
-        .delete(deployment_id)
+        .deployment_events()
+        .record(
+            &deployment,
+            DeploymentEventKind::TornDown,
+            deployment.build_id,
+            reason,
+            "kennel",
+        )
         .await
-        .map_err(|e| crate::DeployerError::Other(anyhow::anyhow!(e)))?;
+    {
+        warn!(
+            "Failed to record teardown of deployment {}: {}",
+            deployment_id, e
+        );
+    }
 
     info!("Successfully tore down deployment {}", deployment_id);
 
@@ -1,3 +1,5 @@
+use sha2::{Digest, Sha256};
+
 pub fn sanitize_identifier(s: &str) -> String {
     s.chars()
         .map(|c| {
@@ -10,13 +12,27 @@ pub fn sanitize_identifier(s: &str) -> String {
         .collect()
 }
 
+/// Longest user name systemd accepts for `DynamicUser=`.
+const MAX_USERNAME_LEN: usize = 31;
+
+/// User a service runs as. Names built from the project, branch and service
+/// quickly outgrow what systemd accepts, so they are hashed instead.
 pub fn sanitize_username(project: &str, branch: &str, service: &str) -> String {
-    format!(
-        "kennel-{}-{}-{}",
-        sanitize_identifier(project),
-        sanitize_identifier(branch),
-        sanitize_identifier(service)
-    )
+    let mut hasher = Sha256::new();
+    for part in [project, branch, service] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+
+    let mut username = format!("kennel-{}", hex::encode(hasher.finalize()));
+    username.truncate(MAX_USERNAME_LEN);
+    username
+}
+
+/// Identifier of `git_ref` in domains and state paths. Tags, recorded as
+/// `refs/tags/<tag>`, become `tags-<tag>`.
+pub fn ref_slug(git_ref: &str) -> String {
+    sanitize_identifier(git_ref.strip_prefix("refs/").unwrap_or(git_ref))
 }
 
 pub fn generate_deployment_domain(
@@ -33,3 +49,65 @@ pub fn generate_deployment_domain(
         base_domain
     )
 }
+
+/// Systemd unit name for a service deployment. The deployment id is part of
+/// the name so the old and new units of a blue-green deploy can run side by
+/// side. Deployments started before that keep the unit they were given.
+pub fn service_unit_name(deployment: &entity::deployments::Model) -> String {
+    if let Some(unit_name) = &deployment.unit_name {
+        return unit_name.clone();
+    }
+
+    format!(
+        "kennel-{}-{}-{}-{}",
+        deployment.project_name, deployment.branch_slug, deployment.service_name, deployment.id
+    )
+}
+
+/// Prefix of the discovery variables other services on the branch get for
+/// `service_name`, e.g. `KENNEL_SERVICE_API` for `api`.
+pub fn discovery_env_prefix(service_name: &str) -> String {
+    let name: String = service_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!("KENNEL_SERVICE_{}", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_username_fits_systemd_limit() {
+        let branch = "feature/a-very-long-branch-name-that-keeps-going-and-going";
+        let username = sanitize_username("my-project", branch, "api");
+
+        assert!(username.starts_with("kennel-"));
+        assert!(username.len() <= MAX_USERNAME_LEN);
+        assert_eq!(username, sanitize_username("my-project", branch, "api"));
+    }
+
+    #[test]
+    fn test_usernames_are_unique() {
+        let branch = "feature/a-very-long-branch-name-that-keeps-going-and-going";
+        let usernames = [
+            sanitize_username("my-project", branch, "api"),
+            sanitize_username("my-project", &format!("{}-2", branch), "api"),
+            sanitize_username("my-project", branch, "web"),
+            sanitize_username("other-project", branch, "api"),
+            sanitize_username("my-project-main", "api", ""),
+            sanitize_username("my-project", "main-api", ""),
+        ];
+
+        let unique: std::collections::HashSet<_> = usernames.iter().collect();
+        assert_eq!(unique.len(), usernames.len());
+    }
+}
@@ -0,0 +1,1694 @@
+use entity::sea_orm_active_enums::*;
+use entity::{build_results, deployment_events, deployments, freeze_windows, projects, services};
+use kennel_config::ResourcePolicy;
+use kennel_deployer::{
+    BranchLocks, DeployerConfig, DeployerPaths, DeploymentRequest, DeploymentTrigger, LogQuery,
+    ProcessRuntime, ServiceRuntime, UnitStatus, active_freeze, collect_job_runs, deploy_build,
+    expire_deployments, plan_promotion, process_teardown, release_frozen_builds,
+    run_remediation_worker, service_unit_name, stop_idle_deployments, wake_deployment,
+};
+use kennel_forge::StatusReporter;
+use kennel_router::{HealthReport, InFlightTracker};
+use kennel_store::Store;
+use sea_orm::{ColumnTrait, Database, EntityTrait, QueryFilter, Set};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Not a real test: deployed fixture services run this test binary with
+/// `--ignored`, and it serves 200 OK on `$PORT` until killed.
+#[test]
+#[ignore]
+fn serve_fixture() {
+    let Ok(port) = std::env::var("PORT") else {
+        return;
+    };
+
+    let listener = std::net::TcpListener::bind(format!("127.0.0.1:{}", port)).unwrap();
+    println!("fixture listening on {}", port);
+
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+
+        let mut buf = [0u8; 1024];
+        let _ = stream.read(&mut buf);
+        let _ = stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+    }
+}
+
+struct Harness {
+    store: Arc<Store>,
+    runtime: Arc<ProcessRuntime>,
+    config: DeployerConfig,
+    dir: TempDir,
+    project: String,
+}
+
+impl Harness {
+    async fn new(project: &str) -> Self {
+        let db_url = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+        let store = Arc::new(Store::new(Database::connect(&db_url).await.unwrap()));
+
+        cleanup(&store, project).await;
+
+        store
+            .projects()
+            .create(projects::ActiveModel {
+                name: Set(project.to_string()),
+                repo_url: Set(format!("https://github.com/{}", project)),
+                repo_type: Set(RepoType::Github),
+                webhook_secret: Set("secret".to_string()),
+                default_branch: Set("main".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let runtime = Arc::new(ProcessRuntime::new(dir.path()));
+
+        let config = DeployerConfig {
+            store: store.clone(),
+            router_tx: None,
+            dns_manager: None,
+            base_domain: "kennel.test".to_string(),
+            max_concurrent_deploys: 1,
+            in_flight: InFlightTracker::new(),
+            branch_locks: BranchLocks::default(),
+            runtime: runtime.clone(),
+            paths: DeployerPaths {
+                builds_dir: dir.path().join("builds"),
+                sites_dir: dir.path().join("sites"),
+                secrets_dir: dir.path().join("secrets"),
+                secret_sources_dir: dir.path().join("secret-sources"),
+            },
+            resources: ResourcePolicy::default(),
+            forge: None,
+        };
+
+        Self {
+            store,
+            runtime,
+            config,
+            dir,
+            project: project.to_string(),
+        }
+    }
+
+    /// Create a successful build of service `api` whose binary runs `script`.
+    async fn build(&self, commit_sha: &str, kennel_toml: &str, script: &str) -> i32 {
+        self.build_services(commit_sha, kennel_toml, &[("api", script)])
+            .await
+    }
+
+    /// Create a successful build with one output per `(name, script)`.
+    async fn build_services(
+        &self,
+        commit_sha: &str,
+        kennel_toml: &str,
+        services: &[(&str, &str)],
+    ) -> i32 {
+        let build = self
+            .store
+            .builds()
+            .create_build(
+                self.project.clone(),
+                "main".to_string(),
+                commit_sha.to_string(),
+                "alice".to_string(),
+            )
+            .await
+            .unwrap();
+
+        let build_dir = self.config.paths.builds_dir.join(build.id.to_string());
+        tokio::fs::create_dir_all(&build_dir).await.unwrap();
+        tokio::fs::write(build_dir.join("kennel.toml"), kennel_toml)
+            .await
+            .unwrap();
+
+        for (name, script) in services {
+            let store_path = self
+                .dir
+                .path()
+                .join("store")
+                .join(format!("{}-{}", build.id, name));
+            write_executable(&store_path.join("bin").join(name), script).await;
+
+            self.store
+                .build_results()
+                .create(build_results::ActiveModel {
+                    build_id: Set(build.id),
+                    service_name: Set(name.to_string()),
+                    store_path: Set(Some(store_path.display().to_string())),
+                    status: Set(BuildResultStatus::Success),
+                    changed: Set(true),
+                    created_at: Set(chrono::Utc::now().naive_utc()),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+
+        build.id
+    }
+
+    async fn deploy(&self, build_id: i32) {
+        deploy_build(
+            &DeploymentRequest {
+                build_id,
+                project_name: self.project.clone(),
+                git_ref: "main".to_string(),
+                services: Vec::new(),
+                trigger: DeploymentTrigger::Build,
+            },
+            &self.config,
+        )
+        .await
+        .unwrap();
+    }
+
+    async fn active(&self, service: &str) -> deployments::Model {
+        self.store
+            .deployments()
+            .find_active_by_ref(&self.project, "main", service)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    async fn deployments(&self) -> Vec<deployments::Model> {
+        deployments::Entity::find()
+            .filter(deployments::Column::ProjectName.eq(&self.project))
+            .all(self.store.db())
+            .await
+            .unwrap()
+    }
+
+    async fn cleanup(&self) {
+        for deployment in self.deployments().await {
+            self.runtime
+                .uninstall(&service_unit_name(&deployment))
+                .await;
+
+            let _ = self
+                .store
+                .port_allocations()
+                .release_deployment_ports(deployment.id)
+                .await;
+        }
+
+        cleanup(&self.store, &self.project).await;
+    }
+}
+
+async fn cleanup(store: &Store, project: &str) {
+    let _ = deployment_events::Entity::delete_many()
+        .filter(deployment_events::Column::ProjectName.eq(project))
+        .exec(store.db())
+        .await;
+    let _ = store.projects().delete(project).await;
+}
+
+async fn write_executable(path: &Path, script: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    tokio::fs::create_dir_all(path.parent().unwrap())
+        .await
+        .unwrap();
+    tokio::fs::write(path, script).await.unwrap();
+    tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+        .await
+        .unwrap();
+}
+
+fn fixture_script() -> String {
+    format!(
+        "#!/bin/sh\nexec {} --exact serve_fixture --ignored --nocapture\n",
+        std::env::current_exe().unwrap().display()
+    )
+}
+
+const KENNEL_TOML: &str = r#"
+[services.api]
+health_check_path = "/health"
+health_check_timeout_secs = 20
+drain_timeout_secs = 1
+"#;
+
+#[tokio::test]
+async fn test_deploy_and_teardown_service() {
+    let harness = Harness::new("test-deployer-e2e").await;
+
+    let build_id = harness
+        .build("abc123", KENNEL_TOML, &fixture_script())
+        .await;
+    harness.deploy(build_id).await;
+
+    let deployments = harness.deployments().await;
+    assert_eq!(deployments.len(), 1);
+    let deployment = deployments[0].clone();
+    assert_eq!(deployment.status, DeploymentStatus::Active);
+    assert_eq!(deployment.build_id, Some(build_id));
+    assert_eq!(deployment.service_type, ServiceType::Service);
+
+    let unit_name = service_unit_name(&deployment);
+    let port = deployment
+        .port
+        .expect("Service deployment should have a port");
+
+    assert_eq!(
+        harness.runtime.status(&unit_name).await.unwrap(),
+        UnitStatus::Running
+    );
+    let state_dir = harness
+        .runtime
+        .state_dir(Path::new("kennel/services/test-deployer-e2e/main/api"));
+    assert!(state_dir.exists());
+    assert!(
+        reqwest::get(format!("http://127.0.0.1:{}/health", port))
+            .await
+            .unwrap()
+            .status()
+            .is_success()
+    );
+    assert!(
+        harness
+            .store
+            .services()
+            .find_by_project_and_name("test-deployer-e2e", "api")
+            .await
+            .unwrap()
+            .is_some(),
+        "Deploying should register the service from kennel.toml"
+    );
+
+    let query = LogQuery {
+        grep: Some("fixture listening".to_string()),
+        lines: 10,
+        ..Default::default()
+    };
+    let logs = harness.runtime.logs(&unit_name, &query).await.unwrap();
+    assert_eq!(logs.len(), 1);
+    assert!(
+        logs[0]
+            .message
+            .ends_with(&format!("fixture listening on {}", port))
+    );
+
+    let mut followed = harness
+        .runtime
+        .follow_logs(&unit_name, &query)
+        .await
+        .unwrap();
+    let first = tokio::time::timeout(Duration::from_secs(5), followed.recv())
+        .await
+        .unwrap();
+    assert_eq!(first, logs.first().cloned());
+    drop(followed);
+
+    let secrets_file = harness
+        .config
+        .paths
+        .secrets_dir
+        .join("test-deployer-e2e-main-api.env");
+    assert!(secrets_file.exists());
+
+    // Redeclaring the name as a static site must not strand the running unit
+    let mut service: services::ActiveModel = harness
+        .store
+        .services()
+        .find_by_project_and_name("test-deployer-e2e", "api")
+        .await
+        .unwrap()
+        .unwrap()
+        .into();
+    service.r#type = Set(ServiceType::Static);
+    harness.store.services().update(service).await.unwrap();
+
+    harness
+        .store
+        .deployments()
+        .mark_ids_tearing_down(&[deployment.id], "Branch deleted")
+        .await
+        .unwrap();
+    process_teardown(deployment.id, &harness.config)
+        .await
+        .unwrap();
+
+    let torn_down = harness
+        .store
+        .deployments()
+        .find_by_id(deployment.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(torn_down.status, DeploymentStatus::TornDown);
+    assert_eq!(
+        harness.runtime.status(&unit_name).await.unwrap(),
+        UnitStatus::NotFound
+    );
+    assert!(!state_dir.exists());
+    assert!(!secrets_file.exists());
+    assert!(
+        harness
+            .store
+            .port_allocations()
+            .find_by_deployment(deployment.id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let kinds: Vec<_> = harness
+        .store
+        .deployment_events()
+        .list_by_deployment(deployment.id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![DeploymentEventKind::Deployed, DeploymentEventKind::TornDown]
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_redeploy_drains_old_deployment() {
+    let harness = Harness::new("test-deployer-redeploy").await;
+
+    let first_build = harness
+        .build("abc123", KENNEL_TOML, &fixture_script())
+        .await;
+    harness.deploy(first_build).await;
+    let old = harness.deployments().await.remove(0);
+
+    let second_build = harness
+        .build("def456", KENNEL_TOML, &fixture_script())
+        .await;
+    harness.deploy(second_build).await;
+
+    let new = harness
+        .store
+        .deployments()
+        .find_active_by_ref("test-deployer-redeploy", "main", "api")
+        .await
+        .unwrap()
+        .expect("New deployment should be active");
+    assert_eq!(new.build_id, Some(second_build));
+    assert_ne!(new.id, old.id);
+
+    let mut old_status = None;
+    for _ in 0..50 {
+        old_status = harness
+            .store
+            .deployments()
+            .find_by_id(old.id)
+            .await
+            .unwrap()
+            .map(|d| d.status);
+        if old_status == Some(DeploymentStatus::TornDown) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(old_status, Some(DeploymentStatus::TornDown));
+    assert_eq!(
+        harness
+            .runtime
+            .status(&service_unit_name(&old))
+            .await
+            .unwrap(),
+        UnitStatus::NotFound
+    );
+    assert_eq!(
+        harness
+            .runtime
+            .status(&service_unit_name(&new))
+            .await
+            .unwrap(),
+        UnitStatus::Running
+    );
+    // The new deployment reads the same secrets file when it restarts
+    assert!(
+        harness
+            .config
+            .paths
+            .secrets_dir
+            .join("test-deployer-redeploy-main-api.env")
+            .exists()
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_failed_health_check_marks_deployment_failed() {
+    let harness = Harness::new("test-deployer-unhealthy").await;
+
+    let kennel_toml = r#"
+[services.api]
+health_check_timeout_secs = 2
+"#;
+    let build_id = harness
+        .build("abc123", kennel_toml, "#!/bin/sh\nexit 1\n")
+        .await;
+    harness.deploy(build_id).await;
+
+    let deployments = harness.deployments().await;
+    assert_eq!(deployments.len(), 1);
+    let deployment = &deployments[0];
+
+    assert_eq!(deployment.status, DeploymentStatus::Failed);
+    assert!(deployment.status_message.is_some());
+    assert_eq!(
+        harness
+            .runtime
+            .status(&service_unit_name(deployment))
+            .await
+            .unwrap(),
+        UnitStatus::NotFound
+    );
+    assert!(
+        harness
+            .store
+            .port_allocations()
+            .find_by_deployment(deployment.id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_release_command_gates_deployment() {
+    let harness = Harness::new("test-deployer-release").await;
+
+    let release_toml = |command: &str| {
+        format!(
+            "{}release_command = '{}'\nrelease_timeout_secs = 2\n",
+            KENNEL_TOML, command
+        )
+    };
+
+    let first_build = harness
+        .build(
+            "abc123",
+            &release_toml("echo migrating on $PORT; echo v1 > schema"),
+            &fixture_script(),
+        )
+        .await;
+    harness.deploy(first_build).await;
+
+    let old = harness.deployments().await.remove(0);
+    assert_eq!(old.status, DeploymentStatus::Active);
+    assert_eq!(
+        old.release_log,
+        Some(format!("migrating on {}", old.port.unwrap()))
+    );
+    let state_dir = harness
+        .runtime
+        .state_dir(Path::new("kennel/services/test-deployer-release/main/api"));
+    assert_eq!(
+        tokio::fs::read_to_string(state_dir.join("schema"))
+            .await
+            .unwrap(),
+        "v1\n"
+    );
+
+    for (commit_sha, command, message) in [
+        (
+            "def456",
+            "echo bad migration; exit 2",
+            "release command failed: exited with code 2",
+        ),
+        (
+            "fed789",
+            "sleep 30",
+            "release command failed: timed out after 2s",
+        ),
+    ] {
+        let build_id = harness
+            .build(commit_sha, &release_toml(command), &fixture_script())
+            .await;
+        harness.deploy(build_id).await;
+
+        let failed = harness
+            .deployments()
+            .await
+            .into_iter()
+            .find(|d| d.build_id == Some(build_id))
+            .unwrap();
+        assert_eq!(failed.status, DeploymentStatus::Failed);
+        assert_eq!(failed.status_message.as_deref(), Some(message));
+        assert_eq!(
+            harness
+                .runtime
+                .status(&service_unit_name(&failed))
+                .await
+                .unwrap(),
+            UnitStatus::NotFound
+        );
+    }
+
+    let failed_release_log = harness
+        .deployments()
+        .await
+        .into_iter()
+        .find_map(|d| d.release_log.filter(|log| log.contains("bad")));
+    assert_eq!(failed_release_log.as_deref(), Some("bad migration"));
+
+    let live = harness
+        .store
+        .deployments()
+        .find_active_by_ref("test-deployer-release", "main", "api")
+        .await
+        .unwrap()
+        .expect("Old deployment should keep serving");
+    assert_eq!(live.id, old.id);
+    assert_eq!(
+        harness
+            .runtime
+            .status(&service_unit_name(&live))
+            .await
+            .unwrap(),
+        UnitStatus::Running
+    );
+
+    harness.cleanup().await;
+}
+
+/// Whether `web` has logged the discovery variables for `api`.
+async fn logs_discovered_api(
+    harness: &Harness,
+    web: &deployments::Model,
+    api: &deployments::Model,
+) -> bool {
+    let expected = format!(
+        "api at http://127.0.0.1:{} https://{}",
+        api.port.unwrap(),
+        api.domain
+    );
+    let query = LogQuery {
+        grep: Some("api at".to_string()),
+        lines: 10,
+        ..Default::default()
+    };
+
+    harness
+        .runtime
+        .logs(&service_unit_name(web), &query)
+        .await
+        .unwrap()
+        .iter()
+        .any(|entry| entry.message == expected)
+}
+
+#[tokio::test]
+async fn test_dependencies_deploy_first_and_are_discoverable() {
+    let harness = Harness::new("test-deployer-depends").await;
+
+    let kennel_toml = r#"
+[services.web]
+depends_on = ["api"]
+health_check_timeout_secs = 20
+drain_timeout_secs = 1
+
+[services.api]
+health_check_timeout_secs = 20
+drain_timeout_secs = 1
+"#;
+    let web_script = fixture_script().replacen(
+        "exec ",
+        "echo \"api at $KENNEL_SERVICE_API_URL $KENNEL_SERVICE_API_PUBLIC_URL\"\nexec ",
+        1,
+    );
+
+    let build_id = harness
+        .build_services(
+            "abc123",
+            kennel_toml,
+            &[("web", &web_script), ("api", &fixture_script())],
+        )
+        .await;
+    harness.deploy(build_id).await;
+
+    let api = harness.active("api").await;
+    let web = harness.active("web").await;
+    assert!(api.id < web.id, "api should be deployed before web");
+    assert!(logs_discovered_api(&harness, &web, &api).await);
+
+    // Redeploying api alone moves it to a new port; web follows
+    let api_build = harness
+        .build("def456", kennel_toml, &fixture_script())
+        .await;
+    harness.deploy(api_build).await;
+
+    let new_api = harness.active("api").await;
+    assert_ne!(new_api.port, api.port);
+    assert_eq!(harness.active("web").await.id, web.id);
+    assert!(logs_discovered_api(&harness, &web, &new_api).await);
+
+    harness.cleanup().await;
+}
+
+const WORKER_TOML: &str = r#"
+[services.api]
+kind = "worker"
+grace_period_secs = 1
+"#;
+
+#[tokio::test]
+async fn test_deploy_and_replace_worker() {
+    let harness = Harness::new("test-deployer-worker").await;
+
+    let first_build = harness
+        .build("abc123", WORKER_TOML, "#!/bin/sh\nexec sleep 300\n")
+        .await;
+    harness.deploy(first_build).await;
+
+    let old = harness.deployments().await.remove(0);
+    assert_eq!(old.status, DeploymentStatus::Active);
+    assert_eq!(old.port, None);
+    assert_eq!(old.service_type, ServiceType::Worker);
+    assert_eq!(
+        harness
+            .runtime
+            .status(&service_unit_name(&old))
+            .await
+            .unwrap(),
+        UnitStatus::Running
+    );
+    assert_eq!(
+        harness
+            .store
+            .services()
+            .find_by_project_and_name("test-deployer-worker", "api")
+            .await
+            .unwrap()
+            .unwrap()
+            .r#type,
+        ServiceType::Worker
+    );
+
+    let second_build = harness
+        .build("def456", WORKER_TOML, "#!/bin/sh\nexec sleep 300\n")
+        .await;
+    harness.deploy(second_build).await;
+
+    let new = harness
+        .store
+        .deployments()
+        .find_active_by_ref("test-deployer-worker", "main", "api")
+        .await
+        .unwrap()
+        .expect("New worker deployment should be active");
+    assert_ne!(new.id, old.id);
+
+    let mut old_status = None;
+    for _ in 0..50 {
+        old_status = harness
+            .store
+            .deployments()
+            .find_by_id(old.id)
+            .await
+            .unwrap()
+            .map(|d| d.status);
+        if old_status == Some(DeploymentStatus::TornDown) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(old_status, Some(DeploymentStatus::TornDown));
+
+    harness
+        .store
+        .deployments()
+        .mark_ids_tearing_down(&[new.id], "Branch deleted")
+        .await
+        .unwrap();
+    process_teardown(new.id, &harness.config).await.unwrap();
+
+    assert_eq!(
+        harness
+            .runtime
+            .status(&service_unit_name(&new))
+            .await
+            .unwrap(),
+        UnitStatus::NotFound
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_worker_exiting_during_grace_period_fails() {
+    let harness = Harness::new("test-deployer-worker-exit").await;
+
+    let build_id = harness
+        .build("abc123", WORKER_TOML, "#!/bin/sh\nexit 1\n")
+        .await;
+    harness.deploy(build_id).await;
+
+    let deployments = harness.deployments().await;
+    assert_eq!(deployments.len(), 1);
+    assert_eq!(deployments[0].status, DeploymentStatus::Failed);
+    assert_eq!(
+        harness
+            .runtime
+            .status(&service_unit_name(&deployments[0]))
+            .await
+            .unwrap(),
+        UnitStatus::NotFound
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_deploy_job_and_record_runs() {
+    let harness = Harness::new("test-deployer-job").await;
+
+    let kennel_toml = r#"
+[jobs.api]
+schedule = "daily"
+
+[jobs.api.env]
+REPORT = "nightly"
+"#;
+    let build_id = harness
+        .build(
+            "abc123",
+            kennel_toml,
+            "#!/bin/sh\necho \"report $REPORT done\"\nexit 3\n",
+        )
+        .await;
+    harness.deploy(build_id).await;
+
+    let deployment = harness.deployments().await.remove(0);
+    assert_eq!(deployment.status, DeploymentStatus::Active);
+    assert_eq!(deployment.port, None);
+
+    let unit_name = service_unit_name(&deployment);
+    assert_eq!(
+        harness.runtime.status(&unit_name).await.unwrap(),
+        UnitStatus::Running
+    );
+    assert_eq!(collect_job_runs(&harness.config).await.unwrap(), 0);
+
+    let run = harness.runtime.run_job(&unit_name).await.unwrap();
+    assert!(!run.succeeded);
+    assert_eq!(run.exit_code, Some(3));
+
+    assert_eq!(collect_job_runs(&harness.config).await.unwrap(), 1);
+    assert_eq!(collect_job_runs(&harness.config).await.unwrap(), 0);
+
+    let recorded = harness
+        .store
+        .job_runs()
+        .latest_by_deployment(deployment.id)
+        .await
+        .unwrap()
+        .expect("Run should be recorded");
+    assert_eq!(recorded.job_name, "api");
+    assert!(!recorded.succeeded);
+    assert_eq!(recorded.exit_code, Some(3));
+    assert_eq!(recorded.log.as_deref(), Some("report nightly done"));
+
+    harness
+        .store
+        .deployments()
+        .mark_ids_tearing_down(&[deployment.id], "Branch deleted")
+        .await
+        .unwrap();
+    process_teardown(deployment.id, &harness.config)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        harness.runtime.status(&unit_name).await.unwrap(),
+        UnitStatus::NotFound
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_replicas_get_a_port_each() {
+    let harness = Harness::new("test-deployer-replicas").await;
+
+    let kennel_toml = format!("{}replicas = 2\n", KENNEL_TOML);
+    let build_id = harness
+        .build("abc123", &kennel_toml, &fixture_script())
+        .await;
+    harness.deploy(build_id).await;
+
+    let deployment = harness.active("api").await;
+    let ports: Vec<i32> = harness
+        .store
+        .port_allocations()
+        .list_by_deployment(deployment.id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|a| a.port)
+        .collect();
+    assert_eq!(ports.len(), 2);
+    assert_eq!(deployment.port, Some(ports[0]));
+
+    for port in &ports {
+        assert!(
+            reqwest::get(format!("http://127.0.0.1:{}/health", port))
+                .await
+                .unwrap()
+                .status()
+                .is_success(),
+            "Replica on port {} should be serving",
+            port
+        );
+    }
+
+    let unit_name = service_unit_name(&deployment);
+    let listening = harness
+        .runtime
+        .logs(
+            &unit_name,
+            &LogQuery {
+                grep: Some("fixture listening".to_string()),
+                lines: 10,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(listening.len(), 2, "Each replica should log its own port");
+
+    harness
+        .store
+        .deployments()
+        .mark_ids_tearing_down(&[deployment.id], "Branch deleted")
+        .await
+        .unwrap();
+    process_teardown(deployment.id, &harness.config)
+        .await
+        .unwrap();
+
+    assert!(
+        harness
+            .store
+            .port_allocations()
+            .list_by_deployment(deployment.id)
+            .await
+            .unwrap()
+            .is_empty(),
+        "Teardown should release every replica's port"
+    );
+    assert_eq!(
+        harness.runtime.status(&unit_name).await.unwrap(),
+        UnitStatus::NotFound
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_unhealthy_replica_is_restarted_alone() {
+    let harness = Harness::new("test-deployer-replica-restart").await;
+
+    let kennel_toml = format!(
+        "{}replicas = 2\n\n[services.api.remediation]\nfailure_threshold = 1\n",
+        KENNEL_TOML
+    );
+    let build_id = harness
+        .build("abc123", &kennel_toml, &fixture_script())
+        .await;
+    harness.deploy(build_id).await;
+
+    let deployment = harness.active("api").await;
+    let ports: Vec<u16> = harness
+        .store
+        .port_allocations()
+        .list_by_deployment(deployment.id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|a| a.port as u16)
+        .collect();
+
+    let (report_tx, report_rx) = tokio::sync::mpsc::channel(10);
+    let worker = tokio::spawn(run_remediation_worker(report_rx, harness.config.clone()));
+
+    report_tx
+        .send(HealthReport {
+            deployment_id: deployment.id,
+            port: ports[0],
+            healthy: true,
+        })
+        .await
+        .unwrap();
+    report_tx
+        .send(HealthReport {
+            deployment_id: deployment.id,
+            port: ports[1],
+            healthy: false,
+        })
+        .await
+        .unwrap();
+    drop(report_tx);
+    worker.await.unwrap();
+
+    let events = harness
+        .store
+        .deployment_events()
+        .list_by_deployment(deployment.id)
+        .await
+        .unwrap();
+    let restarts: Vec<_> = events
+        .iter()
+        .filter(|e| e.kind == DeploymentEventKind::Restarted)
+        .collect();
+    assert_eq!(restarts.len(), 1);
+    assert!(
+        restarts[0]
+            .message
+            .as_deref()
+            .unwrap()
+            .contains(&format!("port {}", ports[1]))
+    );
+
+    // Only the failing replica came back up, so three starts get logged
+    let mut listening = Vec::new();
+    for _ in 0..50 {
+        listening = harness
+            .runtime
+            .logs(
+                &service_unit_name(&deployment),
+                &LogQuery {
+                    grep: Some("fixture listening".to_string()),
+                    lines: 10,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        if listening.len() >= 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(listening.len(), 3);
+    assert!(listening[2].message.contains(&ports[1].to_string()));
+    assert_eq!(harness.active("api").await.id, deployment.id);
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_idle_deployment_stops_and_wakes() {
+    let harness = Harness::new("test-deployer-idle").await;
+
+    let kennel_toml = format!("{}idle_timeout_secs = 1\n", KENNEL_TOML);
+    let build_id = harness
+        .build("abc123", &kennel_toml, &fixture_script())
+        .await;
+    harness.deploy(build_id).await;
+
+    // Production never idles
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(stop_idle_deployments(&harness.config).await.unwrap(), 0);
+
+    let deployment = harness.active("api").await;
+    let mut preview: deployments::ActiveModel = deployment.clone().into();
+    preview.environment = Set("preview".to_string());
+    harness.store.deployments().update(preview).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(stop_idle_deployments(&harness.config).await.unwrap(), 1);
+
+    let unit_name = service_unit_name(&deployment);
+    let port = deployment.port.unwrap();
+    assert_eq!(
+        harness.runtime.status(&unit_name).await.unwrap(),
+        UnitStatus::Stopped
+    );
+    let idle = harness.active("api").await;
+    assert!(idle.idle_since.is_some(), "An idle deployment stays active");
+    assert!(
+        reqwest::get(format!("http://127.0.0.1:{}/health", port))
+            .await
+            .is_err()
+    );
+
+    wake_deployment(deployment.id, &harness.config)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        harness.runtime.status(&unit_name).await.unwrap(),
+        UnitStatus::Running
+    );
+    assert!(harness.active("api").await.idle_since.is_none());
+    assert!(
+        reqwest::get(format!("http://127.0.0.1:{}/health", port))
+            .await
+            .unwrap()
+            .status()
+            .is_success()
+    );
+
+    // Waking restarts the idle clock
+    assert_eq!(stop_idle_deployments(&harness.config).await.unwrap(), 0);
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_idle_dependencies_follow_their_dependents() {
+    let harness = Harness::new("test-deployer-idle-depends").await;
+
+    let kennel_toml = r#"
+[services.web]
+depends_on = ["api"]
+health_check_timeout_secs = 20
+idle_timeout_secs = 1
+
+[services.api]
+health_check_timeout_secs = 20
+idle_timeout_secs = 1
+"#;
+    let build_id = harness
+        .build_services(
+            "abc123",
+            kennel_toml,
+            &[("web", &fixture_script()), ("api", &fixture_script())],
+        )
+        .await;
+    harness.deploy(build_id).await;
+
+    let project = harness
+        .store
+        .projects()
+        .find_by_name(&harness.project)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut project: projects::ActiveModel = project.into();
+    project.environment_rules = Set(Some(serde_json::json!([
+        { "branch": "main", "environment": "live", "kind": "production" },
+    ])));
+    harness.store.projects().update(project).await.unwrap();
+
+    let set_environment = |deployment: deployments::Model, environment: &str| {
+        let mut deployment: deployments::ActiveModel = deployment.into();
+        deployment.environment = Set(environment.to_string());
+        deployment
+    };
+
+    // An environment the project marks as production never idles
+    for service in ["web", "api"] {
+        let deployment = set_environment(harness.active(service).await, "live");
+        harness
+            .store
+            .deployments()
+            .update(deployment)
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(stop_idle_deployments(&harness.config).await.unwrap(), 0);
+
+    for service in ["web", "api"] {
+        let deployment = set_environment(harness.active(service).await, "dev");
+        harness
+            .store
+            .deployments()
+            .update(deployment)
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    // api keeps running while web, which calls it directly, is up
+    assert_eq!(stop_idle_deployments(&harness.config).await.unwrap(), 1);
+    assert!(harness.active("web").await.idle_since.is_some());
+    assert!(harness.active("api").await.idle_since.is_none());
+
+    assert_eq!(stop_idle_deployments(&harness.config).await.unwrap(), 1);
+    let api = harness.active("api").await;
+    assert!(api.idle_since.is_some());
+
+    // Waking web wakes api first
+    wake_deployment(harness.active("web").await.id, &harness.config)
+        .await
+        .unwrap();
+    assert!(harness.active("api").await.idle_since.is_none());
+    assert_eq!(
+        harness
+            .runtime
+            .status(&service_unit_name(&api))
+            .await
+            .unwrap(),
+        UnitStatus::Running
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_branch_expires_as_a_whole() {
+    let harness = Harness::new("test-deployer-expiry-branch").await;
+    let (teardown_tx, mut teardown_rx) = tokio::sync::mpsc::channel(10);
+
+    let kennel_toml = r#"
+[expiry]
+branch_ttl_days = 2
+
+[services.web]
+health_check_timeout_secs = 20
+
+[services.api]
+health_check_timeout_secs = 20
+"#;
+    let build_id = harness
+        .build_services(
+            "abc123",
+            kennel_toml,
+            &[("web", &fixture_script()), ("api", &fixture_script())],
+        )
+        .await;
+    harness.deploy(build_id).await;
+
+    let set_last_activity = |deployment: deployments::Model, hours: i64| {
+        let mut dev: deployments::ActiveModel = deployment.into();
+        dev.environment = Set("dev".to_string());
+        dev.last_activity = Set(chrono::Utc::now().naive_utc() - chrono::TimeDelta::hours(hours));
+        let store = harness.store.clone();
+        async move { store.deployments().update(dev).await.unwrap() }
+    };
+
+    // api only gets requests from web, which the router never sees
+    set_last_activity(harness.active("api").await, 72).await;
+    set_last_activity(harness.active("web").await, 1).await;
+    assert_eq!(
+        expire_deployments(&harness.config, &teardown_tx)
+            .await
+            .unwrap(),
+        0
+    );
+
+    set_last_activity(harness.active("web").await, 72).await;
+    assert_eq!(
+        expire_deployments(&harness.config, &teardown_tx)
+            .await
+            .unwrap(),
+        2
+    );
+    assert!(teardown_rx.try_recv().is_ok());
+    assert!(teardown_rx.try_recv().is_ok());
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_expiry_policy_warns_expires_and_honours_pins() {
+    let harness = Harness::new("test-deployer-expiry").await;
+    let (teardown_tx, mut teardown_rx) = tokio::sync::mpsc::channel(10);
+
+    let kennel_toml = format!("[expiry]\nbranch_ttl_days = 2\n{}", KENNEL_TOML);
+    let build_id = harness
+        .build("abc123", &kennel_toml, &fixture_script())
+        .await;
+    harness.deploy(build_id).await;
+
+    let project = harness
+        .store
+        .projects()
+        .find_by_name(&harness.project)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut project: projects::ActiveModel = project.into();
+    project.expiry_policy = Set(Some(serde_json::json!({
+        "branch_ttl_days": 30,
+        "warning_hours": 24,
+    })));
+    harness.store.projects().update(project).await.unwrap();
+
+    let deployment = harness.active("api").await;
+    let set_last_activity = |hours: i64| {
+        let mut dev: deployments::ActiveModel = deployment.clone().into();
+        dev.environment = Set("dev".to_string());
+        dev.last_activity = Set(chrono::Utc::now().naive_utc() - chrono::TimeDelta::hours(hours));
+        let store = harness.store.clone();
+        async move { store.deployments().update(dev).await.unwrap() }
+    };
+    let warnings = || async {
+        deployment_events::Entity::find()
+            .filter(deployment_events::Column::DeploymentId.eq(deployment.id))
+            .filter(deployment_events::Column::Kind.eq(DeploymentEventKind::ExpiryWarning))
+            .all(harness.store.db())
+            .await
+            .unwrap()
+            .len()
+    };
+
+    // kennel.toml's two days win over the project's thirty; warn once
+    set_last_activity(30).await;
+    for _ in 0..2 {
+        assert_eq!(
+            expire_deployments(&harness.config, &teardown_tx)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+    assert_eq!(warnings().await, 1);
+
+    harness
+        .store
+        .branch_pins()
+        .pin(&harness.project, "main", "alice")
+        .await
+        .unwrap();
+    set_last_activity(72).await;
+    assert_eq!(
+        expire_deployments(&harness.config, &teardown_tx)
+            .await
+            .unwrap(),
+        0
+    );
+
+    assert!(
+        harness
+            .store
+            .branch_pins()
+            .unpin(&harness.project, "main")
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        expire_deployments(&harness.config, &teardown_tx)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(teardown_rx.try_recv().unwrap(), deployment.id);
+
+    let expired = harness
+        .store
+        .deployments()
+        .find_by_id(deployment.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(expired.status, DeploymentStatus::TearingDown);
+    assert_eq!(
+        expired.status_message.as_deref(),
+        Some("Expired after 2 days without requests")
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_environment_mapping_selects_environment_and_secrets() {
+    let harness = Harness::new("test-deployer-environments").await;
+
+    let project = harness
+        .store
+        .projects()
+        .find_by_name(&harness.project)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut project: projects::ActiveModel = project.into();
+    project.environment_rules = Set(Some(serde_json::json!([
+        { "branch": "ma*", "environment": "qa" },
+    ])));
+    harness.store.projects().update(project).await.unwrap();
+
+    let secrets_dir = harness
+        .config
+        .paths
+        .secret_sources_dir
+        .join(&harness.project);
+    for (environment, value) in [("prod", "prod-key"), ("qa", "qa-key")] {
+        tokio::fs::create_dir_all(secrets_dir.join(environment))
+            .await
+            .unwrap();
+        tokio::fs::write(secrets_dir.join(environment).join("API_KEY"), value)
+            .await
+            .unwrap();
+    }
+
+    let kennel_toml = format!("{}secrets = [\"API_KEY\"]\n", KENNEL_TOML);
+    let build_id = harness
+        .build("abc123", &kennel_toml, &fixture_script())
+        .await;
+    harness.deploy(build_id).await;
+
+    assert_eq!(harness.active("api").await.environment, "qa");
+
+    let env_file = tokio::fs::read_to_string(
+        harness
+            .config
+            .paths
+            .secrets_dir
+            .join("test-deployer-environments-main-api.env"),
+    )
+    .await
+    .unwrap();
+    assert!(env_file.contains("KENNEL_ENVIRONMENT=qa\n"));
+    assert!(env_file.contains("API_KEY=qa-key\n"));
+    assert!(!env_file.contains("prod-key"));
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_promote_deploys_same_store_path_with_provenance() {
+    let harness = Harness::new("test-deployer-promote").await;
+
+    let build_id = harness
+        .build("abc123", KENNEL_TOML, &fixture_script())
+        .await;
+    deploy_build(
+        &DeploymentRequest {
+            build_id,
+            project_name: harness.project.clone(),
+            git_ref: "staging".to_string(),
+            services: Vec::new(),
+            trigger: DeploymentTrigger::Build,
+        },
+        &harness.config,
+    )
+    .await
+    .unwrap();
+
+    let staging = harness
+        .store
+        .deployments()
+        .find_active_by_ref(&harness.project, "staging", "api")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(staging.environment, "staging");
+
+    assert!(
+        plan_promotion(
+            &harness.store,
+            &harness.project,
+            "staging",
+            "staging",
+            None,
+            "alice"
+        )
+        .await
+        .is_err()
+    );
+    assert!(
+        plan_promotion(
+            &harness.store,
+            &harness.project,
+            "feature",
+            "main",
+            None,
+            "alice"
+        )
+        .await
+        .is_err()
+    );
+
+    let requests = plan_promotion(
+        &harness.store,
+        &harness.project,
+        "staging",
+        "main",
+        None,
+        "alice",
+    )
+    .await
+    .unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].build_id, build_id);
+    assert_eq!(requests[0].git_ref, "main");
+    assert_eq!(requests[0].services, vec!["api".to_string()]);
+
+    for request in &requests {
+        deploy_build(request, &harness.config).await.unwrap();
+    }
+
+    let prod = harness.active("api").await;
+    assert_eq!(prod.environment, "prod");
+    assert_eq!(prod.build_id, Some(build_id));
+    assert_eq!(prod.store_path, staging.store_path);
+    assert_eq!(prod.promoted_from_branch.as_deref(), Some("staging"));
+    assert_eq!(prod.promoted_from_environment.as_deref(), Some("staging"));
+    assert_ne!(prod.id, staging.id);
+
+    let events = deployment_events::Entity::find()
+        .filter(deployment_events::Column::DeploymentId.eq(prod.id))
+        .filter(deployment_events::Column::Kind.eq(DeploymentEventKind::Promoted))
+        .all(harness.store.db())
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].actor, "alice");
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_freeze_holds_builds_and_records_overrides() {
+    let harness = Harness::new("test-deployer-freeze").await;
+    let now = chrono::Utc::now().naive_utc();
+
+    let freeze = |reason: &str| freeze_windows::ActiveModel {
+        project_name: Set(Some(harness.project.clone())),
+        environment: Set(Some("prod".to_string())),
+        reason: Set(reason.to_string()),
+        starts_at: Set(Some(now - chrono::TimeDelta::hours(1))),
+        ends_at: Set(Some(now + chrono::TimeDelta::hours(1))),
+        created_by: Set("alice".to_string()),
+        ..Default::default()
+    };
+    let finals = harness
+        .store
+        .freeze_windows()
+        .create(freeze("Finals week"))
+        .await
+        .unwrap();
+
+    let first = harness
+        .build("abc123", KENNEL_TOML, &fixture_script())
+        .await;
+    harness.deploy(first).await;
+
+    // The build is held with the freeze as its reason instead of deploying
+    assert!(harness.deployments().await.is_empty());
+    let held = harness
+        .store
+        .builds()
+        .find_by_id(first)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(held.status, BuildStatus::Frozen);
+    assert!(held.hold_reason.unwrap().contains("Finals week"));
+
+    // Manual deployments are refused outright
+    let rollback = deploy_build(
+        &DeploymentRequest {
+            build_id: first,
+            project_name: harness.project.clone(),
+            git_ref: "main".to_string(),
+            services: Vec::new(),
+            trigger: DeploymentTrigger::Rollback {
+                actor: "bob".to_string(),
+            },
+        },
+        &harness.config,
+    )
+    .await;
+    assert!(rollback.is_err());
+
+    let (deploy_tx, mut deploy_rx) = tokio::sync::mpsc::channel(10);
+    assert_eq!(
+        release_frozen_builds(&harness.config, &deploy_tx)
+            .await
+            .unwrap(),
+        0
+    );
+
+    // Once the freeze is lifted the held build is queued for deployment
+    harness
+        .store
+        .freeze_windows()
+        .delete(finals.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        release_frozen_builds(&harness.config, &deploy_tx)
+            .await
+            .unwrap(),
+        1
+    );
+    let request = deploy_rx.recv().await.unwrap();
+    assert_eq!(request.build_id, first);
+    assert_eq!(request.trigger, DeploymentTrigger::Build);
+    deploy_build(&request, &harness.config).await.unwrap();
+    assert_eq!(harness.active("api").await.build_id, Some(first));
+
+    // An admin override deploys a held build during a freeze and is recorded
+    harness
+        .store
+        .freeze_windows()
+        .create(freeze("Demo day"))
+        .await
+        .unwrap();
+    let second = harness
+        .build("def456", KENNEL_TOML, &fixture_script())
+        .await;
+    harness.deploy(second).await;
+    assert_eq!(harness.active("api").await.build_id, Some(first));
+
+    let released = harness
+        .store
+        .builds()
+        .release_hold(second)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(released.status, BuildStatus::Success);
+    assert!(released.hold_reason.is_none());
+
+    deploy_build(
+        &DeploymentRequest {
+            build_id: second,
+            project_name: harness.project.clone(),
+            git_ref: "main".to_string(),
+            services: Vec::new(),
+            trigger: DeploymentTrigger::OverrideFreeze {
+                actor: "admin".to_string(),
+            },
+        },
+        &harness.config,
+    )
+    .await
+    .unwrap();
+
+    let active = harness.active("api").await;
+    assert_eq!(active.build_id, Some(second));
+
+    let overrides = deployment_events::Entity::find()
+        .filter(deployment_events::Column::DeploymentId.eq(active.id))
+        .filter(deployment_events::Column::Kind.eq(DeploymentEventKind::FreezeOverridden))
+        .all(harness.store.db())
+        .await
+        .unwrap();
+    assert_eq!(overrides.len(), 1);
+    assert_eq!(overrides[0].actor, "admin");
+
+    // A freeze on every environment skips the project's preview environments
+    harness
+        .store
+        .freeze_windows()
+        .create(freeze_windows::ActiveModel {
+            environment: Set(None),
+            ..freeze("Code freeze")
+        })
+        .await
+        .unwrap();
+    let project = harness
+        .store
+        .projects()
+        .find_by_name(&harness.project)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut project: projects::ActiveModel = project.into();
+    project.environment_rules = Set(Some(serde_json::json!([
+        { "branch": "pr-*", "environment": "review", "kind": "preview" },
+    ])));
+    let project = harness.store.projects().update(project).await.unwrap();
+    assert!(
+        active_freeze(&harness.store, &project, "review")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        active_freeze(&harness.store, &project, "prod")
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    harness.cleanup().await;
+}
+
+#[tokio::test]
+async fn test_deploy_reports_commit_statuses() {
+    let mut harness = Harness::new("test-deployer-statuses").await;
+
+    // A forge recording the statuses posted to it
+    let statuses = Arc::new(std::sync::Mutex::new(Vec::<serde_json::Value>::new()));
+    let recorded = statuses.clone();
+    let forge = axum::Router::new().fallback(move |body: String| {
+        let recorded = recorded.clone();
+        async move {
+            recorded
+                .lock()
+                .unwrap()
+                .push(serde_json::from_str(&body).unwrap());
+            axum::http::StatusCode::CREATED
+        }
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let forge_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, forge).await.unwrap() });
+
+    harness
+        .store
+        .projects()
+        .update(projects::ActiveModel {
+            name: sea_orm::ActiveValue::Unchanged(harness.project.clone()),
+            repo_url: Set(format!("{}/owner/{}", forge_url, harness.project)),
+            forge_token: Set(Some("token".to_string())),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    harness.config.forge = Some(Arc::new(StatusReporter::new(harness.store.clone(), None)));
+
+    let build_id = harness
+        .build("abc123", KENNEL_TOML, &fixture_script())
+        .await;
+    harness.deploy(build_id).await;
+
+    let active = harness.active("api").await;
+    let statuses = statuses.lock().unwrap().clone();
+    assert_eq!(statuses.len(), 2);
+    assert!(statuses.iter().all(|s| s["context"] == "kennel/deploy/api"));
+    assert_eq!(statuses[0]["state"], "pending");
+    assert_eq!(statuses[1]["state"], "success");
+    assert_eq!(statuses[1]["description"], "Deployed to prod");
+    assert_eq!(
+        statuses[1]["target_url"],
+        format!("https://{}", active.domain)
+    );
+
+    harness.cleanup().await;
+}
@@ -0,0 +1,135 @@
+use crate::{Error, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Posts commit statuses and pull request comments to the forge hosting a
+/// repository.
+#[async_trait]
+pub trait ForgeClient: Send + Sync {
+    async fn create_status(&self, sha: &str, status: &CommitStatus) -> Result<()>;
+
+    /// Comment on a pull request, returning the new comment's id.
+    async fn create_comment(&self, pr_number: u64, body: &str) -> Result<u64>;
+
+    /// Edit a comment on a pull request. GitLab addresses comments through
+    /// their merge request, so the pull request is passed along.
+    async fn update_comment(&self, pr_number: u64, comment_id: u64, body: &str) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CommitStatus {
+    pub state: StatusState,
+    /// Distinguishes kennel's statuses on a commit, e.g. `kennel/build/api`.
+    pub context: String,
+    pub description: String,
+    pub target_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusState {
+    Pending,
+    Success,
+    Failure,
+    Error,
+}
+
+/// Owner, repository name and host parsed from a clone URL such as
+/// `https://github.com/owner/repo.git`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoRef {
+    /// Scheme and authority, e.g. `https://github.com`.
+    pub origin: String,
+    pub owner: String,
+    pub name: String,
+}
+
+impl RepoRef {
+    pub fn parse(repo_url: &str) -> Result<Self> {
+        let invalid = || Error::InvalidRepoUrl(repo_url.to_string());
+
+        let (scheme, rest) = repo_url.split_once("://").ok_or_else(invalid)?;
+        let (host, path) = rest.split_once('/').ok_or_else(invalid)?;
+        let path = path.trim_end_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        let (owner, name) = path.rsplit_once('/').ok_or_else(invalid)?;
+
+        if host.is_empty() || owner.is_empty() || name.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            origin: format!("{}://{}", scheme, host),
+            owner: owner.to_string(),
+            name: name.to_string(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct CommentBody<'a> {
+    pub body: &'a str,
+}
+
+/// The part of a created comment kennel keeps.
+#[derive(Deserialize)]
+pub(crate) struct Comment {
+    pub id: u64,
+}
+
+/// Send `body` as JSON, turning non-2xx responses into errors and returning
+/// the response body.
+pub(crate) async fn send_json(
+    request: reqwest::RequestBuilder,
+    body: &impl Serialize,
+) -> Result<Vec<u8>> {
+    let body = serde_json::to_vec(body).expect("request body serializes");
+
+    let response = request
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(reqwest::header::USER_AGENT, "kennel")
+        .body(body)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(Error::Api {
+            status: response.status().as_u16(),
+            body: response.text().await.unwrap_or_default(),
+        });
+    }
+
+    Ok(response.bytes().await?.to_vec())
+}
+
+pub(crate) fn parse_comment(body: &[u8]) -> Result<u64> {
+    let comment: Comment =
+        serde_json::from_slice(body).map_err(|e| Error::InvalidResponse(e.to_string()))?;
+
+    Ok(comment.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repo_url() {
+        let repo = RepoRef::parse("https://github.com/ScottyLabs/kennel.git").unwrap();
+        assert_eq!(repo.origin, "https://github.com");
+        assert_eq!(repo.owner, "ScottyLabs");
+        assert_eq!(repo.name, "kennel");
+
+        let repo = RepoRef::parse("https://codeberg.org/ScottyLabs/kennel/").unwrap();
+        assert_eq!(repo.origin, "https://codeberg.org");
+        assert_eq!(repo.name, "kennel");
+
+        let repo = RepoRef::parse("http://127.0.0.1:3000/group/sub/app").unwrap();
+        assert_eq!(repo.origin, "http://127.0.0.1:3000");
+        assert_eq!(repo.owner, "group/sub");
+        assert_eq!(repo.name, "app");
+
+        assert!(RepoRef::parse("github.com/owner/repo").is_err());
+        assert!(RepoRef::parse("https://github.com/repo").is_err());
+    }
+}
@@ -0,0 +1,359 @@
+use crate::reporter::StatusReporter;
+use crate::{Error, Result, client_for};
+use entity::builds;
+use entity::sea_orm_active_enums::{BuildResultStatus, BuildStatus, DeploymentStatus, ServiceType};
+use kennel_store::StoreError;
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// Builds and deployments update a pull request's comment from different
+/// tasks; serialising them keeps two from creating a comment each.
+static COMMENT_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// The pull request a preview ref such as `pr-12` belongs to.
+pub fn pr_number(git_ref: &str) -> Option<u64> {
+    git_ref.strip_prefix("pr-")?.parse().ok()
+}
+
+/// One line of the preview table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PreviewRow {
+    pub service: String,
+    pub url: Option<String>,
+    pub status: &'static str,
+    pub commit: String,
+}
+
+pub(crate) fn render_preview(build: &builds::Model, rows: &[PreviewRow]) -> String {
+    let commit = short_sha(&build.commit_sha);
+
+    let mut body = format!(
+        "### Preview deployments\n\nLatest commit `{}`: {} (build #{}).\n",
+        commit,
+        describe_build(&build.status),
+        build.id
+    );
+
+    if !rows.is_empty() {
+        body.push_str("\n| Service | URL | Status | Commit |\n| --- | --- | --- | --- |\n");
+        for row in rows {
+            body.push_str(&format!(
+                "| {} | {} | {} | `{}` |\n",
+                row.service,
+                row.url.as_deref().unwrap_or("-"),
+                row.status,
+                row.commit
+            ));
+        }
+    }
+
+    body
+}
+
+pub(crate) fn render_torn_down(build: Option<&builds::Model>) -> String {
+    let mut body = "### Preview deployments\n\nTorn down when the pull request closed".to_string();
+    if let Some(build) = build {
+        body.push_str(&format!(
+            "; the last build was of `{}`",
+            short_sha(&build.commit_sha)
+        ));
+    }
+    body.push_str(".\n");
+    body
+}
+
+fn describe_build(status: &BuildStatus) -> &'static str {
+    match status {
+        BuildStatus::Queued => "queued",
+        BuildStatus::Building => "building",
+        BuildStatus::Success => "built",
+        BuildStatus::Failed => "build failed",
+        BuildStatus::Cancelled => "build cancelled",
+        BuildStatus::AwaitingApproval => "waiting for approval",
+        BuildStatus::Rejected => "rejected",
+        BuildStatus::Expired => "approval expired",
+        BuildStatus::Frozen => "held by a deploy freeze",
+    }
+}
+
+fn short_sha(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
+
+impl StatusReporter {
+    /// Create or edit the sticky comment listing a pull request's preview
+    /// deployments. Refs that are not pull requests are ignored.
+    pub async fn update_preview_comment(&self, project_name: &str, git_ref: &str) {
+        let Some(pr) = pr_number(git_ref) else {
+            return;
+        };
+
+        let result = async {
+            let Some(build) = self.latest_build(project_name, git_ref).await? else {
+                return Ok(());
+            };
+            let rows = self.preview_rows(project_name, git_ref, &build).await?;
+            let body = render_preview(&build, &rows);
+            self.upsert_comment(project_name, pr, &body).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            warn!(
+                "Failed to update preview comment on {} PR #{}: {}",
+                project_name, pr, e
+            );
+        }
+    }
+
+    /// Mark a closed pull request's comment as torn down. Pull requests that
+    /// never got a comment are left alone.
+    pub async fn mark_preview_torn_down(&self, project_name: &str, pr: u64) {
+        let result = async {
+            if self
+                .store
+                .preview_comments()
+                .find(project_name, pr as i64)
+                .await?
+                .is_none()
+            {
+                return Ok(());
+            }
+
+            let git_ref = format!("pr-{}", pr);
+            let build = self.latest_build(project_name, &git_ref).await?;
+            self.upsert_comment(project_name, pr, &render_torn_down(build.as_ref()))
+                .await
+        }
+        .await;
+
+        if let Err(e) = result {
+            warn!(
+                "Failed to mark preview comment on {} PR #{} torn down: {}",
+                project_name, pr, e
+            );
+        }
+    }
+
+    async fn latest_build(
+        &self,
+        project_name: &str,
+        git_ref: &str,
+    ) -> Result<Option<builds::Model>> {
+        Ok(self
+            .store
+            .builds()
+            .list_by_project_and_branch(project_name, git_ref)
+            .await
+            .map_err(StoreError::from)?
+            .into_iter()
+            .next())
+    }
+
+    /// A row per service and static site that is live or in the latest
+    /// build; jobs have nothing to preview.
+    async fn preview_rows(
+        &self,
+        project_name: &str,
+        git_ref: &str,
+        latest: &builds::Model,
+    ) -> Result<Vec<PreviewRow>> {
+        let store = &self.store;
+
+        let service_types: HashMap<String, ServiceType> = store
+            .services()
+            .list_by_project(project_name)
+            .await
+            .map_err(StoreError::from)?
+            .into_iter()
+            .map(|s| (s.name, s.r#type))
+            .collect();
+        let commits: HashMap<i32, String> = store
+            .builds()
+            .list_by_project_and_branch(project_name, git_ref)
+            .await
+            .map_err(StoreError::from)?
+            .into_iter()
+            .map(|b| (b.id, short_sha(&b.commit_sha).to_string()))
+            .collect();
+        let deployments: Vec<_> = store
+            .deployments()
+            .list_by_project(project_name)
+            .await
+            .map_err(StoreError::from)?
+            .into_iter()
+            .filter(|d| d.git_ref == git_ref)
+            .collect();
+        let results = store.build_results().find_by_build_id(latest.id).await?;
+
+        let mut services: BTreeMap<String, (Option<_>, Option<_>)> = BTreeMap::new();
+        for deployment in deployments
+            .iter()
+            .filter(|d| d.status == DeploymentStatus::Active)
+        {
+            services
+                .entry(deployment.service_name.clone())
+                .or_default()
+                .0 = Some(deployment);
+        }
+        for result in &results {
+            services.entry(result.service_name.clone()).or_default().1 = Some(result);
+        }
+
+        let latest_commit = short_sha(&latest.commit_sha).to_string();
+        let in_progress = matches!(latest.status, BuildStatus::Queued | BuildStatus::Building);
+
+        let mut rows = Vec::new();
+        for (service, (active, result)) in services {
+            let service_type = service_types.get(&service);
+            if service_type == Some(&ServiceType::Job) {
+                continue;
+            }
+
+            let deploy_failed = deployments.iter().any(|d| {
+                d.service_name == service
+                    && d.build_id == Some(latest.id)
+                    && d.status == DeploymentStatus::Failed
+            });
+            let live_latest = active.is_some_and(|d| d.build_id == Some(latest.id));
+
+            let status = match result.map(|r| &r.status) {
+                Some(BuildResultStatus::Failed) => "Build failed",
+                Some(_) if live_latest => "Deployed",
+                Some(_) if deploy_failed => "Deploy failed",
+                Some(_) if latest.status == BuildStatus::Success => "Deploying",
+                Some(_) => describe_build(&latest.status),
+                None if in_progress => "Building",
+                None => "Deployed",
+            };
+
+            let url = active
+                .filter(|_| service_type != Some(&ServiceType::Worker))
+                .map(|d| format!("https://{}", d.domain));
+            let commit = active
+                .and_then(|d| d.build_id)
+                .and_then(|id| commits.get(&id).cloned())
+                .unwrap_or_else(|| latest_commit.clone());
+
+            rows.push(PreviewRow {
+                service,
+                url,
+                status,
+                commit,
+            });
+        }
+
+        Ok(rows)
+    }
+
+    async fn upsert_comment(&self, project_name: &str, pr: u64, body: &str) -> Result<()> {
+        let _guard = COMMENT_LOCK.lock().await;
+
+        let Some(project) = self.store.projects().find_by_name(project_name).await? else {
+            return Ok(());
+        };
+        let Some(token) = project.forge_token.as_deref() else {
+            return Ok(());
+        };
+        let client = client_for(&project.repo_type, &project.repo_url, token)?;
+
+        let existing = self
+            .store
+            .preview_comments()
+            .find(project_name, pr as i64)
+            .await?;
+
+        if let Some(comment) = existing {
+            match client
+                .update_comment(pr, comment.comment_id as u64, body)
+                .await
+            {
+                Ok(()) => {
+                    debug!("Updated preview comment on {} PR #{}", project_name, pr);
+                    return Ok(());
+                }
+                // Someone deleted the comment; post a new one
+                Err(Error::Api { status: 404, .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let comment_id = client.create_comment(pr, body).await?;
+        self.store
+            .preview_comments()
+            .save(project_name, pr as i64, comment_id as i64)
+            .await?;
+
+        debug!("Created preview comment on {} PR #{}", project_name, pr);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(status: BuildStatus) -> builds::Model {
+        let now = chrono::NaiveDateTime::default();
+        builds::Model {
+            id: 7,
+            project_name: "app".to_string(),
+            branch: "pr-3".to_string(),
+            git_ref: "pr-3".to_string(),
+            commit_sha: "abcdef0123456789".to_string(),
+            status,
+            started_at: None,
+            finished_at: None,
+            created_at: now,
+            updated_at: now,
+            author: None,
+            approval_expires_at: None,
+            reviewed_by: None,
+            reviewed_at: None,
+            hold_reason: None,
+            pr_head_ref: None,
+            pr_base_ref: None,
+            pr_title: None,
+            pr_from_fork: false,
+        }
+    }
+
+    #[test]
+    fn test_pr_number() {
+        assert_eq!(pr_number("pr-12"), Some(12));
+        assert_eq!(pr_number("main"), None);
+        assert_eq!(pr_number("pr-feature"), None);
+    }
+
+    #[test]
+    fn test_render_preview() {
+        let rows = vec![
+            PreviewRow {
+                service: "api".to_string(),
+                url: Some("https://api-pr-3.app.kennel.test".to_string()),
+                status: "Deployed",
+                commit: "abcdef0".to_string(),
+            },
+            PreviewRow {
+                service: "worker".to_string(),
+                url: None,
+                status: "Build failed",
+                commit: "abcdef0".to_string(),
+            },
+        ];
+
+        let body = render_preview(&build(BuildStatus::Success), &rows);
+
+        assert!(body.contains("Latest commit `abcdef0`: built (build #7)."));
+        assert!(body.contains("| api | https://api-pr-3.app.kennel.test | Deployed | `abcdef0` |"));
+        assert!(body.contains("| worker | - | Build failed | `abcdef0` |"));
+    }
+
+    #[test]
+    fn test_render_torn_down() {
+        let body = render_torn_down(Some(&build(BuildStatus::Success)));
+        assert!(body.contains("Torn down when the pull request closed"));
+        assert!(body.contains("`abcdef0`"));
+    }
+}
@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Forge API returned {status}: {body}")]
+    Api { status: u16, body: String },
+
+    #[error("Unexpected forge response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Invalid repository URL: {0}")]
+    InvalidRepoUrl(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] kennel_store::StoreError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
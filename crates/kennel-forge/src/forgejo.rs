@@ -0,0 +1,61 @@
+use crate::Result;
+use crate::client::{CommentBody, CommitStatus, ForgeClient, RepoRef, parse_comment, send_json};
+use async_trait::async_trait;
+
+/// Commit statuses and comments through the Forgejo API.
+pub struct ForgejoClient {
+    http: reqwest::Client,
+    repo: RepoRef,
+    token: String,
+}
+
+impl ForgejoClient {
+    pub fn new(repo_url: &str, token: &str) -> Result<Self> {
+        Ok(Self {
+            http: crate::http_client(),
+            repo: RepoRef::parse(repo_url)?,
+            token: token.to_string(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!(
+            "{}/api/v1/repos/{}/{}/{}",
+            self.repo.origin, self.repo.owner, self.repo.name, path
+        );
+
+        self.http.request(method, url).header(
+            reqwest::header::AUTHORIZATION,
+            format!("token {}", self.token),
+        )
+    }
+}
+
+#[async_trait]
+impl ForgeClient for ForgejoClient {
+    async fn create_status(&self, sha: &str, status: &CommitStatus) -> Result<()> {
+        let path = format!("statuses/{}", sha);
+        send_json(self.request(reqwest::Method::POST, &path), status).await?;
+        Ok(())
+    }
+
+    async fn create_comment(&self, pr_number: u64, body: &str) -> Result<u64> {
+        let path = format!("issues/{}/comments", pr_number);
+        let response = send_json(
+            self.request(reqwest::Method::POST, &path),
+            &CommentBody { body },
+        )
+        .await?;
+        parse_comment(&response)
+    }
+
+    async fn update_comment(&self, _pr_number: u64, comment_id: u64, body: &str) -> Result<()> {
+        let path = format!("issues/comments/{}", comment_id);
+        send_json(
+            self.request(reqwest::Method::PATCH, &path),
+            &CommentBody { body },
+        )
+        .await?;
+        Ok(())
+    }
+}
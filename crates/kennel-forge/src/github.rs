@@ -0,0 +1,74 @@
+use crate::Result;
+use crate::client::{CommentBody, CommitStatus, ForgeClient, RepoRef, parse_comment, send_json};
+use async_trait::async_trait;
+
+/// Commit statuses and comments through the GitHub REST API, on github.com
+/// or a GitHub Enterprise host.
+pub struct GithubClient {
+    http: reqwest::Client,
+    api_url: String,
+    repo: RepoRef,
+    token: String,
+}
+
+impl GithubClient {
+    pub fn new(repo_url: &str, token: &str) -> Result<Self> {
+        let repo = RepoRef::parse(repo_url)?;
+        let api_url = if repo.origin == "https://github.com" {
+            "https://api.github.com".to_string()
+        } else {
+            format!("{}/api/v3", repo.origin)
+        };
+
+        Ok(Self {
+            http: crate::http_client(),
+            api_url,
+            repo,
+            token: token.to_string(),
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/repos/{}/{}/{}",
+            self.api_url, self.repo.owner, self.repo.name, path
+        )
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, self.url(path))
+            .bearer_auth(&self.token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+    }
+}
+
+#[async_trait]
+impl ForgeClient for GithubClient {
+    async fn create_status(&self, sha: &str, status: &CommitStatus) -> Result<()> {
+        let path = format!("statuses/{}", sha);
+        send_json(self.request(reqwest::Method::POST, &path), status).await?;
+        Ok(())
+    }
+
+    async fn create_comment(&self, pr_number: u64, body: &str) -> Result<u64> {
+        // Pull requests share their number with an issue, which holds comments
+        let path = format!("issues/{}/comments", pr_number);
+        let response = send_json(
+            self.request(reqwest::Method::POST, &path),
+            &CommentBody { body },
+        )
+        .await?;
+        parse_comment(&response)
+    }
+
+    async fn update_comment(&self, _pr_number: u64, comment_id: u64, body: &str) -> Result<()> {
+        let path = format!("issues/comments/{}", comment_id);
+        send_json(
+            self.request(reqwest::Method::PATCH, &path),
+            &CommentBody { body },
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::Result;
+use crate::client::{
+    CommentBody, CommitStatus, ForgeClient, RepoRef, StatusState, parse_comment, send_json,
+};
+use async_trait::async_trait;
+use serde::Serialize;
+
+/// Commit statuses and merge request notes through the GitLab REST API.
+pub struct GitlabClient {
+    http: reqwest::Client,
+    project_url: String,
+    token: String,
+}
+
+/// GitLab names its states differently and calls the context `name`.
+#[derive(Serialize)]
+struct GitlabStatus<'a> {
+    state: &'static str,
+    name: &'a str,
+    description: &'a str,
+    target_url: Option<&'a str>,
+}
+
+impl GitlabClient {
+    pub fn new(repo_url: &str, token: &str) -> Result<Self> {
+        let repo = RepoRef::parse(repo_url)?;
+        // Projects are addressed by their URL-encoded path, which may include
+        // subgroups
+        let project = format!("{}/{}", repo.owner, repo.name).replace('/', "%2F");
+
+        Ok(Self {
+            http: crate::http_client(),
+            project_url: format!("{}/api/v4/projects/{}", repo.origin, project),
+            token: token.to_string(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, format!("{}/{}", self.project_url, path))
+            .header("PRIVATE-TOKEN", &self.token)
+    }
+}
+
+#[async_trait]
+impl ForgeClient for GitlabClient {
+    async fn create_status(&self, sha: &str, status: &CommitStatus) -> Result<()> {
+        let state = match status.state {
+            StatusState::Pending => "pending",
+            StatusState::Success => "success",
+            StatusState::Failure | StatusState::Error => "failed",
+        };
+        let body = GitlabStatus {
+            state,
+            name: &status.context,
+            description: &status.description,
+            target_url: status.target_url.as_deref(),
+        };
+
+        let path = format!("statuses/{}", sha);
+        send_json(self.request(reqwest::Method::POST, &path), &body).await?;
+        Ok(())
+    }
+
+    async fn create_comment(&self, pr_number: u64, body: &str) -> Result<u64> {
+        let path = format!("merge_requests/{}/notes", pr_number);
+        let response = send_json(
+            self.request(reqwest::Method::POST, &path),
+            &CommentBody { body },
+        )
+        .await?;
+        parse_comment(&response)
+    }
+
+    async fn update_comment(&self, pr_number: u64, comment_id: u64, body: &str) -> Result<()> {
+        let path = format!("merge_requests/{}/notes/{}", pr_number, comment_id);
+        send_json(
+            self.request(reqwest::Method::PUT, &path),
+            &CommentBody { body },
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+mod client;
+mod comment;
+mod error;
+mod forgejo;
+mod github;
+mod gitlab;
+mod reporter;
+
+pub use client::{CommitStatus, ForgeClient, RepoRef, StatusState};
+pub use comment::pr_number;
+pub use error::{Error, Result};
+pub use forgejo::ForgejoClient;
+pub use github::GithubClient;
+pub use gitlab::GitlabClient;
+pub use reporter::{StatusReporter, build_context, deploy_context, service_build_context};
+
+use entity::sea_orm_active_enums::RepoType;
+
+/// The client speaking the status API of the forge a project is hosted on.
+pub fn client_for(
+    repo_type: &RepoType,
+    repo_url: &str,
+    token: &str,
+) -> Result<Box<dyn ForgeClient>> {
+    Ok(match repo_type {
+        RepoType::Github => Box::new(GithubClient::new(repo_url, token)?),
+        RepoType::Gitlab => Box::new(GitlabClient::new(repo_url, token)?),
+        // Forgejo is a Gitea fork and kept its API
+        RepoType::Forgejo | RepoType::Gitea => Box::new(ForgejoClient::new(repo_url, token)?),
+    })
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(kennel_config::constants::FORGE_REQUEST_TIMEOUT)
+        .build()
+        .expect("static reqwest configuration is valid")
+}
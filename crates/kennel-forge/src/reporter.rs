@@ -0,0 +1,89 @@
+use crate::client::{CommitStatus, StatusState};
+use crate::{Result, client_for};
+use entity::builds;
+use kennel_store::Store;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Context of the status summarising a whole build and its deployment.
+pub fn build_context() -> String {
+    "kennel/build".to_string()
+}
+
+/// Context of the status tracking one service's package build.
+pub fn service_build_context(service: &str) -> String {
+    format!("kennel/build/{}", service)
+}
+
+/// Context of the status tracking one service's deployment.
+pub fn deploy_context(service: &str) -> String {
+    format!("kennel/deploy/{}", service)
+}
+
+/// Reports build and deployment progress to the forge hosting each project,
+/// as commit statuses and a comment on pull requests. Projects without a
+/// forge token are skipped, and failures are logged rather than returned so a
+/// forge outage never fails a build or deployment.
+pub struct StatusReporter {
+    pub(crate) store: Arc<Store>,
+    failure_url: Option<String>,
+}
+
+impl StatusReporter {
+    /// `failure_url` is a page, such as a log viewer or dashboard, that
+    /// failed and errored statuses link to when they have no link of their
+    /// own.
+    pub fn new(store: Arc<Store>, failure_url: Option<String>) -> Self {
+        Self { store, failure_url }
+    }
+
+    pub async fn report(
+        &self,
+        build: &builds::Model,
+        context: String,
+        state: StatusState,
+        description: impl Into<String>,
+        target_url: Option<String>,
+    ) {
+        let status = CommitStatus {
+            state,
+            context,
+            // GitHub rejects descriptions longer than 140 characters
+            description: description.into().chars().take(140).collect(),
+            target_url: target_url.or_else(|| match state {
+                StatusState::Failure | StatusState::Error => self.failure_url.clone(),
+                StatusState::Pending | StatusState::Success => None,
+            }),
+        };
+
+        if let Err(e) = self.try_report(build, &status).await {
+            warn!(
+                "Failed to report {} status for build {} ({}): {}",
+                status.context, build.id, build.commit_sha, e
+            );
+        }
+    }
+
+    async fn try_report(&self, build: &builds::Model, status: &CommitStatus) -> Result<()> {
+        let Some(project) = self
+            .store
+            .projects()
+            .find_by_name(&build.project_name)
+            .await?
+        else {
+            return Ok(());
+        };
+        let Some(token) = project.forge_token.as_deref() else {
+            return Ok(());
+        };
+
+        let client = client_for(&project.repo_type, &project.repo_url, token)?;
+        client.create_status(&build.commit_sha, status).await?;
+
+        debug!(
+            "Reported {:?} for {} on {}",
+            status.state, status.context, build.commit_sha
+        );
+        Ok(())
+    }
+}
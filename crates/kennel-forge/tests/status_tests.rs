@@ -0,0 +1,547 @@
+use axum::Router;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use entity::{build_results, deployments, projects, sea_orm_active_enums::*, services};
+use kennel_forge::{
+    CommitStatus, Error, ForgeClient, ForgejoClient, GithubClient, GitlabClient, StatusReporter,
+    StatusState, build_context, deploy_context,
+};
+use kennel_store::Store;
+use sea_orm::{ColumnTrait, Database, EntityTrait, IntoActiveModel, QueryFilter, Set};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone)]
+struct Recorded {
+    method: Method,
+    path: String,
+    authorization: Option<String>,
+    private_token: Option<String>,
+    body: serde_json::Value,
+}
+
+type MockState = (Arc<Mutex<Vec<Recorded>>>, StatusCode, Arc<AtomicBool>);
+
+/// A forge answering every request with `status` and recording what it got.
+/// Created comments get id 42.
+#[derive(Clone)]
+struct MockForge {
+    url: String,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+    comments_deleted: Arc<AtomicBool>,
+}
+
+impl MockForge {
+    async fn start(status: StatusCode) -> Self {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let comments_deleted = Arc::new(AtomicBool::new(false));
+
+        let app = Router::new()
+            .fallback(
+                |State((requests, status, comments_deleted)): State<MockState>,
+                 method: Method,
+                 uri: Uri,
+                 headers: HeaderMap,
+                 body: String| async move {
+                    let status =
+                        if method != Method::POST && comments_deleted.load(Ordering::SeqCst) {
+                            StatusCode::NOT_FOUND
+                        } else {
+                            status
+                        };
+                    requests.lock().unwrap().push(Recorded {
+                        method,
+                        path: uri.path().to_string(),
+                        authorization: headers
+                            .get("authorization")
+                            .and_then(|v| v.to_str().ok())
+                            .map(str::to_string),
+                        private_token: headers
+                            .get("private-token")
+                            .and_then(|v| v.to_str().ok())
+                            .map(str::to_string),
+                        body: serde_json::from_str(&body).unwrap_or_default(),
+                    });
+                    (status, r#"{"id": 42}"#)
+                },
+            )
+            .with_state((requests.clone(), status, comments_deleted.clone()));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Self {
+            url,
+            requests,
+            comments_deleted,
+        }
+    }
+
+    /// Answer edits with 404, as if someone deleted the comments.
+    fn delete_comments(&self) {
+        self.comments_deleted.store(true, Ordering::SeqCst);
+    }
+
+    fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn status(state: StatusState) -> CommitStatus {
+    CommitStatus {
+        state,
+        context: build_context(),
+        description: "Built".to_string(),
+        target_url: Some("https://kennel.test/projects/app/builds/1".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_github_client_posts_status() {
+    let forge = MockForge::start(StatusCode::CREATED).await;
+    let client = GithubClient::new(&format!("{}/owner/app.git", forge.url), "gh-token").unwrap();
+
+    client
+        .create_status("abc123", &status(StatusState::Success))
+        .await
+        .unwrap();
+
+    let requests = forge.requests();
+    assert_eq!(requests.len(), 1);
+    // Hosts other than github.com are GitHub Enterprise, served under /api/v3
+    assert_eq!(requests[0].path, "/api/v3/repos/owner/app/statuses/abc123");
+    assert_eq!(
+        requests[0].authorization.as_deref(),
+        Some("Bearer gh-token")
+    );
+    assert_eq!(requests[0].body["state"], "success");
+    assert_eq!(requests[0].body["context"], "kennel/build");
+    assert_eq!(requests[0].body["description"], "Built");
+    assert_eq!(
+        requests[0].body["target_url"],
+        "https://kennel.test/projects/app/builds/1"
+    );
+}
+
+#[tokio::test]
+async fn test_forgejo_client_posts_status() {
+    let forge = MockForge::start(StatusCode::CREATED).await;
+    let client = ForgejoClient::new(&format!("{}/owner/app", forge.url), "fj-token").unwrap();
+
+    client
+        .create_status("def456", &status(StatusState::Failure))
+        .await
+        .unwrap();
+
+    let requests = forge.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/api/v1/repos/owner/app/statuses/def456");
+    assert_eq!(requests[0].authorization.as_deref(), Some("token fj-token"));
+    assert_eq!(requests[0].body["state"], "failure");
+}
+
+#[tokio::test]
+async fn test_gitlab_client_posts_status_and_notes() {
+    let forge = MockForge::start(StatusCode::CREATED).await;
+    let client =
+        GitlabClient::new(&format!("{}/group/sub/app.git", forge.url), "gl-token").unwrap();
+
+    client
+        .create_status("abc123", &status(StatusState::Error))
+        .await
+        .unwrap();
+    let id = client.create_comment(9, "first").await.unwrap();
+    client.update_comment(9, id, "second").await.unwrap();
+
+    let requests = forge.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(
+        requests[0].path,
+        "/api/v4/projects/group%2Fsub%2Fapp/statuses/abc123"
+    );
+    assert_eq!(requests[0].private_token.as_deref(), Some("gl-token"));
+    assert_eq!(requests[0].body["state"], "failed");
+    assert_eq!(requests[0].body["name"], "kennel/build");
+    assert_eq!(
+        requests[1].path,
+        "/api/v4/projects/group%2Fsub%2Fapp/merge_requests/9/notes"
+    );
+    assert_eq!(requests[2].method, Method::PUT);
+    assert_eq!(
+        requests[2].path,
+        "/api/v4/projects/group%2Fsub%2Fapp/merge_requests/9/notes/42"
+    );
+    assert_eq!(requests[2].body["body"], "second");
+}
+
+#[tokio::test]
+async fn test_client_surfaces_api_errors() {
+    let forge = MockForge::start(StatusCode::UNPROCESSABLE_ENTITY).await;
+    let client = ForgejoClient::new(&format!("{}/owner/app", forge.url), "fj-token").unwrap();
+
+    let err = client
+        .create_status("def456", &status(StatusState::Pending))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Api { status: 422, .. }));
+}
+
+async fn create_project(store: &Store, name: &str, repo_url: String, token: Option<&str>) {
+    let _ = store.projects().delete(name).await;
+
+    store
+        .projects()
+        .create(projects::ActiveModel {
+            name: Set(name.to_string()),
+            repo_url: Set(repo_url),
+            repo_type: Set(RepoType::Forgejo),
+            webhook_secret: Set("secret".to_string()),
+            default_branch: Set("main".to_string()),
+            forge_token: Set(token.map(str::to_string)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_reporter_posts_for_projects_with_a_token() {
+    let db_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+    let store = Arc::new(Store::new(Database::connect(&db_url).await.unwrap()));
+    let forge = MockForge::start(StatusCode::CREATED).await;
+
+    let project = "test-forge-reporter";
+    create_project(
+        &store,
+        project,
+        format!("{}/owner/{}.git", forge.url, project),
+        Some("fj-token"),
+    )
+    .await;
+    let build = store
+        .builds()
+        .create_build(
+            project.to_string(),
+            "main".to_string(),
+            "abc123".to_string(),
+            "alice".to_string(),
+        )
+        .await
+        .unwrap();
+
+    let reporter = StatusReporter::new(store.clone(), None);
+
+    reporter
+        .report(
+            &build,
+            deploy_context("api"),
+            StatusState::Success,
+            "x".repeat(200),
+            Some("https://api.kennel.test".to_string()),
+        )
+        .await;
+
+    let requests = forge.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].path,
+        format!("/api/v1/repos/owner/{}/statuses/abc123", project)
+    );
+    assert_eq!(requests[0].body["context"], "kennel/deploy/api");
+    assert_eq!(requests[0].body["target_url"], "https://api.kennel.test");
+    assert_eq!(requests[0].body["description"].as_str().unwrap().len(), 140);
+
+    // Without a token the project opted out of statuses
+    create_project(
+        &store,
+        project,
+        format!("{}/owner/{}.git", forge.url, project),
+        None,
+    )
+    .await;
+    let build = store
+        .builds()
+        .create_build(
+            project.to_string(),
+            "main".to_string(),
+            "def456".to_string(),
+            "alice".to_string(),
+        )
+        .await
+        .unwrap();
+
+    reporter
+        .report(
+            &build,
+            build_context(),
+            StatusState::Pending,
+            "Building",
+            None,
+        )
+        .await;
+    assert_eq!(forge.requests().len(), 1);
+
+    store.projects().delete(project).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_github_client_creates_and_edits_comments() {
+    let forge = MockForge::start(StatusCode::CREATED).await;
+    let client = GithubClient::new(&format!("{}/owner/app", forge.url), "gh-token").unwrap();
+
+    let id = client.create_comment(5, "first").await.unwrap();
+    assert_eq!(id, 42);
+    client.update_comment(5, id, "second").await.unwrap();
+
+    let requests = forge.requests();
+    assert_eq!(requests[0].method, Method::POST);
+    assert_eq!(
+        requests[0].path,
+        "/api/v3/repos/owner/app/issues/5/comments"
+    );
+    assert_eq!(requests[0].body["body"], "first");
+    assert_eq!(requests[1].method, Method::PATCH);
+    assert_eq!(
+        requests[1].path,
+        "/api/v3/repos/owner/app/issues/comments/42"
+    );
+    assert_eq!(requests[1].body["body"], "second");
+}
+
+#[tokio::test]
+async fn test_preview_comment_is_sticky() {
+    let db_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+    let store = Arc::new(Store::new(Database::connect(&db_url).await.unwrap()));
+    let forge = MockForge::start(StatusCode::CREATED).await;
+
+    let project = "test-forge-preview-comment";
+    let _ = deployments::Entity::delete_many()
+        .filter(deployments::Column::ProjectName.eq(project))
+        .exec(store.db())
+        .await;
+    create_project(
+        &store,
+        project,
+        format!("{}/owner/{}", forge.url, project),
+        Some("fj-token"),
+    )
+    .await;
+    let reporter = StatusReporter::new(store.clone(), None);
+
+    // Branch builds have no pull request to comment on
+    reporter.update_preview_comment(project, "main").await;
+    assert!(forge.requests().is_empty());
+
+    // The first build of the pull request creates the comment
+    let first = store
+        .builds()
+        .create_build(
+            project.to_string(),
+            "pr-5".to_string(),
+            "abc1234567".to_string(),
+            "alice".to_string(),
+        )
+        .await
+        .unwrap();
+    reporter.update_preview_comment(project, "pr-5").await;
+
+    let requests = forge.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, Method::POST);
+    assert_eq!(
+        requests[0].path,
+        format!("/api/v1/repos/owner/{}/issues/5/comments", project)
+    );
+    assert!(
+        requests[0].body["body"]
+            .as_str()
+            .unwrap()
+            .contains("`abc1234`: queued")
+    );
+    assert_eq!(
+        store
+            .preview_comments()
+            .find(project, 5)
+            .await
+            .unwrap()
+            .unwrap()
+            .comment_id,
+        42
+    );
+
+    // Once deployed, the same comment lists the service's URL
+    let mut build = first.into_active_model();
+    build.status = Set(BuildStatus::Success);
+    let first = store.builds().update(build).await.unwrap();
+    store
+        .build_results()
+        .create(build_results::ActiveModel {
+            build_id: Set(first.id),
+            service_name: Set("web".to_string()),
+            status: Set(BuildResultStatus::Success),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    store
+        .services()
+        .create(services::ActiveModel {
+            project_name: Set(project.to_string()),
+            name: Set("web".to_string()),
+            r#type: Set(ServiceType::Service),
+            package: Set("web".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let now = chrono::Utc::now().naive_utc();
+    store
+        .deployments()
+        .create(deployments::ActiveModel {
+            project_name: Set(project.to_string()),
+            service_name: Set("web".to_string()),
+            branch: Set("pr-5".to_string()),
+            branch_slug: Set("pr-5".to_string()),
+            environment: Set("preview".to_string()),
+            git_ref: Set("pr-5".to_string()),
+            domain: Set("web-pr-5.app.kennel.test".to_string()),
+            status: Set(DeploymentStatus::Active),
+            dns_status: Set("pending".to_string()),
+            build_id: Set(Some(first.id)),
+            created_at: Set(now),
+            updated_at: Set(now),
+            last_activity: Set(now),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    reporter.update_preview_comment(project, "pr-5").await;
+
+    let requests = forge.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].method, Method::PATCH);
+    assert_eq!(
+        requests[1].path,
+        format!("/api/v1/repos/owner/{}/issues/comments/42", project)
+    );
+    assert!(
+        requests[1].body["body"]
+            .as_str()
+            .unwrap()
+            .contains("| web | https://web-pr-5.app.kennel.test | Deployed | `abc1234` |")
+    );
+
+    // A synchronize shows the new commit building while the old one serves
+    store
+        .builds()
+        .create_build(
+            project.to_string(),
+            "pr-5".to_string(),
+            "def4567890".to_string(),
+            "alice".to_string(),
+        )
+        .await
+        .unwrap();
+    reporter.update_preview_comment(project, "pr-5").await;
+
+    let body = forge.requests()[2].body["body"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(body.contains("`def4567`: queued"));
+    assert!(body.contains("| web | https://web-pr-5.app.kennel.test | Building | `abc1234` |"));
+
+    // Closing marks the comment torn down, posting a new one if it was deleted
+    forge.delete_comments();
+    reporter.mark_preview_torn_down(project, 5).await;
+
+    let requests = forge.requests();
+    assert_eq!(requests.len(), 5);
+    assert_eq!(requests[3].method, Method::PATCH);
+    assert_eq!(requests[4].method, Method::POST);
+    assert!(
+        requests[4].body["body"]
+            .as_str()
+            .unwrap()
+            .contains("Torn down when the pull request closed")
+    );
+
+    deployments::Entity::delete_many()
+        .filter(deployments::Column::ProjectName.eq(project))
+        .exec(store.db())
+        .await
+        .unwrap();
+    store.projects().delete(project).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reporter_links_failures_to_the_failure_url() {
+    let db_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+    let store = Arc::new(Store::new(Database::connect(&db_url).await.unwrap()));
+    let forge = MockForge::start(StatusCode::CREATED).await;
+
+    let project = "test-forge-failure-url";
+    create_project(
+        &store,
+        project,
+        format!("{}/owner/{}.git", forge.url, project),
+        Some("fj-token"),
+    )
+    .await;
+    let build = store
+        .builds()
+        .create_build(
+            project.to_string(),
+            "main".to_string(),
+            "abc123".to_string(),
+            "alice".to_string(),
+        )
+        .await
+        .unwrap();
+
+    let reporter = StatusReporter::new(store.clone(), Some("https://logs.kennel.test".to_string()));
+
+    for state in [
+        StatusState::Pending,
+        StatusState::Success,
+        StatusState::Failure,
+        StatusState::Error,
+    ] {
+        reporter
+            .report(&build, build_context(), state, "Building", None)
+            .await;
+    }
+    // A link of the status's own wins
+    reporter
+        .report(
+            &build,
+            deploy_context("api"),
+            StatusState::Failure,
+            "Health check failed",
+            Some("https://api.kennel.test".to_string()),
+        )
+        .await;
+
+    let targets: Vec<_> = forge
+        .requests()
+        .iter()
+        .map(|r| r.body["target_url"].as_str().map(str::to_string))
+        .collect();
+    assert_eq!(
+        targets,
+        vec![
+            None,
+            None,
+            Some("https://logs.kennel.test".to_string()),
+            Some("https://logs.kennel.test".to_string()),
+            Some("https://api.kennel.test".to_string()),
+        ]
+    );
+
+    store.projects().delete(project).await.unwrap();
+}
@@ -0,0 +1,175 @@
+use entity::sea_orm_active_enums::LoadBalancing;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+#[derive(Debug)]
+struct Backend {
+    port: u16,
+    in_flight: AtomicUsize,
+    ejected: AtomicBool,
+}
+
+/// The replicas of one service deployment and how requests are spread
+/// across them. Shared by every route to the deployment, so ejecting a
+/// replica through one domain ejects it everywhere.
+#[derive(Debug)]
+pub struct BackendSet {
+    backends: Vec<Backend>,
+    strategy: LoadBalancing,
+    next: AtomicUsize,
+    /// The deployment's unit is stopped for lack of traffic.
+    asleep: AtomicBool,
+}
+
+impl BackendSet {
+    pub fn new(ports: Vec<u16>, strategy: LoadBalancing) -> Self {
+        Self {
+            backends: ports
+                .into_iter()
+                .map(|port| Backend {
+                    port,
+                    in_flight: AtomicUsize::new(0),
+                    ejected: AtomicBool::new(false),
+                })
+                .collect(),
+            strategy,
+            next: AtomicUsize::new(0),
+            asleep: AtomicBool::new(false),
+        }
+    }
+
+    pub fn ports(&self) -> Vec<u16> {
+        self.backends.iter().map(|b| b.port).collect()
+    }
+
+    /// Take `port` out of rotation, or put it back. Returns whether the port
+    /// belongs to this set.
+    pub fn set_ejected(&self, port: u16, ejected: bool) -> bool {
+        match self.backends.iter().find(|b| b.port == port) {
+            Some(backend) => {
+                backend.ejected.store(ejected, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_ejected(&self, port: u16) -> bool {
+        self.backends
+            .iter()
+            .any(|b| b.port == port && b.ejected.load(Ordering::Relaxed))
+    }
+
+    pub fn is_asleep(&self) -> bool {
+        self.asleep.load(Ordering::Relaxed)
+    }
+
+    pub fn set_asleep(&self, asleep: bool) {
+        self.asleep.store(asleep, Ordering::Relaxed);
+    }
+
+    /// Choose a replica for one request, counting it as in flight until the
+    /// guard is dropped. Returns `None` when every replica is ejected.
+    pub fn pick(self: &Arc<Self>) -> Option<BackendGuard> {
+        let candidates: Vec<usize> = (0..self.backends.len())
+            .filter(|&i| !self.backends[i].ejected.load(Ordering::Relaxed))
+            .collect();
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let turn = self.next.fetch_add(1, Ordering::Relaxed);
+        let index = match self.strategy {
+            LoadBalancing::RoundRobin => candidates[turn % candidates.len()],
+            // Rotate the starting point so ties are spread as well
+            LoadBalancing::LeastConnections => (0..candidates.len())
+                .map(|offset| candidates[(turn + offset) % candidates.len()])
+                .min_by_key(|&i| self.backends[i].in_flight.load(Ordering::Relaxed))?,
+        };
+
+        self.backends[index]
+            .in_flight
+            .fetch_add(1, Ordering::Relaxed);
+
+        Some(BackendGuard {
+            set: self.clone(),
+            index,
+        })
+    }
+}
+
+/// One request to the replica chosen by [`BackendSet::pick`].
+pub struct BackendGuard {
+    set: Arc<BackendSet>,
+    index: usize,
+}
+
+impl BackendGuard {
+    pub fn port(&self) -> u16 {
+        self.set.backends[self.index].port
+    }
+}
+
+impl Drop for BackendGuard {
+    fn drop(&mut self) {
+        self.set.backends[self.index]
+            .in_flight
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_robin() {
+        let set = Arc::new(BackendSet::new(
+            vec![18000, 18001, 18002],
+            LoadBalancing::RoundRobin,
+        ));
+
+        let ports: Vec<u16> = (0..6).map(|_| set.pick().unwrap().port()).collect();
+        assert_eq!(ports, vec![18000, 18001, 18002, 18000, 18001, 18002]);
+    }
+
+    #[test]
+    fn test_least_connections() {
+        let set = Arc::new(BackendSet::new(
+            vec![18000, 18001],
+            LoadBalancing::LeastConnections,
+        ));
+
+        let first = set.pick().unwrap();
+        let second = set.pick().unwrap();
+        assert_ne!(first.port(), second.port());
+
+        // The replica whose request finished is the only idle one
+        let idle = first.port();
+        drop(first);
+        for _ in 0..3 {
+            let next = set.pick().unwrap();
+            assert_eq!(next.port(), idle);
+        }
+    }
+
+    #[test]
+    fn test_ejected_replicas_get_no_requests() {
+        let set = Arc::new(BackendSet::new(
+            vec![18000, 18001],
+            LoadBalancing::RoundRobin,
+        ));
+
+        assert!(set.set_ejected(18001, true));
+        assert!(!set.set_ejected(19999, true));
+        assert!(set.is_ejected(18001));
+        assert!((0..4).all(|_| set.pick().unwrap().port() == 18000));
+
+        assert!(set.set_ejected(18000, true));
+        assert!(set.pick().is_none());
+
+        set.set_ejected(18001, false);
+        assert_eq!(set.pick().unwrap().port(), 18001);
+    }
+}
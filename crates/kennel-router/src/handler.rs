@@ -1,16 +1,15 @@
-use crate::table::{RouteTarget, RoutingTable};
-use crate::{proxy, static_serve};
+use crate::table::RouteTarget;
+use crate::{RouterState, WakeRequest, proxy, static_serve};
 use axum::body::Body;
 use axum::extract::{ConnectInfo, Request, State};
 use axum::http::{Response, StatusCode};
 use axum_extra::TypedHeader;
 use axum_extra::headers::Host;
 use std::net::SocketAddr;
-use std::sync::Arc;
 use tracing::{info, warn};
 
 pub async fn route_request(
-    State(table): State<Arc<RoutingTable>>,
+    State(state): State<RouterState>,
     TypedHeader(host): TypedHeader<Host>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     request: Request<Body>,
@@ -19,11 +18,34 @@ pub async fn route_request(
 
     info!("Routing request for domain: {} from {}", domain, addr);
 
-    match table.get(domain).await {
+    let route = state.table.get(domain).await;
+    if let Some(route) = &route {
+        state.traffic.record(route.deployment_id);
+    }
+
+    match route {
         Some(route) => match route.target {
-            RouteTarget::Service { port } => {
-                info!("Proxying to service on port {}", port);
-                proxy::proxy_to_service(request, port, addr.ip()).await
+            RouteTarget::Service { backends } => {
+                // Counting the request first keeps the deployment from idling again
+                let _guard = state.in_flight.start(route.deployment_id);
+
+                if backends.is_asleep() {
+                    if !wake(&state, route.deployment_id).await {
+                        return unavailable();
+                    }
+                    backends.set_asleep(false);
+                }
+
+                match backends.pick() {
+                    Some(backend) => {
+                        info!("Proxying to service on port {}", backend.port());
+                        proxy::proxy_to_service(request, backend.port(), addr.ip()).await
+                    }
+                    None => {
+                        warn!("Every replica for domain {} is ejected", domain);
+                        unavailable()
+                    }
+                }
             }
             RouteTarget::StaticSite { path, spa } => {
                 info!("Serving static site from {:?}", path);
@@ -40,3 +62,46 @@ pub async fn route_request(
         }
     }
 }
+
+/// Ask the deployer to start the idle deployment `deployment_id` and wait
+/// until it is healthy. Returns false if it could not be started in time.
+async fn wake(state: &RouterState, deployment_id: i32) -> bool {
+    let Some(wake_tx) = &state.wake_tx else {
+        warn!("Deployment {} is idle and cannot be woken", deployment_id);
+        return false;
+    };
+
+    info!("Waking idle deployment {}", deployment_id);
+
+    let (reply, woken) = tokio::sync::oneshot::channel();
+    if wake_tx
+        .send(WakeRequest {
+            deployment_id,
+            reply,
+        })
+        .await
+        .is_err()
+    {
+        warn!("Deployer is not accepting wake requests");
+        return false;
+    }
+
+    match tokio::time::timeout(kennel_config::constants::WAKE_TIMEOUT, woken).await {
+        Ok(Ok(true)) => true,
+        Ok(_) => {
+            warn!("Failed to wake deployment {}", deployment_id);
+            false
+        }
+        Err(_) => {
+            warn!("Timed out waking deployment {}", deployment_id);
+            false
+        }
+    }
+}
+
+fn unavailable() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .body(Body::from("Service unavailable"))
+        .unwrap()
+}
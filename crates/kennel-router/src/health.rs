@@ -1,22 +1,40 @@
 use crate::table::RoutingTable;
+use entity::deployments;
 use kennel_store::Store;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::mpsc;
 use tokio::time;
 use tracing::{debug, error, info, warn};
 
+/// Result of a single health check, forwarded to whoever acts on failures.
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    pub deployment_id: i32,
+    /// Port of the replica that was checked.
+    pub port: u16,
+    pub healthy: bool,
+}
+
 #[derive(Debug, Clone)]
 struct HealthStatus {
     consecutive_failures: u32,
     is_healthy: bool,
 }
 
-pub async fn run_health_monitor(table: Arc<RoutingTable>, store: Arc<Store>) {
+/// Check every replica of every active service deployment. A replica that
+/// fails enough checks in a row is ejected from its route until it passes
+/// again. Every replica is reported on its own, so a crashed one is restarted
+/// even while its siblings keep serving.
+pub async fn run_health_monitor(
+    table: Arc<RoutingTable>,
+    store: Arc<Store>,
+    report_tx: Option<mpsc::Sender<HealthReport>>,
+) {
     info!("Starting health monitor");
 
-    let health_status: Arc<RwLock<HashMap<String, HealthStatus>>> =
-        Arc::new(RwLock::new(HashMap::new()));
+    // Keyed by replica port
+    let mut health_status: HashMap<u16, HealthStatus> = HashMap::new();
     let mut interval = time::interval(kennel_config::constants::HEALTH_CHECK_INTERVAL);
 
     loop {
@@ -24,88 +42,157 @@ pub async fn run_health_monitor(table: Arc<RoutingTable>, store: Arc<Store>) {
 
         debug!("Running health checks");
 
-        // Get all active service deployments
-        match store.deployments().list_active().await {
-            Ok(deployments) => {
-                for deployment in deployments {
-                    // Only check service deployments, not static sites
-                    if let Some(port) = deployment.port {
-                        let health_url = format!("http://localhost:{}/health", port);
-
-                        let is_healthy = match tokio::time::timeout(
-                            kennel_config::constants::HEALTH_CHECK_TIMEOUT,
-                            reqwest::get(&health_url),
-                        )
-                        .await
-                        {
-                            Ok(Ok(response)) if response.status().is_success() => true,
-                            Ok(Ok(response)) => {
-                                warn!(
-                                    "Health check failed for deployment {} ({}): HTTP {}",
-                                    deployment.id,
-                                    deployment.domain,
-                                    response.status()
-                                );
-                                false
-                            }
-                            Ok(Err(e)) => {
-                                warn!(
-                                    "Health check failed for deployment {} ({}): {}",
-                                    deployment.id, deployment.domain, e
-                                );
-                                false
-                            }
-                            Err(_) => {
-                                warn!(
-                                    "Health check timeout for deployment {} ({})",
-                                    deployment.id, deployment.domain
-                                );
-                                false
-                            }
-                        };
-
-                        let mut status_map = health_status.write().await;
-                        let status =
-                            status_map
-                                .entry(deployment.domain.clone())
-                                .or_insert(HealthStatus {
-                                    consecutive_failures: 0,
-                                    is_healthy: true,
-                                });
-
-                        if is_healthy {
-                            // Reset failures on success
-                            if status.consecutive_failures > 0 {
-                                info!(
-                                    "Deployment {} ({}) recovered",
-                                    deployment.id, deployment.domain
-                                );
-                            }
-                            status.consecutive_failures = 0;
-                            status.is_healthy = true;
-                        } else {
-                            status.consecutive_failures += 1;
-
-                            if status.consecutive_failures
-                                >= kennel_config::constants::MAX_CONSECUTIVE_HEALTH_FAILURES
-                                && status.is_healthy
-                            {
-                                error!(
-                                    "Deployment {} ({}) failed {} consecutive health checks, removing from routing table",
-                                    deployment.id, deployment.domain, status.consecutive_failures
-                                );
-
-                                // Remove from routing table
-                                table.remove(&deployment.domain).await;
-                                status.is_healthy = false;
-                            }
-                        }
-                    }
-                }
-            }
+        let deployments = match store.deployments().list_active().await {
+            Ok(deployments) => deployments,
             Err(e) => {
                 error!("Failed to query deployments for health check: {}", e);
+                continue;
+            }
+        };
+
+        let mut checked = Vec::new();
+
+        for deployment in deployments {
+            // Only check service deployments, not static sites, and leave
+            // idle ones stopped
+            let Some(port) = deployment.port else {
+                continue;
+            };
+            if deployment.idle_since.is_some() {
+                continue;
+            }
+
+            // Probe the path the service declared, or /health when it was
+            // never registered or declared none
+            let health_check_path = match store
+                .services()
+                .find_by_project_and_name(&deployment.project_name, &deployment.service_name)
+                .await
+            {
+                Ok(service) => service
+                    .and_then(|s| s.health_check)
+                    .unwrap_or_else(|| "/health".to_string()),
+                Err(e) => {
+                    warn!(
+                        "Failed to look up the health check of deployment {}: {}",
+                        deployment.id, e
+                    );
+                    continue;
+                }
+            };
+
+            let ports = match store
+                .port_allocations()
+                .list_by_deployment(deployment.id)
+                .await
+            {
+                Ok(allocations) if !allocations.is_empty() => allocations
+                    .into_iter()
+                    .map(|a| a.port as u16)
+                    .collect::<Vec<_>>(),
+                Ok(_) => vec![port as u16],
+                Err(e) => {
+                    warn!(
+                        "Failed to list replicas of deployment {}: {}",
+                        deployment.id, e
+                    );
+                    continue;
+                }
+            };
+
+            for port in ports {
+                let is_healthy = check_replica(&deployment, port, &health_check_path).await;
+                checked.push(port);
+
+                let status = health_status.entry(port).or_insert(HealthStatus {
+                    consecutive_failures: 0,
+                    is_healthy: true,
+                });
+
+                if is_healthy {
+                    if !status.is_healthy {
+                        info!(
+                            "Replica on port {} of deployment {} ({}) recovered, restoring it",
+                            port, deployment.id, deployment.domain
+                        );
+                        table
+                            .set_backend_ejected(&deployment.domain, port, false)
+                            .await;
+                    }
+                    status.consecutive_failures = 0;
+                    status.is_healthy = true;
+                } else {
+                    status.consecutive_failures += 1;
+
+                    if status.consecutive_failures
+                        >= kennel_config::constants::MAX_CONSECUTIVE_HEALTH_FAILURES
+                        && status.is_healthy
+                    {
+                        error!(
+                            "Replica on port {} of deployment {} ({}) failed {} consecutive health checks, ejecting it",
+                            port, deployment.id, deployment.domain, status.consecutive_failures
+                        );
+
+                        table
+                            .set_backend_ejected(&deployment.domain, port, true)
+                            .await;
+                        status.is_healthy = false;
+                    }
+                }
+
+                // Never wait on remediation, or one slow rollback would stall
+                // health checks for every deployment; a dropped report is
+                // followed by another on the next check
+                if let Some(report_tx) = &report_tx
+                    && let Err(e) = report_tx.try_send(HealthReport {
+                        deployment_id: deployment.id,
+                        port,
+                        healthy: is_healthy,
+                    })
+                {
+                    warn!("Dropped health report: {}", e);
+                }
             }
         }
+
+        // Forget replicas that have gone away; their ports may be reused
+        health_status.retain(|port, _| checked.contains(port));
+    }
+}
+
+async fn check_replica(deployment: &deployments::Model, port: u16, path: &str) -> bool {
+    let health_url = format!("http://127.0.0.1:{}{}", port, path);
+
+    match tokio::time::timeout(
+        kennel_config::constants::HEALTH_CHECK_TIMEOUT,
+        reqwest::get(&health_url),
+    )
+    .await
+    {
+        Ok(Ok(response)) if response.status().is_success() => true,
+        Ok(Ok(response)) => {
+            warn!(
+                "Health check failed for deployment {} ({}) on port {}: HTTP {}",
+                deployment.id,
+                deployment.domain,
+                port,
+                response.status()
+            );
+            false
+        }
+        Ok(Err(e)) => {
+            warn!(
+                "Health check failed for deployment {} ({}) on port {}: {}",
+                deployment.id, deployment.domain, port, e
+            );
+            false
+        }
+        Err(_) => {
+            warn!(
+                "Health check timeout for deployment {} ({}) on port {}",
+                deployment.id, deployment.domain, port
+            );
+            false
+        }
     }
 }
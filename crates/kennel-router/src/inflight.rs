@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Counts requests currently being proxied to each deployment, so a replaced
+/// backend can be stopped as soon as it goes idle, and remembers when each
+/// deployment last got a request.
+#[derive(Debug, Clone, Default)]
+pub struct InFlightTracker {
+    counts: Arc<Mutex<HashMap<i32, usize>>>,
+    last_request: Arc<Mutex<HashMap<i32, Instant>>>,
+}
+
+impl InFlightTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a request against `deployment_id` until the guard is dropped.
+    pub fn start(&self, deployment_id: i32) -> InFlightGuard {
+        self.last_request
+            .lock()
+            .unwrap()
+            .insert(deployment_id, Instant::now());
+
+        *self
+            .counts
+            .lock()
+            .unwrap()
+            .entry(deployment_id)
+            .or_insert(0) += 1;
+
+        InFlightGuard {
+            tracker: self.clone(),
+            deployment_id,
+        }
+    }
+
+    pub fn count(&self, deployment_id: i32) -> usize {
+        self.counts
+            .lock()
+            .unwrap()
+            .get(&deployment_id)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// When `deployment_id` last got a request, if it has had one since
+    /// kennel started.
+    pub fn last_request(&self, deployment_id: i32) -> Option<Instant> {
+        self.last_request
+            .lock()
+            .unwrap()
+            .get(&deployment_id)
+            .copied()
+    }
+
+    /// Wait until `deployment_id` has no requests in flight. Returns false if
+    /// `timeout` expired first.
+    pub async fn wait_idle(&self, deployment_id: i32, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if self.count(deployment_id) == 0 {
+                return true;
+            }
+
+            if Instant::now() >= deadline {
+                return false;
+            }
+
+            tokio::time::sleep(IDLE_POLL_INTERVAL.min(deadline - Instant::now())).await;
+        }
+    }
+
+    fn finish(&self, deployment_id: i32) {
+        let mut counts = self.counts.lock().unwrap();
+
+        if let Some(count) = counts.get_mut(&deployment_id) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&deployment_id);
+            }
+        }
+    }
+}
+
+pub struct InFlightGuard {
+    tracker: InFlightTracker,
+    deployment_id: i32,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.tracker.finish(self.deployment_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_counts_requests() {
+        let tracker = InFlightTracker::new();
+
+        let first = tracker.start(1);
+        let second = tracker.start(1);
+        let _other = tracker.start(2);
+        assert_eq!(tracker.count(1), 2);
+
+        drop(first);
+        assert_eq!(tracker.count(1), 1);
+
+        drop(second);
+        assert_eq!(tracker.count(1), 0);
+        assert_eq!(tracker.count(2), 1);
+    }
+
+    #[test]
+    fn test_last_request() {
+        let tracker = InFlightTracker::new();
+        assert!(tracker.last_request(1).is_none());
+
+        let before = Instant::now();
+        drop(tracker.start(1));
+        assert!(tracker.last_request(1).is_some_and(|at| at >= before));
+        assert!(tracker.last_request(2).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wait_idle() {
+        let tracker = InFlightTracker::new();
+
+        assert!(tracker.wait_idle(1, Duration::ZERO).await);
+
+        let guard = tracker.start(1);
+        assert!(!tracker.wait_idle(1, Duration::from_millis(50)).await);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+        assert!(tracker.wait_idle(1, Duration::from_secs(5)).await);
+    }
+}
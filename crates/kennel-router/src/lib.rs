@@ -1,35 +1,58 @@
 mod acme;
+mod backends;
 mod error;
 mod handler;
 mod health;
+mod inflight;
 mod proxy;
 mod static_serve;
 mod table;
 mod tls;
+mod traffic;
 
 pub use acme::{create_acme_state, run_acme_event_loop};
+pub use backends::{BackendGuard, BackendSet};
 pub use error::{Result, RouterError};
-pub use health::run_health_monitor;
+pub use health::{HealthReport, run_health_monitor};
+pub use inflight::{InFlightGuard, InFlightTracker};
 pub use table::{Route, RouteTarget, RoutingTable};
 pub use tls::serve_with_tls;
+pub use traffic::{DeploymentTraffic, TrafficTracker, flush_traffic, run_traffic_flusher};
 
 #[derive(Debug, Clone)]
 pub enum RouterUpdate {
     DeploymentActive {
         deployment_id: i32,
         domain: String,
-        port: Option<u16>,
+        /// One port per replica; empty for static sites.
+        ports: Vec<u16>,
+        load_balancing: LoadBalancing,
         store_path: Option<String>,
         spa: bool,
     },
     DeploymentRemoved {
         domain: String,
     },
+    /// The deployment's unit was stopped for lack of traffic; the next
+    /// request to it wakes it.
+    DeploymentIdle {
+        deployment_id: i32,
+    },
     FullReload,
 }
 
+/// Asks the deployer to start an idle deployment. The reply says whether it
+/// is running and healthy again.
+#[derive(Debug)]
+pub struct WakeRequest {
+    pub deployment_id: i32,
+    pub reply: tokio::sync::oneshot::Sender<bool>,
+}
+
 use axum::Router;
+use entity::sea_orm_active_enums::LoadBalancing;
 use kennel_store::Store;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tracing::{error, info};
@@ -41,6 +64,24 @@ pub struct RouterConfig {
     pub acme_email: Option<String>,
     pub acme_production: bool,
     pub acme_cache_dir: Option<std::path::PathBuf>,
+    /// Shared with the deployer so replaced backends are drained before stopping.
+    pub in_flight: InFlightTracker,
+    /// Shared with the health monitor, which ejects failing replicas.
+    pub table: Arc<RoutingTable>,
+    /// Requests routed to each deployment, flushed to the store by
+    /// [`run_traffic_flusher`].
+    pub traffic: TrafficTracker,
+    /// Where to ask for idle deployments to be started. Without it, requests
+    /// to an idle deployment are refused.
+    pub wake_tx: Option<tokio::sync::mpsc::Sender<WakeRequest>>,
+}
+
+#[derive(Clone)]
+pub(crate) struct RouterState {
+    pub table: Arc<RoutingTable>,
+    pub in_flight: InFlightTracker,
+    pub traffic: TrafficTracker,
+    pub wake_tx: Option<tokio::sync::mpsc::Sender<WakeRequest>>,
 }
 
 pub async fn run_router(
@@ -49,20 +90,9 @@ pub async fn run_router(
 ) -> Result<()> {
     info!("Starting router on {}", config.bind_addr);
 
-    let routing_table = Arc::new(RoutingTable::new());
-
-    let active_deployments = config
-        .store
-        .deployments()
-        .list_active_with_services()
-        .await
-        .map_err(|e| RouterError::Other(anyhow::anyhow!(e)))?;
-
-    routing_table
-        .load_from_deployments_with_services(active_deployments)
-        .await?;
+    let routing_table = config.table.clone();
 
-    info!("Loaded {} routes", routing_table.len().await);
+    reload_routing_table(&routing_table, &config.store).await?;
 
     let table_clone = routing_table.clone();
     let store_clone = config.store.clone();
@@ -72,7 +102,12 @@ pub async fn run_router(
 
     let app = Router::new()
         .fallback(handler::route_request)
-        .with_state(routing_table);
+        .with_state(RouterState {
+            table: routing_table,
+            in_flight: config.in_flight.clone(),
+            traffic: config.traffic.clone(),
+            wake_tx: config.wake_tx.clone(),
+        });
 
     if config.tls_enabled {
         let email = config.acme_email.ok_or_else(|| {
@@ -150,11 +185,13 @@ async fn run_update_handler(
         tokio::select! {
             Ok(update) = update_rx.recv() => {
                 match update {
-                    RouterUpdate::DeploymentActive { deployment_id, domain, port, store_path, spa } => {
+                    RouterUpdate::DeploymentActive { deployment_id, domain, ports, load_balancing, store_path, spa } => {
                         info!("Updating route for domain: {} (deployment {})", domain, deployment_id);
 
-                        let target = if let Some(port) = port {
-                            RouteTarget::Service { port }
+                        let target = if !ports.is_empty() {
+                            RouteTarget::Service {
+                                backends: Arc::new(BackendSet::new(ports, load_balancing)),
+                            }
                         } else if let Some(path_str) = store_path {
                             RouteTarget::StaticSite {
                                 path: PathBuf::from(path_str),
@@ -174,6 +211,10 @@ async fn run_update_handler(
                         info!("Removing route for domain: {}", domain);
                         table.remove(&domain).await;
                     }
+                    RouterUpdate::DeploymentIdle { deployment_id } => {
+                        info!("Deployment {} is idle", deployment_id);
+                        table.set_asleep(deployment_id, true).await;
+                    }
                     RouterUpdate::FullReload => {
                         info!("Full routing table reload requested");
                         if let Err(e) = reload_routing_table(&table, &store).await {
@@ -199,8 +240,27 @@ async fn reload_routing_table(table: &RoutingTable, store: &Store) -> Result<()>
         .await
         .map_err(|e| RouterError::Other(anyhow::anyhow!(e)))?;
 
+    // Every replica of a service deployment holds one allocated port
+    let mut ports: HashMap<i32, Vec<u16>> = HashMap::new();
+    for allocation in store
+        .port_allocations()
+        .list_allocated()
+        .await
+        .map_err(|e| RouterError::Other(anyhow::anyhow!(e)))?
+    {
+        if let Some(deployment_id) = allocation.deployment_id {
+            ports
+                .entry(deployment_id)
+                .or_default()
+                .push(allocation.port as u16);
+        }
+    }
+    for replica_ports in ports.values_mut() {
+        replica_ports.sort();
+    }
+
     table
-        .load_from_deployments_with_services(active_deployments)
+        .load_from_deployments_with_services(active_deployments, &ports)
         .await?;
 
     info!("Reloaded routing table with {} routes", table.len().await);
@@ -1,13 +1,15 @@
+use crate::backends::BackendSet;
 use crate::error::Result;
+use entity::sea_orm_active_enums::ServiceType;
 use entity::{deployments, services};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 #[derive(Debug, Clone)]
 pub enum RouteTarget {
-    Service { port: u16 },
+    Service { backends: Arc<BackendSet> },
     StaticSite { path: PathBuf, spa: bool },
 }
 
@@ -43,6 +45,31 @@ impl RoutingTable {
         routes.remove(domain)
     }
 
+    /// Take one replica of the service at `domain` out of rotation, or put
+    /// it back. Returns false if `domain` has no such replica.
+    pub async fn set_backend_ejected(&self, domain: &str, port: u16, ejected: bool) -> bool {
+        let routes = self.routes.read().await;
+
+        match routes.get(domain).map(|route| &route.target) {
+            Some(RouteTarget::Service { backends }) => backends.set_ejected(port, ejected),
+            _ => false,
+        }
+    }
+
+    /// Mark every route to the service deployment `deployment_id` as asleep
+    /// or awake.
+    pub async fn set_asleep(&self, deployment_id: i32, asleep: bool) {
+        let routes = self.routes.read().await;
+
+        for route in routes.values() {
+            if route.deployment_id == deployment_id
+                && let RouteTarget::Service { backends } = &route.target
+            {
+                backends.set_asleep(asleep);
+            }
+        }
+    }
+
     pub async fn len(&self) -> usize {
         let routes = self.routes.read().await;
         routes.len()
@@ -53,11 +80,29 @@ impl RoutingTable {
         routes.is_empty()
     }
 
+    /// Replace every route with the given deployments. `ports` holds the
+    /// replica ports of each service deployment; replicas ejected before the
+    /// reload stay ejected.
     pub async fn load_from_deployments_with_services(
         &self,
         deployments_with_services: Vec<(deployments::Model, Option<services::Model>)>,
+        ports: &HashMap<i32, Vec<u16>>,
     ) -> Result<()> {
         let mut routes = self.routes.write().await;
+
+        let ejected: HashSet<u16> = routes
+            .values()
+            .filter_map(|route| match &route.target {
+                RouteTarget::Service { backends } => Some(backends),
+                RouteTarget::StaticSite { .. } => None,
+            })
+            .flat_map(|backends| {
+                backends
+                    .ports()
+                    .into_iter()
+                    .filter(|port| backends.is_ejected(*port))
+            })
+            .collect();
         routes.clear();
 
         for (deployment, service) in deployments_with_services {
@@ -68,8 +113,31 @@ impl RoutingTable {
                 ))
             })?;
 
+            // Workers and jobs serve no traffic
+            if matches!(
+                deployment.service_type,
+                ServiceType::Worker | ServiceType::Job
+            ) {
+                continue;
+            }
+
             let target = if let Some(port) = deployment.port {
-                RouteTarget::Service { port: port as u16 }
+                let replica_ports = ports
+                    .get(&deployment.id)
+                    .filter(|ports| !ports.is_empty())
+                    .cloned()
+                    .unwrap_or_else(|| vec![port as u16]);
+                let backends = BackendSet::new(replica_ports, service.load_balancing.clone());
+                for port in backends.ports() {
+                    if ejected.contains(&port) {
+                        backends.set_ejected(port, true);
+                    }
+                }
+                backends.set_asleep(deployment.idle_since.is_some());
+
+                RouteTarget::Service {
+                    backends: Arc::new(backends),
+                }
             } else {
                 let path = deployment
                     .store_path
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+use kennel_store::Store;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::time;
+use tracing::{debug, info, warn};
+
+/// Requests routed to one deployment since the last flush.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeploymentTraffic {
+    pub requests: u64,
+    pub last_request: DateTime<Utc>,
+}
+
+/// Counts routed requests per deployment in memory until they are flushed
+/// to the store, where they keep deployments from expiring.
+#[derive(Debug, Clone, Default)]
+pub struct TrafficTracker {
+    pending: Arc<Mutex<HashMap<i32, DeploymentTraffic>>>,
+}
+
+impl TrafficTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, deployment_id: i32) {
+        self.record_at(deployment_id, 1, Utc::now());
+    }
+
+    fn record_at(&self, deployment_id: i32, requests: u64, at: DateTime<Utc>) {
+        let mut pending = self.pending.lock().unwrap();
+        let traffic = pending.entry(deployment_id).or_insert(DeploymentTraffic {
+            requests: 0,
+            last_request: at,
+        });
+
+        traffic.requests += requests;
+        traffic.last_request = traffic.last_request.max(at);
+    }
+
+    /// Take everything recorded since the last call.
+    pub fn take(&self) -> HashMap<i32, DeploymentTraffic> {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+}
+
+/// Periodically write recorded traffic to the store.
+pub async fn run_traffic_flusher(tracker: TrafficTracker, store: Arc<Store>) {
+    info!("Starting traffic flusher");
+
+    let mut interval = time::interval(kennel_config::constants::TRAFFIC_FLUSH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        flush_traffic(&tracker, &store).await;
+    }
+}
+
+/// Write recorded traffic to the store. Traffic that fails to be written is
+/// kept for the next flush.
+pub async fn flush_traffic(tracker: &TrafficTracker, store: &Store) {
+    let traffic = tracker.take();
+    if traffic.is_empty() {
+        return;
+    }
+
+    debug!("Flushing traffic of {} deployment(s)", traffic.len());
+
+    for (deployment_id, traffic) in traffic {
+        if let Err(e) = store
+            .deployments()
+            .record_traffic(
+                deployment_id,
+                traffic.requests,
+                traffic.last_request.naive_utc(),
+            )
+            .await
+        {
+            warn!(
+                "Failed to record traffic of deployment {}: {}",
+                deployment_id, e
+            );
+            tracker.record_at(deployment_id, traffic.requests, traffic.last_request);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_record_and_take() {
+        let tracker = TrafficTracker::new();
+        let earlier = Utc::now() - TimeDelta::minutes(5);
+
+        tracker.record(1);
+        tracker.record(1);
+        tracker.record_at(1, 1, earlier);
+        tracker.record(2);
+
+        let traffic = tracker.take();
+        assert_eq!(traffic[&1].requests, 3);
+        assert!(traffic[&1].last_request > earlier);
+        assert_eq!(traffic[&2].requests, 1);
+
+        assert!(tracker.take().is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+use ::entity::{branch_pins, prelude::*};
+use sea_orm::*;
+
+pub struct BranchPinRepository<'a> {
+    db: &'a DatabaseConnection,
+}
+
+impl<'a> BranchPinRepository<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Keep a branch from expiring. Pinning a branch that is already pinned
+    /// keeps the original pin.
+    pub async fn pin(
+        &self,
+        project_name: &str,
+        branch: &str,
+        pinned_by: &str,
+    ) -> crate::Result<branch_pins::Model> {
+        if let Some(pin) = self.find(project_name, branch).await? {
+            return Ok(pin);
+        }
+
+        let pin = branch_pins::ActiveModel {
+            project_name: Set(project_name.to_string()),
+            branch: Set(branch.to_string()),
+            pinned_by: Set(pinned_by.to_string()),
+            ..Default::default()
+        };
+
+        Ok(pin.insert(self.db).await?)
+    }
+
+    /// Returns whether the branch was pinned.
+    pub async fn unpin(&self, project_name: &str, branch: &str) -> crate::Result<bool> {
+        let result = BranchPins::delete_by_id((project_name.to_string(), branch.to_string()))
+            .exec(self.db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn find(
+        &self,
+        project_name: &str,
+        branch: &str,
+    ) -> crate::Result<Option<branch_pins::Model>> {
+        Ok(
+            BranchPins::find_by_id((project_name.to_string(), branch.to_string()))
+                .one(self.db)
+                .await?,
+        )
+    }
+
+    pub async fn list_by_project(
+        &self,
+        project_name: &str,
+    ) -> crate::Result<Vec<branch_pins::Model>> {
+        Ok(BranchPins::find()
+            .filter(branch_pins::Column::ProjectName.eq(project_name))
+            .order_by_asc(branch_pins::Column::Branch)
+            .all(self.db)
+            .await?)
+    }
+}
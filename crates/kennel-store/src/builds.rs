@@ -1,5 +1,10 @@
-use ::entity::{build_results, builds, prelude::*, sea_orm_active_enums::BuildStatus};
-use sea_orm::*;
+use ::entity::{
+    build_results, builds, deployments,
+    prelude::*,
+    sea_orm_active_enums::{BuildStatus, DeploymentStatus},
+};
+use sea_orm::prelude::DateTime;
+use sea_orm::{sea_query::Expr, *};
 
 pub struct BuildRepository<'a> {
     db: &'a DatabaseConnection,
@@ -70,14 +75,24 @@ impl<'a> BuildRepository<'a> {
         Builds::delete_by_id(id).exec(self.db).await
     }
 
+    /// Builds that finished more than `days` ago, except those a deployment
+    /// not yet torn down still runs, which promotion and remediation need.
     pub async fn find_old_finished_builds(&self, days: i64) -> crate::Result<Vec<builds::Model>> {
         use chrono::{Duration, Utc};
 
         let cutoff = Utc::now().naive_utc() - Duration::days(days);
 
+        let in_use = sea_query::Query::select()
+            .column(deployments::Column::BuildId)
+            .from(Deployments)
+            .and_where(deployments::Column::BuildId.is_not_null())
+            .and_where(deployments::Column::Status.ne(DeploymentStatus::TornDown))
+            .to_owned();
+
         Ok(Builds::find()
             .filter(builds::Column::FinishedAt.is_not_null())
             .filter(builds::Column::FinishedAt.lt(cutoff))
+            .filter(builds::Column::Id.not_in_subquery(in_use))
             .all(self.db)
             .await?)
     }
@@ -98,36 +113,217 @@ impl<'a> BuildRepository<'a> {
         Ok(count > 0)
     }
 
+    /// The build of `commit_sha` on `git_ref`; a commit builds once per ref.
+    pub async fn find_by_ref_and_commit(
+        &self,
+        project_name: &str,
+        git_ref: &str,
+        commit_sha: &str,
+    ) -> crate::Result<Option<builds::Model>> {
+        Ok(Builds::find()
+            .filter(builds::Column::ProjectName.eq(project_name))
+            .filter(builds::Column::GitRef.eq(git_ref))
+            .filter(builds::Column::CommitSha.eq(commit_sha))
+            .one(self.db)
+            .await?)
+    }
+
+    /// Hold a finished build until someone approves or rejects it, or
+    /// `expires_at` passes. Builds of the same ref still waiting are
+    /// superseded and cancelled.
+    pub async fn hold_for_approval(
+        &self,
+        build: builds::Model,
+        expires_at: DateTime,
+    ) -> crate::Result<builds::Model> {
+        Builds::update_many()
+            .col_expr(builds::Column::Status, BuildStatus::Cancelled.as_enum())
+            .filter(builds::Column::ProjectName.eq(&build.project_name))
+            .filter(builds::Column::GitRef.eq(&build.git_ref))
+            .filter(builds::Column::Status.eq(BuildStatus::AwaitingApproval))
+            .filter(builds::Column::Id.ne(build.id))
+            .exec(self.db)
+            .await?;
+
+        let mut build = build.into_active_model();
+        build.status = Set(BuildStatus::AwaitingApproval);
+        build.finished_at = Set(Some(chrono::Utc::now().naive_utc()));
+        build.approval_expires_at = Set(Some(expires_at));
+
+        Ok(build.update(self.db).await?)
+    }
+
+    /// Approve or reject a build waiting for approval, recording `reviewer`.
+    /// Returns `None` if the build is not waiting or its approval expired.
+    pub async fn review(
+        &self,
+        build_id: i32,
+        approved: bool,
+        reviewer: &str,
+    ) -> crate::Result<Option<builds::Model>> {
+        let now = chrono::Utc::now().naive_utc();
+        let status = if approved {
+            BuildStatus::Success
+        } else {
+            BuildStatus::Rejected
+        };
+
+        let result = Builds::update_many()
+            .col_expr(builds::Column::Status, status.as_enum())
+            .col_expr(builds::Column::ReviewedBy, Expr::value(reviewer))
+            .col_expr(builds::Column::ReviewedAt, Expr::value(now))
+            .filter(builds::Column::Id.eq(build_id))
+            .filter(builds::Column::Status.eq(BuildStatus::AwaitingApproval))
+            .filter(builds::Column::ApprovalExpiresAt.gt(now))
+            .exec(self.db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        self.find_by_id(build_id).await
+    }
+
+    /// Expire builds whose approval window has passed. Returns how many
+    /// expired.
+    pub async fn expire_pending_approvals(&self) -> crate::Result<u64> {
+        let result = Builds::update_many()
+            .col_expr(builds::Column::Status, BuildStatus::Expired.as_enum())
+            .filter(builds::Column::Status.eq(BuildStatus::AwaitingApproval))
+            .filter(builds::Column::ApprovalExpiresAt.lte(chrono::Utc::now().naive_utc()))
+            .exec(self.db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// Hold a build that a freeze window keeps from deploying, with `reason`
+    /// shown on the build. Builds of the same ref already held are
+    /// superseded and cancelled.
+    pub async fn hold_for_freeze(
+        &self,
+        build: builds::Model,
+        reason: &str,
+    ) -> crate::Result<builds::Model> {
+        Builds::update_many()
+            .col_expr(builds::Column::Status, BuildStatus::Cancelled.as_enum())
+            .filter(builds::Column::ProjectName.eq(&build.project_name))
+            .filter(builds::Column::GitRef.eq(&build.git_ref))
+            .filter(builds::Column::Status.eq(BuildStatus::Frozen))
+            .filter(builds::Column::Id.ne(build.id))
+            .exec(self.db)
+            .await?;
+
+        let mut build = build.into_active_model();
+        build.status = Set(BuildStatus::Frozen);
+        build.hold_reason = Set(Some(reason.to_string()));
+
+        Ok(build.update(self.db).await?)
+    }
+
+    /// Let a build held by a freeze deploy again. Returns `None` if the build
+    /// is not held.
+    pub async fn release_hold(&self, build_id: i32) -> crate::Result<Option<builds::Model>> {
+        let result = Builds::update_many()
+            .col_expr(builds::Column::Status, BuildStatus::Success.as_enum())
+            .col_expr(
+                builds::Column::HoldReason,
+                Expr::value(Option::<String>::None),
+            )
+            .filter(builds::Column::Id.eq(build_id))
+            .filter(builds::Column::Status.eq(BuildStatus::Frozen))
+            .exec(self.db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        self.find_by_id(build_id).await
+    }
+
+    pub async fn list_awaiting_approval(
+        &self,
+        project_name: &str,
+    ) -> crate::Result<Vec<builds::Model>> {
+        Ok(Builds::find()
+            .filter(builds::Column::ProjectName.eq(project_name))
+            .filter(builds::Column::Status.eq(BuildStatus::AwaitingApproval))
+            .order_by_desc(builds::Column::CreatedAt)
+            .all(self.db)
+            .await?)
+    }
+
     pub async fn create_build(
         &self,
         project_name: String,
         git_ref: String,
         commit_sha: String,
-        _author: String,
+        author: String,
     ) -> crate::Result<builds::Model> {
-        use chrono::Utc;
+        Ok(queued_build(project_name, git_ref, commit_sha, author)
+            .insert(self.db)
+            .await?)
+    }
 
-        let now = Utc::now().naive_utc();
+    /// Queue a build of a pull request, recording where it comes from.
+    pub async fn create_pull_request_build(
+        &self,
+        project_name: String,
+        git_ref: String,
+        commit_sha: String,
+        author: String,
+        pull_request: PullRequestInfo,
+    ) -> crate::Result<builds::Model> {
+        let mut build = queued_build(project_name, git_ref, commit_sha, author);
+        build.pr_head_ref = Set(Some(pull_request.head_ref));
+        build.pr_base_ref = Set(Some(pull_request.base_ref));
+        build.pr_title = Set(Some(pull_request.title));
+        build.pr_from_fork = Set(pull_request.from_fork);
 
-        let branch = if git_ref.starts_with("refs/heads/") {
-            git_ref.strip_prefix("refs/heads/").unwrap().to_string()
-        } else {
-            git_ref.clone()
-        };
+        Ok(build.insert(self.db).await?)
+    }
+}
 
-        let build = builds::ActiveModel {
-            project_name: Set(project_name),
-            branch: Set(branch),
-            git_ref: Set(git_ref),
-            commit_sha: Set(commit_sha),
-            status: Set(BuildStatus::Queued),
-            started_at: NotSet,
-            finished_at: NotSet,
-            created_at: Set(now),
-            updated_at: Set(now),
-            ..Default::default()
-        };
+/// The pull request a build is for, as the forge described it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequestInfo {
+    /// Branch the changes come from, in the fork for fork pull requests.
+    pub head_ref: String,
+    /// Branch the pull request merges into.
+    pub base_ref: String,
+    pub title: String,
+    pub from_fork: bool,
+}
 
-        Ok(build.insert(self.db).await?)
+fn queued_build(
+    project_name: String,
+    git_ref: String,
+    commit_sha: String,
+    author: String,
+) -> builds::ActiveModel {
+    use chrono::Utc;
+
+    let now = Utc::now().naive_utc();
+
+    let branch = git_ref
+        .strip_prefix("refs/heads/")
+        .or_else(|| git_ref.strip_prefix("refs/tags/"))
+        .unwrap_or(&git_ref)
+        .to_string();
+
+    builds::ActiveModel {
+        project_name: Set(project_name),
+        branch: Set(branch),
+        git_ref: Set(git_ref),
+        commit_sha: Set(commit_sha),
+        author: Set(Some(author)),
+        status: Set(BuildStatus::Queued),
+        started_at: NotSet,
+        finished_at: NotSet,
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
     }
 }
@@ -22,6 +22,14 @@ impl Store {
             .await
     }
 
+    /// Active deployments outside the protected environments, which may
+    /// expire depending on their project's expiry policy.
+    pub async fn find_expirable_deployments(&self) -> Result<Vec<::entity::deployments::Model>> {
+        self.deployments()
+            .list_active_excluding(&["prod", "staging"])
+            .await
+    }
+
     /// Find builds that finished more than the specified number of days ago.
     ///
     /// This returns the list of old builds but does not delete their logs.
@@ -32,4 +40,23 @@ impl Store {
     ) -> Result<Vec<::entity::builds::Model>> {
         self.builds().find_old_finished_builds(retention_days).await
     }
+
+    /// Delete torn-down deployment rows and deployment events past their
+    /// retention periods, returning how many of each were removed.
+    pub async fn prune_deployment_history(
+        &self,
+        deployment_retention_days: i64,
+        event_retention_days: i64,
+    ) -> Result<(u64, u64)> {
+        let deployments = self
+            .deployments()
+            .delete_torn_down_before(deployment_retention_days)
+            .await?;
+        let events = self
+            .deployment_events()
+            .delete_older_than(event_retention_days)
+            .await?;
+
+        Ok((deployments, events))
+    }
 }
@@ -0,0 +1,113 @@
+use ::entity::{
+    deployment_events, deployments, prelude::*, sea_orm_active_enums::DeploymentEventKind,
+};
+use sea_orm::*;
+
+pub struct DeploymentEventRepository<'a> {
+    db: &'a DatabaseConnection,
+}
+
+impl<'a> DeploymentEventRepository<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Record an event against a deployment, copying its identifying columns
+    /// so the event outlives the deployment row.
+    pub async fn record(
+        &self,
+        deployment: &deployments::Model,
+        kind: DeploymentEventKind,
+        build_id: Option<i32>,
+        message: Option<String>,
+        actor: &str,
+    ) -> crate::Result<deployment_events::Model> {
+        let event = deployment_events::ActiveModel {
+            deployment_id: Set(Some(deployment.id)),
+            project_name: Set(deployment.project_name.clone()),
+            service_name: Set(deployment.service_name.clone()),
+            branch: Set(deployment.branch.clone()),
+            kind: Set(kind),
+            build_id: Set(build_id),
+            message: Set(message),
+            actor: Set(actor.to_string()),
+            ..Default::default()
+        };
+
+        Ok(event.insert(self.db).await?)
+    }
+
+    pub async fn list_by_deployment(
+        &self,
+        deployment_id: i32,
+    ) -> crate::Result<Vec<deployment_events::Model>> {
+        Ok(DeploymentEvents::find()
+            .filter(deployment_events::Column::DeploymentId.eq(deployment_id))
+            .order_by_asc(deployment_events::Column::CreatedAt)
+            .order_by_asc(deployment_events::Column::Id)
+            .all(self.db)
+            .await?)
+    }
+
+    /// The most recent event of `kind` recorded against a deployment.
+    pub async fn latest_by_deployment(
+        &self,
+        deployment_id: i32,
+        kind: DeploymentEventKind,
+    ) -> crate::Result<Option<deployment_events::Model>> {
+        Ok(DeploymentEvents::find()
+            .filter(deployment_events::Column::DeploymentId.eq(deployment_id))
+            .filter(deployment_events::Column::Kind.eq(kind))
+            .order_by_desc(deployment_events::Column::CreatedAt)
+            .order_by_desc(deployment_events::Column::Id)
+            .one(self.db)
+            .await?)
+    }
+
+    /// The most recent event of `kind` for any service of a branch.
+    pub async fn latest_by_branch(
+        &self,
+        project_name: &str,
+        branch: &str,
+        kind: DeploymentEventKind,
+    ) -> crate::Result<Option<deployment_events::Model>> {
+        Ok(DeploymentEvents::find()
+            .filter(deployment_events::Column::ProjectName.eq(project_name))
+            .filter(deployment_events::Column::Branch.eq(branch))
+            .filter(deployment_events::Column::Kind.eq(kind))
+            .order_by_desc(deployment_events::Column::CreatedAt)
+            .order_by_desc(deployment_events::Column::Id)
+            .one(self.db)
+            .await?)
+    }
+
+    /// Events for a branch across all of its services, newest first.
+    pub async fn list_by_branch(
+        &self,
+        project_name: &str,
+        branch: &str,
+        limit: u64,
+    ) -> crate::Result<Vec<deployment_events::Model>> {
+        Ok(DeploymentEvents::find()
+            .filter(deployment_events::Column::ProjectName.eq(project_name))
+            .filter(deployment_events::Column::Branch.eq(branch))
+            .order_by_desc(deployment_events::Column::CreatedAt)
+            .order_by_desc(deployment_events::Column::Id)
+            .limit(limit)
+            .all(self.db)
+            .await?)
+    }
+
+    pub async fn delete_older_than(&self, days: i64) -> crate::Result<u64> {
+        use chrono::{Duration, Utc};
+
+        let cutoff = Utc::now().naive_utc() - Duration::days(days);
+
+        let result = DeploymentEvents::delete_many()
+            .filter(deployment_events::Column::CreatedAt.lt(cutoff))
+            .exec(self.db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
@@ -1,5 +1,6 @@
 use ::entity::{deployments, prelude::*, sea_orm_active_enums::DeploymentStatus, services};
 use sea_orm::{entity::*, query::*, sea_query::Expr, *};
+use std::collections::{HashMap, HashSet};
 
 pub struct DeploymentRepository<'a> {
     db: &'a DatabaseConnection,
@@ -45,6 +46,19 @@ impl<'a> DeploymentRepository<'a> {
             .await
     }
 
+    pub async fn list_active_by_project(
+        &self,
+        project_name: &str,
+    ) -> Result<Vec<deployments::Model>, DbErr> {
+        Deployments::find()
+            .filter(deployments::Column::ProjectName.eq(project_name))
+            .filter(deployments::Column::Status.eq(DeploymentStatus::Active))
+            .order_by_asc(deployments::Column::GitRef)
+            .order_by_asc(deployments::Column::ServiceName)
+            .all(self.db)
+            .await
+    }
+
     pub async fn list_by_status(
         &self,
         status: DeploymentStatus,
@@ -111,9 +125,42 @@ impl<'a> DeploymentRepository<'a> {
 
         let cutoff = Utc::now().naive_utc() - Duration::days(days);
 
-        let mut query = Deployments::find()
-            .filter(deployments::Column::LastActivity.lt(cutoff))
-            .filter(deployments::Column::Status.eq(DeploymentStatus::Active));
+        let mut query =
+            Deployments::find().filter(deployments::Column::Status.eq(DeploymentStatus::Active));
+
+        for env in exclude_environments {
+            query = query.filter(deployments::Column::Environment.ne(*env));
+        }
+
+        let deployments = query.all(self.db).await?;
+
+        // Only the router sees requests, so a branch is in use while any of
+        // its deployments is, which keeps its workers alongside its frontend
+        let mut branch_activity: HashMap<(&str, &str), chrono::NaiveDateTime> = HashMap::new();
+        for deployment in &deployments {
+            branch_activity
+                .entry((&deployment.project_name, &deployment.git_ref))
+                .and_modify(|at| *at = (*at).max(deployment.last_activity))
+                .or_insert(deployment.last_activity);
+        }
+        let idle_branches: HashSet<(String, String)> = branch_activity
+            .into_iter()
+            .filter(|(_, at)| *at < cutoff)
+            .map(|((project, git_ref), _)| (project.to_string(), git_ref.to_string()))
+            .collect();
+
+        Ok(deployments
+            .into_iter()
+            .filter(|d| idle_branches.contains(&(d.project_name.clone(), d.git_ref.clone())))
+            .collect())
+    }
+
+    pub async fn list_active_excluding(
+        &self,
+        exclude_environments: &[&str],
+    ) -> crate::Result<Vec<deployments::Model>> {
+        let mut query =
+            Deployments::find().filter(deployments::Column::Status.eq(DeploymentStatus::Active));
 
         for env in exclude_environments {
             query = query.filter(deployments::Column::Environment.ne(*env));
@@ -122,7 +169,7 @@ impl<'a> DeploymentRepository<'a> {
         Ok(query.all(self.db).await?)
     }
 
-    pub async fn mark_ids_tearing_down(&self, ids: &[i32]) -> crate::Result<()> {
+    pub async fn mark_ids_tearing_down(&self, ids: &[i32], reason: &str) -> crate::Result<()> {
         use chrono::Utc;
 
         if !ids.is_empty() {
@@ -130,8 +177,9 @@ impl<'a> DeploymentRepository<'a> {
                 .filter(deployments::Column::Id.is_in(ids.iter().copied()))
                 .col_expr(
                     deployments::Column::Status,
-                    Expr::value(DeploymentStatus::TearingDown),
+                    DeploymentStatus::TearingDown.as_enum(),
                 )
+                .col_expr(deployments::Column::StatusMessage, Expr::value(reason))
                 .col_expr(
                     deployments::Column::UpdatedAt,
                     Expr::value(Utc::now().naive_utc()),
@@ -147,6 +195,7 @@ impl<'a> DeploymentRepository<'a> {
         &self,
         project_name: &str,
         git_ref: &str,
+        reason: &str,
     ) -> crate::Result<Vec<i32>> {
         let ids: Vec<i32> = Deployments::find()
             .filter(deployments::Column::ProjectName.eq(project_name))
@@ -158,11 +207,119 @@ impl<'a> DeploymentRepository<'a> {
             .map(|d| d.id)
             .collect();
 
-        self.mark_ids_tearing_down(&ids).await?;
+        self.mark_ids_tearing_down(&ids, reason).await?;
 
         Ok(ids)
     }
 
+    /// Deployments for a branch that still hold resources, optionally limited to one service.
+    pub async fn find_live_by_branch(
+        &self,
+        project_name: &str,
+        branch: &str,
+        service_name: Option<&str>,
+    ) -> crate::Result<Vec<deployments::Model>> {
+        let mut query = Deployments::find()
+            .filter(deployments::Column::ProjectName.eq(project_name))
+            .filter(deployments::Column::Branch.eq(branch))
+            .filter(
+                deployments::Column::Status
+                    .is_not_in([DeploymentStatus::TornDown, DeploymentStatus::Failed]),
+            );
+
+        if let Some(service_name) = service_name {
+            query = query.filter(deployments::Column::ServiceName.eq(service_name));
+        }
+
+        Ok(query.all(self.db).await?)
+    }
+
+    /// Hard-delete torn-down deployments last updated more than `days` ago.
+    pub async fn delete_torn_down_before(&self, days: i64) -> crate::Result<u64> {
+        use chrono::{Duration, Utc};
+
+        let cutoff = Utc::now().naive_utc() - Duration::days(days);
+
+        let result = Deployments::delete_many()
+            .filter(
+                deployments::Column::Status
+                    .is_in([DeploymentStatus::TornDown, DeploymentStatus::Failed]),
+            )
+            .filter(deployments::Column::UpdatedAt.lt(cutoff))
+            .exec(self.db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
+    pub async fn mark_failed(&self, id: i32, message: &str) -> crate::Result<()> {
+        use chrono::Utc;
+
+        Deployments::update_many()
+            .filter(deployments::Column::Id.eq(id))
+            .col_expr(
+                deployments::Column::Status,
+                DeploymentStatus::Failed.as_enum(),
+            )
+            .col_expr(deployments::Column::StatusMessage, Expr::value(message))
+            .col_expr(
+                deployments::Column::UpdatedAt,
+                Expr::value(Utc::now().naive_utc()),
+            )
+            .exec(self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_release_log(&self, id: i32, log: &str) -> crate::Result<()> {
+        Deployments::update_many()
+            .filter(deployments::Column::Id.eq(id))
+            .col_expr(deployments::Column::ReleaseLog, Expr::value(log))
+            .exec(self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Mark a deployment as stopped for lack of traffic, or as running
+    /// again. Idle deployments stay active and routed.
+    pub async fn set_idle(&self, id: i32, idle: bool) -> crate::Result<()> {
+        let idle_since = idle.then(|| chrono::Utc::now().naive_utc());
+
+        Deployments::update_many()
+            .filter(deployments::Column::Id.eq(id))
+            .col_expr(deployments::Column::IdleSince, Expr::value(idle_since))
+            .exec(self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Add `requests` proxied by the router to a deployment's count and move
+    /// its `last_activity` up to `last_request`, which keeps it from expiring.
+    pub async fn record_traffic(
+        &self,
+        id: i32,
+        requests: u64,
+        last_request: chrono::NaiveDateTime,
+    ) -> crate::Result<()> {
+        Deployments::update_many()
+            .filter(deployments::Column::Id.eq(id))
+            .col_expr(
+                deployments::Column::RequestCount,
+                Expr::col(deployments::Column::RequestCount).add(requests as i64),
+            )
+            .col_expr(
+                deployments::Column::LastActivity,
+                Expr::cust_with_values("GREATEST(last_activity, $1)", [last_request]),
+            )
+            .exec(self.db)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn find_by_dns_status(
         &self,
         dns_status: &str,
@@ -2,6 +2,7 @@ use crate::Result;
 use entity::dns_records;
 use sea_orm::{
     ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    sea_query::Expr,
 };
 
 pub struct Repository<'a> {
@@ -57,4 +58,16 @@ impl<'a> Repository<'a> {
         dns_records::Entity::delete_by_id(id).exec(self.db).await?;
         Ok(())
     }
+
+    /// Move records from a replaced deployment onto its successor so the
+    /// custom domain survives teardown of the old deployment.
+    pub async fn reassign_deployment(&self, from_id: i32, to_id: i32) -> Result<u64> {
+        let result = dns_records::Entity::update_many()
+            .col_expr(dns_records::Column::DeploymentId, Expr::value(to_id))
+            .filter(dns_records::Column::DeploymentId.eq(from_id))
+            .exec(self.db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
 }
@@ -0,0 +1,61 @@
+use ::entity::{freeze_windows, prelude::*};
+use sea_orm::*;
+
+pub struct FreezeWindowRepository<'a> {
+    db: &'a DatabaseConnection,
+}
+
+impl<'a> FreezeWindowRepository<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn create(
+        &self,
+        window: freeze_windows::ActiveModel,
+    ) -> crate::Result<freeze_windows::Model> {
+        Ok(window.insert(self.db).await?)
+    }
+
+    /// Returns whether the window existed.
+    pub async fn delete(&self, id: i32) -> crate::Result<bool> {
+        let result = FreezeWindows::delete_by_id(id).exec(self.db).await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> crate::Result<Option<freeze_windows::Model>> {
+        Ok(FreezeWindows::find_by_id(id).one(self.db).await?)
+    }
+
+    /// Windows that apply to every project.
+    pub async fn list_global(&self) -> crate::Result<Vec<freeze_windows::Model>> {
+        Ok(FreezeWindows::find()
+            .filter(freeze_windows::Column::ProjectName.is_null())
+            .order_by_asc(freeze_windows::Column::Id)
+            .all(self.db)
+            .await?)
+    }
+
+    pub async fn list_by_project(
+        &self,
+        project_name: &str,
+    ) -> crate::Result<Vec<freeze_windows::Model>> {
+        Ok(FreezeWindows::find()
+            .filter(freeze_windows::Column::ProjectName.eq(project_name))
+            .order_by_asc(freeze_windows::Column::Id)
+            .all(self.db)
+            .await?)
+    }
+
+    /// The project's own windows followed by the global ones.
+    pub async fn list_applicable(
+        &self,
+        project_name: &str,
+    ) -> crate::Result<Vec<freeze_windows::Model>> {
+        let mut windows = self.list_by_project(project_name).await?;
+        windows.extend(self.list_global().await?);
+
+        Ok(windows)
+    }
+}
@@ -0,0 +1,64 @@
+use ::entity::{deployments, job_runs, prelude::*};
+use chrono::NaiveDateTime;
+use sea_orm::*;
+
+pub struct JobRunRepository<'a> {
+    db: &'a DatabaseConnection,
+}
+
+impl<'a> JobRunRepository<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Record a finished run of the job deployed as `deployment`.
+    pub async fn record(
+        &self,
+        deployment: &deployments::Model,
+        started_at: NaiveDateTime,
+        finished_at: NaiveDateTime,
+        succeeded: bool,
+        exit_code: Option<i32>,
+        log: Option<String>,
+    ) -> crate::Result<job_runs::Model> {
+        let run = job_runs::ActiveModel {
+            deployment_id: Set(deployment.id),
+            project_name: Set(deployment.project_name.clone()),
+            job_name: Set(deployment.service_name.clone()),
+            branch: Set(deployment.branch.clone()),
+            started_at: Set(started_at),
+            finished_at: Set(finished_at),
+            succeeded: Set(succeeded),
+            exit_code: Set(exit_code),
+            log: Set(log),
+            ..Default::default()
+        };
+
+        Ok(run.insert(self.db).await?)
+    }
+
+    pub async fn latest_by_deployment(
+        &self,
+        deployment_id: i32,
+    ) -> crate::Result<Option<job_runs::Model>> {
+        Ok(JobRuns::find()
+            .filter(job_runs::Column::DeploymentId.eq(deployment_id))
+            .order_by_desc(job_runs::Column::StartedAt)
+            .one(self.db)
+            .await?)
+    }
+
+    /// Runs of a job deployment, newest first.
+    pub async fn list_by_deployment(
+        &self,
+        deployment_id: i32,
+        limit: u64,
+    ) -> crate::Result<Vec<job_runs::Model>> {
+        Ok(JobRuns::find()
+            .filter(job_runs::Column::DeploymentId.eq(deployment_id))
+            .order_by_desc(job_runs::Column::StartedAt)
+            .limit(limit)
+            .all(self.db)
+            .await?)
+    }
+}
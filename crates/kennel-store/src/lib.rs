@@ -1,17 +1,24 @@
+pub mod branch_pins;
 pub mod build_results;
 pub mod builds;
 pub mod cleanup;
+pub mod deployment_events;
 pub mod deployments;
 pub mod dns_records;
 pub mod error;
+pub mod freeze_windows;
+pub mod job_runs;
 pub mod port_allocations;
+pub mod preview_comments;
 pub mod preview_databases;
 pub mod projects;
 pub mod reconciliation;
 pub mod services;
+pub mod webhook_deliveries;
 
 pub use cleanup::CleanupSummary;
 pub use error::{Result, StoreError};
+pub use projects::{project_environment, project_environment_kind};
 pub use reconciliation::ReconciliationSummary;
 
 use sea_orm::DatabaseConnection;
@@ -41,6 +48,22 @@ impl Store {
         deployments::DeploymentRepository::new(&self.db)
     }
 
+    pub fn branch_pins(&self) -> branch_pins::BranchPinRepository<'_> {
+        branch_pins::BranchPinRepository::new(&self.db)
+    }
+
+    pub fn deployment_events(&self) -> deployment_events::DeploymentEventRepository<'_> {
+        deployment_events::DeploymentEventRepository::new(&self.db)
+    }
+
+    pub fn freeze_windows(&self) -> freeze_windows::FreezeWindowRepository<'_> {
+        freeze_windows::FreezeWindowRepository::new(&self.db)
+    }
+
+    pub fn job_runs(&self) -> job_runs::JobRunRepository<'_> {
+        job_runs::JobRunRepository::new(&self.db)
+    }
+
     pub fn builds(&self) -> builds::BuildRepository<'_> {
         builds::BuildRepository::new(&self.db)
     }
@@ -53,6 +76,14 @@ impl Store {
         port_allocations::PortAllocationRepository::new(&self.db)
     }
 
+    pub fn preview_comments(&self) -> preview_comments::PreviewCommentRepository<'_> {
+        preview_comments::PreviewCommentRepository::new(&self.db)
+    }
+
+    pub fn webhook_deliveries(&self) -> webhook_deliveries::WebhookDeliveryRepository<'_> {
+        webhook_deliveries::WebhookDeliveryRepository::new(&self.db)
+    }
+
     pub fn preview_databases(&self) -> preview_databases::PreviewDatabaseRepository<'_> {
         preview_databases::PreviewDatabaseRepository::new(&self.db)
     }
@@ -30,6 +30,27 @@ impl<'a> PortAllocationRepository<'a> {
             .await?)
     }
 
+    /// Every port held by a deployment, one per replica, lowest first.
+    pub async fn list_by_deployment(
+        &self,
+        deployment_id: i32,
+    ) -> Result<Vec<port_allocations::Model>> {
+        Ok(PortAllocations::find()
+            .filter(port_allocations::Column::DeploymentId.eq(deployment_id))
+            .order_by_asc(port_allocations::Column::Port)
+            .all(self.db)
+            .await?)
+    }
+
+    pub async fn release_deployment_ports(&self, deployment_id: i32) -> Result<u64> {
+        let result = PortAllocations::delete_many()
+            .filter(port_allocations::Column::DeploymentId.eq(deployment_id))
+            .exec(self.db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
     pub async fn list_allocated(&self) -> Result<Vec<port_allocations::Model>> {
         Ok(PortAllocations::find().all(self.db).await?)
     }
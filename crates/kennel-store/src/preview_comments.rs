@@ -0,0 +1,52 @@
+use ::entity::{prelude::*, preview_comments};
+use sea_orm::*;
+
+pub struct PreviewCommentRepository<'a> {
+    db: &'a DatabaseConnection,
+}
+
+impl<'a> PreviewCommentRepository<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn find(
+        &self,
+        project_name: &str,
+        pr_number: i64,
+    ) -> crate::Result<Option<preview_comments::Model>> {
+        Ok(
+            PreviewComments::find_by_id((project_name.to_string(), pr_number))
+                .one(self.db)
+                .await?,
+        )
+    }
+
+    /// Remember the forge comment holding a pull request's preview links,
+    /// replacing any earlier one.
+    pub async fn save(
+        &self,
+        project_name: &str,
+        pr_number: i64,
+        comment_id: i64,
+    ) -> crate::Result<preview_comments::Model> {
+        let now = chrono::Utc::now().naive_utc();
+
+        if let Some(existing) = self.find(project_name, pr_number).await? {
+            let mut comment = existing.into_active_model();
+            comment.comment_id = Set(comment_id);
+            comment.updated_at = Set(now);
+            return Ok(comment.update(self.db).await?);
+        }
+
+        let comment = preview_comments::ActiveModel {
+            project_name: Set(project_name.to_string()),
+            pr_number: Set(pr_number),
+            comment_id: Set(comment_id),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+
+        Ok(comment.insert(self.db).await?)
+    }
+}
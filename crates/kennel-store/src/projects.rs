@@ -1,5 +1,7 @@
 use ::entity::{prelude::*, projects};
+use kennel_config::{EnvironmentKind, EnvironmentRule, environment_kind, resolve_environment};
 use sea_orm::*;
+use tracing::warn;
 
 pub struct ProjectRepository<'a> {
     db: &'a DatabaseConnection,
@@ -30,3 +32,36 @@ impl<'a> ProjectRepository<'a> {
         Ok(Projects::delete_by_id(name).exec(self.db).await?)
     }
 }
+
+/// The environment deployments of `git_ref` run in, from the project's
+/// environment mapping in projects.json.
+pub fn project_environment(project: &projects::Model, git_ref: &str) -> String {
+    resolve_environment(
+        &environment_rules(project),
+        &project.default_branch,
+        git_ref,
+    )
+}
+
+/// What `environment` is for in the project's environment mapping.
+pub fn project_environment_kind(project: &projects::Model, environment: &str) -> EnvironmentKind {
+    environment_kind(&environment_rules(project), environment)
+}
+
+fn environment_rules(project: &projects::Model) -> Vec<EnvironmentRule> {
+    match project
+        .environment_rules
+        .clone()
+        .map(serde_json::from_value)
+    {
+        Some(Ok(rules)) => rules,
+        Some(Err(e)) => {
+            warn!(
+                "Invalid environment mapping for project {}: {}",
+                project.name, e
+            );
+            Vec::new()
+        }
+        None => Vec::new(),
+    }
+}
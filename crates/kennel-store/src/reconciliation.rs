@@ -16,7 +16,7 @@ pub struct ReconciliationSummary {
 }
 
 impl Store {
-    /// Find all active service deployments (not static sites).
+    /// Find all active deployments that run as units: services, workers and jobs.
     /// Used during reconciliation to verify systemd units.
     pub async fn find_active_service_deployments(&self) -> Result<Vec<deployments::Model>> {
         use ::entity::prelude::*;
@@ -25,8 +25,25 @@ impl Store {
 
         Ok(Deployments::find()
             .filter(deployments::Column::Status.eq(DeploymentStatus::Active))
-            .inner_join(Services)
-            .filter(::entity::services::Column::Type.eq(ServiceType::Service))
+            .filter(deployments::Column::ServiceType.is_in([
+                ServiceType::Service,
+                ServiceType::Worker,
+                ServiceType::Job,
+            ]))
+            .all(self.db())
+            .await?)
+    }
+
+    /// Find all active scheduled job deployments.
+    /// Used by the deployer to collect finished job runs.
+    pub async fn find_active_job_deployments(&self) -> Result<Vec<deployments::Model>> {
+        use ::entity::prelude::*;
+        use ::entity::sea_orm_active_enums::ServiceType;
+        use sea_orm::*;
+
+        Ok(Deployments::find()
+            .filter(deployments::Column::Status.eq(DeploymentStatus::Active))
+            .filter(deployments::Column::ServiceType.eq(ServiceType::Job))
             .all(self.db())
             .await?)
     }
@@ -40,8 +57,7 @@ impl Store {
 
         Ok(Deployments::find()
             .filter(deployments::Column::Status.eq(DeploymentStatus::Active))
-            .inner_join(Services)
-            .filter(::entity::services::Column::Type.eq(ServiceType::Static))
+            .filter(deployments::Column::ServiceType.eq(ServiceType::Static))
             .all(self.db())
             .await?)
     }
@@ -37,6 +37,29 @@ impl<'a> ServiceRepository<'a> {
         service.insert(self.db).await
     }
 
+    /// Insert or refresh the service row declared by a project's kennel.toml.
+    pub async fn upsert(&self, service: services::ActiveModel) -> Result<services::Model, DbErr> {
+        Services::insert(service)
+            .on_conflict(
+                sea_query::OnConflict::columns([
+                    services::Column::ProjectName,
+                    services::Column::Name,
+                ])
+                .update_columns([
+                    services::Column::Type,
+                    services::Column::Package,
+                    services::Column::HealthCheck,
+                    services::Column::CustomDomain,
+                    services::Column::Spa,
+                    services::Column::LoadBalancing,
+                    services::Column::IdleTimeoutSecs,
+                ])
+                .to_owned(),
+            )
+            .exec_with_returning(self.db)
+            .await
+    }
+
     pub async fn update(&self, service: services::ActiveModel) -> Result<services::Model, DbErr> {
         service.update(self.db).await
     }
@@ -0,0 +1,73 @@
+use ::entity::{prelude::*, webhook_deliveries};
+use sea_orm::*;
+
+pub struct WebhookDeliveryRepository<'a> {
+    db: &'a DatabaseConnection,
+}
+
+impl<'a> WebhookDeliveryRepository<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn create(
+        &self,
+        delivery: webhook_deliveries::ActiveModel,
+    ) -> crate::Result<webhook_deliveries::Model> {
+        Ok(delivery.insert(self.db).await?)
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> crate::Result<Option<webhook_deliveries::Model>> {
+        Ok(WebhookDeliveries::find_by_id(id).one(self.db).await?)
+    }
+
+    /// A project's most recent deliveries, newest first.
+    pub async fn list_by_project(
+        &self,
+        project_name: &str,
+        limit: u64,
+    ) -> crate::Result<Vec<webhook_deliveries::Model>> {
+        Ok(WebhookDeliveries::find()
+            .filter(webhook_deliveries::Column::ProjectName.eq(project_name))
+            .order_by_desc(webhook_deliveries::Column::Id)
+            .limit(limit)
+            .all(self.db)
+            .await?)
+    }
+
+    /// Record `delivery` as claiming its forge-assigned id before it is
+    /// handled. Returns `None` when another delivery with the id already holds
+    /// it, because it was handled or is being handled right now.
+    pub async fn claim(
+        &self,
+        mut delivery: webhook_deliveries::ActiveModel,
+    ) -> crate::Result<Option<webhook_deliveries::Model>> {
+        delivery.claimed = Set(true);
+
+        match delivery.insert(self.db).await {
+            Ok(delivery) => Ok(Some(delivery)),
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn update(
+        &self,
+        delivery: webhook_deliveries::ActiveModel,
+    ) -> crate::Result<webhook_deliveries::Model> {
+        Ok(delivery.update(self.db).await?)
+    }
+
+    pub async fn delete_older_than(&self, days: i64) -> crate::Result<u64> {
+        use chrono::{Duration, Utc};
+
+        let cutoff = Utc::now().naive_utc() - Duration::days(days);
+
+        let result = WebhookDeliveries::delete_many()
+            .filter(webhook_deliveries::Column::CreatedAt.lt(cutoff))
+            .exec(self.db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
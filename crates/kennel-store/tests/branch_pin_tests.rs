@@ -0,0 +1,79 @@
+use entity::{projects, sea_orm_active_enums::*};
+use kennel_store::Store;
+use sea_orm::{Database, DbErr, Set};
+
+async fn setup_test_db() -> Result<Store, DbErr> {
+    let db_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+
+    let db = Database::connect(&db_url).await?;
+    Ok(Store::new(db))
+}
+
+async fn create_test_project(store: &Store, project: &str) {
+    let _ = store.projects().delete(project).await;
+
+    store
+        .projects()
+        .create(projects::ActiveModel {
+            name: Set(project.to_string()),
+            repo_url: Set(format!("https://github.com/{}", project)),
+            repo_type: Set(RepoType::Github),
+            webhook_secret: Set("secret".to_string()),
+            default_branch: Set("main".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_pin_and_unpin_branch() {
+    let store = setup_test_db().await.unwrap();
+    let project = "pin-test";
+    create_test_project(&store, project).await;
+
+    let pin = store
+        .branch_pins()
+        .pin(project, "feature/demo", "alice")
+        .await
+        .unwrap();
+    assert_eq!(pin.pinned_by, "alice");
+
+    // Pinning again keeps the original pin
+    let again = store
+        .branch_pins()
+        .pin(project, "feature/demo", "bob")
+        .await
+        .unwrap();
+    assert_eq!(again.pinned_by, "alice");
+
+    let pins = store.branch_pins().list_by_project(project).await.unwrap();
+    assert_eq!(pins.len(), 1);
+    assert_eq!(pins[0].branch, "feature/demo");
+
+    assert!(
+        store
+            .branch_pins()
+            .unpin(project, "feature/demo")
+            .await
+            .unwrap()
+    );
+    assert!(
+        !store
+            .branch_pins()
+            .unpin(project, "feature/demo")
+            .await
+            .unwrap()
+    );
+    assert!(
+        store
+            .branch_pins()
+            .find(project, "feature/demo")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    store.projects().delete(project).await.unwrap();
+}
@@ -0,0 +1,155 @@
+use chrono::{Duration, Utc};
+use entity::{projects, sea_orm_active_enums::*};
+use kennel_store::Store;
+use sea_orm::{Database, DbErr, Set};
+
+async fn setup_test_db() -> Result<Store, DbErr> {
+    let db_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+
+    let db = Database::connect(&db_url).await?;
+    Ok(Store::new(db))
+}
+
+async fn create_test_project(store: &Store, project: &str) {
+    let _ = store.projects().delete(project).await;
+
+    store
+        .projects()
+        .create(projects::ActiveModel {
+            name: Set(project.to_string()),
+            repo_url: Set(format!("https://github.com/{}", project)),
+            repo_type: Set(RepoType::Github),
+            webhook_secret: Set("secret".to_string()),
+            default_branch: Set("main".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+}
+
+async fn held_build(store: &Store, project: &str, sha: &str, hours: i64) -> i32 {
+    let build = store
+        .builds()
+        .create_build(
+            project.to_string(),
+            "main".to_string(),
+            sha.to_string(),
+            "alice".to_string(),
+        )
+        .await
+        .unwrap();
+
+    let build = store
+        .builds()
+        .hold_for_approval(build, Utc::now().naive_utc() + Duration::hours(hours))
+        .await
+        .unwrap();
+    assert_eq!(build.status, BuildStatus::AwaitingApproval);
+    assert!(build.finished_at.is_some());
+
+    build.id
+}
+
+async fn status(store: &Store, build_id: i32) -> BuildStatus {
+    store
+        .builds()
+        .find_by_id(build_id)
+        .await
+        .unwrap()
+        .unwrap()
+        .status
+}
+
+#[tokio::test]
+async fn test_approve_and_reject() {
+    let store = setup_test_db().await.unwrap();
+    let project = "approval-review-test";
+    create_test_project(&store, project).await;
+
+    let first = held_build(&store, project, "aaa111", 24).await;
+    let second = held_build(&store, project, "bbb222", 24).await;
+
+    // A newer build of the same ref supersedes the one still waiting
+    assert_eq!(status(&store, first).await, BuildStatus::Cancelled);
+    assert!(
+        store
+            .builds()
+            .review(first, true, "bob")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let pending = store
+        .builds()
+        .list_awaiting_approval(project)
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].id, second);
+
+    let approved = store
+        .builds()
+        .review(second, true, "bob")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(approved.status, BuildStatus::Success);
+    assert_eq!(approved.reviewed_by.as_deref(), Some("bob"));
+    assert!(approved.reviewed_at.is_some());
+
+    // Already reviewed
+    assert!(
+        store
+            .builds()
+            .review(second, false, "carol")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let third = held_build(&store, project, "ccc333", 24).await;
+    let rejected = store
+        .builds()
+        .review(third, false, "carol")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rejected.status, BuildStatus::Rejected);
+    assert_eq!(rejected.reviewed_by.as_deref(), Some("carol"));
+
+    store.projects().delete(project).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pending_approvals_expire() {
+    let store = setup_test_db().await.unwrap();
+    let project = "approval-expiry-test";
+    create_test_project(&store, project).await;
+
+    let build_id = held_build(&store, project, "ddd444", -1).await;
+
+    // Past its window the build can no longer be approved
+    assert!(
+        store
+            .builds()
+            .review(build_id, true, "bob")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    assert!(store.builds().expire_pending_approvals().await.unwrap() >= 1);
+    assert_eq!(status(&store, build_id).await, BuildStatus::Expired);
+    assert!(
+        store
+            .builds()
+            .list_awaiting_approval(project)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    store.projects().delete(project).await.unwrap();
+}
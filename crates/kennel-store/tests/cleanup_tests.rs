@@ -136,6 +136,125 @@ async fn test_find_expired_excludes_prod() {
     cleanup(&store, "cleanup-test2").await;
 }
 
+#[tokio::test]
+async fn test_recorded_traffic_prevents_expiry() {
+    let store = setup_test_db().await.expect("Failed to connect");
+
+    create_test_project(&store, "cleanup-test-traffic")
+        .await
+        .expect("Failed to create project");
+    create_test_service(&store, "cleanup-test-traffic", "web")
+        .await
+        .expect("Failed to create service");
+
+    let old_activity = now() - Duration::days(10);
+
+    let created = store
+        .deployments()
+        .create(deployments::ActiveModel {
+            project_name: Set("cleanup-test-traffic".to_string()),
+            service_name: Set("web".to_string()),
+            branch: Set("visited".to_string()),
+            branch_slug: Set("visited".to_string()),
+            environment: Set("preview".to_string()),
+            git_ref: Set("visited".to_string()),
+            domain: Set("visited.test.com".to_string()),
+            status: Set(DeploymentStatus::Active),
+            last_activity: Set(old_activity),
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to create deployment");
+    assert_eq!(created.request_count, 0);
+
+    let yesterday = now() - Duration::days(1);
+    store
+        .deployments()
+        .record_traffic(created.id, 3, yesterday)
+        .await
+        .expect("Failed to record traffic");
+    // An older flush never moves last_activity back
+    store
+        .deployments()
+        .record_traffic(created.id, 2, old_activity)
+        .await
+        .expect("Failed to record traffic");
+
+    let visited = store
+        .deployments()
+        .find_by_id(created.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(visited.request_count, 5);
+    assert_eq!(
+        visited.last_activity.and_utc().timestamp(),
+        yesterday.and_utc().timestamp()
+    );
+
+    let expired = store
+        .find_expired_deployments(7)
+        .await
+        .expect("Failed to find expired");
+    assert!(
+        !expired.iter().any(|d| d.id == created.id),
+        "Recent requests should keep a deployment from expiring"
+    );
+
+    cleanup(&store, "cleanup-test-traffic").await;
+}
+
+#[tokio::test]
+async fn test_branch_expires_as_a_whole() {
+    let store = setup_test_db().await.expect("Failed to connect");
+
+    create_test_project(&store, "cleanup-test-branch")
+        .await
+        .expect("Failed to create project");
+    create_test_service(&store, "cleanup-test-branch", "web")
+        .await
+        .expect("Failed to create service");
+    create_test_service(&store, "cleanup-test-branch", "queue")
+        .await
+        .expect("Failed to create service");
+
+    let mut ids = Vec::new();
+    for (service, last_activity) in [
+        ("web", now() - Duration::hours(1)),
+        ("queue", now() - Duration::days(8)),
+    ] {
+        let deployment = store
+            .deployments()
+            .create(deployments::ActiveModel {
+                project_name: Set("cleanup-test-branch".to_string()),
+                service_name: Set(service.to_string()),
+                branch: Set("feature".to_string()),
+                branch_slug: Set("feature".to_string()),
+                environment: Set("dev".to_string()),
+                git_ref: Set("feature".to_string()),
+                domain: Set(format!("{}-feature.test.com", service)),
+                status: Set(DeploymentStatus::Active),
+                last_activity: Set(last_activity),
+                ..Default::default()
+            })
+            .await
+            .expect("Failed to create deployment");
+        ids.push(deployment.id);
+    }
+
+    // The worker never sees a request, but the branch's frontend does
+    let expired = store
+        .find_expired_deployments(7)
+        .await
+        .expect("Failed to find expired");
+    assert!(
+        !expired.iter().any(|d| ids.contains(&d.id)),
+        "A branch with recent requests should not expire any of its deployments"
+    );
+
+    cleanup(&store, "cleanup-test-branch").await;
+}
+
 #[tokio::test]
 async fn test_find_old_builds() {
     let store = setup_test_db().await.expect("Failed to connect");
@@ -215,3 +334,77 @@ async fn test_find_old_builds_excludes_unfinished() {
 
     cleanup(&store, "cleanup-test4").await;
 }
+
+#[tokio::test]
+async fn test_find_old_builds_excludes_deployed() {
+    let store = setup_test_db().await.expect("Failed to connect");
+
+    create_test_project(&store, "cleanup-test5")
+        .await
+        .expect("Failed to create project");
+    create_test_service(&store, "cleanup-test5", "web")
+        .await
+        .expect("Failed to create service");
+
+    let old_finish_time = Utc::now().naive_utc() - Duration::days(35);
+    let create_build = |commit_sha: &str| builds::ActiveModel {
+        project_name: Set("cleanup-test5".to_string()),
+        branch: Set("main".to_string()),
+        git_ref: Set("main".to_string()),
+        commit_sha: Set(commit_sha.to_string()),
+        status: Set(BuildStatus::Success),
+        finished_at: Set(Some(old_finish_time)),
+        created_at: Set(now()),
+        updated_at: Set(now()),
+        ..Default::default()
+    };
+    let live = store
+        .builds()
+        .create(create_build("live000"))
+        .await
+        .expect("Failed to create build");
+    let replaced = store
+        .builds()
+        .create(create_build("replaced000"))
+        .await
+        .expect("Failed to create build");
+
+    for (build, status) in [
+        (&live, DeploymentStatus::Active),
+        (&replaced, DeploymentStatus::TornDown),
+    ] {
+        store
+            .deployments()
+            .create(deployments::ActiveModel {
+                project_name: Set("cleanup-test5".to_string()),
+                service_name: Set("web".to_string()),
+                branch: Set("main".to_string()),
+                branch_slug: Set("main".to_string()),
+                environment: Set("prod".to_string()),
+                git_ref: Set("main".to_string()),
+                domain: Set(format!("{}.cleanup-test5.test.com", build.commit_sha)),
+                status: Set(status),
+                build_id: Set(Some(build.id)),
+                last_activity: Set(now()),
+                ..Default::default()
+            })
+            .await
+            .expect("Failed to create deployment");
+    }
+
+    let old_builds = store
+        .find_old_builds(30)
+        .await
+        .expect("Failed to find old builds");
+
+    assert!(
+        !old_builds.iter().any(|b| b.id == live.id),
+        "A build still deployed should be kept"
+    );
+    assert!(
+        old_builds.iter().any(|b| b.id == replaced.id),
+        "A build only torn-down deployments used should be cleaned up"
+    );
+
+    cleanup(&store, "cleanup-test5").await;
+}
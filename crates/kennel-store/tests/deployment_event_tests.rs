@@ -0,0 +1,286 @@
+use chrono::Utc;
+use entity::{deployment_events, deployments, projects, sea_orm_active_enums::*, services};
+use kennel_store::Store;
+use sea_orm::{ColumnTrait, Database, DbErr, EntityTrait, QueryFilter, Set, sea_query::Expr};
+
+async fn setup_test_db() -> Result<Store, DbErr> {
+    let db_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+
+    let db = Database::connect(&db_url).await?;
+    Ok(Store::new(db))
+}
+
+async fn create_test_deployment(
+    store: &Store,
+    project: &str,
+    service: &str,
+) -> Result<deployments::Model, DbErr> {
+    let proj = projects::ActiveModel {
+        name: Set(project.to_string()),
+        repo_url: Set(format!("https://github.com/{}", project)),
+        repo_type: Set(RepoType::Github),
+        webhook_secret: Set("secret".to_string()),
+        default_branch: Set("main".to_string()),
+        ..Default::default()
+    };
+
+    let _ = store.projects().create(proj).await.ok();
+
+    let svc = services::ActiveModel {
+        project_name: Set(project.to_string()),
+        name: Set(service.to_string()),
+        r#type: Set(ServiceType::Service),
+        package: Set("default".to_string()),
+        ..Default::default()
+    };
+
+    let _ = store.services().create(svc).await.ok();
+
+    let now = Utc::now().naive_utc();
+    let dep = deployments::ActiveModel {
+        project_name: Set(project.to_string()),
+        service_name: Set(service.to_string()),
+        branch: Set("main".to_string()),
+        branch_slug: Set("main".to_string()),
+        environment: Set("prod".to_string()),
+        git_ref: Set("main".to_string()),
+        domain: Set(format!("{}-{}.events.test.com", project, service)),
+        status: Set(DeploymentStatus::Active),
+        dns_status: Set("pending".to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        last_activity: Set(now),
+        ..Default::default()
+    };
+
+    store.deployments().create(dep).await
+}
+
+async fn cleanup(store: &Store, project: &str) {
+    let _ = deployment_events::Entity::delete_many()
+        .filter(deployment_events::Column::ProjectName.eq(project))
+        .exec(store.db())
+        .await;
+    let _ = store.projects().delete(project).await;
+}
+
+#[tokio::test]
+async fn test_record_and_list_events() {
+    let store = setup_test_db().await.expect("Failed to connect");
+    cleanup(&store, "test-events").await;
+
+    let deployment = create_test_deployment(&store, "test-events", "api")
+        .await
+        .expect("Failed to create deployment");
+
+    store
+        .deployment_events()
+        .record(
+            &deployment,
+            DeploymentEventKind::Restarted,
+            None,
+            Some("restart".to_string()),
+            "kennel",
+        )
+        .await
+        .expect("Failed to record restart");
+
+    store
+        .deployment_events()
+        .record(
+            &deployment,
+            DeploymentEventKind::Failed,
+            None,
+            None,
+            "kennel",
+        )
+        .await
+        .expect("Failed to record failure");
+
+    let events = store
+        .deployment_events()
+        .list_by_deployment(deployment.id)
+        .await
+        .expect("Failed to list events");
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].kind, DeploymentEventKind::Restarted);
+    assert_eq!(events[0].message.as_deref(), Some("restart"));
+    assert_eq!(events[1].kind, DeploymentEventKind::Failed);
+    assert_eq!(events[1].project_name, "test-events");
+    assert_eq!(events[1].service_name, "api");
+
+    cleanup(&store, "test-events").await;
+}
+
+#[tokio::test]
+async fn test_mark_failed_sets_message() {
+    let store = setup_test_db().await.expect("Failed to connect");
+    cleanup(&store, "test-mark-failed").await;
+
+    let deployment = create_test_deployment(&store, "test-mark-failed", "api")
+        .await
+        .expect("Failed to create deployment");
+
+    store
+        .deployments()
+        .mark_failed(deployment.id, "health checks failed")
+        .await
+        .expect("Failed to mark deployment failed");
+
+    let updated = store
+        .deployments()
+        .find_by_id(deployment.id)
+        .await
+        .expect("Failed to query deployment")
+        .expect("Deployment not found");
+
+    assert_eq!(updated.status, DeploymentStatus::Failed);
+    assert_eq!(
+        updated.status_message.as_deref(),
+        Some("health checks failed")
+    );
+
+    cleanup(&store, "test-mark-failed").await;
+}
+
+#[tokio::test]
+async fn test_list_by_branch_newest_first() {
+    let store = setup_test_db().await.expect("Failed to connect");
+    cleanup(&store, "test-timeline").await;
+
+    let deployment = create_test_deployment(&store, "test-timeline", "web")
+        .await
+        .expect("Failed to create deployment");
+
+    for kind in [
+        DeploymentEventKind::Deployed,
+        DeploymentEventKind::Replaced,
+        DeploymentEventKind::TornDown,
+    ] {
+        store
+            .deployment_events()
+            .record(&deployment, kind, None, None, "kennel")
+            .await
+            .expect("Failed to record event");
+    }
+
+    let timeline = store
+        .deployment_events()
+        .list_by_branch("test-timeline", "main", 2)
+        .await
+        .expect("Failed to list timeline");
+
+    assert_eq!(timeline.len(), 2);
+    assert_eq!(timeline[0].kind, DeploymentEventKind::TornDown);
+    assert_eq!(timeline[1].kind, DeploymentEventKind::Replaced);
+
+    cleanup(&store, "test-timeline").await;
+}
+
+#[tokio::test]
+async fn test_find_live_by_branch_skips_torn_down() {
+    let store = setup_test_db().await.expect("Failed to connect");
+    cleanup(&store, "test-live").await;
+
+    let deployment = create_test_deployment(&store, "test-live", "api")
+        .await
+        .expect("Failed to create deployment");
+
+    let live = store
+        .deployments()
+        .find_live_by_branch("test-live", "main", Some("api"))
+        .await
+        .expect("Failed to find live deployments");
+    assert_eq!(live.len(), 1);
+
+    store
+        .deployments()
+        .mark_failed(deployment.id, "gone")
+        .await
+        .expect("Failed to mark deployment failed");
+
+    let live = store
+        .deployments()
+        .find_live_by_branch("test-live", "main", None)
+        .await
+        .expect("Failed to find live deployments");
+    assert!(live.is_empty());
+
+    cleanup(&store, "test-live").await;
+}
+
+#[tokio::test]
+async fn test_prune_deployment_history() {
+    let store = setup_test_db().await.expect("Failed to connect");
+    cleanup(&store, "test-prune").await;
+
+    let deployment = create_test_deployment(&store, "test-prune", "api")
+        .await
+        .expect("Failed to create deployment");
+
+    store
+        .deployments()
+        .mark_failed(deployment.id, "gone")
+        .await
+        .expect("Failed to mark deployment failed");
+
+    let old_event = store
+        .deployment_events()
+        .record(
+            &deployment,
+            DeploymentEventKind::Deployed,
+            None,
+            None,
+            "kennel",
+        )
+        .await
+        .expect("Failed to record event");
+    let recent_event = store
+        .deployment_events()
+        .record(
+            &deployment,
+            DeploymentEventKind::Failed,
+            None,
+            None,
+            "kennel",
+        )
+        .await
+        .expect("Failed to record event");
+
+    deployment_events::Entity::update_many()
+        .col_expr(
+            deployment_events::Column::CreatedAt,
+            Expr::value(Utc::now().naive_utc() - chrono::Duration::days(100)),
+        )
+        .filter(deployment_events::Column::Id.eq(old_event.id))
+        .exec(store.db())
+        .await
+        .expect("Failed to age event");
+
+    store
+        .prune_deployment_history(30, 90)
+        .await
+        .expect("Failed to prune history");
+
+    assert!(
+        store
+            .deployments()
+            .find_by_id(deployment.id)
+            .await
+            .expect("Failed to query deployment")
+            .is_some(),
+        "Recently failed deployment should be kept"
+    );
+
+    let events = store
+        .deployment_events()
+        .list_by_deployment(deployment.id)
+        .await
+        .expect("Failed to list events");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id, recent_event.id);
+
+    cleanup(&store, "test-prune").await;
+}
@@ -0,0 +1,127 @@
+use chrono::Utc;
+use entity::{deployments, projects, sea_orm_active_enums::*, services};
+use kennel_store::Store;
+use sea_orm::{Database, DbErr, Set};
+
+async fn setup_test_db() -> Result<Store, DbErr> {
+    let db_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+
+    let db = Database::connect(&db_url).await?;
+    Ok(Store::new(db))
+}
+
+async fn create_test_project(store: &Store, name: &str) -> Result<(), DbErr> {
+    let proj = projects::ActiveModel {
+        name: Set(name.to_string()),
+        repo_url: Set(format!("https://github.com/{}", name)),
+        repo_type: Set(RepoType::Github),
+        webhook_secret: Set("secret".to_string()),
+        default_branch: Set("main".to_string()),
+        ..Default::default()
+    };
+
+    let _ = store.projects().create(proj).await.ok();
+    Ok(())
+}
+
+async fn create_test_service(
+    store: &Store,
+    project: &str,
+    service: &str,
+    r#type: ServiceType,
+) -> Result<(), DbErr> {
+    let svc = services::ActiveModel {
+        project_name: Set(project.to_string()),
+        name: Set(service.to_string()),
+        r#type: Set(r#type),
+        package: Set("default".to_string()),
+        ..Default::default()
+    };
+
+    let _ = store.services().create(svc).await.ok();
+    Ok(())
+}
+
+async fn create_test_deployment(
+    store: &Store,
+    project: &str,
+    service: &str,
+) -> Result<deployments::Model, DbErr> {
+    let now = Utc::now().naive_utc();
+    let dep = deployments::ActiveModel {
+        project_name: Set(project.to_string()),
+        service_name: Set(service.to_string()),
+        branch: Set("main".to_string()),
+        branch_slug: Set("main".to_string()),
+        environment: Set("prod".to_string()),
+        git_ref: Set("main".to_string()),
+        domain: Set(format!("{}-{}.deployments.test.com", service, project)),
+        status: Set(DeploymentStatus::Active),
+        dns_status: Set("pending".to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        last_activity: Set(now),
+        ..Default::default()
+    };
+
+    store.deployments().create(dep).await
+}
+
+async fn cleanup(store: &Store, project: &str) {
+    let _ = store.projects().delete(project).await;
+}
+
+#[tokio::test]
+async fn test_list_active_with_services_joins_on_project_and_name() {
+    let store = setup_test_db().await.expect("Failed to connect");
+    cleanup(&store, "test-deployment-services").await;
+
+    create_test_project(&store, "test-deployment-services")
+        .await
+        .expect("Failed to create project");
+    create_test_service(
+        &store,
+        "test-deployment-services",
+        "api",
+        ServiceType::Service,
+    )
+    .await
+    .expect("Failed to create service");
+    create_test_service(
+        &store,
+        "test-deployment-services",
+        "docs",
+        ServiceType::Static,
+    )
+    .await
+    .expect("Failed to create service");
+
+    let api = create_test_deployment(&store, "test-deployment-services", "api")
+        .await
+        .expect("Failed to create deployment");
+    let docs = create_test_deployment(&store, "test-deployment-services", "docs")
+        .await
+        .expect("Failed to create deployment");
+
+    let active = store
+        .deployments()
+        .list_active_with_services()
+        .await
+        .expect("Failed to list deployments");
+
+    for deployment in [&api, &docs] {
+        let (_, service) = active
+            .iter()
+            .find(|(d, _)| d.id == deployment.id)
+            .expect("Active deployment should be listed");
+        let service = service
+            .as_ref()
+            .expect("Deployment should have its service");
+
+        assert_eq!(service.project_name, "test-deployment-services");
+        assert_eq!(service.name, deployment.service_name);
+    }
+
+    cleanup(&store, "test-deployment-services").await;
+}
@@ -124,6 +124,56 @@ async fn test_allocate_multiple_ports() {
     cleanup(&store, "test-multi2", Some(port2)).await;
 }
 
+#[tokio::test]
+async fn test_replica_ports_released_together() {
+    let store = setup_test_db().await.expect("Failed to connect");
+
+    let deployment_id = create_test_deployment(&store, "test-replicas", "web", "main")
+        .await
+        .expect("Failed to create deployment");
+
+    let mut ports = Vec::new();
+    for _ in 0..3 {
+        ports.push(
+            store
+                .port_allocations()
+                .allocate_port(deployment_id, "test-replicas", "web", "main")
+                .await
+                .expect("Failed to allocate"),
+        );
+    }
+    ports.sort();
+
+    let allocated: Vec<i32> = store
+        .port_allocations()
+        .list_by_deployment(deployment_id)
+        .await
+        .expect("Failed to list")
+        .into_iter()
+        .map(|a| a.port)
+        .collect();
+    assert_eq!(allocated, ports);
+
+    let released = store
+        .port_allocations()
+        .release_deployment_ports(deployment_id)
+        .await
+        .expect("Failed to release");
+    assert_eq!(released, 3);
+
+    for port in ports {
+        assert!(
+            store
+                .port_allocations()
+                .is_port_available(port)
+                .await
+                .expect("Failed to check")
+        );
+    }
+
+    cleanup(&store, "test-replicas", None).await;
+}
+
 #[tokio::test]
 async fn test_port_reuse_after_release() {
     let store = setup_test_db().await.expect("Failed to connect");
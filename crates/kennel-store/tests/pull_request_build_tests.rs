@@ -0,0 +1,130 @@
+use entity::{projects, sea_orm_active_enums::*};
+use kennel_store::Store;
+use kennel_store::builds::PullRequestInfo;
+use sea_orm::{Database, DbErr, Set};
+
+async fn setup_test_db() -> Result<Store, DbErr> {
+    let db_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+
+    let db = Database::connect(&db_url).await?;
+    Ok(Store::new(db))
+}
+
+#[tokio::test]
+async fn test_pull_request_builds_record_metadata() {
+    let store = setup_test_db().await.unwrap();
+    let project = "pull-request-metadata-test";
+    let _ = store.projects().delete(project).await;
+
+    let created = store
+        .projects()
+        .create(projects::ActiveModel {
+            name: Set(project.to_string()),
+            repo_url: Set(format!("https://github.com/{}", project)),
+            repo_type: Set(RepoType::Github),
+            webhook_secret: Set("secret".to_string()),
+            default_branch: Set("main".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(created.fork_policy, ForkPolicy::Refuse);
+    assert_eq!(created.tag_pattern, None);
+
+    let build = store
+        .builds()
+        .create_pull_request_build(
+            project.to_string(),
+            "pr-4".to_string(),
+            "abc123".to_string(),
+            "mallory".to_string(),
+            PullRequestInfo {
+                head_ref: "patch-1".to_string(),
+                base_ref: "main".to_string(),
+                title: "Fix typo".to_string(),
+                from_fork: true,
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(build.git_ref, "pr-4");
+    assert_eq!(build.status, BuildStatus::Queued);
+    assert_eq!(build.pr_head_ref.as_deref(), Some("patch-1"));
+    assert_eq!(build.pr_base_ref.as_deref(), Some("main"));
+    assert_eq!(build.pr_title.as_deref(), Some("Fix typo"));
+    assert!(build.pr_from_fork);
+
+    let push = store
+        .builds()
+        .create_build(
+            project.to_string(),
+            "main".to_string(),
+            "def456".to_string(),
+            "alice".to_string(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(push.pr_head_ref, None);
+    assert!(!push.pr_from_fork);
+
+    store.projects().delete(project).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_commits_build_once_per_ref() {
+    let store = setup_test_db().await.unwrap();
+    let project = "build-per-ref-test";
+    let _ = store.projects().delete(project).await;
+
+    store
+        .projects()
+        .create(projects::ActiveModel {
+            name: Set(project.to_string()),
+            repo_url: Set(format!("https://github.com/{}", project)),
+            repo_type: Set(RepoType::Github),
+            webhook_secret: Set("secret".to_string()),
+            default_branch: Set("main".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let builds = store.builds();
+    let create = |git_ref: &str| {
+        builds.create_build(
+            project.to_string(),
+            git_ref.to_string(),
+            "abc123".to_string(),
+            "alice".to_string(),
+        )
+    };
+
+    let branch = create("main").await.unwrap();
+
+    // Tagging the commit gets a build of its own, named after the tag
+    let tag = create("refs/tags/v1.0.0").await.unwrap();
+    assert_ne!(tag.id, branch.id);
+    assert_eq!(tag.branch, "v1.0.0");
+
+    // The same commit on the same ref is still a duplicate
+    assert!(create("main").await.is_err());
+    assert_eq!(
+        builds
+            .find_by_ref_and_commit(project, "main", "abc123")
+            .await
+            .unwrap()
+            .map(|b| b.id),
+        Some(branch.id)
+    );
+    assert!(
+        builds
+            .find_by_ref_and_commit(project, "staging", "abc123")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    store.projects().delete(project).await.unwrap();
+}
@@ -0,0 +1,111 @@
+use entity::{projects, sea_orm_active_enums::*, webhook_deliveries};
+use kennel_store::Store;
+use sea_orm::{Database, DbErr, JsonValue, Set};
+
+async fn setup_test_db() -> Result<Store, DbErr> {
+    let db_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://127.0.0.1:5432/kennel".to_string());
+
+    let db = Database::connect(&db_url).await?;
+    Ok(Store::new(db))
+}
+
+fn delivery(
+    project: &str,
+    delivery_id: &str,
+    error: Option<&str>,
+    days_ago: i64,
+) -> webhook_deliveries::ActiveModel {
+    webhook_deliveries::ActiveModel {
+        project_name: Set(project.to_string()),
+        delivery_id: Set(Some(delivery_id.to_string())),
+        event_type: Set(Some("push".to_string())),
+        source_ip: Set("192.0.2.1".to_string()),
+        headers: Set(JsonValue::Object(Default::default())),
+        body: Set("{}".to_string()),
+        verified: Set(true),
+        error: Set(error.map(str::to_string)),
+        response_status: Set(if error.is_some() { 500 } else { 200 }),
+        created_at: Set((chrono::Utc::now() - chrono::Duration::days(days_ago)).naive_utc()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_webhook_deliveries_are_idempotent_and_pruned() {
+    let store = setup_test_db().await.unwrap();
+    let project = "webhook-delivery-test";
+    let _ = store.projects().delete(project).await;
+
+    store
+        .projects()
+        .create(projects::ActiveModel {
+            name: Set(project.to_string()),
+            repo_url: Set(format!("https://github.com/{}", project)),
+            repo_type: Set(RepoType::Github),
+            webhook_secret: Set("secret".to_string()),
+            default_branch: Set("main".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let deliveries = store.webhook_deliveries();
+
+    // Only one delivery can hold an id; copies logged unclaimed do not
+    let handled = deliveries
+        .claim(delivery(project, "handled", None, 0))
+        .await
+        .unwrap();
+    assert!(handled.unwrap().claimed);
+    assert!(
+        deliveries
+            .claim(delivery(project, "handled", None, 0))
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        !deliveries
+            .create(delivery(project, "handled", None, 0))
+            .await
+            .unwrap()
+            .claimed
+    );
+
+    // A failed delivery gives up its id, leaving the redelivery to be handled
+    let failed = deliveries
+        .claim(delivery(project, "failed", None, 0))
+        .await
+        .unwrap()
+        .unwrap();
+    let mut failed: webhook_deliveries::ActiveModel = failed.into();
+    failed.claimed = Set(false);
+    failed.error = Set(Some("Builder unavailable".to_string()));
+    deliveries.update(failed).await.unwrap();
+    assert!(
+        deliveries
+            .claim(delivery(project, "failed", None, 0))
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    let old = deliveries
+        .create(delivery(project, "old", None, 31))
+        .await
+        .unwrap();
+
+    let listed = deliveries.list_by_project(project, 10).await.unwrap();
+    assert_eq!(listed.len(), 5);
+    assert_eq!(listed[0].id, old.id);
+
+    assert!(deliveries.delete_older_than(30).await.unwrap() >= 1);
+    assert!(deliveries.find_by_id(old.id).await.unwrap().is_none());
+    assert_eq!(
+        deliveries.list_by_project(project, 10).await.unwrap().len(),
+        4
+    );
+
+    store.projects().delete(project).await.unwrap();
+}
@@ -30,9 +30,9 @@ pub enum WebhookError {
 
 pub type Result<T> = std::result::Result<T, WebhookError>;
 
-impl IntoResponse for WebhookError {
-    fn into_response(self) -> Response {
-        let status = match &self {
+impl WebhookError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
             WebhookError::InvalidSignature => StatusCode::UNAUTHORIZED,
             WebhookError::ProjectNotFound(_) => StatusCode::NOT_FOUND,
             WebhookError::InvalidPayload(_) => StatusCode::BAD_REQUEST,
@@ -40,8 +40,12 @@ impl IntoResponse for WebhookError {
             WebhookError::BuilderUnavailable => StatusCode::SERVICE_UNAVAILABLE,
             WebhookError::Store(_) => StatusCode::INTERNAL_SERVER_ERROR,
             WebhookError::Json(_) => StatusCode::BAD_REQUEST,
-        };
+        }
+    }
+}
 
-        (status, self.to_string()).into_response()
+impl IntoResponse for WebhookError {
+    fn into_response(self) -> Response {
+        (self.status_code(), self.to_string()).into_response()
     }
 }
@@ -3,19 +3,32 @@ use serde::Deserialize;
 #[derive(Debug, Clone)]
 pub enum WebhookEvent {
     Push {
+        /// Branch or, for tag pushes, tag name without the `refs/` prefix.
         git_ref: String,
         commit_sha: String,
         author: String,
         deleted: bool,
+        tag: bool,
     },
     PullRequest {
         action: String,
         pr_number: u64,
         commit_sha: String,
         author: String,
+        head_ref: String,
+        base_ref: String,
+        title: String,
+        from_fork: bool,
     },
+    /// Sent when a webhook is created to check it reaches kennel.
+    Ping,
+    /// An event kennel accepts but has nothing to do for, such as a branch
+    /// being created (the push that comes with it starts the build).
+    Ignored { event_type: String },
 }
 
+/// Gitea sends the same payloads as Forgejo, so both use the `Forgejo*`
+/// structs.
 #[derive(Debug, Deserialize)]
 pub struct ForgejoPushEvent {
     #[serde(rename = "ref")]
@@ -52,7 +65,9 @@ pub struct ForgejoPullRequestEvent {
 
 #[derive(Debug, Deserialize)]
 pub struct ForgejoPullRequest {
+    pub title: String,
     pub head: ForgejoHead,
+    pub base: ForgejoBase,
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,6 +78,16 @@ pub struct ForgejoSender {
 #[derive(Debug, Deserialize)]
 pub struct ForgejoHead {
     pub sha: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub repo_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgejoBase {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub repo_id: i64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -75,15 +100,85 @@ pub struct GitHubPullRequestEvent {
 
 #[derive(Debug, Deserialize)]
 pub struct GitHubPullRequest {
+    pub title: String,
     pub head: GitHubHead,
+    pub base: GitHubBase,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GitHubHead {
     pub sha: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// `null` once the fork the pull request came from is deleted.
+    pub repo: Option<GitHubRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubBase {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub repo: GitHubRepository,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubRepository {
+    pub id: i64,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GitHubSender {
     pub login: String,
 }
+
+/// Forgejo, Gitea and GitHub all describe a deleted branch or tag this way.
+#[derive(Debug, Deserialize)]
+pub struct DeleteEvent {
+    /// Branch or tag name, without a `refs/` prefix.
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// `branch` or `tag`.
+    pub ref_type: String,
+    pub sender: GitHubSender,
+}
+
+/// Also used for GitLab's tag push events, which have the same shape.
+#[derive(Debug, Deserialize)]
+pub struct GitLabPushEvent {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub after: String,
+    pub user_username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabMergeRequestEvent {
+    pub user: GitLabUser,
+    pub object_attributes: GitLabMergeRequest,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabUser {
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabMergeRequest {
+    /// The merge request's number within its project, as shown in the UI.
+    pub iid: u64,
+    /// Absent on some older GitLab versions' events.
+    pub action: Option<String>,
+    /// The previous head, only present when an update pushed new commits.
+    pub oldrev: Option<String>,
+    pub last_commit: GitLabCommit,
+    pub title: String,
+    pub source_branch: String,
+    pub target_branch: String,
+    pub source_project_id: i64,
+    pub target_project_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabCommit {
+    pub id: String,
+}
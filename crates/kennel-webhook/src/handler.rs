@@ -1,17 +1,43 @@
 use crate::WebhookConfig;
 use crate::error::{Result, WebhookError};
 use crate::events::WebhookEvent;
-use crate::parse::parse_webhook_event;
+use crate::parse::{
+    delivery_id, event_headers, event_type, headers_from_json, parse_webhook_event,
+};
 use crate::verify::verify_signature;
 use axum::{
     body::Bytes,
     extract::{ConnectInfo, Path, State},
     http::{HeaderMap, StatusCode},
 };
+use entity::sea_orm_active_enums::ForkPolicy;
+use entity::{builds, projects, webhook_deliveries};
+use kennel_config::{tag_matches, tag_ref};
+use kennel_store::builds::PullRequestInfo;
+use sea_orm::ActiveValue::Set;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tracing::{error, info, warn};
 
+/// How handling a delivery went, as recorded in the delivery log.
+struct Outcome {
+    verified: bool,
+    parse_error: Option<String>,
+    build_id: Option<i32>,
+    result: Result<StatusCode>,
+}
+
+impl Outcome {
+    fn handled(result: Result<StatusCode>, build_id: Option<i32>) -> Self {
+        Self {
+            verified: true,
+            parse_error: None,
+            build_id,
+            result,
+        }
+    }
+}
+
 pub async fn handle_webhook(
     State(config): State<Arc<WebhookConfig>>,
     Path(project_name): Path<String>,
@@ -29,12 +55,16 @@ pub async fn handle_webhook(
         .await?
         .ok_or_else(|| WebhookError::ProjectNotFound(project_name.clone()))?;
 
-    // Determine event type from headers for logging
-    let event_type = headers
-        .get("X-Forgejo-Event")
-        .or_else(|| headers.get("X-GitHub-Event"))
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("unknown");
+    let delivery = webhook_deliveries::ActiveModel {
+        project_name: Set(project.name.clone()),
+        delivery_id: Set(delivery_id(&headers).map(str::to_string)),
+        event_type: Set(event_type(&headers).map(str::to_string)),
+        source_ip: Set(addr.ip().to_string()),
+        headers: Set(event_headers(&headers)),
+        body: Set(String::from_utf8_lossy(&body).into_owned()),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
 
     // Verify signature
     if let Err(e) = verify_signature(&headers, &body, &project.webhook_secret) {
@@ -42,13 +72,199 @@ pub async fn handle_webhook(
             "Signature verification failed for project '{}', IP: {}, event type: {}",
             project_name,
             addr.ip(),
-            event_type
+            event_type(&headers).unwrap_or("unknown")
         );
-        return Err(e);
+        let outcome = Outcome {
+            verified: false,
+            parse_error: None,
+            build_id: None,
+            result: Err(e),
+        };
+        return log_delivery(&config, delivery, outcome).await;
     }
 
-    // Parse event
-    let event = parse_webhook_event(&headers, &body)?;
+    let Some(id) = delivery_id(&headers) else {
+        let outcome = process(&config, &project, &headers, &body).await;
+        return log_delivery(&config, delivery, outcome).await;
+    };
+
+    // Forges redeliver with the same id. The delivery is recorded as pending
+    // before it is handled, so once one copy holds the id the rest have
+    // nothing left to do, even while the first is still being handled
+    let mut pending = delivery.clone();
+    pending.verified = Set(true);
+    pending.response_status = Set(i32::from(StatusCode::ACCEPTED.as_u16()));
+    let Some(claimed) = config.store.webhook_deliveries().claim(pending).await? else {
+        info!(
+            "Delivery {} for project {} was already handled",
+            id, project_name
+        );
+        let outcome = Outcome::handled(Ok(StatusCode::OK), None);
+        return log_delivery(&config, delivery, outcome).await;
+    };
+
+    let outcome = process(&config, &project, &headers, &body).await;
+
+    // A failed delivery gives up the id so the forge's redelivery is handled
+    let mut delivery: webhook_deliveries::ActiveModel = claimed.into();
+    delivery.claimed = Set(outcome.result.is_ok());
+    log_delivery(&config, delivery, outcome).await
+}
+
+/// Run a recorded delivery through the handler again and record the result
+/// as a new delivery. Only deliveries whose signature checked out can be
+/// replayed, since the signature is not stored; the redelivery check is
+/// skipped, as replaying is asking for the delivery to be handled again.
+pub async fn replay_delivery(
+    config: &WebhookConfig,
+    original: &webhook_deliveries::Model,
+    replayed_by: &str,
+) -> Result<webhook_deliveries::Model> {
+    if !original.verified {
+        return Err(WebhookError::InvalidSignature);
+    }
+
+    let project = config
+        .store
+        .projects()
+        .find_by_name(&original.project_name)
+        .await?
+        .ok_or_else(|| WebhookError::ProjectNotFound(original.project_name.clone()))?;
+
+    info!(
+        "Replaying delivery {} for project {} on behalf of {}",
+        original.id, project.name, replayed_by
+    );
+
+    let headers = headers_from_json(&original.headers);
+    let outcome = process(config, &project, &headers, original.body.as_bytes()).await;
+
+    let delivery = webhook_deliveries::ActiveModel {
+        project_name: Set(project.name.clone()),
+        delivery_id: Set(original.delivery_id.clone()),
+        event_type: Set(original.event_type.clone()),
+        source_ip: Set(original.source_ip.clone()),
+        headers: Set(original.headers.clone()),
+        body: Set(original.body.clone()),
+        replay_of: Set(Some(original.id)),
+        replayed_by: Set(Some(replayed_by.to_string())),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    record(config, delivery, &outcome).await
+}
+
+/// Record a delivery, returning its outcome as the webhook response. Failing
+/// to record is logged rather than failing the delivery; a claimed delivery
+/// then stays pending, still holding its id.
+async fn log_delivery(
+    config: &WebhookConfig,
+    delivery: webhook_deliveries::ActiveModel,
+    outcome: Outcome,
+) -> Result<StatusCode> {
+    if let Err(e) = record(config, delivery, &outcome).await {
+        warn!("Failed to record webhook delivery: {}", e);
+    }
+
+    outcome.result
+}
+
+async fn record(
+    config: &WebhookConfig,
+    mut delivery: webhook_deliveries::ActiveModel,
+    outcome: &Outcome,
+) -> Result<webhook_deliveries::Model> {
+    let (status, error) = match &outcome.result {
+        Ok(status) => (*status, None),
+        Err(e) => (e.status_code(), Some(e.to_string())),
+    };
+
+    delivery.verified = Set(outcome.verified);
+    delivery.parse_error = Set(outcome.parse_error.clone());
+    // A parse failure is already in `parse_error`
+    delivery.error = Set(error.filter(|_| outcome.parse_error.is_none()));
+    delivery.response_status = Set(i32::from(status.as_u16()));
+    delivery.build_id = Set(outcome.build_id);
+
+    // Claimed deliveries were recorded as pending before being handled
+    let deliveries = config.store.webhook_deliveries();
+    if delivery.id.is_not_set() {
+        Ok(deliveries.create(delivery).await?)
+    } else {
+        Ok(deliveries.update(delivery).await?)
+    }
+}
+
+/// Parse a verified delivery and act on it.
+async fn process(
+    config: &WebhookConfig,
+    project: &projects::Model,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Outcome {
+    let event = match parse_webhook_event(headers, body) {
+        Ok(event) => event,
+        Err(e) => {
+            warn!(
+                "Failed to parse webhook for project {}: {}",
+                project.name, e
+            );
+            return Outcome {
+                verified: true,
+                parse_error: Some(e.to_string()),
+                build_id: None,
+                result: Err(e),
+            };
+        }
+    };
+
+    match handle_event(config, project, event).await {
+        Ok((status, build_id)) => Outcome::handled(Ok(status), build_id),
+        Err(e) => Outcome::handled(Err(e), None),
+    }
+}
+
+/// Queue `build` with the builder.
+async fn send_to_builder(config: &WebhookConfig, build: &builds::Model) -> Result<()> {
+    config
+        .build_tx
+        .send(build.id)
+        .await
+        .map_err(|_| WebhookError::BuilderUnavailable)
+}
+
+/// The build of `commit_sha` on `git_ref`, if the commit was already built
+/// there.
+async fn existing_build(
+    config: &WebhookConfig,
+    project: &projects::Model,
+    git_ref: &str,
+    commit_sha: &str,
+) -> Result<Option<builds::Model>> {
+    let build = config
+        .store
+        .builds()
+        .find_by_ref_and_commit(&project.name, git_ref, commit_sha)
+        .await?;
+
+    if let Some(build) = &build {
+        info!(
+            "Build {} already exists for {}/{}/{}",
+            build.id, project.name, git_ref, commit_sha
+        );
+    }
+
+    Ok(build)
+}
+
+/// Act on an event, returning the response status and the build it queued.
+async fn handle_event(
+    config: &WebhookConfig,
+    project: &projects::Model,
+    event: WebhookEvent,
+) -> Result<(StatusCode, Option<i32>)> {
+    let project_name = &project.name;
 
     match event {
         WebhookEvent::Push {
@@ -56,16 +272,25 @@ pub async fn handle_webhook(
             commit_sha,
             author,
             deleted,
+            tag,
         } => {
+            let kind = if tag { "Tag" } else { "Branch" };
+            let name = git_ref;
+            let git_ref = if tag { tag_ref(&name) } else { name.clone() };
+
             if deleted {
                 info!(
-                    "Branch deleted: {}/{}, marking deployments for teardown",
-                    project_name, git_ref
+                    "{} deleted: {}/{}, marking deployments for teardown",
+                    kind, project_name, name
                 );
                 let ids = config
                     .store
                     .deployments()
-                    .mark_for_teardown(&project.name, &git_ref)
+                    .mark_for_teardown(
+                        &project.name,
+                        &git_ref,
+                        &format!("{} deleted by {}", kind, author),
+                    )
                     .await?;
                 for id in ids {
                     if let Err(e) = config.teardown_tx.send(id).await {
@@ -75,11 +300,31 @@ pub async fn handle_webhook(
                         );
                     }
                 }
-                return Ok(StatusCode::ACCEPTED);
+                return Ok((StatusCode::ACCEPTED, None));
+            }
+
+            // Only tags matching the project's pattern are release builds
+            if tag
+                && !project
+                    .tag_pattern
+                    .as_deref()
+                    .is_some_and(|pattern| tag_matches(pattern, &name))
+            {
+                info!(
+                    "Ignoring tag {}/{}: it does not match the project's tag pattern",
+                    project_name, name
+                );
+                return Ok((StatusCode::ACCEPTED, None));
+            }
+
+            // Replayed deliveries and merges of an already built commit
+            // have nothing new to build
+            if let Some(build) = existing_build(config, project, &git_ref, &commit_sha).await? {
+                return Ok((StatusCode::OK, Some(build.id)));
             }
 
             // Create build record
-            let build = match config
+            let build = config
                 .store
                 .builds()
                 .create_build(
@@ -88,20 +333,7 @@ pub async fn handle_webhook(
                     commit_sha.clone(),
                     author,
                 )
-                .await
-            {
-                Ok(b) => b,
-                Err(e) => {
-                    if e.to_string().contains("duplicate") || e.to_string().contains("unique") {
-                        info!(
-                            "Build already exists for {}/{}/{}",
-                            project_name, git_ref, commit_sha
-                        );
-                        return Ok(StatusCode::OK);
-                    }
-                    return Err(e.into());
-                }
-            };
+                .await?;
 
             info!(
                 "Created build {} for {}/{}/{}",
@@ -109,50 +341,57 @@ pub async fn handle_webhook(
             );
 
             // Send to builder
-            config
-                .build_tx
-                .send(build.id)
-                .await
-                .map_err(|_| WebhookError::BuilderUnavailable)?;
+            send_to_builder(config, &build).await?;
 
-            Ok(StatusCode::OK)
+            Ok((StatusCode::OK, Some(build.id)))
         }
         WebhookEvent::PullRequest {
             action,
             pr_number,
             commit_sha,
             author,
+            head_ref,
+            base_ref,
+            title,
+            from_fork,
         } => {
             match action.as_str() {
                 "opened" | "synchronize" | "synchronized" | "reopened" => {
                     let git_ref = format!("pr-{}", pr_number);
 
+                    if from_fork && project.fork_policy == ForkPolicy::Refuse {
+                        info!(
+                            "Refusing to build {}/PR#{} from a fork of the repository",
+                            project_name, pr_number
+                        );
+                        return Ok((StatusCode::ACCEPTED, None));
+                    }
+
+                    // Reopening a pull request at a commit already built
+                    // keeps that build
+                    if let Some(build) =
+                        existing_build(config, project, &git_ref, &commit_sha).await?
+                    {
+                        return Ok((StatusCode::OK, Some(build.id)));
+                    }
+
                     // Create build record
-                    let build = match config
+                    let build = config
                         .store
                         .builds()
-                        .create_build(
+                        .create_pull_request_build(
                             project.name.clone(),
                             git_ref.clone(),
                             commit_sha.clone(),
                             author,
+                            PullRequestInfo {
+                                head_ref,
+                                base_ref,
+                                title,
+                                from_fork,
+                            },
                         )
-                        .await
-                    {
-                        Ok(b) => b,
-                        Err(e) => {
-                            if e.to_string().contains("duplicate")
-                                || e.to_string().contains("unique")
-                            {
-                                info!(
-                                    "Build already exists for {}/{}/{}",
-                                    project_name, git_ref, commit_sha
-                                );
-                                return Ok(StatusCode::OK);
-                            }
-                            return Err(e.into());
-                        }
-                    };
+                        .await?;
 
                     info!(
                         "Created PR build {} for {}/PR#{}/{}",
@@ -160,13 +399,9 @@ pub async fn handle_webhook(
                     );
 
                     // Send to builder
-                    config
-                        .build_tx
-                        .send(build.id)
-                        .await
-                        .map_err(|_| WebhookError::BuilderUnavailable)?;
+                    send_to_builder(config, &build).await?;
 
-                    Ok(StatusCode::OK)
+                    Ok((StatusCode::OK, Some(build.id)))
                 }
                 "closed" => {
                     let git_ref = format!("pr-{}", pr_number);
@@ -177,7 +412,11 @@ pub async fn handle_webhook(
                     let ids = config
                         .store
                         .deployments()
-                        .mark_for_teardown(&project.name, &git_ref)
+                        .mark_for_teardown(
+                            &project.name,
+                            &git_ref,
+                            &format!("Pull request #{} closed", pr_number),
+                        )
                         .await?;
                     for id in ids {
                         if let Err(e) = config.teardown_tx.send(id).await {
@@ -187,13 +426,29 @@ pub async fn handle_webhook(
                             );
                         }
                     }
-                    Ok(StatusCode::ACCEPTED)
+
+                    if let Some(forge) = config.forge.clone() {
+                        let project_name = project.name.clone();
+                        tokio::spawn(async move {
+                            forge.mark_preview_torn_down(&project_name, pr_number).await;
+                        });
+                    }
+
+                    Ok((StatusCode::ACCEPTED, None))
                 }
                 _ => {
                     warn!("Ignoring PR action: {}", action);
-                    Ok(StatusCode::ACCEPTED)
+                    Ok((StatusCode::ACCEPTED, None))
                 }
             }
         }
+        WebhookEvent::Ping => {
+            info!("Webhook for project {} is reachable", project_name);
+            Ok((StatusCode::OK, None))
+        }
+        WebhookEvent::Ignored { event_type } => {
+            info!("Ignoring {} event for project {}", event_type, project_name);
+            Ok((StatusCode::ACCEPTED, None))
+        }
     }
 }
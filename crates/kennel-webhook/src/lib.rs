@@ -6,8 +6,10 @@ mod verify;
 
 pub use error::{Result, WebhookError};
 pub use events::WebhookEvent;
+pub use handler::replay_delivery;
 
 use axum::{Router, routing::post};
+use kennel_forge::StatusReporter;
 use kennel_store::Store;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -17,6 +19,8 @@ pub struct WebhookConfig {
     pub store: Arc<Store>,
     pub build_tx: mpsc::Sender<i32>,
     pub teardown_tx: mpsc::Sender<i32>,
+    /// Marks pull request preview comments torn down, when configured.
+    pub forge: Option<Arc<StatusReporter>>,
 }
 
 pub fn router(config: WebhookConfig) -> Router {
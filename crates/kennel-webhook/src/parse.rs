@@ -1,99 +1,244 @@
 use crate::error::{Result, WebhookError};
 use crate::events::*;
-use axum::http::HeaderMap;
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
 
 const ZERO_SHA: &str = "0000000000000000000000000000000000000000";
 
+/// The forge a webhook came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Forge {
+    Forgejo,
+    Gitea,
+    GitHub,
+    GitLab,
+}
+
+/// Event headers in the order they are checked. Forgejo also sends
+/// `X-Gitea-Event`, so it has to come before Gitea.
+const EVENT_HEADERS: [(&str, Forge); 4] = [
+    ("X-Forgejo-Event", Forge::Forgejo),
+    ("X-Gitea-Event", Forge::Gitea),
+    ("X-GitHub-Event", Forge::GitHub),
+    ("X-Gitlab-Event", Forge::GitLab),
+];
+
+fn detect_forge(headers: &HeaderMap) -> Option<(Forge, &HeaderValue)> {
+    EVENT_HEADERS
+        .iter()
+        .find_map(|(name, forge)| headers.get(*name).map(|value| (*forge, value)))
+}
+
+/// The raw event type a forge sent, for logging.
+pub fn event_type(headers: &HeaderMap) -> Option<&str> {
+    detect_forge(headers).and_then(|(_, value)| value.to_str().ok())
+}
+
+/// Headers carrying a delivery's id, which stays the same when a forge
+/// redelivers it. GitLab sends `Idempotency-Key` from 17.4 on.
+const DELIVERY_HEADERS: [&str; 5] = [
+    "X-GitHub-Delivery",
+    "X-Forgejo-Delivery",
+    "X-Gitea-Delivery",
+    "Idempotency-Key",
+    "X-Gitlab-Event-UUID",
+];
+
+/// The id the forge gave this delivery, if it sent one.
+pub fn delivery_id(headers: &HeaderMap) -> Option<&str> {
+    DELIVERY_HEADERS
+        .iter()
+        .find_map(|name| headers.get(*name))
+        .and_then(|value| value.to_str().ok())
+}
+
+/// The event headers of a delivery as a JSON object, enough to parse its body
+/// again when it is replayed.
+pub fn event_headers(headers: &HeaderMap) -> serde_json::Value {
+    let headers: serde_json::Map<_, _> = EVENT_HEADERS
+        .iter()
+        .filter_map(|(name, _)| {
+            let value = headers.get(*name)?.to_str().ok()?;
+            Some((name.to_string(), value.into()))
+        })
+        .collect();
+
+    headers.into()
+}
+
+/// Rebuild the headers [`event_headers`] recorded.
+pub fn headers_from_json(value: &serde_json::Value) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in value.as_object().into_iter().flatten() {
+        if let (Ok(name), Some(Ok(value))) = (
+            name.parse::<HeaderName>(),
+            value.as_str().map(HeaderValue::from_str),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    headers
+}
+
 pub fn parse_webhook_event(headers: &HeaderMap, body: &[u8]) -> Result<WebhookEvent> {
     // Determine platform from event header
-    let event_type = if let Some(forgejo_event) = headers.get("X-Forgejo-Event") {
-        forgejo_event
-            .to_str()
-            .map_err(|_| WebhookError::InvalidPayload("Invalid event header".to_string()))?
-    } else if let Some(github_event) = headers.get("X-GitHub-Event") {
-        github_event
-            .to_str()
-            .map_err(|_| WebhookError::InvalidPayload("Invalid event header".to_string()))?
-    } else {
-        return Err(WebhookError::MissingHeader(
-            "X-Forgejo-Event or X-GitHub-Event",
-        ));
-    };
-
-    match event_type {
-        "push" => parse_push_event(headers, body),
-        "pull_request" => parse_pull_request_event(headers, body),
-        _ => Err(WebhookError::InvalidPayload(format!(
-            "Unsupported event type: {}",
-            event_type
-        ))),
+    let (forge, event_type) = detect_forge(headers).ok_or(WebhookError::MissingHeader(
+        "X-Forgejo-Event, X-Gitea-Event, X-GitHub-Event or X-Gitlab-Event",
+    ))?;
+    let event_type = event_type
+        .to_str()
+        .map_err(|_| WebhookError::InvalidPayload("Invalid event header".to_string()))?;
+
+    match (forge, event_type) {
+        (Forge::GitLab, "Push Hook" | "Tag Push Hook") => parse_gitlab_push_event(body),
+        (Forge::GitLab, "Merge Request Hook") => parse_gitlab_merge_request_event(body),
+        (Forge::GitLab, _) => Err(unsupported(event_type)),
+        (_, "push") => parse_push_event(forge, body),
+        (_, "pull_request") => parse_pull_request_event(forge, body),
+        (_, "delete") => parse_delete_event(body),
+        (_, "ping") => Ok(WebhookEvent::Ping),
+        (_, "create") => Ok(WebhookEvent::Ignored {
+            event_type: event_type.to_string(),
+        }),
+        _ => Err(unsupported(event_type)),
     }
 }
 
-fn parse_push_event(headers: &HeaderMap, body: &[u8]) -> Result<WebhookEvent> {
-    let is_forgejo = headers.contains_key("X-Forgejo-Event");
+fn unsupported(event_type: &str) -> WebhookError {
+    WebhookError::InvalidPayload(format!("Unsupported event type: {}", event_type))
+}
 
-    if is_forgejo {
-        let event: ForgejoPushEvent = serde_json::from_slice(body)?;
+/// Split a full ref into its branch or tag name and whether it is a tag.
+fn split_ref(git_ref: &str) -> (String, bool) {
+    if let Some(tag) = git_ref.strip_prefix("refs/tags/") {
+        return (tag.to_string(), true);
+    }
 
-        let git_ref = event
-            .git_ref
-            .strip_prefix("refs/heads/")
-            .unwrap_or(&event.git_ref)
-            .to_string();
+    let branch = git_ref.strip_prefix("refs/heads/").unwrap_or(git_ref);
+    (branch.to_string(), false)
+}
+
+fn parse_push_event(forge: Forge, body: &[u8]) -> Result<WebhookEvent> {
+    if forge == Forge::GitHub {
+        let event: GitHubPushEvent = serde_json::from_slice(body)?;
 
+        let (git_ref, tag) = split_ref(&event.git_ref);
         let deleted = event.after == ZERO_SHA;
 
         Ok(WebhookEvent::Push {
             git_ref,
             commit_sha: event.after,
-            author: event.pusher.username,
+            author: event.pusher.name,
             deleted,
+            tag,
         })
     } else {
-        let event: GitHubPushEvent = serde_json::from_slice(body)?;
-
-        let git_ref = event
-            .git_ref
-            .strip_prefix("refs/heads/")
-            .unwrap_or(&event.git_ref)
-            .to_string();
+        let event: ForgejoPushEvent = serde_json::from_slice(body)?;
 
+        let (git_ref, tag) = split_ref(&event.git_ref);
         let deleted = event.after == ZERO_SHA;
 
         Ok(WebhookEvent::Push {
             git_ref,
             commit_sha: event.after,
-            author: event.pusher.name,
+            author: event.pusher.username,
             deleted,
+            tag,
         })
     }
 }
 
-fn parse_pull_request_event(headers: &HeaderMap, body: &[u8]) -> Result<WebhookEvent> {
-    let is_forgejo = headers.contains_key("X-Forgejo-Event");
-
-    if is_forgejo {
-        let event: ForgejoPullRequestEvent = serde_json::from_slice(body)?;
+fn parse_pull_request_event(forge: Forge, body: &[u8]) -> Result<WebhookEvent> {
+    if forge == Forge::GitHub {
+        let event: GitHubPullRequestEvent = serde_json::from_slice(body)?;
+        let pr = event.pull_request;
+        // A pull request whose fork was deleted has no head repository
+        let from_fork = pr.head.repo.map(|repo| repo.id) != Some(pr.base.repo.id);
 
         Ok(WebhookEvent::PullRequest {
             action: event.action,
             pr_number: event.number,
-            commit_sha: event.pull_request.head.sha,
+            commit_sha: pr.head.sha,
             author: event.sender.login,
+            head_ref: pr.head.git_ref,
+            base_ref: pr.base.git_ref,
+            title: pr.title,
+            from_fork,
         })
     } else {
-        let event: GitHubPullRequestEvent = serde_json::from_slice(body)?;
+        let event: ForgejoPullRequestEvent = serde_json::from_slice(body)?;
+        let pr = event.pull_request;
 
         Ok(WebhookEvent::PullRequest {
             action: event.action,
             pr_number: event.number,
-            commit_sha: event.pull_request.head.sha,
+            commit_sha: pr.head.sha,
             author: event.sender.login,
+            head_ref: pr.head.git_ref,
+            base_ref: pr.base.git_ref,
+            title: pr.title,
+            from_fork: pr.head.repo_id != pr.base.repo_id,
         })
     }
 }
 
+fn parse_gitlab_push_event(body: &[u8]) -> Result<WebhookEvent> {
+    let event: GitLabPushEvent = serde_json::from_slice(body)?;
+
+    let (git_ref, tag) = split_ref(&event.git_ref);
+    let deleted = event.after == ZERO_SHA;
+
+    Ok(WebhookEvent::Push {
+        git_ref,
+        commit_sha: event.after,
+        author: event.user_username,
+        deleted,
+        tag,
+    })
+}
+
+fn parse_gitlab_merge_request_event(body: &[u8]) -> Result<WebhookEvent> {
+    let event: GitLabMergeRequestEvent = serde_json::from_slice(body)?;
+    let mr = event.object_attributes;
+
+    // Map GitLab's actions onto the GitHub names the handler acts on. An
+    // update without `oldrev` only changed the title, labels or the like.
+    let action = match mr.action.as_deref() {
+        Some("open") => "opened".to_string(),
+        Some("reopen") => "reopened".to_string(),
+        Some("update") if mr.oldrev.is_some() => "synchronize".to_string(),
+        Some("update") => "edited".to_string(),
+        Some("close") | Some("merge") => "closed".to_string(),
+        Some(other) => other.to_string(),
+        None => "unknown".to_string(),
+    };
+
+    Ok(WebhookEvent::PullRequest {
+        action,
+        pr_number: mr.iid,
+        commit_sha: mr.last_commit.id,
+        author: event.user.username,
+        head_ref: mr.source_branch,
+        base_ref: mr.target_branch,
+        title: mr.title,
+        from_fork: mr.source_project_id != mr.target_project_id,
+    })
+}
+
+/// A deleted branch or tag tears down its deployments like a deletion push
+/// does. Forges that send both are fine, since only live deployments are
+/// marked.
+fn parse_delete_event(body: &[u8]) -> Result<WebhookEvent> {
+    let event: DeleteEvent = serde_json::from_slice(body)?;
+
+    Ok(WebhookEvent::Push {
+        git_ref: event.git_ref,
+        commit_sha: ZERO_SHA.to_string(),
+        author: event.sender.login,
+        deleted: true,
+        tag: event.ref_type == "tag",
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,11 +265,13 @@ mod tests {
                 commit_sha,
                 author,
                 deleted,
+                tag,
             } => {
                 assert_eq!(git_ref, "main");
                 assert_eq!(commit_sha, "def456");
                 assert_eq!(author, "alice");
                 assert!(!deleted);
+                assert!(!tag);
             }
             _ => panic!("Expected Push event"),
         }
@@ -152,11 +299,13 @@ mod tests {
                 commit_sha,
                 author,
                 deleted,
+                tag,
             } => {
                 assert_eq!(git_ref, "feature-branch");
                 assert_eq!(commit_sha, "def456");
                 assert_eq!(author, "bob");
                 assert!(!deleted);
+                assert!(!tag);
             }
             _ => panic!("Expected Push event"),
         }
@@ -192,9 +341,15 @@ mod tests {
             "action": "opened",
             "number": 42,
             "pull_request": {
+                "title": "Add feature",
                 "head": {
                     "sha": "abc123",
-                    "ref": "feature-branch"
+                    "ref": "feature-branch",
+                    "repo_id": 1
+                },
+                "base": {
+                    "ref": "main",
+                    "repo_id": 1
                 }
             },
             "sender": {
@@ -212,11 +367,19 @@ mod tests {
                 action,
                 pr_number,
                 commit_sha,
+                head_ref,
+                base_ref,
+                title,
+                from_fork,
                 ..
             } => {
                 assert_eq!(action, "opened");
                 assert_eq!(pr_number, 42);
                 assert_eq!(commit_sha, "abc123");
+                assert_eq!(head_ref, "feature-branch");
+                assert_eq!(base_ref, "main");
+                assert_eq!(title, "Add feature");
+                assert!(!from_fork);
             }
             _ => panic!("Expected PullRequest event"),
         }
@@ -228,8 +391,21 @@ mod tests {
             "action": "synchronize",
             "number": 99,
             "pull_request": {
+                "title": "Fix login",
                 "head": {
-                    "sha": "xyz789"
+                    "sha": "xyz789",
+                    "ref": "fix-login",
+                    "repo": {
+                        "id": 2,
+                        "full_name": "charlie/app"
+                    }
+                },
+                "base": {
+                    "ref": "main",
+                    "repo": {
+                        "id": 1,
+                        "full_name": "owner/app"
+                    }
                 }
             },
             "sender": {
@@ -248,13 +424,487 @@ mod tests {
                 pr_number,
                 commit_sha,
                 author,
+                from_fork,
+                ..
             } => {
                 assert_eq!(action, "synchronize");
                 assert_eq!(pr_number, 99);
                 assert_eq!(commit_sha, "xyz789");
                 assert_eq!(author, "charlie");
+                assert!(from_fork);
             }
             _ => panic!("Expected PullRequest event"),
         }
     }
+
+    #[test]
+    fn test_parse_gitea_push_event() {
+        let body = r#"{
+            "ref": "refs/heads/main",
+            "before": "abc123",
+            "after": "def456",
+            "pusher": {
+                "login": "dana",
+                "username": "dana"
+            }
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitea-Event", "push".parse().unwrap());
+
+        let event = parse_webhook_event(&headers, body.as_bytes()).unwrap();
+
+        match event {
+            WebhookEvent::Push {
+                git_ref,
+                commit_sha,
+                author,
+                deleted,
+                tag,
+            } => {
+                assert_eq!(git_ref, "main");
+                assert_eq!(commit_sha, "def456");
+                assert_eq!(author, "dana");
+                assert!(!deleted);
+                assert!(!tag);
+            }
+            _ => panic!("Expected Push event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_gitea_pr_event() {
+        let body = r#"{
+            "action": "synchronized",
+            "number": 7,
+            "pull_request": {
+                "title": "Fix typo",
+                "head": {
+                    "sha": "fed321",
+                    "ref": "fix-typo",
+                    "repo_id": 3
+                },
+                "base": {
+                    "ref": "main",
+                    "repo_id": 3
+                }
+            },
+            "sender": {
+                "login": "dana"
+            }
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitea-Event", "pull_request".parse().unwrap());
+
+        let event = parse_webhook_event(&headers, body.as_bytes()).unwrap();
+
+        match event {
+            WebhookEvent::PullRequest {
+                action,
+                pr_number,
+                commit_sha,
+                author,
+                ..
+            } => {
+                assert_eq!(action, "synchronized");
+                assert_eq!(pr_number, 7);
+                assert_eq!(commit_sha, "fed321");
+                assert_eq!(author, "dana");
+            }
+            _ => panic!("Expected PullRequest event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_gitlab_push_event() {
+        let body = r#"{
+            "object_kind": "push",
+            "event_name": "push",
+            "before": "abc123",
+            "after": "def456",
+            "ref": "refs/heads/feature-branch",
+            "user_name": "Erin Example",
+            "user_username": "erin",
+            "project": {
+                "path_with_namespace": "group/app"
+            }
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitlab-Event", "Push Hook".parse().unwrap());
+
+        let event = parse_webhook_event(&headers, body.as_bytes()).unwrap();
+
+        match event {
+            WebhookEvent::Push {
+                git_ref,
+                commit_sha,
+                author,
+                deleted,
+                tag,
+            } => {
+                assert_eq!(git_ref, "feature-branch");
+                assert_eq!(commit_sha, "def456");
+                assert_eq!(author, "erin");
+                assert!(!deleted);
+                assert!(!tag);
+            }
+            _ => panic!("Expected Push event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_gitlab_branch_deletion() {
+        let body = r#"{
+            "object_kind": "push",
+            "before": "abc123",
+            "after": "0000000000000000000000000000000000000000",
+            "ref": "refs/heads/old-branch",
+            "user_username": "erin"
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitlab-Event", "Push Hook".parse().unwrap());
+
+        let event = parse_webhook_event(&headers, body.as_bytes()).unwrap();
+
+        match event {
+            WebhookEvent::Push { deleted, .. } => {
+                assert!(deleted);
+            }
+            _ => panic!("Expected Push event"),
+        }
+    }
+
+    fn gitlab_merge_request(action: &str, oldrev: Option<&str>) -> String {
+        let oldrev = oldrev
+            .map(|sha| format!(r#""oldrev": "{}","#, sha))
+            .unwrap_or_default();
+
+        format!(
+            r#"{{
+                "object_kind": "merge_request",
+                "event_type": "merge_request",
+                "user": {{
+                    "name": "Erin Example",
+                    "username": "erin"
+                }},
+                "object_attributes": {{
+                    "id": 9001,
+                    "iid": 12,
+                    "action": "{}",
+                    {}
+                    "title": "Add feature",
+                    "source_branch": "feature-branch",
+                    "target_branch": "main",
+                    "source_project_id": 5,
+                    "target_project_id": 5,
+                    "last_commit": {{
+                        "id": "abc123"
+                    }}
+                }}
+            }}"#,
+            action, oldrev
+        )
+    }
+
+    fn parse_gitlab_merge_request(body: &str) -> WebhookEvent {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitlab-Event", "Merge Request Hook".parse().unwrap());
+        parse_webhook_event(&headers, body.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_gitlab_merge_request_event() {
+        let event = parse_gitlab_merge_request(&gitlab_merge_request("open", None));
+
+        match event {
+            WebhookEvent::PullRequest {
+                action,
+                pr_number,
+                commit_sha,
+                author,
+                head_ref,
+                base_ref,
+                title,
+                from_fork,
+            } => {
+                assert_eq!(action, "opened");
+                assert_eq!(pr_number, 12);
+                assert_eq!(commit_sha, "abc123");
+                assert_eq!(author, "erin");
+                assert_eq!(head_ref, "feature-branch");
+                assert_eq!(base_ref, "main");
+                assert_eq!(title, "Add feature");
+                assert!(!from_fork);
+            }
+            _ => panic!("Expected PullRequest event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_gitlab_merge_request_actions() {
+        let action = |body: String| match parse_gitlab_merge_request(&body) {
+            WebhookEvent::PullRequest { action, .. } => action,
+            _ => panic!("Expected PullRequest event"),
+        };
+
+        assert_eq!(
+            action(gitlab_merge_request("update", Some("def456"))),
+            "synchronize"
+        );
+        assert_eq!(action(gitlab_merge_request("update", None)), "edited");
+        assert_eq!(action(gitlab_merge_request("reopen", None)), "reopened");
+        assert_eq!(action(gitlab_merge_request("close", None)), "closed");
+        assert_eq!(action(gitlab_merge_request("merge", None)), "closed");
+        assert_eq!(action(gitlab_merge_request("approved", None)), "approved");
+    }
+
+    #[test]
+    fn test_forgejo_header_wins_over_gitea() {
+        let body = r#"{
+            "ref": "refs/heads/main",
+            "after": "def456",
+            "pusher": {
+                "username": "alice"
+            }
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forgejo-Event", "push".parse().unwrap());
+        headers.insert("X-Gitea-Event", "push".parse().unwrap());
+
+        assert_eq!(event_type(&headers), Some("push"));
+        assert!(parse_webhook_event(&headers, body.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_gitlab_event() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitlab-Event", "Pipeline Hook".parse().unwrap());
+
+        assert!(matches!(
+            parse_webhook_event(&headers, b"{}"),
+            Err(WebhookError::InvalidPayload(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_tag_push() {
+        let body = r#"{
+            "ref": "refs/tags/v1.0.0",
+            "before": "0000000000000000000000000000000000000000",
+            "after": "def456",
+            "pusher": {
+                "name": "bob"
+            }
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-GitHub-Event", "push".parse().unwrap());
+
+        let event = parse_webhook_event(&headers, body.as_bytes()).unwrap();
+
+        match event {
+            WebhookEvent::Push {
+                git_ref,
+                deleted,
+                tag,
+                ..
+            } => {
+                assert_eq!(git_ref, "v1.0.0");
+                assert!(!deleted);
+                assert!(tag);
+            }
+            _ => panic!("Expected Push event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_gitlab_tag_push() {
+        let body = r#"{
+            "object_kind": "tag_push",
+            "before": "0000000000000000000000000000000000000000",
+            "after": "def456",
+            "ref": "refs/tags/v2.1",
+            "user_username": "erin"
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitlab-Event", "Tag Push Hook".parse().unwrap());
+
+        let event = parse_webhook_event(&headers, body.as_bytes()).unwrap();
+
+        match event {
+            WebhookEvent::Push { git_ref, tag, .. } => {
+                assert_eq!(git_ref, "v2.1");
+                assert!(tag);
+            }
+            _ => panic!("Expected Push event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_pr_from_deleted_fork() {
+        let body = r#"{
+            "action": "synchronize",
+            "number": 100,
+            "pull_request": {
+                "title": "Abandoned change",
+                "head": {
+                    "sha": "xyz789",
+                    "ref": "patch-1",
+                    "repo": null
+                },
+                "base": {
+                    "ref": "main",
+                    "repo": {
+                        "id": 1
+                    }
+                }
+            },
+            "sender": {
+                "login": "charlie"
+            }
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-GitHub-Event", "pull_request".parse().unwrap());
+
+        match parse_webhook_event(&headers, body.as_bytes()).unwrap() {
+            WebhookEvent::PullRequest { from_fork, .. } => assert!(from_fork),
+            _ => panic!("Expected PullRequest event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_forgejo_pr_from_fork() {
+        let body = r#"{
+            "action": "opened",
+            "number": 8,
+            "pull_request": {
+                "title": "Drive-by fix",
+                "head": {
+                    "sha": "abc123",
+                    "ref": "main",
+                    "repo_id": 9
+                },
+                "base": {
+                    "ref": "main",
+                    "repo_id": 1
+                }
+            },
+            "sender": {
+                "login": "mallory"
+            }
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forgejo-Event", "pull_request".parse().unwrap());
+
+        match parse_webhook_event(&headers, body.as_bytes()).unwrap() {
+            WebhookEvent::PullRequest { from_fork, .. } => assert!(from_fork),
+            _ => panic!("Expected PullRequest event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_ping_event() {
+        let body = r#"{
+            "zen": "Keep it logically awesome.",
+            "hook_id": 123
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-GitHub-Event", "ping".parse().unwrap());
+
+        assert!(matches!(
+            parse_webhook_event(&headers, body.as_bytes()).unwrap(),
+            WebhookEvent::Ping
+        ));
+    }
+
+    #[test]
+    fn test_parse_create_event_is_ignored() {
+        let body = r#"{
+            "ref": "feature-branch",
+            "ref_type": "branch",
+            "sender": {
+                "login": "alice"
+            }
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forgejo-Event", "create".parse().unwrap());
+
+        match parse_webhook_event(&headers, body.as_bytes()).unwrap() {
+            WebhookEvent::Ignored { event_type } => assert_eq!(event_type, "create"),
+            _ => panic!("Expected Ignored event"),
+        }
+    }
+
+    #[test]
+    fn test_parse_delete_event() {
+        let body = r#"{
+            "ref": "old-branch",
+            "ref_type": "branch",
+            "sender": {
+                "login": "alice"
+            }
+        }"#;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitea-Event", "delete".parse().unwrap());
+
+        match parse_webhook_event(&headers, body.as_bytes()).unwrap() {
+            WebhookEvent::Push {
+                git_ref,
+                author,
+                deleted,
+                tag,
+                ..
+            } => {
+                assert_eq!(git_ref, "old-branch");
+                assert_eq!(author, "alice");
+                assert!(deleted);
+                assert!(!tag);
+            }
+            _ => panic!("Expected Push event"),
+        }
+    }
+
+    #[test]
+    fn test_delivery_id() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(delivery_id(&headers), None);
+
+        headers.insert(
+            "X-GitHub-Delivery",
+            "72d3162e-cc78-11e3-81ab-4c9367dc0958".parse().unwrap(),
+        );
+        assert_eq!(
+            delivery_id(&headers),
+            Some("72d3162e-cc78-11e3-81ab-4c9367dc0958")
+        );
+    }
+
+    #[test]
+    fn test_event_headers_round_trip() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forgejo-Event", "push".parse().unwrap());
+        headers.insert("X-Gitea-Event", "push".parse().unwrap());
+        headers.insert("X-Forgejo-Signature", "abc123".parse().unwrap());
+
+        let recorded = event_headers(&headers);
+        assert_eq!(
+            recorded,
+            serde_json::json!({ "X-Forgejo-Event": "push", "X-Gitea-Event": "push" })
+        );
+
+        let restored = headers_from_json(&recorded);
+        assert_eq!(event_type(&restored), Some("push"));
+        assert!(restored.contains_key("X-Forgejo-Event"));
+        assert!(!restored.contains_key("X-Forgejo-Signature"));
+    }
 }
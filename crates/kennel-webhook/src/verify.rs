@@ -2,6 +2,7 @@ use crate::error::{Result, WebhookError};
 use axum::http::HeaderMap;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
+use subtle::ConstantTimeEq;
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -14,6 +15,15 @@ pub fn verify_signature(headers: &HeaderMap, body: &[u8], secret: &str) -> Resul
         return verify_forgejo_signature(body, secret, sig);
     }
 
+    // Gitea signs like Forgejo; Forgejo sends both headers, so it is
+    // checked first
+    if let Some(gitea_sig) = headers.get("X-Gitea-Signature") {
+        let sig = gitea_sig
+            .to_str()
+            .map_err(|_| WebhookError::InvalidSignature)?;
+        return verify_forgejo_signature(body, secret, sig);
+    }
+
     // Check for GitHub signature
     if let Some(github_sig) = headers.get("X-Hub-Signature-256") {
         let sig = github_sig
@@ -22,8 +32,16 @@ pub fn verify_signature(headers: &HeaderMap, body: &[u8], secret: &str) -> Resul
         return verify_github_signature(body, secret, sig);
     }
 
+    // GitLab does not sign payloads; it sends the secret back as a token
+    if let Some(gitlab_token) = headers.get("X-Gitlab-Token") {
+        let token = gitlab_token
+            .to_str()
+            .map_err(|_| WebhookError::InvalidSignature)?;
+        return verify_gitlab_token(secret, token);
+    }
+
     Err(WebhookError::MissingHeader(
-        "X-Forgejo-Signature or X-Hub-Signature-256",
+        "X-Forgejo-Signature, X-Gitea-Signature, X-Hub-Signature-256 or X-Gitlab-Token",
     ))
 }
 
@@ -63,6 +81,14 @@ fn verify_github_signature(body: &[u8], secret: &str, signature: &str) -> Result
     }
 }
 
+fn verify_gitlab_token(secret: &str, token: &str) -> Result<()> {
+    if bool::from(secret.as_bytes().ct_eq(token.as_bytes())) {
+        Ok(())
+    } else {
+        Err(WebhookError::InvalidSignature)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,4 +154,54 @@ mod tests {
 
         assert!(verify_signature(&headers, body, secret).is_err());
     }
+
+    #[test]
+    fn test_gitea_signature_valid() {
+        let body = b"test payload";
+        let secret = "my-secret";
+
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitea-Signature", signature.parse().unwrap());
+
+        assert!(verify_signature(&headers, body, secret).is_ok());
+    }
+
+    #[test]
+    fn test_gitea_signature_invalid() {
+        let body = b"test payload";
+        let secret = "my-secret";
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitea-Signature", "invalid".parse().unwrap());
+
+        assert!(verify_signature(&headers, body, secret).is_err());
+    }
+
+    #[test]
+    fn test_gitlab_token_valid() {
+        let body = b"test payload";
+        let secret = "my-secret";
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitlab-Token", "my-secret".parse().unwrap());
+
+        assert!(verify_signature(&headers, body, secret).is_ok());
+    }
+
+    #[test]
+    fn test_gitlab_token_invalid() {
+        let body = b"test payload";
+        let secret = "my-secret";
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitlab-Token", "my-secreT".parse().unwrap());
+        assert!(verify_signature(&headers, body, secret).is_err());
+
+        headers.insert("X-Gitlab-Token", "my-secret-longer".parse().unwrap());
+        assert!(verify_signature(&headers, body, secret).is_err());
+    }
 }
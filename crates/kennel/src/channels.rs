@@ -10,6 +10,10 @@ pub struct Channels {
     pub teardown_rx: mpsc::Receiver<i32>,
     pub router_update_tx: tokio::sync::broadcast::Sender<kennel_router::RouterUpdate>,
     pub router_update_rx: tokio::sync::broadcast::Receiver<kennel_router::RouterUpdate>,
+    pub health_report_tx: mpsc::Sender<kennel_router::HealthReport>,
+    pub health_report_rx: mpsc::Receiver<kennel_router::HealthReport>,
+    pub wake_tx: mpsc::Sender<kennel_router::WakeRequest>,
+    pub wake_rx: mpsc::Receiver<kennel_router::WakeRequest>,
 }
 
 pub fn create_channels() -> Channels {
@@ -18,6 +22,9 @@ pub fn create_channels() -> Channels {
     let (teardown_tx, teardown_rx) = mpsc::channel(constants::TEARDOWN_CHANNEL_CAPACITY);
     let (router_update_tx, router_update_rx) =
         tokio::sync::broadcast::channel(constants::ROUTER_UPDATE_CHANNEL_CAPACITY);
+    let (health_report_tx, health_report_rx) =
+        mpsc::channel(constants::HEALTH_REPORT_CHANNEL_CAPACITY);
+    let (wake_tx, wake_rx) = mpsc::channel(constants::WAKE_CHANNEL_CAPACITY);
 
     Channels {
         build_tx,
@@ -28,5 +35,9 @@ pub fn create_channels() -> Channels {
         teardown_rx,
         router_update_tx,
         router_update_rx,
+        health_report_tx,
+        health_report_rx,
+        wake_tx,
+        wake_rx,
     }
 }
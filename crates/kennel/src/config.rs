@@ -9,13 +9,14 @@ pub fn create_builder_config(
     deploy_tx: mpsc::Sender<kennel_deployer::DeploymentRequest>,
 ) -> kennel_builder::BuilderConfig {
     kennel_builder::BuilderConfig {
-        store,
+        store: store.clone(),
         deploy_tx,
         max_concurrent_builds: std::env::var("MAX_CONCURRENT_BUILDS")
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(constants::DEFAULT_MAX_CONCURRENT_BUILDS),
         work_dir: std::env::var("WORK_DIR").unwrap_or_else(|_| constants::DEFAULT_WORK_DIR.into()),
+        forge: Some(Arc::new(create_status_reporter(store))),
     }
 }
 
@@ -24,17 +25,71 @@ pub fn create_deployer_config(
     router_tx: tokio::sync::broadcast::Sender<kennel_router::RouterUpdate>,
     dns_manager: Option<Arc<kennel_dns::DnsManager>>,
     base_domain: String,
+    in_flight: kennel_router::InFlightTracker,
+    runtime: Arc<dyn kennel_deployer::ServiceRuntime>,
+    resources: kennel_config::ResourcePolicy,
 ) -> kennel_deployer::DeployerConfig {
     kennel_deployer::DeployerConfig {
-        store,
+        store: store.clone(),
 
         router_tx: Some(router_tx),
         dns_manager,
         base_domain,
+        max_concurrent_deploys: std::env::var("MAX_CONCURRENT_DEPLOYS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(constants::DEFAULT_MAX_CONCURRENT_DEPLOYS),
+        in_flight,
+        branch_locks: kennel_deployer::BranchLocks::default(),
+        runtime,
+        paths: kennel_deployer::DeployerPaths {
+            builds_dir: std::env::var("WORK_DIR")
+                .unwrap_or_else(|_| constants::DEFAULT_WORK_DIR.into())
+                .into(),
+            ..Default::default()
+        },
+        resources,
+        forge: Some(Arc::new(create_status_reporter(store))),
     }
 }
 
-pub fn create_router_config(store: Arc<Store>) -> kennel_router::RouterConfig {
+/// Reports commit statuses and preview comments, linking failed statuses to
+/// `FORGE_FAILURE_URL`.
+pub fn create_status_reporter(store: Arc<Store>) -> kennel_forge::StatusReporter {
+    kennel_forge::StatusReporter::new(store, std::env::var("FORGE_FAILURE_URL").ok())
+}
+
+/// Host resource defaults and per-environment caps written by the NixOS module.
+pub async fn load_resource_policy(path: &str) -> anyhow::Result<kennel_config::ResourcePolicy> {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(_) => {
+            tracing::info!("No resource policy configured, services run without default limits");
+            return Ok(kennel_config::ResourcePolicy::default());
+        }
+    };
+
+    let policy: kennel_config::ResourcePolicy = serde_json::from_str(&content)?;
+
+    policy
+        .defaults
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid default resources: {}", e))?;
+    for (environment, caps) in &policy.caps {
+        caps.validate()
+            .map_err(|e| anyhow::anyhow!("Invalid resource caps for {}: {}", environment, e))?;
+    }
+
+    Ok(policy)
+}
+
+pub fn create_router_config(
+    store: Arc<Store>,
+    in_flight: kennel_router::InFlightTracker,
+    table: Arc<kennel_router::RoutingTable>,
+    traffic: kennel_router::TrafficTracker,
+    wake_tx: tokio::sync::mpsc::Sender<kennel_router::WakeRequest>,
+) -> kennel_router::RouterConfig {
     kennel_router::RouterConfig {
         store,
         bind_addr: std::env::var("ROUTER_ADDR")
@@ -49,5 +104,9 @@ pub fn create_router_config(store: Arc<Store>) -> kennel_router::RouterConfig {
         acme_cache_dir: std::env::var("ACME_CACHE_DIR")
             .ok()
             .map(std::path::PathBuf::from),
+        in_flight,
+        table,
+        traffic,
+        wake_tx: Some(wake_tx),
     }
 }
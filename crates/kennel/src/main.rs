@@ -40,8 +40,11 @@ async fn main() -> anyhow::Result<()> {
         return Err(e);
     }
 
+    let runtime: Arc<dyn kennel_deployer::ServiceRuntime> =
+        Arc::new(kennel_deployer::SystemdRuntime::default());
+
     // Reconcile deployments and resources on startup
-    if let Err(e) = reconcile::reconcile_deployments(store.clone()).await {
+    if let Err(e) = reconcile::reconcile_deployments(store.clone(), runtime.as_ref()).await {
         tracing::error!("Startup reconciliation failed: {}", e);
     }
 
@@ -51,26 +54,48 @@ async fn main() -> anyhow::Result<()> {
 
     let dns_manager = dns::initialize_dns(store.clone(), &base_domain).await?;
     let builder_config = config::create_builder_config(store.clone(), channels.deploy_tx.clone());
+    let in_flight = kennel_router::InFlightTracker::new();
     let deployer_config = config::create_deployer_config(
         store.clone(),
         channels.router_update_tx.clone(),
         dns_manager,
         base_domain,
+        in_flight.clone(),
+        runtime.clone(),
+        config::load_resource_policy(constants::RESOURCES_CONFIG_PATH).await?,
+    );
+    // The health monitor ejects replicas from the table the router serves
+    let routing_table = Arc::new(kennel_router::RoutingTable::new());
+    let traffic = kennel_router::TrafficTracker::new();
+    let router_config = config::create_router_config(
+        store.clone(),
+        in_flight,
+        routing_table.clone(),
+        traffic.clone(),
+        channels.wake_tx,
     );
-    let router_config = config::create_router_config(store.clone());
 
     let webhook_config = kennel_webhook::WebhookConfig {
         store: store.clone(),
         build_tx: channels.build_tx,
         teardown_tx: channels.teardown_tx.clone(),
+        forge: Some(Arc::new(config::create_status_reporter(store.clone()))),
     };
 
     let api_host = std::env::var("API_HOST").unwrap_or_else(|_| constants::DEFAULT_API_HOST.into());
     let api_port = std::env::var("API_PORT").unwrap_or_else(|_| constants::DEFAULT_API_PORT.into());
     let api_addr = format!("{api_host}:{api_port}");
 
+    let api_config = kennel_api::ApiConfig {
+        store: store.clone(),
+        deploy_tx: channels.deploy_tx.clone(),
+        auth: Arc::new(kennel_api::ApiAuth::load(constants::API_TOKENS_CONFIG_PATH).await?),
+        runtime,
+        webhooks: webhook_config.clone(),
+    };
+
     let webhook_router = kennel_webhook::router(webhook_config);
-    let api_router = kennel_api::router(store.clone()).merge(webhook_router);
+    let api_router = kennel_api::router(api_config).merge(webhook_router);
 
     // Spawn builder worker pool
     let builder_handle = tokio::spawn(kennel_builder::run_worker_pool(
@@ -96,15 +121,37 @@ async fn main() -> anyhow::Result<()> {
         channels.teardown_tx.clone(),
     ));
 
+    // Spawn freeze monitor, which deploys held builds once a freeze ends
+    let freeze_handle = tokio::spawn(kennel_deployer::run_freeze_monitor(
+        deployer_config.clone(),
+        channels.deploy_tx.clone(),
+    ));
+
+    // Spawn remediation worker
+    let remediation_handle = tokio::spawn(kennel_deployer::run_remediation_worker(
+        channels.health_report_rx,
+        deployer_config.clone(),
+    ));
+
     // Spawn build log cleanup job
     let log_cleanup_handle = tokio::spawn(kennel_deployer::run_log_cleanup_job(
         deployer_config.clone(),
     ));
 
+    // Spawn scheduled job monitor
+    let job_monitor_handle =
+        tokio::spawn(kennel_deployer::run_job_monitor(deployer_config.clone()));
+
+    // Spawn idle monitor and the handler waking idle deployments on request
+    let idle_monitor_handle =
+        tokio::spawn(kennel_deployer::run_idle_monitor(deployer_config.clone()));
+    let wake_handle = tokio::spawn(kennel_deployer::run_wake_handler(
+        channels.wake_rx,
+        deployer_config.clone(),
+    ));
+
     // Spawn router
     let router_store = store.clone();
-    let routing_table = Arc::new(kennel_router::RoutingTable::new());
-    let routing_table_clone = routing_table.clone();
     let router_handle = tokio::spawn(async move {
         if let Err(e) = kennel_router::run_router(router_config, channels.router_update_rx).await {
             tracing::error!("Router failed: {}", e);
@@ -113,10 +160,14 @@ async fn main() -> anyhow::Result<()> {
 
     // Spawn health monitor
     let health_handle = tokio::spawn(kennel_router::run_health_monitor(
-        routing_table_clone,
-        router_store,
+        routing_table,
+        router_store.clone(),
+        Some(channels.health_report_tx),
     ));
 
+    // Spawn traffic flusher, which keeps visited deployments from expiring
+    let traffic_handle = tokio::spawn(kennel_router::run_traffic_flusher(traffic, router_store));
+
     tracing::info!("Starting API server on {api_addr}");
     let listener = TcpListener::bind(&api_addr).await?;
     let server_handle = tokio::spawn(async move {
@@ -142,9 +193,15 @@ async fn main() -> anyhow::Result<()> {
                 deployer_handle,
                 teardown_handle,
                 cleanup_handle,
+                freeze_handle,
+                remediation_handle,
                 log_cleanup_handle,
+                job_monitor_handle,
+                idle_monitor_handle,
+                wake_handle,
                 router_handle,
                 health_handle,
+                traffic_handle,
             );
         } => {
             tracing::info!("All components shut down gracefully");
@@ -1,5 +1,9 @@
-use entity::sea_orm_active_enums::RepoType;
-use kennel_config::constants;
+use entity::sea_orm_active_enums::{ForkPolicy, RepoType};
+use kennel_config::{
+    ApprovalPolicy, EnvironmentRule, ExpiryPolicy, constants, validate_environment_rules,
+    validate_tag_pattern,
+};
+use kennel_deployer::ServiceRuntime;
 use kennel_store::Store;
 use sea_orm::ActiveValue;
 use serde::Deserialize;
@@ -13,7 +17,22 @@ struct ProjectConfig {
     repo_url: String,
     repo_type: String,
     webhook_secret_file: String,
+    /// API token used to post commit statuses to the forge.
+    #[serde(default)]
+    forge_token_file: Option<String>,
     default_branch: String,
+    #[serde(default)]
+    expiry: ExpiryPolicy,
+    #[serde(default)]
+    environments: Vec<EnvironmentRule>,
+    #[serde(default)]
+    require_approval: ApprovalPolicy,
+    /// Glob selecting the tags whose pushes start release builds.
+    #[serde(default)]
+    tag_pattern: Option<String>,
+    /// `refuse`, `build` or `approve`; refused when unset.
+    #[serde(default)]
+    fork_pull_requests: Option<String>,
 }
 
 pub async fn reconcile_projects(store: Arc<Store>) -> anyhow::Result<()> {
@@ -39,10 +58,13 @@ pub async fn reconcile_projects(store: Arc<Store>) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub async fn reconcile_deployments(store: Arc<Store>) -> anyhow::Result<()> {
+pub async fn reconcile_deployments(
+    store: Arc<Store>,
+    runtime: &dyn ServiceRuntime,
+) -> anyhow::Result<()> {
     info!("Running startup resource reconciliation");
 
-    reconcile_systemd_units(&store).await?;
+    reconcile_units(&store, runtime).await?;
     reconcile_port_allocations(&store).await?;
     reconcile_static_site_symlinks(&store).await?;
 
@@ -56,9 +78,16 @@ async fn reconcile_project(store: &Store, project: &ProjectConfig) -> anyhow::Re
         .trim()
         .to_string();
 
+    let forge_token = match &project.forge_token_file {
+        Some(path) => Some(tokio::fs::read_to_string(path).await?.trim().to_string()),
+        None => None,
+    };
+
     let repo_type_enum = match project.repo_type.as_str() {
         "forgejo" => RepoType::Forgejo,
+        "gitea" => RepoType::Gitea,
         "github" => RepoType::Github,
+        "gitlab" => RepoType::Gitlab,
         _ => anyhow::bail!(
             "Invalid repo_type '{}' for project {}",
             project.repo_type,
@@ -66,6 +95,58 @@ async fn reconcile_project(store: &Store, project: &ProjectConfig) -> anyhow::Re
         ),
     };
 
+    let fork_policy = match project.fork_pull_requests.as_deref() {
+        None | Some("refuse") => ForkPolicy::Refuse,
+        Some("build") => ForkPolicy::Build,
+        Some("approve") => ForkPolicy::Approve,
+        Some(other) => anyhow::bail!(
+            "Invalid fork_pull_requests '{}' for project {}",
+            other,
+            project.name
+        ),
+    };
+
+    if let Some(pattern) = &project.tag_pattern {
+        validate_tag_pattern(pattern).map_err(|e| {
+            anyhow::anyhow!("Invalid tag pattern for project {}: {}", project.name, e)
+        })?;
+    }
+
+    project.expiry.validate().map_err(|e| {
+        anyhow::anyhow!("Invalid expiry policy for project {}: {}", project.name, e)
+    })?;
+    let expiry_policy = if project.expiry.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_value(&project.expiry)?)
+    };
+
+    validate_environment_rules(&project.environments).map_err(|e| {
+        anyhow::anyhow!(
+            "Invalid environment mapping for project {}: {}",
+            project.name,
+            e
+        )
+    })?;
+    let environment_rules = if project.environments.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_value(&project.environments)?)
+    };
+
+    project.require_approval.validate().map_err(|e| {
+        anyhow::anyhow!(
+            "Invalid approval policy for project {}: {}",
+            project.name,
+            e
+        )
+    })?;
+    let approval_policy = if project.require_approval.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_value(&project.require_approval)?)
+    };
+
     match store.projects().find_by_name(&project.name).await? {
         Some(_existing) => {
             let project_model = entity::projects::ActiveModel {
@@ -73,7 +154,13 @@ async fn reconcile_project(store: &Store, project: &ProjectConfig) -> anyhow::Re
                 repo_url: ActiveValue::Set(project.repo_url.clone()),
                 repo_type: ActiveValue::Set(repo_type_enum),
                 webhook_secret: ActiveValue::Set(webhook_secret),
+                forge_token: ActiveValue::Set(forge_token),
                 default_branch: ActiveValue::Set(project.default_branch.clone()),
+                expiry_policy: ActiveValue::Set(expiry_policy),
+                environment_rules: ActiveValue::Set(environment_rules),
+                approval_policy: ActiveValue::Set(approval_policy),
+                tag_pattern: ActiveValue::Set(project.tag_pattern.clone()),
+                fork_policy: ActiveValue::Set(fork_policy.clone()),
                 ..Default::default()
             };
 
@@ -86,7 +173,13 @@ async fn reconcile_project(store: &Store, project: &ProjectConfig) -> anyhow::Re
                 repo_url: ActiveValue::Set(project.repo_url.clone()),
                 repo_type: ActiveValue::Set(repo_type_enum),
                 webhook_secret: ActiveValue::Set(webhook_secret),
+                forge_token: ActiveValue::Set(forge_token),
                 default_branch: ActiveValue::Set(project.default_branch.clone()),
+                expiry_policy: ActiveValue::Set(expiry_policy),
+                environment_rules: ActiveValue::Set(environment_rules),
+                approval_policy: ActiveValue::Set(approval_policy),
+                tag_pattern: ActiveValue::Set(project.tag_pattern.clone()),
+                fork_policy: ActiveValue::Set(fork_policy),
                 ..Default::default()
             };
 
@@ -117,74 +210,27 @@ async fn cleanup_removed_projects(
     Ok(())
 }
 
-async fn reconcile_systemd_units(store: &Store) -> anyhow::Result<()> {
-    info!("Reconciling systemd units");
-
-    let output = tokio::process::Command::new("systemctl")
-        .args(["list-units", "--all", "--plain", "--no-legend", "kennel-*"])
-        .output()
-        .await?;
-
-    if !output.status.success() {
-        warn!("Failed to list systemd units");
-        return Ok(());
-    }
+async fn reconcile_units(store: &Store, runtime: &dyn ServiceRuntime) -> anyhow::Result<()> {
+    info!("Reconciling service units");
 
-    let units_output = String::from_utf8_lossy(&output.stdout);
-    let running_units: HashSet<String> = units_output
-        .lines()
-        .filter_map(|line| {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            parts.first().map(|s| s.to_string())
-        })
-        .filter(|unit| unit.starts_with("kennel-") && unit.ends_with(".service"))
-        .collect();
+    let units: HashSet<String> = match runtime.list_units().await {
+        Ok(units) => units.into_iter().collect(),
+        Err(e) => {
+            warn!("Failed to list service units: {}", e);
+            return Ok(());
+        }
+    };
 
-    let active_deployments = store.deployments().list_active().await?;
-    let expected_units: HashSet<String> = active_deployments
+    let expected_units: HashSet<String> = store
+        .find_active_service_deployments()
+        .await?
         .iter()
-        .map(|d| {
-            format!(
-                "kennel-{}-{}-{}.service",
-                d.project_name, d.branch_slug, d.service_name
-            )
-        })
+        .map(kennel_deployer::service_unit_name)
         .collect();
 
-    for orphaned_unit in running_units.difference(&expected_units) {
-        info!("Stopping orphaned systemd unit: {}", orphaned_unit);
-        if let Err(e) = tokio::process::Command::new("systemctl")
-            .args(["stop", orphaned_unit])
-            .output()
-            .await
-        {
-            warn!("Failed to stop orphaned unit {}: {}", orphaned_unit, e);
-        }
-
-        if let Err(e) = tokio::process::Command::new("systemctl")
-            .args(["disable", orphaned_unit])
-            .output()
-            .await
-        {
-            warn!("Failed to disable orphaned unit {}: {}", orphaned_unit, e);
-        }
-
-        let unit_file = format!("/etc/systemd/system/{}", orphaned_unit);
-        if let Err(e) = tokio::fs::remove_file(&unit_file).await {
-            warn!("Failed to remove orphaned unit file {}: {}", unit_file, e);
-        }
-    }
-
-    if !running_units
-        .difference(&expected_units)
-        .collect::<Vec<_>>()
-        .is_empty()
-        && let Err(e) = tokio::process::Command::new("systemctl")
-            .arg("daemon-reload")
-            .output()
-            .await
-    {
-        warn!("Failed to reload systemd daemon: {}", e);
+    for orphaned_unit in units.difference(&expected_units) {
+        info!("Removing orphaned unit: {}", orphaned_unit);
+        runtime.uninstall(orphaned_unit).await;
     }
 
     Ok(())
@@ -230,11 +276,9 @@ async fn reconcile_static_site_symlinks(store: &Store) -> anyhow::Result<()> {
     }
 
     let active_static_deployments: HashSet<String> = store
-        .deployments()
-        .list_active()
+        .find_active_static_deployments()
         .await?
         .into_iter()
-        .filter(|d| d.port.is_none())
         .map(|d| format!("{}/{}/{}", d.project_name, d.branch_slug, d.service_name))
         .collect();
 
@@ -12,6 +12,28 @@ mod m20260224_195047_create_build_results;
 mod m20260225_055843_add_error_message_to_build_results;
 mod m20260226_063306_create_dns_records;
 mod m20260226_215312_add_builds_unique_constraint;
+mod m20260302_180412_add_build_to_deployments;
+mod m20260302_181105_create_deployment_events;
+mod m20260302_183520_add_unit_name_to_deployments;
+mod m20260303_094520_extend_deployment_history;
+mod m20260304_101500_add_worker_service_type;
+mod m20260304_113000_add_service_type_to_deployments;
+mod m20260305_093000_create_job_runs;
+mod m20260306_084500_add_release_log_to_deployments;
+mod m20260307_110000_add_load_balancing_to_services;
+mod m20260308_141752_add_scale_to_zero;
+mod m20260309_102346_add_request_count_to_deployments;
+mod m20260310_163108_add_expiry_policies;
+mod m20260311_095417_add_environment_rules_to_projects;
+mod m20260312_134925_add_promotion_provenance;
+mod m20260313_111638_add_build_approvals;
+mod m20260314_152204_create_freeze_windows;
+mod m20260315_103957_add_forge_token_to_projects;
+mod m20260316_141219_create_preview_comments;
+mod m20260317_092743_add_gitea_and_gitlab_repo_types;
+mod m20260318_154031_add_release_tags_and_pull_request_metadata;
+mod m20260318_162845_add_git_ref_to_builds_unique_constraint;
+mod m20260319_110523_create_webhook_deliveries;
 
 pub struct Migrator;
 
@@ -31,6 +53,28 @@ impl MigratorTrait for Migrator {
             Box::new(m20260225_055843_add_error_message_to_build_results::Migration),
             Box::new(m20260226_063306_create_dns_records::Migration),
             Box::new(m20260226_215312_add_builds_unique_constraint::Migration),
+            Box::new(m20260302_180412_add_build_to_deployments::Migration),
+            Box::new(m20260302_181105_create_deployment_events::Migration),
+            Box::new(m20260302_183520_add_unit_name_to_deployments::Migration),
+            Box::new(m20260303_094520_extend_deployment_history::Migration),
+            Box::new(m20260304_101500_add_worker_service_type::Migration),
+            Box::new(m20260304_113000_add_service_type_to_deployments::Migration),
+            Box::new(m20260305_093000_create_job_runs::Migration),
+            Box::new(m20260306_084500_add_release_log_to_deployments::Migration),
+            Box::new(m20260307_110000_add_load_balancing_to_services::Migration),
+            Box::new(m20260308_141752_add_scale_to_zero::Migration),
+            Box::new(m20260309_102346_add_request_count_to_deployments::Migration),
+            Box::new(m20260310_163108_add_expiry_policies::Migration),
+            Box::new(m20260311_095417_add_environment_rules_to_projects::Migration),
+            Box::new(m20260312_134925_add_promotion_provenance::Migration),
+            Box::new(m20260313_111638_add_build_approvals::Migration),
+            Box::new(m20260314_152204_create_freeze_windows::Migration),
+            Box::new(m20260315_103957_add_forge_token_to_projects::Migration),
+            Box::new(m20260316_141219_create_preview_comments::Migration),
+            Box::new(m20260317_092743_add_gitea_and_gitlab_repo_types::Migration),
+            Box::new(m20260318_154031_add_release_tags_and_pull_request_metadata::Migration),
+            Box::new(m20260318_162845_add_git_ref_to_builds_unique_constraint::Migration),
+            Box::new(m20260319_110523_create_webhook_deliveries::Migration),
         ]
     }
 }
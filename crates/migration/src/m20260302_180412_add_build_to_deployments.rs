@@ -0,0 +1,104 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Blue-green deployments keep the old row active while the new one
+        // comes up, so (project, service, branch) can no longer be unique.
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_deployments_project_service_branch")
+                    .table(Deployments::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Deployments::Table)
+                    .add_column(integer_null(Deployments::BuildId))
+                    .add_column(text_null(Deployments::StatusMessage))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_deployments_build_id")
+                            .from_tbl(Deployments::Table)
+                            .from_col(Deployments::BuildId)
+                            .to_tbl(Builds::Table)
+                            .to_col(Builds::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_deployments_build_id")
+                    .table(Deployments::Table)
+                    .col(Deployments::BuildId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_deployments_build_id")
+                    .table(Deployments::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Deployments::Table)
+                    .drop_foreign_key(Alias::new("fk_deployments_build_id"))
+                    .drop_column(Deployments::BuildId)
+                    .drop_column(Deployments::StatusMessage)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_deployments_project_service_branch")
+                    .table(Deployments::Table)
+                    .col(Deployments::ProjectName)
+                    .col(Deployments::ServiceName)
+                    .col(Deployments::Branch)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Deployments {
+    Table,
+    ProjectName,
+    ServiceName,
+    Branch,
+    BuildId,
+    StatusMessage,
+}
+
+#[derive(DeriveIden)]
+enum Builds {
+    Table,
+    Id,
+}
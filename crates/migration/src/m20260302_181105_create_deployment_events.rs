@@ -0,0 +1,133 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(Alias::new("deployment_event_kind"))
+                    .values(vec![
+                        Alias::new("restarted"),
+                        Alias::new("rolled_back"),
+                        Alias::new("failed"),
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeploymentEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DeploymentEvents::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(DeploymentEvents::DeploymentId).integer())
+                    .col(
+                        ColumnDef::new(DeploymentEvents::ProjectName)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DeploymentEvents::ServiceName)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeploymentEvents::Branch).text().not_null())
+                    .col(
+                        ColumnDef::new(DeploymentEvents::Kind)
+                            .custom(Alias::new("deployment_event_kind"))
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeploymentEvents::BuildId).integer())
+                    .col(ColumnDef::new(DeploymentEvents::Message).text())
+                    .col(ColumnDef::new(DeploymentEvents::Actor).text().not_null())
+                    .col(
+                        ColumnDef::new(DeploymentEvents::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_deployment_events_deployment_id")
+                            .from(DeploymentEvents::Table, DeploymentEvents::DeploymentId)
+                            .to(Deployments::Table, Deployments::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_deployment_events_branch")
+                    .table(DeploymentEvents::Table)
+                    .col(DeploymentEvents::ProjectName)
+                    .col(DeploymentEvents::Branch)
+                    .col(DeploymentEvents::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_deployment_events_deployment_id")
+                    .table(DeploymentEvents::Table)
+                    .col(DeploymentEvents::DeploymentId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DeploymentEvents::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(
+                Type::drop()
+                    .name(Alias::new("deployment_event_kind"))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum DeploymentEvents {
+    Table,
+    Id,
+    DeploymentId,
+    ProjectName,
+    ServiceName,
+    Branch,
+    Kind,
+    BuildId,
+    Message,
+    Actor,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Deployments {
+    Table,
+    Id,
+}
@@ -0,0 +1,51 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Deployments::Table)
+                    .add_column(text_null(Deployments::UnitName))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Units started before they were named per deployment keep running
+        // under the shared name, so the deployer and reconciler adopt them
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Deployments::Table)
+                    .value(
+                        Deployments::UnitName,
+                        Expr::cust("'kennel-' || project_name || '-' || branch_slug || '-' || service_name"),
+                    )
+                    .and_where(Expr::col(Deployments::Port).is_not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Deployments::Table)
+                    .drop_column(Deployments::UnitName)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Deployments {
+    Table,
+    Port,
+    UnitName,
+}
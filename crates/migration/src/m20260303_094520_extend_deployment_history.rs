@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for value in ["deployed", "replaced", "expired", "torn_down"] {
+            manager
+                .alter_type(
+                    Type::alter()
+                        .name(Alias::new("deployment_event_kind"))
+                        .add_value(Alias::new(value))
+                        .if_not_exists()
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Builds::Table)
+                    .add_column(text_null(Builds::Author))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // PostgreSQL cannot drop enum values, so the added event kinds stay
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Builds::Table)
+                    .drop_column(Builds::Author)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Builds {
+    Table,
+    Author,
+}
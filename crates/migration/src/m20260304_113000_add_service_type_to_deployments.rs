@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Deployments::Table)
+                    .add_column(
+                        ColumnDef::new(Deployments::ServiceType)
+                            .custom(Alias::new("service_type"))
+                            .not_null()
+                            .default(Expr::cust("'service'")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Only services hold a port; workers are told apart by their service
+        // row. The worker value was only just added, so it is copied rather
+        // than named.
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Deployments::Table)
+                    .value(
+                        Deployments::ServiceType,
+                        Expr::cust(
+                            "COALESCE((SELECT services.type FROM services \
+                             WHERE services.project_name = deployments.project_name \
+                             AND services.name = deployments.service_name \
+                             AND services.type::text = 'worker'), 'static')",
+                        ),
+                    )
+                    .and_where(Expr::col(Deployments::Port).is_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Deployments::Table)
+                    .drop_column(Deployments::ServiceType)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Deployments {
+    Table,
+    Port,
+    ServiceType,
+}
@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_type(
+                Type::alter()
+                    .name(Alias::new("service_type"))
+                    .add_value(Alias::new("job"))
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(JobRuns::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(JobRuns::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(JobRuns::DeploymentId).integer().not_null())
+                    .col(ColumnDef::new(JobRuns::ProjectName).text().not_null())
+                    .col(ColumnDef::new(JobRuns::JobName).text().not_null())
+                    .col(ColumnDef::new(JobRuns::Branch).text().not_null())
+                    .col(ColumnDef::new(JobRuns::StartedAt).timestamp().not_null())
+                    .col(ColumnDef::new(JobRuns::FinishedAt).timestamp().not_null())
+                    .col(ColumnDef::new(JobRuns::Succeeded).boolean().not_null())
+                    .col(ColumnDef::new(JobRuns::ExitCode).integer())
+                    .col(ColumnDef::new(JobRuns::Log).text())
+                    .col(
+                        ColumnDef::new(JobRuns::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_job_runs_deployment_id")
+                            .from(JobRuns::Table, JobRuns::DeploymentId)
+                            .to(Deployments::Table, Deployments::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_job_runs_deployment_started")
+                    .table(JobRuns::Table)
+                    .col(JobRuns::DeploymentId)
+                    .col(JobRuns::StartedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // PostgreSQL cannot drop enum values, so the job type stays
+        manager
+            .drop_table(Table::drop().table(JobRuns::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum JobRuns {
+    Table,
+    Id,
+    DeploymentId,
+    ProjectName,
+    JobName,
+    Branch,
+    StartedAt,
+    FinishedAt,
+    Succeeded,
+    ExitCode,
+    Log,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Deployments {
+    Table,
+    Id,
+}
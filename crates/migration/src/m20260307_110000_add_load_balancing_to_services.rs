@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(Alias::new("load_balancing"))
+                    .values(vec![
+                        Alias::new("round_robin"),
+                        Alias::new("least_connections"),
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Services::Table)
+                    .add_column(
+                        ColumnDef::new(Services::LoadBalancing)
+                            .custom(Alias::new("load_balancing"))
+                            .not_null()
+                            .default("round_robin"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Services::Table)
+                    .drop_column(Services::LoadBalancing)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(Alias::new("load_balancing")).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Services {
+    Table,
+    LoadBalancing,
+}
@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for value in ["expiry_warning", "restored"] {
+            manager
+                .alter_type(
+                    Type::alter()
+                        .name(Alias::new("deployment_event_kind"))
+                        .add_value(Alias::new(value))
+                        .if_not_exists()
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .add_column(json_binary_null(Projects::ExpiryPolicy))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BranchPins::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(BranchPins::ProjectName).text().not_null())
+                    .col(ColumnDef::new(BranchPins::Branch).text().not_null())
+                    .col(ColumnDef::new(BranchPins::PinnedBy).text().not_null())
+                    .col(
+                        ColumnDef::new(BranchPins::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(BranchPins::ProjectName)
+                            .col(BranchPins::Branch),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_branch_pins_project_name")
+                            .from(BranchPins::Table, BranchPins::ProjectName)
+                            .to(Projects::Table, Projects::Name)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // PostgreSQL cannot drop enum values, so the added event kinds stay
+        manager
+            .drop_table(Table::drop().table(BranchPins::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .drop_column(Projects::ExpiryPolicy)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Projects {
+    Table,
+    Name,
+    ExpiryPolicy,
+}
+
+#[derive(DeriveIden)]
+enum BranchPins {
+    Table,
+    ProjectName,
+    Branch,
+    PinnedBy,
+    CreatedAt,
+}
@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for value in ["awaiting_approval", "rejected", "expired"] {
+            manager
+                .alter_type(
+                    Type::alter()
+                        .name(Alias::new("build_status"))
+                        .add_value(Alias::new(value))
+                        .if_not_exists()
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Builds::Table)
+                    .add_column(timestamp_null(Builds::ApprovalExpiresAt))
+                    .add_column(text_null(Builds::ReviewedBy))
+                    .add_column(timestamp_null(Builds::ReviewedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .add_column(json_binary_null(Projects::ApprovalPolicy))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // PostgreSQL cannot drop enum values, so the added build statuses stay
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .drop_column(Projects::ApprovalPolicy)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Builds::Table)
+                    .drop_column(Builds::ApprovalExpiresAt)
+                    .drop_column(Builds::ReviewedBy)
+                    .drop_column(Builds::ReviewedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Builds {
+    Table,
+    ApprovalExpiresAt,
+    ReviewedBy,
+    ReviewedAt,
+}
+
+#[derive(DeriveIden)]
+enum Projects {
+    Table,
+    ApprovalPolicy,
+}
@@ -0,0 +1,118 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_type(
+                Type::alter()
+                    .name(Alias::new("build_status"))
+                    .add_value(Alias::new("frozen"))
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_type(
+                Type::alter()
+                    .name(Alias::new("deployment_event_kind"))
+                    .add_value(Alias::new("freeze_overridden"))
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Builds::Table)
+                    .add_column(text_null(Builds::HoldReason))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FreezeWindows::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FreezeWindows::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FreezeWindows::ProjectName).text())
+                    .col(ColumnDef::new(FreezeWindows::Environment).text())
+                    .col(ColumnDef::new(FreezeWindows::Reason).text().not_null())
+                    .col(ColumnDef::new(FreezeWindows::StartsAt).timestamp())
+                    .col(ColumnDef::new(FreezeWindows::EndsAt).timestamp())
+                    .col(ColumnDef::new(FreezeWindows::Recurrence).text())
+                    .col(ColumnDef::new(FreezeWindows::CreatedBy).text().not_null())
+                    .col(
+                        ColumnDef::new(FreezeWindows::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_freeze_windows_project_name")
+                            .from(FreezeWindows::Table, FreezeWindows::ProjectName)
+                            .to(Projects::Table, Projects::Name)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // PostgreSQL cannot drop enum values, so the frozen status and
+        // freeze_overridden kind stay
+        manager
+            .drop_table(Table::drop().table(FreezeWindows::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Builds::Table)
+                    .drop_column(Builds::HoldReason)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Builds {
+    Table,
+    HoldReason,
+}
+
+#[derive(DeriveIden)]
+enum FreezeWindows {
+    Table,
+    Id,
+    ProjectName,
+    Environment,
+    Reason,
+    StartsAt,
+    EndsAt,
+    Recurrence,
+    CreatedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Projects {
+    Table,
+    Name,
+}
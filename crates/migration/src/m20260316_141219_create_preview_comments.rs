@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PreviewComments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PreviewComments::ProjectName)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PreviewComments::PrNumber)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PreviewComments::CommentId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PreviewComments::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(PreviewComments::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(PreviewComments::ProjectName)
+                            .col(PreviewComments::PrNumber),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_preview_comments_project_name")
+                            .from(PreviewComments::Table, PreviewComments::ProjectName)
+                            .to(Projects::Table, Projects::Name)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PreviewComments::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Projects {
+    Table,
+    Name,
+}
+
+#[derive(DeriveIden)]
+enum PreviewComments {
+    Table,
+    ProjectName,
+    PrNumber,
+    CommentId,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,94 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(Alias::new("fork_policy"))
+                    .values(vec![
+                        Alias::new("refuse"),
+                        Alias::new("build"),
+                        Alias::new("approve"),
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .add_column(text_null(Projects::TagPattern))
+                    .add_column(
+                        ColumnDef::new(Projects::ForkPolicy)
+                            .custom(Alias::new("fork_policy"))
+                            .not_null()
+                            .default("refuse"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Builds::Table)
+                    .add_column(text_null(Builds::PrHeadRef))
+                    .add_column(text_null(Builds::PrBaseRef))
+                    .add_column(text_null(Builds::PrTitle))
+                    .add_column(boolean(Builds::PrFromFork).default(false))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Builds::Table)
+                    .drop_column(Builds::PrHeadRef)
+                    .drop_column(Builds::PrBaseRef)
+                    .drop_column(Builds::PrTitle)
+                    .drop_column(Builds::PrFromFork)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .drop_column(Projects::TagPattern)
+                    .drop_column(Projects::ForkPolicy)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(Alias::new("fork_policy")).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Projects {
+    Table,
+    TagPattern,
+    ForkPolicy,
+}
+
+#[derive(DeriveIden)]
+enum Builds {
+    Table,
+    PrHeadRef,
+    PrBaseRef,
+    PrTitle,
+    PrFromFork,
+}
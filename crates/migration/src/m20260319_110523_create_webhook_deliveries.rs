@@ -0,0 +1,166 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDeliveries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::ProjectName)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookDeliveries::DeliveryId).text())
+                    .col(ColumnDef::new(WebhookDeliveries::EventType).text())
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::SourceIp)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::Headers)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookDeliveries::Body).text().not_null())
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::Verified)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookDeliveries::ParseError).text())
+                    .col(ColumnDef::new(WebhookDeliveries::Error).text())
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::ResponseStatus)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookDeliveries::BuildId).integer())
+                    .col(ColumnDef::new(WebhookDeliveries::ReplayOf).integer())
+                    .col(ColumnDef::new(WebhookDeliveries::ReplayedBy).text())
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::Claimed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhook_deliveries_project_name")
+                            .from(WebhookDeliveries::Table, WebhookDeliveries::ProjectName)
+                            .to(Projects::Table, Projects::Name)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhook_deliveries_build_id")
+                            .from(WebhookDeliveries::Table, WebhookDeliveries::BuildId)
+                            .to(Builds::Table, Builds::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhook_deliveries_replay_of")
+                            .from(WebhookDeliveries::Table, WebhookDeliveries::ReplayOf)
+                            .to(WebhookDeliveries::Table, WebhookDeliveries::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_delivery_id")
+                    .table(WebhookDeliveries::Table)
+                    .col(WebhookDeliveries::ProjectName)
+                    .col(WebhookDeliveries::DeliveryId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_created_at")
+                    .table(WebhookDeliveries::Table)
+                    .col(WebhookDeliveries::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Only one delivery per id can be claimed, so concurrent redeliveries
+        // cannot both be handled
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_claimed")
+                    .table(WebhookDeliveries::Table)
+                    .col(WebhookDeliveries::ProjectName)
+                    .col(WebhookDeliveries::DeliveryId)
+                    .unique()
+                    .and_where(Expr::col(WebhookDeliveries::Claimed).eq(true))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookDeliveries::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Projects {
+    Table,
+    Name,
+}
+
+#[derive(DeriveIden)]
+enum Builds {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum WebhookDeliveries {
+    Table,
+    Id,
+    ProjectName,
+    DeliveryId,
+    EventType,
+    SourceIp,
+    Headers,
+    Body,
+    Verified,
+    ParseError,
+    Error,
+    ResponseStatus,
+    BuildId,
+    ReplayOf,
+    ReplayedBy,
+    CreatedAt,
+    Claimed,
+}
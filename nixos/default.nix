@@ -4,6 +4,45 @@ with lib;
 
 let
   cfg = config.services.kennel;
+
+  resourceLimitsType = types.submodule {
+    options = {
+      memoryMax = mkOption {
+        type = types.nullOr types.str;
+        default = null;
+        example = "512M";
+        description = "systemd MemoryMax";
+      };
+
+      cpuQuota = mkOption {
+        type = types.nullOr types.str;
+        default = null;
+        example = "50%";
+        description = "systemd CPUQuota";
+      };
+
+      tasksMax = mkOption {
+        type = types.nullOr types.int;
+        default = null;
+        example = 256;
+        description = "systemd TasksMax";
+      };
+
+      ioWeight = mkOption {
+        type = types.nullOr (types.ints.between 1 10000);
+        default = null;
+        example = 100;
+        description = "systemd IOWeight";
+      };
+    };
+  };
+
+  resourceLimitsJSON = limits: {
+    memory_max = limits.memoryMax;
+    cpu_quota = limits.cpuQuota;
+    tasks_max = limits.tasksMax;
+    io_weight = limits.ioWeight;
+  };
 in
 {
   options.services.kennel = {
@@ -60,6 +99,27 @@ in
         default = 3000;
         description = "API server port";
       };
+
+      tokens = mkOption {
+        type = types.attrsOf (types.submodule {
+          options = {
+            tokenFile = mkOption {
+              type = types.path;
+              example = "/run/secrets/kennel-api-token";
+              description = "Path to file containing the bearer token";
+            };
+
+            projects = mkOption {
+              type = types.nullOr (types.listOf types.str);
+              default = null;
+              example = [ "kennel" ];
+              description = "Projects the token may act on (null for all projects)";
+            };
+          };
+        });
+        default = { };
+        description = "Bearer tokens accepted by authenticated API endpoints";
+      };
     };
 
     projects = mkOption {
@@ -72,9 +132,9 @@ in
           };
 
           repoType = mkOption {
-            type = types.enum [ "forgejo" "github" ];
+            type = types.enum [ "forgejo" "gitea" "github" "gitlab" ];
             default = "forgejo";
-            description = "Repository type (forgejo or github)";
+            description = "Repository type (forgejo, gitea, github or gitlab)";
           };
 
           webhookSecretFile = mkOption {
@@ -83,11 +143,123 @@ in
             description = "Path to file containing webhook secret";
           };
 
+          forgeTokenFile = mkOption {
+            type = types.nullOr types.path;
+            default = null;
+            example = "/run/secrets/kennel-forge-token";
+            description = "Path to file containing a forge API token; when set, build and deploy progress is posted as commit statuses";
+          };
+
           defaultBranch = mkOption {
             type = types.str;
             default = "main";
             description = "Default branch name";
           };
+
+          environments = mkOption {
+            type = types.listOf (types.submodule {
+              options = {
+                branch = mkOption {
+                  type = types.str;
+                  example = "release/*";
+                  description = "Glob pattern matched against the branch (pull requests are pr-<number>)";
+                };
+
+                environment = mkOption {
+                  type = types.strMatching "[a-z0-9-]+";
+                  example = "staging";
+                  description = "Environment deployments of matching branches run in";
+                };
+
+                kind = mkOption {
+                  type = types.nullOr (types.enum [ "production" "staging" "development" "preview" ]);
+                  default = null;
+                  example = "preview";
+                  description = "What the environment is for, when its name is not prod, staging or preview (production never idles, previews are never frozen)";
+                };
+              };
+            });
+            default = [ ];
+            example = [
+              { branch = "release/*"; environment = "staging"; }
+              { branch = "qa/*"; environment = "qa"; }
+            ];
+            description = ''
+              Branch to environment rules, first match wins. Unmatched branches
+              use the default mapping: the default branch to prod, staging to
+              staging, pull requests to preview and anything else to dev.
+            '';
+          };
+
+          tagPattern = mkOption {
+            type = types.nullOr types.str;
+            default = null;
+            example = "v*";
+            description = "Glob pattern of tags whose pushes start release builds (null to ignore tag pushes)";
+          };
+
+          forkPullRequests = mkOption {
+            type = types.enum [ "refuse" "build" "approve" ];
+            default = "refuse";
+            description = ''
+              What to do with pull requests from forks: refuse ignores them,
+              build builds them without deploying, and approve holds their
+              builds for approval before deploying.
+            '';
+          };
+
+          expiry = {
+            previewTtlDays = mkOption {
+              type = types.nullOr types.ints.positive;
+              default = null;
+              example = 3;
+              description = "Days without requests before a pull request preview expires (null for the default of 7)";
+            };
+
+            branchTtlDays = mkOption {
+              type = types.nullOr types.ints.positive;
+              default = null;
+              example = 14;
+              description = "Days without requests before any other branch expires (null for the default of 7)";
+            };
+
+            neverExpire = mkOption {
+              type = types.listOf types.str;
+              default = [ ];
+              example = [ "main" "staging" "release/*" ];
+              description = "Glob patterns of branches whose deployments never expire";
+            };
+
+            warningHours = mkOption {
+              type = types.nullOr types.ints.unsigned;
+              default = null;
+              example = 48;
+              description = "Hours before expiry to record a warning event (null for the default of 24, 0 to disable)";
+            };
+          };
+
+          requireApproval = {
+            environments = mkOption {
+              type = types.listOf types.str;
+              default = [ ];
+              example = [ "prod" ];
+              description = "Environments whose builds wait for approval through the API before deploying";
+            };
+
+            approvers = mkOption {
+              type = types.listOf types.str;
+              default = [ ];
+              example = [ "release-manager" ];
+              description = "API token names allowed to approve or reject builds (empty for any token with access to the project)";
+            };
+
+            expiryHours = mkOption {
+              type = types.nullOr types.ints.positive;
+              default = null;
+              example = 24;
+              description = "Hours a build waits for approval before it expires (null for the default of 72)";
+            };
+          };
         };
       });
       default = { };
@@ -165,6 +337,33 @@ in
       };
     };
 
+    deployer = {
+      maxConcurrentDeploys = mkOption {
+        type = types.int;
+        default = 4;
+        description = "Maximum concurrent deployments (deployments of the same branch always run one at a time, in the order they arrive)";
+      };
+
+      resources = {
+        defaults = mkOption {
+          type = resourceLimitsType;
+          default = { };
+          description = "Resource limits for services that do not set their own in kennel.toml";
+        };
+
+        caps = mkOption {
+          type = types.attrsOf resourceLimitsType;
+          default = { };
+          example = literalExpression ''
+            {
+              preview = { memoryMax = "512M"; cpuQuota = "50%"; };
+            }
+          '';
+          description = "Upper bounds on service resource limits per environment (prod, staging, dev, preview)";
+        };
+      };
+    };
+
     cleanup = {
       interval = mkOption {
         type = types.int;
@@ -173,6 +372,15 @@ in
       };
     };
 
+    forge = {
+      failureUrl = mkOption {
+        type = types.nullOr types.str;
+        default = null;
+        example = "https://logs.example.com/kennel";
+        description = "Page that failed and errored commit statuses link to, such as a log viewer or dashboard";
+      };
+    };
+
     dns = {
       enable = mkEnableOption "Automatic DNS management";
 
@@ -280,6 +488,8 @@ in
         Type = "notify";
         User = cfg.user;
         Group = cfg.group;
+        # Read service output for the logs API
+        SupplementaryGroups = [ "systemd-journal" ];
         ExecStart = "${cfg.package}/bin/kennel";
         Restart = "on-failure";
         RestartSec = 5;
@@ -303,7 +513,10 @@ in
           "BASE_DOMAIN=${cfg.router.baseDomain}"
           "MAX_CONCURRENT_BUILDS=${toString cfg.builder.maxConcurrentBuilds}"
           "WORK_DIR=${cfg.builder.workDir}"
+          "MAX_CONCURRENT_DEPLOYS=${toString cfg.deployer.maxConcurrentDeploys}"
           "AUTO_EXPIRY_CHECK_INTERVAL_SECS=${toString cfg.cleanup.interval}"
+        ] ++ optionals (cfg.forge.failureUrl != null) [
+          "FORGE_FAILURE_URL=${cfg.forge.failureUrl}"
         ] ++ optionals cfg.router.tls.enable [
           "ACME_EMAIL=${cfg.router.tls.email}"
           "ACME_STAGING=${if cfg.router.tls.staging then "true" else "false"}"
@@ -334,7 +547,21 @@ in
           repo_url = proj.repoUrl;
           repo_type = proj.repoType;
           webhook_secret_file = proj.webhookSecretFile;
+          forge_token_file = proj.forgeTokenFile;
           default_branch = proj.defaultBranch;
+          inherit (proj) environments;
+          expiry = {
+            preview_ttl_days = proj.expiry.previewTtlDays;
+            branch_ttl_days = proj.expiry.branchTtlDays;
+            never_expire = proj.expiry.neverExpire;
+            warning_hours = proj.expiry.warningHours;
+          };
+          require_approval = {
+            inherit (proj.requireApproval) environments approvers;
+            expiry_hours = proj.requireApproval.expiryHours;
+          };
+          tag_pattern = proj.tagPattern;
+          fork_pull_requests = proj.forkPullRequests;
         })
         cfg.projects);
       mode = "0440";
@@ -342,6 +569,31 @@ in
       group = cfg.group;
     };
 
+    # Create API token configuration for authenticated endpoints
+    environment.etc."kennel/api-tokens.json" = mkIf (cfg.api.tokens != { }) {
+      text = builtins.toJSON (mapAttrsToList
+        (name: token: {
+          inherit name;
+          token_file = token.tokenFile;
+          projects = token.projects;
+        })
+        cfg.api.tokens);
+      mode = "0440";
+      user = cfg.user;
+      group = cfg.group;
+    };
+
+    # Create resource defaults and per-environment caps for service units
+    environment.etc."kennel/resources.json" = {
+      text = builtins.toJSON {
+        defaults = resourceLimitsJSON cfg.deployer.resources.defaults;
+        caps = mapAttrs (_: resourceLimitsJSON) cfg.deployer.resources.caps;
+      };
+      mode = "0440";
+      user = cfg.user;
+      group = cfg.group;
+    };
+
     systemd.tmpfiles.rules = [
       "d /var/lib/kennel 0755 ${cfg.user} ${cfg.group} -"
       "d /var/lib/kennel/builds 0755 ${cfg.user} ${cfg.group} -"
@@ -349,6 +601,7 @@ in
       "d /var/lib/kennel/logs 0755 ${cfg.user} ${cfg.group} -"
       "d /var/lib/kennel/services 0755 ${cfg.user} ${cfg.group} -"
       "d /var/lib/kennel/acme 0700 ${cfg.user} ${cfg.group} -"
+      "d /var/lib/kennel/secrets 0700 ${cfg.user} ${cfg.group} -"
       "d /run/kennel 0755 ${cfg.user} ${cfg.group} -"
       "d /run/kennel/secrets 0700 ${cfg.user} ${cfg.group} -"
     ];